@@ -0,0 +1,201 @@
+//! Cold storage archival of game folders
+//!
+//! A game folder is zipped into the configured archive target (another
+//! drive or a NAS share), the archive is verified against a manifest of the
+//! files it should contain, and only then is the original folder removed.
+//! Restoring extracts the archive back to the original location.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+
+use crate::backup;
+
+/// Manifest written into each game archive (removed again on restore)
+pub const ARCHIVE_MANIFEST: &str = ".gamevault-archive.json";
+
+/// Archive file name for a game: `<id>-<folder name>.zip`
+pub fn archive_file_name(game_id: i64, folder_name: &str) -> String {
+    let safe: String = folder_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.' | '(' | ')' | '[' | ']') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}-{}.zip", game_id, safe.trim().trim_matches('.'))
+}
+
+/// Zip a folder, including a manifest listing every file
+fn zip_folder(
+    source: &Path,
+    dest: &Path,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let mut zip = zip::ZipWriter::new(BufWriter::new(File::create(dest)?));
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(source).min_depth(1) {
+        let entry = entry?;
+        let rel = entry.path().strip_prefix(source)?;
+        // Zip paths always use forward slashes
+        let name = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if entry.file_type().is_dir() {
+            zip.add_directory(name, SimpleFileOptions::default())?;
+        } else if entry.file_type().is_file() {
+            let size = entry.metadata()?.len();
+            let options = SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .large_file(size >= u32::MAX as u64);
+            zip.start_file(name.as_str(), options)?;
+            io::copy(&mut File::open(entry.path())?, &mut zip)?;
+            files.push(name);
+        }
+    }
+
+    zip.start_file(ARCHIVE_MANIFEST, SimpleFileOptions::default())?;
+    serde_json::to_writer(&mut zip, &serde_json::json!({ "files": files }))?;
+    zip.finish()?;
+
+    Ok(files.len())
+}
+
+/// Archive a game folder into `target_dir`, verify it, then delete the original
+///
+/// Returns the archive path. On any failure the original folder is kept.
+pub fn archive_game_folder(
+    folder: &Path,
+    target_dir: &Path,
+    file_name: &str,
+) -> Result<PathBuf, String> {
+    let archive_path = target_dir.join(file_name);
+    if archive_path.exists() {
+        return Err(format!(
+            "Archive already exists: {}",
+            archive_path.display()
+        ));
+    }
+
+    // Write to a temporary name so an interrupted run never looks complete
+    let partial = target_dir.join(format!("{}.partial", file_name));
+    let file_count = match zip_folder(folder, &partial) {
+        Ok(n) => n,
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("Failed to write archive: {}", e));
+        }
+    };
+
+    let verification = backup::verify_archive(&partial, ARCHIVE_MANIFEST, false);
+    if !verification.valid || verification.file_count != file_count {
+        let _ = fs::remove_file(&partial);
+        return Err(format!(
+            "Archive verification failed: {}",
+            verification.errors.join("; ")
+        ));
+    }
+
+    fs::rename(&partial, &archive_path)
+        .map_err(|e| format!("Failed to finalize archive: {}", e))?;
+
+    fs::remove_dir_all(folder).map_err(|e| {
+        format!(
+            "Archive written to {} but the original folder could not be removed: {}",
+            archive_path.display(),
+            e
+        )
+    })?;
+
+    Ok(archive_path)
+}
+
+/// Extract an archive back to the game's original folder
+pub fn restore_game_folder(archive_path: &Path, folder: &Path) -> Result<(), String> {
+    if folder.exists() {
+        return Err(format!("Folder already exists: {}", folder.display()));
+    }
+
+    let verification = backup::verify_archive(archive_path, ARCHIVE_MANIFEST, false);
+    if !verification.valid {
+        return Err(format!(
+            "Archive verification failed: {}",
+            verification.errors.join("; ")
+        ));
+    }
+
+    let parent = folder
+        .parent()
+        .ok_or_else(|| "Game folder has no parent directory".to_string())?;
+    let staging = tempfile::Builder::new()
+        .prefix(".gamevault-restore-")
+        .tempdir_in(parent)
+        .map_err(|e| format!("Failed to create staging folder: {}", e))?;
+
+    File::open(archive_path)
+        .map_err(zip::result::ZipError::Io)
+        .and_then(zip::ZipArchive::new)
+        .and_then(|mut archive| archive.extract(staging.path()))
+        .map_err(|e| format!("Failed to extract archive: {}", e))?;
+
+    let _ = fs::remove_file(staging.path().join(ARCHIVE_MANIFEST));
+
+    // Move the staged folder into place; the TempDir guard is released first
+    let staged = staging.keep();
+    fs::rename(&staged, folder).map_err(|e| {
+        let _ = fs::remove_dir_all(&staged);
+        format!("Failed to move restored folder into place: {}", e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_file_name() {
+        assert_eq!(archive_file_name(7, "Hades [GOG]"), "7-Hades [GOG].zip");
+        assert_eq!(archive_file_name(8, "a/b:c"), "8-a_b_c.zip");
+    }
+
+    #[test]
+    fn test_archive_and_restore_roundtrip() {
+        let library = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+
+        let game = library.path().join("Some Game");
+        fs::create_dir_all(game.join("bin")).unwrap();
+        fs::create_dir_all(game.join("empty")).unwrap();
+        fs::write(game.join("bin/game.exe"), b"binary").unwrap();
+        fs::write(game.join("readme.txt"), b"hello").unwrap();
+
+        let archive = archive_game_folder(&game, target.path(), "1-Some Game.zip").unwrap();
+        assert!(archive.is_file());
+        assert!(
+            !game.exists(),
+            "original folder is removed after verification"
+        );
+
+        restore_game_folder(&archive, &game).unwrap();
+        assert_eq!(fs::read(game.join("bin/game.exe")).unwrap(), b"binary");
+        assert_eq!(fs::read(game.join("readme.txt")).unwrap(), b"hello");
+        assert!(game.join("empty").is_dir());
+        assert!(!game.join(ARCHIVE_MANIFEST).exists());
+    }
+
+    #[test]
+    fn test_restore_refuses_existing_folder() {
+        let library = tempfile::tempdir().unwrap();
+        let archive = library.path().join("missing.zip");
+        assert!(restore_game_folder(&archive, library.path()).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Cover thumbnails, blurhashes and dominant colors
+//!
+//! The library grid shows covers at a few fixed sizes, with a blurhash
+//! placeholder and the cover's dominant color while they load. Generating
+//! these on demand means the first grid view after an enrichment run decodes
+//! every new cover at once, so instead enrichment queues the games it cached
+//! images for and a single background worker pre-generates everything, one
+//! cover at a time. Requests for a size that isn't there yet (older covers,
+//! a cleared cache) still generate it on demand.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, RgbImage};
+use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+
+use crate::{db, local_storage, media};
+
+/// Cover thumbnail sizes (name, longest edge in pixels)
+pub const COVER_SIZES: &[(&str, u32)] = &[("small", 184), ("medium", 368)];
+
+/// BlurHash detail (components across and down)
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+
+/// Edge of the downscaled copy used for the blurhash and color
+const SAMPLE_SIZE: u32 = 64;
+
+type ArtworkError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Placeholder data for a cover
+#[derive(Debug, Clone, PartialEq)]
+pub struct Artwork {
+    pub blurhash: String,
+    /// "#rrggbb"
+    pub color: String,
+}
+
+/// Longest edge for a named cover size
+pub fn cover_size(name: &str) -> Option<u32> {
+    COVER_SIZES
+        .iter()
+        .find(|(size, _)| *size == name)
+        .map(|(_, edge)| *edge)
+}
+
+/// Where a cover thumbnail is cached
+pub fn cover_thumbnail_path(cache_dir: &Path, game_id: i64, size: &str) -> PathBuf {
+    cache_dir
+        .join("thumbnails")
+        .join("covers")
+        .join(format!("{}-{}.jpg", game_id, size))
+}
+
+fn write_thumbnail(img: &DynamicImage, path: &Path, edge: u32) -> Result<(), image::ImageError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    img.thumbnail(edge, edge)
+        .to_rgb8()
+        .save_with_format(path, image::ImageFormat::Jpeg)
+}
+
+/// Create (or refresh) one cover thumbnail
+pub fn ensure_cover_thumbnail(
+    cover: &Path,
+    thumbnail: &Path,
+    edge: u32,
+) -> Result<(), image::ImageError> {
+    if media::is_up_to_date(cover, thumbnail) {
+        return Ok(());
+    }
+    write_thumbnail(&image::open(cover)?, thumbnail, edge)
+}
+
+/// Most common color, averaged within its bucket to avoid banding
+///
+/// Pixels are bucketed at 4 bits per channel, so a cover that's mostly one
+/// color wins over a few bright details.
+fn dominant_color(img: &RgbImage) -> String {
+    let mut buckets: HashMap<(u8, u8, u8), (u32, [u32; 3])> = HashMap::new();
+    for pixel in img.pixels() {
+        let [r, g, b] = pixel.0;
+        let entry = buckets.entry((r >> 4, g >> 4, b >> 4)).or_default();
+        entry.0 += 1;
+        for (sum, value) in entry.1.iter_mut().zip(pixel.0) {
+            *sum += u32::from(value);
+        }
+    }
+
+    let (count, sums) = buckets
+        .into_values()
+        .max_by_key(|(count, _)| *count)
+        .unwrap_or((1, [0; 3]));
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        sums[0] / count,
+        sums[1] / count,
+        sums[2] / count
+    )
+}
+
+/// Blurhash and dominant color of an image
+pub fn analyze(img: &DynamicImage) -> Result<Artwork, ArtworkError> {
+    let sample = img.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE);
+    let rgba = sample.to_rgba8();
+    let (x, y) = BLURHASH_COMPONENTS;
+    let blurhash = blurhash::encode(x, y, rgba.width(), rgba.height(), rgba.as_raw())
+        .map_err(|e| format!("BlurHash failed: {:?}", e))?;
+
+    Ok(Artwork {
+        blurhash,
+        color: dominant_color(&sample.to_rgb8()),
+    })
+}
+
+/// Generate every thumbnail size and the placeholder data for a cover
+pub fn process_cover(
+    cover: &Path,
+    cache_dir: &Path,
+    game_id: i64,
+) -> Result<Artwork, ArtworkError> {
+    let img = image::open(cover)?;
+    for (size, edge) in COVER_SIZES {
+        let thumbnail = cover_thumbnail_path(cache_dir, game_id, size);
+        if !media::is_up_to_date(cover, &thumbnail) {
+            write_thumbnail(&img, &thumbnail, *edge)?;
+        }
+    }
+    analyze(&img)
+}
+
+/// Queue of games whose covers need pre-generating
+#[derive(Clone)]
+pub struct ArtworkQueue {
+    sender: mpsc::UnboundedSender<i64>,
+}
+
+impl ArtworkQueue {
+    /// Start the background worker
+    pub fn start(pool: SqlitePool, cache_dir: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run_worker(pool, cache_dir, receiver));
+        Self { sender }
+    }
+
+    /// Queue games for processing (ids already queued are processed once)
+    pub fn enqueue(&self, game_ids: impl IntoIterator<Item = i64>) {
+        for id in game_ids {
+            // Only fails once the worker is gone (shutdown)
+            let _ = self.sender.send(id);
+        }
+    }
+}
+
+async fn run_worker(
+    pool: SqlitePool,
+    cache_dir: PathBuf,
+    mut receiver: mpsc::UnboundedReceiver<i64>,
+) {
+    while let Some(first) = receiver.recv().await {
+        // Take everything queued so far, so repeats are processed once
+        let mut batch = BTreeSet::from([first]);
+        while let Ok(id) = receiver.try_recv() {
+            batch.insert(id);
+        }
+
+        let (mut processed, mut failed) = (0, 0);
+        for id in batch {
+            match prewarm_game(&pool, &cache_dir, id).await {
+                Ok(true) => processed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to pre-generate artwork for game {}: {}", id, e);
+                    failed += 1;
+                }
+            }
+        }
+        tracing::info!(
+            "Artwork pre-generation: {} covers processed, {} failed",
+            processed,
+            failed
+        );
+    }
+}
+
+/// Process one game's cover; false when it has none cached
+async fn prewarm_game(pool: &SqlitePool, cache_dir: &Path, id: i64) -> Result<bool, ArtworkError> {
+    let Some(folder_path) = db::get_game_folder_path(pool, id).await? else {
+        return Ok(false);
+    };
+    let cover = local_storage::get_cover_path(&folder_path);
+    if !cover.exists() {
+        return Ok(false);
+    }
+
+    let cache_dir = cache_dir.to_path_buf();
+    let artwork =
+        tokio::task::spawn_blocking(move || process_cover(&cover, &cache_dir, id)).await??;
+    db::update_game_artwork(pool, id, &artwork.blurhash, &artwork.color).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_dominant_color() {
+        // Mostly dark blue with a bright red stripe
+        let img = RgbImage::from_fn(40, 20, |x, _| {
+            if x < 8 {
+                Rgb([250, 10, 10])
+            } else {
+                Rgb([20, 30, 90])
+            }
+        });
+        assert_eq!(dominant_color(&img), "#141e5a");
+    }
+
+    #[test]
+    fn test_process_cover() {
+        let dir = tempfile::tempdir().unwrap();
+        let cover = dir.path().join("cover.jpg");
+        RgbImage::from_pixel(460, 215, Rgb([200, 120, 40]))
+            .save(&cover)
+            .unwrap();
+
+        let cache = dir.path().join("cache");
+        let artwork = process_cover(&cover, &cache, 7).unwrap();
+        assert_eq!(artwork.blurhash.len(), 6 + 2 * (4 * 3 - 1));
+        assert!(artwork.color.starts_with('#') && artwork.color.len() == 7);
+
+        for (size, edge) in COVER_SIZES {
+            let thumbnail = image::open(cover_thumbnail_path(&cache, 7, size)).unwrap();
+            assert_eq!(thumbnail.width(), *edge);
+        }
+        assert_eq!(cover_size("medium"), Some(368));
+        assert_eq!(cover_size("huge"), None);
+    }
+}
@@ -0,0 +1,271 @@
+//! Save backup verification
+//!
+//! Backups are zip files in `.gamevault/saves/`. Verification reads every
+//! entry to the end so the zip reader checks its CRC, compares the entry
+//! count with an optional `manifest.json` written alongside the saves, and can
+//! extract everything to a temporary directory as a test restore. Encrypted
+//! backups (`.zip.enc`, see `encryption`) are decrypted to a temporary file
+//! first.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::encryption;
+
+/// Name of the optional manifest inside a backup zip
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Manifest describing the files a backup should contain
+#[derive(Debug, Deserialize)]
+pub struct BackupManifest {
+    /// Relative paths of the backed-up files
+    #[serde(default)]
+    pub files: Option<Vec<String>>,
+    /// Number of backed-up files (used when `files` is absent)
+    #[serde(default)]
+    pub file_count: Option<usize>,
+}
+
+impl BackupManifest {
+    fn expected_files(&self) -> Option<usize> {
+        self.files.as_ref().map(|f| f.len()).or(self.file_count)
+    }
+}
+
+/// Result of verifying a backup
+#[derive(Debug, Default, Serialize)]
+pub struct BackupVerification {
+    pub filename: String,
+    /// True when no errors were found
+    pub valid: bool,
+    /// Number of files in the archive (excluding directories and the manifest)
+    pub file_count: usize,
+    pub uncompressed_bytes: u64,
+    /// File count declared by `manifest.json`, if present
+    pub manifest_file_count: Option<usize>,
+    /// Whether a test extraction was performed and succeeded
+    pub test_extracted: bool,
+    /// Whether the backup is encrypted (and was decrypted for verification)
+    pub encrypted: bool,
+    pub errors: Vec<String>,
+}
+
+/// Verify a backup zip, optionally test-extracting it to a temp directory
+///
+/// `secret` is the configured encryption passphrase or keyfile contents,
+/// needed only for encrypted backups.
+pub fn verify_backup(path: &Path, test_extract: bool, secret: Option<&[u8]>) -> BackupVerification {
+    if !encryption::is_encrypted_path(path) {
+        return verify_archive(path, MANIFEST_NAME, test_extract);
+    }
+
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let fail = |error: String| BackupVerification {
+        filename: filename.clone(),
+        encrypted: true,
+        errors: vec![error],
+        ..Default::default()
+    };
+
+    let Some(secret) = secret else {
+        return fail("Backup is encrypted but no backup key is configured".to_string());
+    };
+    let decrypted = match encryption::decrypt_to_temp(path, secret) {
+        Ok(f) => f,
+        Err(e) => return fail(e),
+    };
+
+    BackupVerification {
+        filename,
+        encrypted: true,
+        ..verify_archive(decrypted.path(), MANIFEST_NAME, test_extract)
+    }
+}
+
+/// Verify any zip written with a manifest entry named `manifest_name`
+pub fn verify_archive(path: &Path, manifest_name: &str, test_extract: bool) -> BackupVerification {
+    let mut result = BackupVerification {
+        filename: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let mut archive = match File::open(path)
+        .map_err(zip::result::ZipError::Io)
+        .and_then(zip::ZipArchive::new)
+    {
+        Ok(a) => a,
+        Err(e) => {
+            result.errors.push(format!("Cannot open archive: {}", e));
+            return result;
+        }
+    };
+
+    let mut manifest: Option<BackupManifest> = None;
+
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(e) => e,
+            Err(e) => {
+                result.errors.push(format!("Entry {}: {}", i, e));
+                continue;
+            }
+        };
+
+        if entry.is_dir() {
+            continue;
+        }
+
+        let name = entry.name().to_string();
+        if entry.enclosed_name().is_none() {
+            result.errors.push(format!("{}: unsafe path", name));
+            continue;
+        }
+
+        if name == manifest_name {
+            let mut text = String::new();
+            match entry.read_to_string(&mut text) {
+                Ok(_) => match serde_json::from_str(&text) {
+                    Ok(m) => manifest = Some(m),
+                    Err(e) => result.errors.push(format!("Invalid manifest: {}", e)),
+                },
+                Err(e) => result.errors.push(format!("{}: {}", name, e)),
+            }
+            continue;
+        }
+
+        // Reading to the end makes the zip reader validate the CRC
+        match io::copy(&mut entry, &mut io::sink()) {
+            Ok(bytes) => {
+                result.file_count += 1;
+                result.uncompressed_bytes += bytes;
+            }
+            Err(e) => result.errors.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    if let Some(expected) = manifest.as_ref().and_then(|m| m.expected_files()) {
+        result.manifest_file_count = Some(expected);
+        if expected != result.file_count {
+            result.errors.push(format!(
+                "Manifest lists {} files but archive contains {}",
+                expected, result.file_count
+            ));
+        }
+    }
+
+    if test_extract && result.errors.is_empty() {
+        match test_extract_archive(&mut archive) {
+            Ok(()) => result.test_extracted = true,
+            Err(e) => result.errors.push(format!("Test extraction failed: {}", e)),
+        }
+    }
+
+    result.valid = result.errors.is_empty();
+    result
+}
+
+/// Extract an archive into a temporary directory that is removed afterwards
+fn test_extract_archive<R: Read + io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("gamevault-restore-test-")
+        .tempdir()?;
+    archive.extract(temp_dir.path())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for (name, data) in files {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_verify_valid_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.zip");
+        write_zip(
+            &path,
+            &[
+                ("slot1.sav", b"save data"),
+                ("slot2.sav", b"more save data"),
+                (MANIFEST_NAME, br#"{"files": ["slot1.sav", "slot2.sav"]}"#),
+            ],
+        );
+
+        let result = verify_backup(&path, true, None);
+        assert!(result.valid, "{:?}", result.errors);
+        assert_eq!(result.file_count, 2);
+        assert_eq!(result.manifest_file_count, Some(2));
+        assert!(result.test_extracted);
+    }
+
+    #[test]
+    fn test_verify_manifest_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.zip");
+        write_zip(
+            &path,
+            &[
+                ("slot1.sav", b"save data"),
+                (MANIFEST_NAME, br#"{"file_count": 3}"#),
+            ],
+        );
+
+        let result = verify_backup(&path, true, None);
+        assert!(!result.valid);
+        assert!(!result.test_extracted);
+    }
+
+    #[test]
+    fn test_verify_corrupt_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.zip");
+        write_zip(&path, &[("slot1.sav", b"save data that will be damaged")]);
+
+        // Flip a byte inside the stored file data
+        let mut bytes = std::fs::read(&path).unwrap();
+        let pos = bytes.windows(4).position(|w| w == b"save").unwrap();
+        bytes[pos] ^= 0xFF;
+        std::fs::write(&path, bytes).unwrap();
+
+        assert!(!verify_backup(&path, false, None).valid);
+    }
+
+    #[test]
+    fn test_verify_encrypted_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.zip");
+        write_zip(&path, &[("slot1.sav", b"save data")]);
+        let encrypted = encryption::encrypt_file(&path, b"key").unwrap();
+
+        let result = verify_backup(&encrypted, true, Some(b"key"));
+        assert!(result.valid, "{:?}", result.errors);
+        assert!(result.encrypted);
+        assert_eq!(result.filename, "backup.zip.enc");
+        assert_eq!(result.file_count, 1);
+
+        assert!(!verify_backup(&encrypted, true, None).valid);
+        assert!(!verify_backup(&encrypted, true, Some(b"wrong")).valid);
+    }
+}
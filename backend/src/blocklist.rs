@@ -0,0 +1,140 @@
+//! Enrichment blocklist
+//!
+//! Some library folders (tools, benchmarks, trainers) pass the scanner but
+//! should never be matched to Steam. Blocklist entries are either a pattern,
+//! matched case-insensitively against the folder name and title with `*`
+//! (any run of characters) and `?` (one character) as wildcards, or an
+//! explicit game id. Matching games are flagged `excluded_from_enrichment`,
+//! which keeps them out of enrichment and the pending counts.
+
+use regex::Regex;
+use sqlx::SqlitePool;
+
+use crate::db;
+use crate::models::{BlocklistEntry, Game};
+
+/// Longest pattern accepted
+pub const MAX_PATTERN_LENGTH: usize = 200;
+
+/// Validate a user-supplied pattern
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    let trimmed = pattern.trim();
+    if trimmed.is_empty() {
+        return Err("Pattern must not be empty".to_string());
+    }
+    if trimmed.len() > MAX_PATTERN_LENGTH {
+        return Err(format!(
+            "Pattern must be at most {} characters",
+            MAX_PATTERN_LENGTH
+        ));
+    }
+    if trimmed.chars().all(|c| c == '*' || c == '?') {
+        return Err("Pattern would match every game".to_string());
+    }
+    Ok(())
+}
+
+/// Compile a wildcard pattern into an anchored, case-insensitive regex
+pub fn compile(pattern: &str) -> Option<Regex> {
+    let mut re = String::from("(?i)^");
+    for c in pattern.trim().chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    Regex::new(&re).ok()
+}
+
+/// Compiled blocklist
+pub struct Blocklist {
+    patterns: Vec<Regex>,
+    game_ids: Vec<i64>,
+}
+
+impl Blocklist {
+    pub fn new(entries: &[BlocklistEntry]) -> Self {
+        Blocklist {
+            patterns: entries
+                .iter()
+                .filter_map(|e| e.pattern.as_deref())
+                .filter_map(compile)
+                .collect(),
+            game_ids: entries.iter().filter_map(|e| e.game_id).collect(),
+        }
+    }
+
+    /// Whether a game must be kept out of enrichment
+    pub fn is_blocked(&self, game: &Game) -> bool {
+        self.game_ids.contains(&game.id)
+            || self
+                .patterns
+                .iter()
+                .any(|re| re.is_match(&game.folder_name) || re.is_match(&game.title))
+    }
+}
+
+/// Re-evaluate every game against the stored blocklist
+///
+/// Returns the number of excluded games.
+pub async fn apply(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let blocklist = Blocklist::new(&db::get_blocklist(pool).await?);
+    let excluded: Vec<i64> = db::get_all_games(pool)
+        .await?
+        .iter()
+        .filter(|g| blocklist.is_blocked(g))
+        .map(|g| g.id)
+        .collect();
+
+    db::set_enrichment_exclusions(pool, &excluded).await?;
+    Ok(excluded.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn entry(pattern: Option<&str>, game_id: Option<i64>) -> BlocklistEntry {
+        BlocklistEntry {
+            id: 1,
+            pattern: pattern.map(String::from),
+            game_id,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn game(id: i64, folder_name: &str) -> Game {
+        Game {
+            match_status: "pending".to_string(),
+            ..create_test_game(id, folder_name)
+        }
+    }
+
+    #[test]
+    fn test_blocklist_patterns_and_ids() {
+        let blocklist = Blocklist::new(&[
+            entry(Some("*benchmark*"), None),
+            entry(Some("Trainer v?"), None),
+            entry(None, Some(7)),
+        ]);
+
+        assert!(blocklist.is_blocked(&game(1, "3DMark Benchmark Suite")));
+        assert!(blocklist.is_blocked(&game(2, "trainer v2")));
+        assert!(!blocklist.is_blocked(&game(3, "Trainer v10")));
+        assert!(blocklist.is_blocked(&game(7, "Hades")));
+        assert!(!blocklist.is_blocked(&game(8, "Hades")));
+        // Regex characters in patterns are literal
+        assert!(!Blocklist::new(&[entry(Some("H.des"), None)]).is_blocked(&game(9, "Hades")));
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("*tool*").is_ok());
+        assert!(validate_pattern("  ").is_err());
+        assert!(validate_pattern("**").is_err());
+        assert!(validate_pattern(&"a".repeat(MAX_PATTERN_LENGTH + 1)).is_err());
+    }
+}
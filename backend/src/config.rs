@@ -3,18 +3,51 @@
 //! Loads configuration from:
 //! 1. Default values (built-in)
 //! 2. config.toml next to executable
-//! 3. Environment variables (GAMEVAULT_* prefix)
+//! 3. Environment variables (GAMEVAULT__* prefix)
+//!
+//! The settings UI writes config.toml through [`update_config_file`], which
+//! refuses the write when the file changed since the client read it (its
+//! [`config_revision`]), so two tabs can't silently undo each other.
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use config::{Config, ConfigError, File};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 /// Application configuration
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct AppConfig {
     pub paths: PathsConfig,
     pub server: ServerConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub steam: SteamConfig,
+    #[serde(default)]
+    pub media: MediaConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub i18n: I18nConfig,
+    #[serde(default)]
+    pub startup: StartupConfig,
 }
 
 /// Path configuration for data storage
@@ -28,6 +61,16 @@ pub struct PathsConfig {
     pub cache: PathBuf,
 }
 
+impl Default for PathsConfig {
+    fn default() -> Self {
+        PathsConfig {
+            game_library: PathBuf::from("."),
+            database: "sqlite:./data/gamevault.db?mode=rwc".to_string(),
+            cache: PathBuf::from("./cache"),
+        }
+    }
+}
+
 /// Server configuration
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ServerConfig {
@@ -37,17 +80,396 @@ pub struct ServerConfig {
     pub auto_open_browser: bool,
     /// Address to bind to
     pub bind_address: String,
+    /// Separate port for the admin and config routes (0 = serve them on `port`)
+    #[serde(default)]
+    pub admin_port: u16,
+    /// Address the admin port binds to
+    #[serde(default = "default_admin_bind_address")]
+    pub admin_bind_address: String,
+}
+
+fn default_admin_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            port: 3000,
+            auto_open_browser: true,
+            bind_address: "127.0.0.1".to_string(),
+            admin_port: 0,
+            admin_bind_address: default_admin_bind_address(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Address of the separate admin listener, if one is configured
+    ///
+    /// The admin port equal to the main port means no separate listener.
+    pub fn admin_address(&self) -> Option<String> {
+        (self.admin_port != 0 && self.admin_port != self.port)
+            .then(|| format!("{}:{}", self.admin_bind_address, self.admin_port))
+    }
+}
+
+/// Notification configuration
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Webhook URL that receives JSON event payloads (Discord/Slack compatible)
+    pub webhook_url: String,
+    /// Playtime digest schedule: "off", "weekly" or "monthly"
+    pub digest: DigestSchedule,
+    /// Free space (GB) on the library drive below which a disk low notification is raised (0 = off)
+    pub disk_low_gb: u64,
+    /// Post crash reports to the webhook
+    pub crash_reports: bool,
+    /// Base URL of this server for links in notifications, e.g.
+    /// "https://gamevault.lan" (empty = http://localhost:<port>)
+    pub public_url: String,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            webhook_url: String::new(),
+            digest: DigestSchedule::default(),
+            disk_low_gb: 10,
+            crash_reports: false,
+            public_url: String::new(),
+        }
+    }
+}
+
+/// Steam Web API credentials (optional, for account-specific data)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct SteamConfig {
+    /// Steam Web API key (https://steamcommunity.com/dev/apikey)
+    pub api_key: String,
+    /// SteamID64 of the account to read subscriptions from
+    pub steam_id: String,
+}
+
+impl SteamConfig {
+    /// Whether both the API key and SteamID are set
+    pub fn is_configured(&self) -> bool {
+        !self.api_key.trim().is_empty() && !self.steam_id.trim().is_empty()
+    }
+}
+
+/// Screenshot and clip indexing, and cached artwork
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct MediaConfig {
+    /// Shared screenshots/captures directory (empty = only per-game `.gamevault/media/`)
+    pub screenshots_dir: PathBuf,
+    /// Re-encode covers/backgrounds cached in `.gamevault/` (false = keep originals)
+    pub optimize_images: bool,
+    /// JPEG quality (1-100) for optimized images
+    pub image_quality: u8,
+    /// Longest edge in pixels for optimized images (0 = keep dimensions)
+    pub image_max_dimension: u32,
+    /// Wishlist covers downloaded per hour at most (0 = don't prefetch)
+    pub prefetch_per_hour: u32,
+    /// Games whose images are downloaded at the same time
+    pub download_workers: u32,
+    /// Download limit for all image downloads together in KB/s (0 = unlimited)
+    pub download_kbps: u32,
+}
+
+impl Default for MediaConfig {
+    fn default() -> Self {
+        MediaConfig {
+            screenshots_dir: PathBuf::new(),
+            optimize_images: false,
+            image_quality: 85,
+            image_max_dimension: 1920,
+            prefetch_per_hour: 60,
+            download_workers: 4,
+            download_kbps: 0,
+        }
+    }
+}
+
+/// Cold storage for archived games
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Directory that receives game archives (another drive or NAS share)
+    pub target: PathBuf,
+}
+
+/// Artwork and metadata export for external launcher frontends
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Directory that receives exported covers and backgrounds
+    pub artwork_target: PathBuf,
+    /// Directory layout written by default
+    pub artwork_layout: ArtworkLayout,
+    /// Directory for `metadata.pegasus.txt` / `gamelist.xml` (empty: the artwork target)
+    pub metadata_target: PathBuf,
+}
+
+/// Media layout of a launcher frontend
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtworkLayout {
+    /// EmulationStation / ES-DE: `covers/<name>.jpg`, `fanart/<name>.jpg`
+    #[default]
+    Esde,
+    /// Pegasus: `<name>/boxFront.jpg`, `<name>/background.jpg`
+    Pegasus,
+}
+
+/// Storage tiers of library locations, for the storage statistics
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Tier of the drive holding the game library
+    pub library_tier: StorageTier,
+    /// Tiers of folders in the library that live on other drives (mounts,
+    /// junctions), e.g. `{ "D:\\Games\\NAS" = "nas" }`
+    pub tiers: HashMap<String, StorageTier>,
+    /// Tier of the archive target (archived games)
+    pub archive_tier: StorageTier,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            library_tier: StorageTier::Unknown,
+            tiers: HashMap::new(),
+            archive_tier: StorageTier::Archive,
+        }
+    }
+}
+
+/// Kind of storage a game lives on, fastest first
+#[derive(
+    Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageTier {
+    Ssd,
+    Hdd,
+    Nas,
+    /// Cold archive
+    Archive,
+    /// Not tagged
+    #[default]
+    Unknown,
+}
+
+/// Save backup encryption (set one of the two to enable)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Passphrase for encrypting backups
+    pub encryption_passphrase: String,
+    /// File whose contents are used as the key (takes precedence over the passphrase)
+    pub encryption_keyfile: PathBuf,
+}
+
+/// HTTP security headers added to every response (empty value = header not sent)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SecurityConfig {
+    /// Content-Security-Policy header
+    pub content_security_policy: String,
+    /// X-Frame-Options header ("DENY" or "SAMEORIGIN")
+    pub frame_options: String,
+    /// Referrer-Policy header
+    pub referrer_policy: String,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        SecurityConfig {
+            // The Next.js static export relies on inline scripts and styles;
+            // cover art may come straight from the Steam CDN
+            content_security_policy: "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+                                      style-src 'self' 'unsafe-inline'; img-src 'self' data: https:; \
+                                      font-src 'self' data:; connect-src 'self'; object-src 'none'; \
+                                      base-uri 'self'; form-action 'self'; frame-ancestors 'self'"
+                .to_string(),
+            frame_options: "SAMEORIGIN".to_string(),
+            referrer_policy: "same-origin".to_string(),
+        }
+    }
+}
+
+/// Identity for outbound requests to external APIs
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// User-Agent sent with every outbound request
+    pub user_agent: String,
+    /// Extra headers per host, e.g. `{ "api.example.com" = { "Client-ID" = "..." } }`
+    pub headers: HashMap<String, HashMap<String, String>>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            user_agent: concat!("GameVault/", env!("CARGO_PKG_VERSION")).to_string(),
+            headers: HashMap::new(),
+        }
+    }
+}
+
+/// API paging limits
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// Page size used when a client doesn't ask for one
+    pub default_page_size: u32,
+    /// Largest page size a client may request
+    pub max_page_size: u32,
+    /// Hard cap on the number of search results
+    pub max_search_results: u32,
+    /// Most games returned by a game list request without paging parameters
+    pub max_unpaged_games: u32,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            default_page_size: 100,
+            max_page_size: 500,
+            max_search_results: 50,
+            max_unpaged_games: 5000,
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Resolve requested paging into (page, page_size), clamped to the configured limits
+    ///
+    /// Pages are 1-based.
+    pub fn page_bounds(&self, page: Option<u32>, page_size: Option<u32>) -> (u32, u32) {
+        let max = self.max_page_size.max(1);
+        let page_size = page_size.unwrap_or(self.default_page_size).clamp(1, max);
+        (page.unwrap_or(1).max(1), page_size)
+    }
+
+    /// Search result limit, never zero
+    pub fn search_limit(&self) -> u32 {
+        self.max_search_results.max(1)
+    }
+
+    /// Unpaged game list limit, never zero
+    pub fn unpaged_limit(&self) -> u32 {
+        self.max_unpaged_games.max(1)
+    }
+}
+
+/// Authentication a route group requires
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthLevel {
+    /// Open to everyone
+    None,
+    /// API_KEY (or the admin key, a paired device or a UI session) when API_KEY is set
+    ApiKey,
+    /// ADMIN_API_KEY; the routes are disabled when it isn't set
+    Admin,
+}
+
+/// Groups of API routes that share an auth level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    Read,
+    Write,
+    Admin,
+    Config,
+}
+
+/// Language of translated server values (see i18n.rs)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// Language code ("de", "fr", ...); empty to follow the Accept-Language header
+    pub language: String,
+}
+
+/// Required authentication per route group
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Read-only endpoints (GET /games, /stats, ...)
+    pub read: AuthLevel,
+    /// Endpoints that change the library (scan, edit, launch, ...)
+    pub write: AuthLevel,
+    /// Destructive endpoints (deleting game files, archiving)
+    pub admin: AuthLevel,
+    /// Configuration, shutdown and restart
+    pub config: AuthLevel,
+    /// Lifetime of the web UI's session tokens in minutes (0 = don't issue any)
+    pub session_token_mins: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            read: AuthLevel::None,
+            write: AuthLevel::ApiKey,
+            admin: AuthLevel::Admin,
+            config: AuthLevel::None,
+            session_token_mins: 60,
+        }
+    }
+}
+
+impl AuthConfig {
+    pub fn level(&self, group: RouteGroup) -> AuthLevel {
+        match group {
+            RouteGroup::Read => self.read,
+            RouteGroup::Write => self.write,
+            RouteGroup::Admin => self.admin,
+            RouteGroup::Config => self.config,
+        }
+    }
+}
+
+/// Tasks run in the background each time the server starts
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct StartupConfig {
+    /// Scan the library for new and changed folders (no online enrichment)
+    pub scan: bool,
+    /// Import metadata from `.gamevault/metadata.json` files
+    pub import_metadata: bool,
+    /// Re-download covers and backgrounds whose cached files are missing
+    pub repair_images: bool,
+}
+
+/// How often the playtime digest is sent
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestSchedule {
+    #[default]
+    Off,
+    Weekly,
+    Monthly,
 }
 
 impl AppConfig {
     /// Load configuration from file and environment
     pub fn load() -> Result<Self, ConfigError> {
-        let exe_dir = get_exe_directory();
-        let config_path = exe_dir.join("config.toml");
+        let config_path = get_config_path();
 
         tracing::info!("Looking for config at: {:?}", config_path);
 
-        let config = Config::builder()
+        Self::load_from(&config_path, true)
+    }
+
+    /// Load defaults and a config file, with environment overrides if `env`
+    fn load_from(config_path: &Path, env: bool) -> Result<Self, ConfigError> {
+        let mut builder = Config::builder()
             // Default values
             .set_default("paths.game_library", ".")?
             .set_default("paths.database", "sqlite:./data/gamevault.db?mode=rwc")?
@@ -55,17 +477,20 @@ impl AppConfig {
             .set_default("server.port", 3000)?
             .set_default("server.auto_open_browser", true)?
             .set_default("server.bind_address", "127.0.0.1")?
+            .set_default("server.admin_port", 0)?
+            .set_default("server.admin_bind_address", "127.0.0.1")?
             // Load from config file if it exists
-            .add_source(File::from(config_path).required(false))
-            // Environment variable overrides (GAMEVAULT_PATHS__GAME_LIBRARY, etc.)
-            .add_source(
-                config::Environment::with_prefix("GAMEVAULT")
+            .add_source(File::from(config_path.to_path_buf()).required(false));
+        if env {
+            // Environment variable overrides (GAMEVAULT__PATHS__GAME_LIBRARY, etc.)
+            builder = builder.add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
                     .separator("__")
                     .try_parsing(true),
-            )
-            .build()?;
+            );
+        }
 
-        config.try_deserialize()
+        builder.build()?.try_deserialize()
     }
 
     /// Get the database URL, resolving relative paths
@@ -91,6 +516,67 @@ impl AppConfig {
     pub fn cache_path(&self) -> PathBuf {
         resolve_path(&self.paths.cache.to_string_lossy())
     }
+
+    /// Get the archive target directory, if configured
+    pub fn archive_target(&self) -> Option<PathBuf> {
+        let dir = self.archive.target.to_string_lossy();
+        if dir.trim().is_empty() {
+            None
+        } else {
+            Some(resolve_path(&dir))
+        }
+    }
+
+    /// Get the artwork export directory, if configured
+    pub fn artwork_export_target(&self) -> Option<PathBuf> {
+        let dir = self.export.artwork_target.to_string_lossy();
+        if dir.trim().is_empty() {
+            None
+        } else {
+            Some(resolve_path(&dir))
+        }
+    }
+
+    /// Get the directory for exported frontend metadata files
+    pub fn export_metadata_dir(&self) -> Option<PathBuf> {
+        let dir = self.export.metadata_target.to_string_lossy();
+        if dir.trim().is_empty() {
+            self.artwork_export_target()
+        } else {
+            Some(resolve_path(&dir))
+        }
+    }
+
+    /// Get the backup encryption secret (keyfile contents or passphrase), if configured
+    pub fn backup_secret(&self) -> Result<Option<Vec<u8>>, String> {
+        let keyfile = self.backup.encryption_keyfile.to_string_lossy();
+        if !keyfile.trim().is_empty() {
+            let path = resolve_path(&keyfile);
+            let key = std::fs::read(&path)
+                .map_err(|e| format!("Failed to read backup keyfile {}: {}", path.display(), e))?;
+            if key.is_empty() {
+                return Err(format!("Backup keyfile {} is empty", path.display()));
+            }
+            return Ok(Some(key));
+        }
+
+        let passphrase = &self.backup.encryption_passphrase;
+        if passphrase.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(passphrase.as_bytes().to_vec()))
+        }
+    }
+
+    /// Get the shared screenshots directory, if configured
+    pub fn screenshots_path(&self) -> Option<PathBuf> {
+        let dir = self.media.screenshots_dir.to_string_lossy();
+        if dir.trim().is_empty() {
+            None
+        } else {
+            Some(resolve_path(&dir))
+        }
+    }
 }
 
 /// Get the directory containing the executable
@@ -147,10 +633,9 @@ pub fn get_config_path() -> PathBuf {
     get_exe_directory().join("config.toml")
 }
 
-/// Write configuration to config.toml atomically
-pub fn write_config(config: &AppConfig) -> anyhow::Result<()> {
-    let config_path = get_config_path();
-    let temp_path = get_exe_directory().join("config.toml.tmp");
+/// Write configuration to a config file atomically
+fn write_config(config_path: &Path, config: &AppConfig) -> anyhow::Result<()> {
+    let temp_path = config_path.with_extension("toml.tmp");
 
     // Serialize to TOML
     let toml_string = toml::to_string_pretty(config)?;
@@ -159,12 +644,98 @@ pub fn write_config(config: &AppConfig) -> anyhow::Result<()> {
     std::fs::write(&temp_path, &toml_string)?;
 
     // Atomic rename
-    std::fs::rename(&temp_path, &config_path)?;
+    std::fs::rename(&temp_path, config_path)?;
 
     tracing::info!("Configuration saved to {:?}", config_path);
     Ok(())
 }
 
+/// Prefix of environment variables overriding config.toml
+const ENV_PREFIX: &str = "GAMEVAULT";
+
+/// Older environment variables read on startup, and the setting each
+/// overrides
+const LEGACY_ENV: &[(&str, &str)] = &[
+    ("DATABASE_URL", "paths.database"),
+    ("GAMES_PATH", "paths.game_library"),
+    ("PORT", "server.port"),
+    ("HOST", "server.bind_address"),
+];
+
+/// A setting taken from an environment variable rather than config.toml
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvOverride {
+    /// Setting, e.g. "server.port"
+    pub key: String,
+    pub variable: String,
+}
+
+/// Settings overridden by the environment, ordered by key
+///
+/// Values aren't included: some (passphrases, keys) are secrets.
+pub fn env_overrides() -> Vec<EnvOverride> {
+    env_overrides_from(std::env::vars().map(|(variable, _)| variable))
+}
+
+fn env_overrides_from(variables: impl IntoIterator<Item = String>) -> Vec<EnvOverride> {
+    let prefix = format!("{}__", ENV_PREFIX.to_lowercase());
+    let mut overrides: Vec<EnvOverride> = variables
+        .into_iter()
+        .filter_map(|variable| {
+            let lower = variable.to_lowercase();
+            let key = match lower.strip_prefix(&prefix) {
+                Some(key) => key.replace("__", "."),
+                None => LEGACY_ENV
+                    .iter()
+                    .find(|(legacy, _)| *legacy == variable)?
+                    .1
+                    .to_string(),
+            };
+            Some(EnvOverride { key, variable })
+        })
+        .collect();
+    overrides.sort_by(|a, b| a.key.cmp(&b.key).then(a.variable.cmp(&b.variable)));
+    overrides
+}
+
+/// Revision of a config file: a hash of its contents, so it changes with
+/// every write (a missing file has the revision of an empty one)
+pub fn config_revision(config_path: &Path) -> String {
+    let contents = std::fs::read(config_path).unwrap_or_default();
+    let hash = format!("{:x}", Sha256::digest(&contents));
+    hash[..16].to_string()
+}
+
+/// Serializes reading, checking and writing config.toml within the process
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Why config.toml wasn't written
+#[derive(Debug)]
+pub enum ConfigWriteError {
+    /// The file changed since the client read it
+    Stale,
+    Io(anyhow::Error),
+}
+
+/// Change a config file unless it was written since `revision` was read
+///
+/// `update` gets the file's settings without environment overrides, so they
+/// aren't copied into the file. Without a revision the write isn't checked.
+/// Returns the new revision.
+pub fn update_config_file(
+    config_path: &Path,
+    revision: Option<&str>,
+    update: impl FnOnce(AppConfig) -> AppConfig,
+) -> Result<String, ConfigWriteError> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if revision.is_some_and(|r| r != config_revision(config_path)) {
+        return Err(ConfigWriteError::Stale);
+    }
+    let current = AppConfig::load_from(config_path, false).unwrap_or_default();
+    write_config(config_path, &update(current)).map_err(ConfigWriteError::Io)?;
+    Ok(config_revision(config_path))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,6 +766,49 @@ mod tests {
         assert!(config.server.auto_open_browser);
     }
 
+    #[test]
+    fn test_auth_policy() {
+        let config: AppConfig = toml::from_str(
+            r#"
+[paths]
+game_library = ""
+database = "sqlite::memory:"
+cache = "./cache"
+
+[server]
+port = 3000
+auto_open_browser = false
+bind_address = "0.0.0.0"
+
+[auth]
+read = "api_key"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.auth.level(RouteGroup::Read), AuthLevel::ApiKey);
+        // Groups left out keep their defaults
+        assert_eq!(config.auth.level(RouteGroup::Write), AuthLevel::ApiKey);
+        assert_eq!(config.auth.level(RouteGroup::Admin), AuthLevel::Admin);
+        assert_eq!(config.auth.level(RouteGroup::Config), AuthLevel::None);
+        assert_eq!(config.auth.session_token_mins, 60);
+        // No separate admin listener unless configured
+        assert_eq!(config.server.admin_address(), None);
+    }
+
+    #[test]
+    fn test_admin_address() {
+        let mut server = ServerConfig {
+            bind_address: "0.0.0.0".to_string(),
+            admin_port: 3001,
+            ..Default::default()
+        };
+        assert_eq!(server.admin_address().as_deref(), Some("127.0.0.1:3001"));
+
+        server.admin_port = server.port;
+        assert_eq!(server.admin_address(), None);
+    }
+
     #[test]
     fn test_resolve_absolute_path() {
         let path = if cfg!(windows) {
@@ -235,7 +849,9 @@ mod tests {
                 port: 8080,
                 auto_open_browser: false,
                 bind_address: "0.0.0.0".to_string(),
+                ..Default::default()
             },
+            ..Default::default()
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -281,4 +897,102 @@ bind_address = "127.0.0.1"
         let config: Result<AppConfig, _> = toml::from_str(toml_str);
         assert!(config.is_ok());
     }
+
+    #[test]
+    fn test_api_page_bounds() {
+        let api = ApiConfig::default();
+
+        assert_eq!(api.page_bounds(None, None), (1, 100));
+        assert_eq!(api.page_bounds(Some(3), Some(20)), (3, 20));
+        // Oversized and zero values are clamped
+        assert_eq!(api.page_bounds(Some(0), Some(100_000)), (1, 500));
+        assert_eq!(api.page_bounds(None, Some(0)), (1, 1));
+    }
+
+    #[test]
+    fn test_notifications_section() {
+        let toml_str = r#"
+[paths]
+game_library = ""
+database = "sqlite:./data/test.db?mode=rwc"
+cache = "./cache"
+
+[server]
+port = 3000
+auto_open_browser = true
+bind_address = "127.0.0.1"
+
+[notifications]
+webhook_url = "https://example.com/hook"
+digest = "weekly"
+"#;
+
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.notifications.webhook_url, "https://example.com/hook");
+        assert_eq!(config.notifications.digest, DigestSchedule::Weekly);
+
+        // Missing section falls back to defaults
+        let config = AppConfig::load().unwrap();
+        assert_eq!(config.notifications.digest, DigestSchedule::Off);
+    }
+
+    #[test]
+    fn test_update_config_file_rejects_stale_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let read = config_revision(&path);
+
+        // The first tab saves; a second one still holding `read` is refused
+        let saved = update_config_file(&path, Some(&read), |mut config| {
+            config.server.port = 8080;
+            config
+        })
+        .unwrap();
+        assert_ne!(saved, read);
+        assert_eq!(saved, config_revision(&path));
+        let stale = update_config_file(&path, Some(&read), |mut config| {
+            config.server.port = 9090;
+            config
+        });
+        assert!(matches!(stale, Err(ConfigWriteError::Stale)));
+
+        // Settings not being changed are kept
+        update_config_file(&path, Some(&saved), |mut config| {
+            config.server.auto_open_browser = false;
+            config
+        })
+        .unwrap();
+        let config = AppConfig::load_from(&path, false).unwrap();
+        assert_eq!(config.server.port, 8080);
+        assert!(!config.server.auto_open_browser);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let overrides = env_overrides_from(
+            [
+                "GAMEVAULT__SERVER__PORT",
+                "PORT",
+                "gamevault__backup__encryption_passphrase",
+                "GAMEVAULT_VERSION",
+                "PATH",
+            ]
+            .map(String::from),
+        );
+        let keys: Vec<_> = overrides
+            .iter()
+            .map(|o| (o.key.as_str(), o.variable.as_str()))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                (
+                    "backup.encryption_passphrase",
+                    "gamevault__backup__encryption_passphrase"
+                ),
+                ("server.port", "GAMEVAULT__SERVER__PORT"),
+                ("server.port", "PORT"),
+            ]
+        );
+    }
 }
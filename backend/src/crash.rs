@@ -0,0 +1,394 @@
+//! Crash reports
+//!
+//! A panic hook writes a crash report to `logs/crash-<timestamp>.json` next
+//! to the executable: the panic message and location, the thread, the
+//! version, a backtrace and the most recent log lines, which are kept in
+//! memory for that purpose. Release builds abort on panic, so the latest
+//! report is loaded again on the next start and shown by
+//! `/api/health/details`. With `[notifications] crash_reports`, each report
+//! is posted to the webhook once: right away when the process survives the
+//! panic (a background task or the tray thread in a debug build), otherwise
+//! after the restart.
+
+use std::collections::VecDeque;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{Level, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::config::NotificationsConfig;
+use crate::http::HttpClient;
+use crate::metadata_sync::MetadataSync;
+use crate::notifications::{self, WebhookEvent};
+use crate::timestamps;
+
+/// Log lines kept in memory (and copied into crash reports)
+pub const LOG_BUFFER_LINES: usize = 200;
+
+/// Error lines kept for `/api/health/details`
+pub const RECENT_ERROR_LINES: usize = 20;
+
+const REPORT_PREFIX: &str = "crash-";
+
+pub const EVENT_CRASH: &str = "crash";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// When the panic happened (UTC)
+    pub occurred_at: String,
+    pub version: String,
+    /// Operating system and architecture
+    pub platform: String,
+    pub thread: String,
+    pub message: String,
+    /// Source file, line and column of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+    /// Log lines leading up to the panic, oldest first
+    pub recent_logs: Vec<String>,
+    /// Posted to the webhook already
+    #[serde(default)]
+    pub reported: bool,
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+/// Server health beyond "OK"
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthDetails {
+    pub version: String,
+    pub platform: String,
+    /// When this process started (UTC)
+    pub started_at: Option<String>,
+    pub uptime_secs: u64,
+    /// Latest crash report, from this run or an earlier one
+    pub last_crash: Option<CrashReport>,
+    /// Most recent error log lines, oldest first
+    pub recent_errors: Vec<String>,
+    /// Whether the web frontend was built into this binary
+    pub frontend_embedded: bool,
+    /// metadata.json files behind the database (filled in by the handler)
+    pub metadata_sync: MetadataSync,
+}
+
+/// In-memory ring buffers of formatted log lines
+struct LogBuffer {
+    lines: Mutex<VecDeque<String>>,
+    errors: Mutex<VecDeque<String>>,
+}
+
+static LOGS: LogBuffer = LogBuffer {
+    lines: Mutex::new(VecDeque::new()),
+    errors: Mutex::new(VecDeque::new()),
+};
+
+static LAST_CRASH: Mutex<Option<CrashReport>> = Mutex::new(None);
+static STARTED: OnceLock<(Instant, String)> = OnceLock::new();
+static REPORTS: OnceLock<mpsc::UnboundedSender<CrashReport>> = OnceLock::new();
+
+fn push_line(buffer: &Mutex<VecDeque<String>>, line: String, capacity: usize) {
+    // try_lock: a panic while the buffer is locked must not deadlock the hook
+    if let Ok(mut lines) = buffer.try_lock() {
+        if lines.len() >= capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+fn snapshot(buffer: &Mutex<VecDeque<String>>) -> Vec<String> {
+    buffer
+        .try_lock()
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// `tracing_subscriber` writer that keeps the latest log lines in memory
+pub struct LogCapture;
+
+pub struct LogCaptureWriter {
+    is_error: bool,
+}
+
+impl io::Write for LogCaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            push_line(&LOGS.lines, line.to_string(), LOG_BUFFER_LINES);
+            if self.is_error {
+                push_line(&LOGS.errors, line.to_string(), RECENT_ERROR_LINES);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogCapture {
+    type Writer = LogCaptureWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogCaptureWriter { is_error: false }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        LogCaptureWriter {
+            is_error: *meta.level() == Level::ERROR,
+        }
+    }
+}
+
+fn platform() -> String {
+    format!("{} {}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+impl CrashReport {
+    fn from_panic(info: &PanicHookInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        CrashReport {
+            occurred_at: timestamps::now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: platform(),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("<unnamed>")
+                .to_string(),
+            message,
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            recent_logs: snapshot(&LOGS.lines),
+            reported: false,
+            path: None,
+        }
+    }
+
+    /// Title and first lines for the webhook
+    fn webhook_event(&self) -> WebhookEvent {
+        let text = format!(
+            "GameVault {} panicked in thread '{}' at {}: {}",
+            self.version,
+            self.thread,
+            self.location.as_deref().unwrap_or("unknown location"),
+            self.message
+        );
+        WebhookEvent::new(EVENT_CRASH, "GameVault crashed", text).with_data(self)
+    }
+}
+
+/// Write a report as `crash-<timestamp>.json` in `dir`
+fn write_report(dir: &Path, report: &mut CrashReport) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S%.3f");
+    let path = dir.join(format!("{}{}.json", REPORT_PREFIX, stamp));
+    let json = serde_json::to_string_pretty(report).map_err(io::Error::other)?;
+    std::fs::write(&path, json)?;
+    report.path = Some(path.clone());
+    Ok(path)
+}
+
+/// The newest report in `dir`, if any
+fn load_latest(dir: &Path) -> Option<CrashReport> {
+    let mut reports: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension().is_some_and(|ext| ext == "json")
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(REPORT_PREFIX))
+        })
+        .collect();
+    reports.sort();
+    let path = reports.pop()?;
+
+    match std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str::<CrashReport>(&json).map_err(|e| e.to_string()))
+    {
+        Ok(mut report) => {
+            report.path = Some(path);
+            Some(report)
+        }
+        Err(e) => {
+            tracing::warn!("Unreadable crash report {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Mark a report as posted, in memory and on disk
+fn mark_reported(report: &mut CrashReport) {
+    report.reported = true;
+    if let Some(path) = &report.path {
+        let written = serde_json::to_string_pretty(report)
+            .map_err(io::Error::other)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(e) = written {
+            tracing::warn!("Failed to update crash report {}: {}", path.display(), e);
+        }
+    }
+    if let Ok(mut last) = LAST_CRASH.lock() {
+        if last.as_ref().map(|l| &l.occurred_at) == Some(&report.occurred_at) {
+            *last = Some(report.clone());
+        }
+    }
+}
+
+/// Install the panic hook, writing reports to `logs_dir`
+///
+/// Also loads the latest report from an earlier run.
+pub fn init(logs_dir: PathBuf) {
+    STARTED.get_or_init(|| (Instant::now(), timestamps::now()));
+
+    if let Some(report) = load_latest(&logs_dir) {
+        tracing::warn!(
+            "Last crash at {} (UTC): {}",
+            report.occurred_at,
+            report.message
+        );
+        if let Ok(mut last) = LAST_CRASH.lock() {
+            *last = Some(report);
+        }
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let mut report = CrashReport::from_panic(info);
+        // Not tracing: the log buffer may be what panicked
+        match write_report(&logs_dir, &mut report) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        if let Some(reports) = REPORTS.get() {
+            let _ = reports.send(report.clone());
+        }
+        if let Ok(mut last) = LAST_CRASH.try_lock() {
+            *last = Some(report);
+        }
+        previous(info);
+    }));
+}
+
+/// Post crash reports to the webhook when `[notifications] crash_reports` is on
+///
+/// Starts with an unreported crash from an earlier run.
+pub fn spawn_reporter(client: HttpClient, config: NotificationsConfig) {
+    if !config.crash_reports || config.webhook_url.is_empty() {
+        return;
+    }
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    if REPORTS.set(sender.clone()).is_err() {
+        return;
+    }
+    let pending = LAST_CRASH
+        .lock()
+        .ok()
+        .and_then(|last| last.clone())
+        .filter(|r| !r.reported);
+    if let Some(report) = pending {
+        let _ = sender.send(report);
+    }
+
+    tokio::spawn(async move {
+        while let Some(mut report) = receiver.recv().await {
+            match notifications::send_webhook(&client, &config.webhook_url, &report.webhook_event())
+                .await
+            {
+                Ok(()) => mark_reported(&mut report),
+                Err(e) => tracing::warn!("Failed to post crash report: {}", e),
+            }
+        }
+    });
+}
+
+/// The most recent log lines, oldest first
+pub fn recent_logs(count: usize) -> Vec<String> {
+    let mut lines = snapshot(&LOGS.lines);
+    lines.drain(..lines.len().saturating_sub(count));
+    lines
+}
+
+/// Version, uptime, the last crash and recent errors
+pub fn health_details() -> HealthDetails {
+    HealthDetails {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        platform: platform(),
+        started_at: STARTED.get().map(|(_, at)| at.clone()),
+        uptime_secs: STARTED
+            .get()
+            .map(|(start, _)| start.elapsed().as_secs())
+            .unwrap_or(0),
+        last_crash: LAST_CRASH.lock().ok().and_then(|last| last.clone()),
+        recent_errors: snapshot(&LOGS.errors),
+        frontend_embedded: crate::embedded::frontend_embedded(),
+        metadata_sync: MetadataSync::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(occurred_at: &str) -> CrashReport {
+        CrashReport {
+            occurred_at: occurred_at.to_string(),
+            version: "0.1.0".to_string(),
+            platform: platform(),
+            thread: "tray".to_string(),
+            message: "index out of bounds".to_string(),
+            location: Some("src/tray.rs:10:5".to_string()),
+            backtrace: String::new(),
+            recent_logs: vec!["INFO starting".to_string()],
+            reported: false,
+            path: None,
+        }
+    }
+
+    #[test]
+    fn test_crash_reports_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_latest(dir.path()).is_none());
+
+        write_report(dir.path(), &mut report("2024-05-10T20:00:00Z")).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let mut latest = report("2024-05-10T21:00:00Z");
+        write_report(dir.path(), &mut latest).unwrap();
+        std::fs::write(dir.path().join("server.log"), "not a report").unwrap();
+
+        let loaded = load_latest(dir.path()).unwrap();
+        assert_eq!(loaded.occurred_at, "2024-05-10T21:00:00Z");
+        assert_eq!(loaded.recent_logs, ["INFO starting"]);
+        assert!(!loaded.reported);
+
+        mark_reported(&mut latest);
+        assert!(load_latest(dir.path()).unwrap().reported);
+    }
+
+    #[test]
+    fn test_log_buffer_keeps_latest_lines() {
+        let buffer = Mutex::new(VecDeque::new());
+        for i in 0..5 {
+            push_line(&buffer, format!("line {}", i), 3);
+        }
+        assert_eq!(snapshot(&buffer), ["line 2", "line 3", "line 4"]);
+    }
+}
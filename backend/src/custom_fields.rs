@@ -0,0 +1,335 @@
+//! User-defined per-game fields ("box location", "purchased price", ...)
+//!
+//! Fields are declared once for the library with a key, a label and a type
+//! (GET/POST /api/custom-fields). Each game's values are stored as one JSON
+//! object in `games.custom_fields`, keyed by field key, so they travel with
+//! the rest of the game's metadata: they're written to metadata.json, read
+//! back by the metadata import, and snapshotted for undo like any column.
+//!
+//! Values are checked against the field's type when set. The game list can
+//! be filtered on a field being set, or on its value.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::models::CustomField;
+
+/// Longest key accepted
+const MAX_KEY_LENGTH: usize = 40;
+/// Longest label accepted
+const MAX_LABEL_LENGTH: usize = 100;
+/// Longest text value accepted
+const MAX_TEXT_LENGTH: usize = 1000;
+
+/// Type of a field's values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    Text,
+    Number,
+    Boolean,
+    /// YYYY-MM-DD
+    Date,
+}
+
+impl FieldType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FieldType::Text => "text",
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+            FieldType::Date => "date",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "text" => Some(FieldType::Text),
+            "number" => Some(FieldType::Number),
+            "boolean" => Some(FieldType::Boolean),
+            "date" => Some(FieldType::Date),
+            _ => None,
+        }
+    }
+
+    /// A value as stored, or why it doesn't fit the type
+    pub fn check(self, value: Value) -> Result<Value, &'static str> {
+        match (self, value) {
+            (FieldType::Text, Value::String(s)) => {
+                let s = s.trim();
+                if s.is_empty() || s.len() > MAX_TEXT_LENGTH {
+                    Err("must be 1-1000 characters")
+                } else {
+                    Ok(Value::String(s.to_string()))
+                }
+            }
+            (FieldType::Number, Value::Number(n)) => Ok(Value::Number(n)),
+            (FieldType::Boolean, Value::Bool(b)) => Ok(Value::Bool(b)),
+            (FieldType::Date, Value::String(s)) => NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+                .map(|d| Value::String(d.to_string()))
+                .map_err(|_| "must be a date (YYYY-MM-DD)"),
+            (FieldType::Text, _) => Err("must be a string"),
+            (FieldType::Number, _) => Err("must be a number"),
+            (FieldType::Boolean, _) => Err("must be true or false"),
+            (FieldType::Date, _) => Err("must be a date (YYYY-MM-DD)"),
+        }
+    }
+
+    /// A filter value from the query string as a JSON literal, for comparing
+    /// with stored values in SQL
+    pub fn filter_literal(self, value: &str) -> Option<String> {
+        let value = match self {
+            FieldType::Text => Value::String(value.trim().to_string()),
+            FieldType::Number => serde_json::from_str::<serde_json::Number>(value.trim())
+                .ok()
+                .map(Value::Number)?,
+            FieldType::Boolean => Value::Bool(value.trim().parse().ok()?),
+            FieldType::Date => self.check(Value::String(value.to_string())).ok()?,
+        };
+        Some(value.to_string())
+    }
+}
+
+/// A field to declare (POST /custom-fields)
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewField {
+    pub key: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+}
+
+impl NewField {
+    /// Trimmed copy, or why the field can't be declared
+    pub fn validate(&self) -> Result<NewField, &'static str> {
+        let key = self.key.trim();
+        let key_ok = !key.is_empty()
+            && key.len() <= MAX_KEY_LENGTH
+            && key
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !key_ok {
+            return Err("Key must be 1-40 lowercase letters, digits or underscores");
+        }
+        let label = self.label.trim();
+        if label.is_empty() || label.len() > MAX_LABEL_LENGTH {
+            return Err("Label must be 1-100 characters");
+        }
+        Ok(NewField {
+            key: key.to_string(),
+            label: label.to_string(),
+            field_type: self.field_type,
+        })
+    }
+}
+
+/// JSON path of a field in `games.custom_fields`
+pub fn json_path(key: &str) -> String {
+    format!("$.\"{}\"", key)
+}
+
+/// Apply updates to a game's stored values (null removes a value)
+///
+/// Returns the new `custom_fields` column value (None once no value is
+/// left), or a message naming the first field that doesn't fit.
+pub fn merge(
+    current: Option<&str>,
+    fields: &[CustomField],
+    updates: Map<String, Value>,
+) -> Result<Option<String>, String> {
+    let types: HashMap<&str, Option<FieldType>> = fields
+        .iter()
+        .map(|f| (f.key.as_str(), FieldType::parse(&f.field_type)))
+        .collect();
+    let mut values: Map<String, Value> = current
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default();
+
+    for (key, value) in updates {
+        let Some(field_type) = types.get(key.as_str()) else {
+            return Err(format!("Unknown custom field: {}", key));
+        };
+        if value.is_null() {
+            values.remove(&key);
+            continue;
+        }
+        let Some(field_type) = field_type else {
+            return Err(format!("Custom field {} has an unknown type", key));
+        };
+        let value = field_type
+            .check(value)
+            .map_err(|reason| format!("{} {}", key, reason))?;
+        values.insert(key, value);
+    }
+
+    Ok((!values.is_empty()).then(|| Value::Object(values).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_and_filter_literal() {
+        assert_eq!(
+            FieldType::Text.check(json!(" Shelf B ")),
+            Ok(json!("Shelf B"))
+        );
+        assert!(FieldType::Text.check(json!(3)).is_err());
+        assert_eq!(FieldType::Number.check(json!(12.5)), Ok(json!(12.5)));
+        assert!(FieldType::Number.check(json!("12.5")).is_err());
+        assert!(FieldType::Boolean.check(json!(1)).is_err());
+        assert_eq!(
+            FieldType::Date.check(json!("2023-11-24")),
+            Ok(json!("2023-11-24"))
+        );
+        assert!(FieldType::Date.check(json!("24.11.2023")).is_err());
+
+        assert_eq!(
+            FieldType::Text.filter_literal("Shelf B").as_deref(),
+            Some("\"Shelf B\"")
+        );
+        assert_eq!(
+            FieldType::Number.filter_literal("12").as_deref(),
+            Some("12")
+        );
+        assert_eq!(FieldType::Number.filter_literal("cheap"), None);
+        assert_eq!(
+            FieldType::Boolean.filter_literal("true").as_deref(),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn test_validate_key() {
+        let field = |key: &str| NewField {
+            key: key.into(),
+            label: "Box location".into(),
+            field_type: FieldType::Text,
+        };
+        assert_eq!(
+            field(" box_location ").validate().unwrap().key,
+            "box_location"
+        );
+        assert!(field("Box Location").validate().is_err());
+        assert!(field("price\"").validate().is_err());
+        assert!(field("").validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_and_filter_values() {
+        use crate::db;
+        use crate::models::GameFilter;
+
+        let (pool, _keepalive) = db::test_pool().await;
+        for (key, label, field_type) in [
+            ("box_location", "Box location", "text"),
+            ("purchased_price", "Purchased price", "number"),
+            ("signed", "Signed copy", "boolean"),
+        ] {
+            assert!(db::insert_custom_field(&pool, key, label, field_type)
+                .await
+                .unwrap());
+        }
+        assert!(
+            !db::insert_custom_field(&pool, "signed", "Signed", "boolean")
+                .await
+                .unwrap()
+        );
+        let fields = db::get_custom_fields(&pool).await.unwrap();
+
+        let mut ids = Vec::new();
+        for title in ["Doom", "Hades", "Celeste"] {
+            ids.push(
+                db::upsert_game(
+                    &pool,
+                    &format!("/games/{}", title),
+                    title,
+                    title,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        let set = |id: i64, updates: Value| {
+            let pool = pool.clone();
+            let fields = fields.clone();
+            async move {
+                let game = db::get_game_by_id(&pool, id).await.unwrap().unwrap();
+                let values = merge(
+                    game.custom_fields.as_deref(),
+                    &fields,
+                    updates.as_object().unwrap().clone(),
+                )
+                .unwrap();
+                db::update_game_custom_fields(&pool, id, values.as_deref())
+                    .await
+                    .unwrap()
+            }
+        };
+        set(
+            ids[0],
+            json!({"box_location": "Shelf B", "purchased_price": 12.5}),
+        )
+        .await;
+        set(ids[1], json!({"box_location": "shelf b", "signed": true})).await;
+        set(ids[2], json!({"box_location": "Attic"})).await;
+        let celeste = set(ids[2], json!({"box_location": null})).await;
+        assert_eq!(celeste.custom_fields, None);
+
+        let err = merge(
+            None,
+            &fields,
+            json!({"price": 1}).as_object().unwrap().clone(),
+        );
+        assert_eq!(err, Err("Unknown custom field: price".to_string()));
+
+        let titles = |key: &str, value: Option<&str>| {
+            let field_type = fields
+                .iter()
+                .find(|f| f.key == key)
+                .and_then(|f| FieldType::parse(&f.field_type))
+                .unwrap();
+            let filter = GameFilter {
+                custom_field: Some(key.to_string()),
+                custom_value: value.map(|v| field_type.filter_literal(v).unwrap()),
+                ..Default::default()
+            };
+            let pool = pool.clone();
+            async move {
+                db::get_games_page(&pool, &filter, 10, 0)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|g| g.title)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(titles("box_location", None).await, ["Doom", "Hades"]);
+        assert_eq!(
+            titles("box_location", Some("Shelf B")).await,
+            ["Doom", "Hades"]
+        );
+        assert_eq!(titles("purchased_price", Some("12.5")).await, ["Doom"]);
+        assert_eq!(titles("signed", Some("true")).await, ["Hades"]);
+        assert!(titles("signed", Some("false")).await.is_empty());
+
+        // Removing the field removes its values
+        assert!(db::delete_custom_field(&pool, "box_location")
+            .await
+            .unwrap());
+        let doom = db::get_game_by_id(&pool, ids[0]).await.unwrap().unwrap();
+        assert_eq!(
+            doom.custom_fields.as_deref(),
+            Some(r#"{"purchased_price":12.5}"#)
+        );
+        let hades = db::get_game_by_id(&pool, ids[1]).await.unwrap().unwrap();
+        assert_eq!(hades.custom_fields.as_deref(), Some(r#"{"signed":true}"#));
+    }
+}
@@ -1,6 +1,29 @@
-use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
 
-use crate::models::{Game, Stats};
+use sqlx::query::QueryAs;
+use sqlx::sqlite::{Sqlite, SqliteArguments, SqliteRow};
+use sqlx::{Column as _, FromRow, Row, SqliteConnection, SqlitePool, TypeInfo as _, ValueRef as _};
+
+use crate::custom_fields;
+use crate::demo::DemoGame;
+use crate::folder_appid::FolderAppId;
+use crate::freshness;
+use crate::import::{FieldKind, IMPORTABLE_FIELDS};
+use crate::linking::{self, ProposedLink};
+use crate::models::{
+    BacklogProgress, BlocklistEntry, CustomField, DeviceToken, Game, GameCompat, GameFilter,
+    GameMedia, GamePlaytime, GameSettings, IdleTrackedSession, ImportReportSummary, Job,
+    LibraryTotals, LinkProposal, MetadataDivergence, Notification, PlaySession, PlayedGame,
+    Profile, ScanReportSummary, SizeRecord, Stats, TitleMapping, UndoOperation, WishlistItem,
+    WishlistPrice, WorkshopItem,
+};
+use crate::notifications;
+use crate::raw;
+use crate::redist::{LibraryRedist, Redist};
+use crate::reviews::{ReviewBand, ReviewBandFilter};
+use crate::settings_profile::{ResolutionFilter, SettingsProfile, Upscaler};
+use crate::steam::SteamWorkshopItem;
+use crate::undo::{self, UNDO_COLUMNS};
 
 const SCHEMA: &str = r#"
 CREATE TABLE IF NOT EXISTS games (
@@ -51,13 +74,365 @@ CREATE TABLE IF NOT EXISTS games (
     -- Save backup pattern
     save_path_pattern TEXT,
 
-    created_at TEXT NOT NULL DEFAULT (datetime('now')),
-    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+    -- Launching and session tracking
+    launch_exe TEXT,
+    idle_timeout_mins INTEGER,
+
+    -- Cold storage archival
+    archived INTEGER DEFAULT 0,
+    archive_path TEXT,
+    archived_at TEXT,
+    archive_status TEXT,
+
+    -- Steam friends who own this game (see steam_friend_games)
+    friends_owned INTEGER,
+
+    notes TEXT,
+
+    -- Steam Deck compatibility: verified, playable, unsupported or unknown
+    deck_compat TEXT,
+    deck_compat_checked_at TEXT,
+
+    -- Release year from the folder name or NFO, used to break matching ties
+    year_hint INTEGER,
+
+    -- Matched by the enrichment blocklist (see blocklist.rs)
+    excluded_from_enrichment INTEGER DEFAULT 0,
+
+    -- Hidden from the game list, e.g. a duplicate copy (see duplicates.rs)
+    hidden INTEGER DEFAULT 0,
+
+    -- Edition/DLC parent and franchise groupings (see linking.rs)
+    parent_game_id INTEGER REFERENCES games(id),
+    franchise TEXT,
+    -- Base game's app id when Steam lists this game as DLC
+    steam_fullgame_app_id INTEGER,
+
+    -- Placeholder and accent color for the cached cover (see artwork.rs)
+    cover_blurhash TEXT,
+    cover_color TEXT,
+
+    -- release_date parsed to YYYY-MM-DD for sorting and filtering (NULL when
+    -- the store date is vague, e.g. "Q3 2024")
+    release_date_iso TEXT,
+
+    -- Steam's rating as a stable key (see reviews.rs); review_summary keeps
+    -- the text in the language Steam returned
+    review_band TEXT,
+
+    -- Genres and tags guessed by the scan (see genre_inference.rs)
+    inferred_genres TEXT,
+
+    -- The user's rating (1-100) and when they finished the game
+    user_rating INTEGER,
+    finished_at TEXT,
+
+    -- Failed metadata.json writes awaiting a retry (see metadata_sync.rs)
+    metadata_dirty_at TEXT,
+    metadata_write_error TEXT,
+    metadata_write_attempts INTEGER,
+
+    -- Minimum age from Steam (see profiles.rs)
+    age_rating INTEGER,
+
+    -- Steam app id named by a file in the folder (see folder_appid.rs)
+    folder_app_id INTEGER,
+    folder_app_id_source TEXT,
+
+    -- Values of user-defined fields (see custom_fields.rs)
+    custom_fields TEXT,
+
+    -- Steam Trading Cards and Points Shop items (see steam.rs)
+    trading_cards INTEGER,
+    points_shop_items INTEGER,
+    steam_items_checked_at TEXT,
+
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
 );
 
 CREATE INDEX IF NOT EXISTS idx_games_title ON games(title);
 CREATE INDEX IF NOT EXISTS idx_games_match_status ON games(match_status);
 CREATE INDEX IF NOT EXISTS idx_games_steam_app_id ON games(steam_app_id);
+
+CREATE TABLE IF NOT EXISTS play_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    started_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    ended_at TEXT,
+    -- Accumulated idle time excluded from the session
+    idle_secs INTEGER NOT NULL DEFAULT 0,
+    -- Set while the session clock is paused due to system idle
+    idle_since TEXT,
+    active_mins INTEGER,
+    -- Host the session ran on, and the restricted profile active at the time
+    machine TEXT,
+    profile_id INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_play_sessions_game_id ON play_sessions(game_id);
+
+CREATE TABLE IF NOT EXISTS digest_log (
+    period TEXT NOT NULL,
+    period_start TEXT NOT NULL,
+    sent_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    PRIMARY KEY (period, period_start)
+);
+
+CREATE TABLE IF NOT EXISTS workshop_items (
+    game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    published_file_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    preview_url TEXT,
+    file_size INTEGER,
+    time_created TEXT,
+    time_updated TEXT,
+    -- Set when a sync sees a newer time_updated than the stored one
+    update_seen_at TEXT,
+    synced_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    PRIMARY KEY (game_id, published_file_id)
+);
+
+CREATE TABLE IF NOT EXISTS game_media (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    path TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    source TEXT NOT NULL,
+    size_bytes INTEGER,
+    taken_at TEXT,
+    indexed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_game_media_game_id ON game_media(game_id);
+
+-- Steam search responses keyed by normalized title (see search_cache.rs)
+CREATE TABLE IF NOT EXISTS steam_search_cache (
+    query TEXT PRIMARY KEY,
+    results TEXT NOT NULL,
+    is_miss INTEGER NOT NULL DEFAULT 0,
+    fetched_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+-- Games owned by the configured account's Steam friends
+CREATE TABLE IF NOT EXISTS steam_friend_games (
+    friend_steam_id TEXT NOT NULL,
+    app_id INTEGER NOT NULL,
+    playtime_mins INTEGER NOT NULL DEFAULT 0,
+    synced_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    PRIMARY KEY (friend_steam_id, app_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_steam_friend_games_app_id ON steam_friend_games(app_id);
+
+CREATE TABLE IF NOT EXISTS wishlist_items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL,
+    steam_app_id INTEGER,
+    notes TEXT,
+    status TEXT NOT NULL DEFAULT 'wanted',
+    -- Folder name reserved in the library while acquiring (see wishlist.rs)
+    target_folder_name TEXT,
+    game_id INTEGER REFERENCES games(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS wishlist_prices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    wishlist_id INTEGER NOT NULL REFERENCES wishlist_items(id) ON DELETE CASCADE,
+    game_id INTEGER REFERENCES games(id) ON DELETE SET NULL,
+    price_cents INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    store TEXT,
+    recorded_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_wishlist_prices_wishlist_id ON wishlist_prices(wishlist_id);
+
+-- Structured compatibility reports, one per game and platform
+CREATE TABLE IF NOT EXISTS game_compat (
+    game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    platform TEXT NOT NULL,
+    status TEXT NOT NULL,
+    wrapper TEXT,
+    launch_options TEXT,
+    dxvk_flags TEXT,
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    PRIMARY KEY (game_id, platform)
+);
+
+CREATE INDEX IF NOT EXISTS idx_game_compat_status ON game_compat(platform, status);
+
+-- Runtime installers found in game folders by the scan
+CREATE TABLE IF NOT EXISTS game_runtimes (
+    game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    runtime TEXT NOT NULL,
+    version TEXT NOT NULL,
+    arch TEXT NOT NULL,
+    installer TEXT NOT NULL,
+    PRIMARY KEY (game_id, runtime, version, arch)
+);
+
+-- Folder size of each game, one row per scan that saw it change
+CREATE TABLE IF NOT EXISTS game_size_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    size_bytes INTEGER NOT NULL,
+    recorded_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_game_size_history_game_id ON game_size_history(game_id);
+
+-- Settings profile per game (resolution, upscaler, FPS cap, key remaps) as JSON
+CREATE TABLE IF NOT EXISTS game_settings (
+    game_id INTEGER PRIMARY KEY REFERENCES games(id) ON DELETE CASCADE,
+    profile TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+-- Outcome of each import run (dry runs included); rows is a JSON array
+CREATE TABLE IF NOT EXISTS import_reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source TEXT NOT NULL,
+    dry_run INTEGER NOT NULL,
+    conflict_policy TEXT NOT NULL,
+    status TEXT NOT NULL,
+    imported INTEGER NOT NULL DEFAULT 0,
+    unchanged INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    not_found INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    total INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    rows TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+-- Library scans; skipped_folders is a JSON array (see scanner.rs)
+CREATE TABLE IF NOT EXISTS scan_reports (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    total_found INTEGER NOT NULL DEFAULT 0,
+    added_or_updated INTEGER NOT NULL DEFAULT 0,
+    wishlist_linked INTEGER NOT NULL DEFAULT 0,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    skipped_folders TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+-- Folders that must never be matched to Steam: a wildcard pattern or a game
+CREATE TABLE IF NOT EXISTS enrichment_blocklist (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pattern TEXT,
+    game_id INTEGER REFERENCES games(id),
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+-- In-app notification inbox (see notifications.rs)
+CREATE TABLE IF NOT EXISTS notifications (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL,
+    message TEXT NOT NULL,
+    game_id INTEGER REFERENCES games(id),
+    read_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications(read_at, id);
+
+-- Paired companion devices (see pairing.rs); only token hashes are stored
+CREATE TABLE IF NOT EXISTS device_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    last_used_at TEXT
+);
+
+-- Title to Steam app id mappings (see mappings.rs); 0 = not on Steam
+CREATE TABLE IF NOT EXISTS title_mappings (
+    title TEXT PRIMARY KEY,
+    steam_app_id INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+-- Proposed edition/DLC and franchise links awaiting confirmation (see linking.rs)
+CREATE TABLE IF NOT EXISTS link_proposals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    kind TEXT NOT NULL,
+    parent_game_id INTEGER REFERENCES games(id),
+    franchise TEXT,
+    source TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    resolved_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_link_proposals_status ON link_proposals(status, id);
+
+-- Daily discovery queue (see discovery.rs); only the current day is kept
+CREATE TABLE IF NOT EXISTS discovery_queue (
+    day TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    PRIMARY KEY (day, position)
+);
+
+-- Metadata write operations that can be undone (see undo.rs)
+CREATE TABLE IF NOT EXISTS undo_operations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    description TEXT NOT NULL,
+    game_count INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    undone_at TEXT
+);
+
+-- Game columns as they were before an operation; data is a JSON object
+CREATE TABLE IF NOT EXISTS undo_snapshots (
+    operation_id INTEGER NOT NULL REFERENCES undo_operations(id),
+    game_id INTEGER NOT NULL REFERENCES games(id),
+    data TEXT NOT NULL,
+    PRIMARY KEY (operation_id, game_id)
+);
+
+-- Background tasks and their outcome (see startup.rs)
+CREATE TABLE IF NOT EXISTS jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    trigger TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running',
+    message TEXT,
+    started_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    finished_at TEXT
+);
+
+-- Restricted profiles (see profiles.rs); at most one is active
+CREATE TABLE IF NOT EXISTS profiles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    max_age INTEGER,
+    blocked_genres TEXT,
+    allowed_franchises TEXT,
+    pin_hash TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
+
+-- User-defined per-game fields (see custom_fields.rs); values live in
+-- games.custom_fields
+CREATE TABLE IF NOT EXISTS custom_fields (
+    key TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    field_type TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+);
 "#;
 
 /// Migration to add new columns to existing databases
@@ -72,49 +447,284 @@ const MIGRATIONS: &[&str] = &[
     "ALTER TABLE games ADD COLUMN hltb_completionist_mins INTEGER",
     "ALTER TABLE games ADD COLUMN save_path_pattern TEXT",
     "ALTER TABLE games ADD COLUMN manually_edited INTEGER DEFAULT 0",
+    "ALTER TABLE games ADD COLUMN launch_exe TEXT",
+    "ALTER TABLE games ADD COLUMN idle_timeout_mins INTEGER",
+    "ALTER TABLE games ADD COLUMN archived INTEGER DEFAULT 0",
+    "ALTER TABLE games ADD COLUMN archive_path TEXT",
+    "ALTER TABLE games ADD COLUMN archived_at TEXT",
+    "ALTER TABLE games ADD COLUMN archive_status TEXT",
+    "ALTER TABLE games ADD COLUMN friends_owned INTEGER",
+    "ALTER TABLE games ADD COLUMN notes TEXT",
+    "ALTER TABLE games ADD COLUMN deck_compat TEXT",
+    "ALTER TABLE games ADD COLUMN deck_compat_checked_at TEXT",
+    "ALTER TABLE games ADD COLUMN year_hint INTEGER",
+    "ALTER TABLE games ADD COLUMN excluded_from_enrichment INTEGER DEFAULT 0",
+    "ALTER TABLE games ADD COLUMN hidden INTEGER DEFAULT 0",
+    "ALTER TABLE games ADD COLUMN parent_game_id INTEGER REFERENCES games(id)",
+    "ALTER TABLE games ADD COLUMN franchise TEXT",
+    "ALTER TABLE games ADD COLUMN steam_fullgame_app_id INTEGER",
+    "ALTER TABLE games ADD COLUMN cover_blurhash TEXT",
+    "ALTER TABLE games ADD COLUMN cover_color TEXT",
+    "ALTER TABLE games ADD COLUMN release_date_iso TEXT",
+    "CREATE INDEX IF NOT EXISTS idx_games_release_date_iso ON games(release_date_iso)",
+    "ALTER TABLE games ADD COLUMN review_band TEXT",
+    "CREATE INDEX IF NOT EXISTS idx_games_review_band ON games(review_band)",
+    "ALTER TABLE games ADD COLUMN inferred_genres TEXT",
+    "ALTER TABLE games ADD COLUMN user_rating INTEGER",
+    "ALTER TABLE games ADD COLUMN finished_at TEXT",
+    // Failed metadata.json writes awaiting a retry (see metadata_sync.rs)
+    "ALTER TABLE games ADD COLUMN metadata_dirty_at TEXT",
+    "ALTER TABLE games ADD COLUMN metadata_write_error TEXT",
+    "ALTER TABLE games ADD COLUMN metadata_write_attempts INTEGER",
+    "ALTER TABLE games ADD COLUMN age_rating INTEGER",
+    "ALTER TABLE games ADD COLUMN folder_app_id INTEGER",
+    "ALTER TABLE games ADD COLUMN folder_app_id_source TEXT",
+    "ALTER TABLE play_sessions ADD COLUMN machine TEXT",
+    "ALTER TABLE play_sessions ADD COLUMN profile_id INTEGER",
+    "ALTER TABLE games ADD COLUMN custom_fields TEXT",
+    "ALTER TABLE games ADD COLUMN trading_cards INTEGER",
+    "ALTER TABLE games ADD COLUMN points_shop_items INTEGER",
+    "ALTER TABLE games ADD COLUMN steam_items_checked_at TEXT",
 ];
 
 pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+
     // Enable WAL mode for better concurrent access
-    sqlx::query("PRAGMA journal_mode=WAL").execute(pool).await?;
+    sqlx::query("PRAGMA journal_mode=WAL")
+        .execute(&mut *conn)
+        .await?;
 
-    sqlx::query(SCHEMA).execute(pool).await?;
+    sqlx::query(SCHEMA).execute(&mut *conn).await?;
 
     // Run migrations for existing databases (ignore errors for already-existing columns)
     for migration in MIGRATIONS {
-        let _ = sqlx::query(migration).execute(pool).await;
+        let _ = sqlx::query(migration).execute(&mut *conn).await;
+    }
+    upgrade_timestamp_defaults(&mut conn).await?;
+
+    // Don't return this connection to the pool: statements it prepared
+    // before the schema changed would report stale column counts
+    conn.detach();
+
+    normalize_timestamps(pool).await?;
+    backfill_release_dates(pool).await?;
+    backfill_review_bands(pool).await
+}
+
+/// Rebuild tables created with naive `datetime('now')` defaults
+///
+/// SQLite can't change a column's default, so each such table is recreated
+/// from its stored definition with the RFC 3339 defaults, its rows copied
+/// over and its indexes recreated. Runs on the migration connection, which
+/// is never returned to the pool.
+async fn upgrade_timestamp_defaults(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let tables: Vec<(String, String)> = sqlx::query_as(
+        "SELECT name, sql FROM sqlite_master WHERE type = 'table' AND sql LIKE '%datetime(''now'')%'",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    if tables.is_empty() {
+        return Ok(());
+    }
+
+    // Keep references from other tables pointing at the rebuilt table
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("PRAGMA legacy_alter_table = ON")
+        .execute(&mut *conn)
+        .await?;
+
+    for (table, sql) in &tables {
+        let indexes: Vec<(String,)> = sqlx::query_as(
+            "SELECT sql FROM sqlite_master WHERE type = 'index' AND tbl_name = ? AND sql IS NOT NULL",
+        )
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await?;
+        let old = format!("{}__old", table);
+
+        let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+        sqlx::query(&format!("ALTER TABLE {} RENAME TO {}", table, old))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&sql.replace("datetime('now')", "strftime('%Y-%m-%dT%H:%M:%SZ', 'now')"))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!("INSERT INTO {} SELECT * FROM {}", table, old))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!("DROP TABLE {}", old))
+            .execute(&mut *tx)
+            .await?;
+        for (index,) in indexes {
+            sqlx::query(&index).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+    }
+
+    tracing::info!("Updated timestamp defaults of {} tables", tables.len());
+    Ok(())
+}
+
+/// Timestamp columns not named `*_at`
+const TIMESTAMP_COLUMNS: &[&str] = &["idle_since", "time_created", "time_updated"];
+
+/// Rewrite naive `datetime('now')` timestamps from older versions as UTC
+/// RFC 3339, the format everything is written in now
+async fn normalize_timestamps(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let columns: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT m.name, p.name FROM sqlite_master m, pragma_table_info(m.name) p
+        WHERE m.type = 'table' AND m.name NOT LIKE 'sqlite_%'
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut normalized = 0;
+    for (table, column) in columns {
+        if !column.ends_with("_at") && !TIMESTAMP_COLUMNS.contains(&column.as_str()) {
+            continue;
+        }
+        let result = sqlx::query(&format!(
+            "UPDATE {0} SET {1} = strftime('%Y-%m-%dT%H:%M:%SZ', {1}) \
+             WHERE {1} GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9] [0-9][0-9]:*'",
+            table, column
+        ))
+        .execute(pool)
+        .await?;
+        normalized += result.rows_affected();
+    }
+    if normalized > 0 {
+        tracing::info!("Converted {} timestamps to RFC 3339", normalized);
+    }
+    Ok(())
+}
+
+/// Fill `release_date_iso` for games enriched before the column existed
+async fn backfill_release_dates(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let rows: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, release_date FROM games WHERE release_date IS NOT NULL AND release_date_iso IS NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut filled = 0;
+    for (id, release_date) in rows {
+        if let Some(iso) = freshness::release_date_iso(&release_date) {
+            sqlx::query("UPDATE games SET release_date_iso = ? WHERE id = ?")
+                .bind(iso)
+                .bind(id)
+                .execute(pool)
+                .await?;
+            filled += 1;
+        }
+    }
+    if filled > 0 {
+        tracing::info!("Parsed release dates of {} games", filled);
     }
+    Ok(())
+}
+
+/// Fill `review_band` for games enriched before the column existed
+async fn backfill_review_bands(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let games: Vec<Game> = sqlx::query_as(
+        r#"
+        SELECT * FROM games
+        WHERE review_band IS NULL AND (review_summary IS NOT NULL OR review_count IS NOT NULL)
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
 
+    let mut filled = 0;
+    for game in games {
+        let summary = game.review_summary.as_deref();
+        if let Some(band) = ReviewBand::detect(None, summary, game.review_score, game.review_count)
+        {
+            sqlx::query("UPDATE games SET review_band = ? WHERE id = ?")
+                .bind(band.as_str())
+                .bind(game.id)
+                .execute(pool)
+                .await?;
+            filled += 1;
+        }
+    }
+    if filled > 0 {
+        tracing::info!("Classified reviews of {} games", filled);
+    }
     Ok(())
 }
 
+/// Run an INSERT or UPDATE ... RETURNING and commit it before returning the row
+///
+/// Fetched in autocommit mode on the pool, the write isn't committed until
+/// sqlx resets the statement, which can happen after the row was returned:
+/// a client that reads it back on another connection right away may not see
+/// it yet. Running the statement in a transaction and committing first closes
+/// that window, so every RETURNING write goes through this helper or
+/// `fetch_optional_committed`.
+async fn fetch_one_committed<'q, T>(
+    pool: &SqlitePool,
+    query: QueryAs<'q, Sqlite, T, SqliteArguments<'q>>,
+) -> Result<T, sqlx::Error>
+where
+    T: Send + Unpin + for<'r> FromRow<'r, SqliteRow>,
+{
+    let mut tx = pool.begin().await?;
+    let row = query.fetch_one(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(row)
+}
+
+/// `fetch_one_committed` for writes that may match no row
+async fn fetch_optional_committed<'q, T>(
+    pool: &SqlitePool,
+    query: QueryAs<'q, Sqlite, T, SqliteArguments<'q>>,
+) -> Result<Option<T>, sqlx::Error>
+where
+    T: Send + Unpin + for<'r> FromRow<'r, SqliteRow>,
+{
+    let mut tx = pool.begin().await?;
+    let row = query.fetch_optional(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(row)
+}
+
 pub async fn upsert_game(
     pool: &SqlitePool,
     folder_path: &str,
     folder_name: &str,
     title: &str,
     size_bytes: Option<i64>,
+    year_hint: Option<i32>,
 ) -> Result<i64, sqlx::Error> {
-    let result = sqlx::query(
-        r#"
-        INSERT INTO games (folder_path, folder_name, title, size_bytes, match_status)
-        VALUES (?, ?, ?, ?, 'pending')
-        ON CONFLICT(folder_path) DO UPDATE SET
-            folder_name = excluded.folder_name,
-            title = excluded.title,
-            size_bytes = COALESCE(excluded.size_bytes, games.size_bytes),
-            updated_at = datetime('now')
-        RETURNING id
-        "#,
+    let (id,): (i64,) = fetch_one_committed(
+        pool,
+        sqlx::query_as(
+            r#"
+            INSERT INTO games (folder_path, folder_name, title, size_bytes, year_hint, match_status)
+            VALUES (?, ?, ?, ?, ?, 'pending')
+            ON CONFLICT(folder_path) DO UPDATE SET
+                folder_name = excluded.folder_name,
+                title = excluded.title,
+                size_bytes = COALESCE(excluded.size_bytes, games.size_bytes),
+                year_hint = COALESCE(excluded.year_hint, games.year_hint),
+                updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            RETURNING id
+            "#,
+        )
+        .bind(folder_path)
+        .bind(folder_name)
+        .bind(title)
+        .bind(size_bytes)
+        .bind(year_hint),
     )
-    .bind(folder_path)
-    .bind(folder_name)
-    .bind(title)
-    .bind(size_bytes)
-    .fetch_one(pool)
     .await?;
 
-    Ok(result.get("id"))
+    Ok(id)
 }
 
 pub async fn get_all_games(pool: &SqlitePool) -> Result<Vec<Game>, sqlx::Error> {
@@ -123,6 +733,25 @@ pub async fn get_all_games(pool: &SqlitePool) -> Result<Vec<Game>, sqlx::Error>
         .await
 }
 
+/// All games the active profile allows, by title
+pub async fn get_visible_games(pool: &SqlitePool) -> Result<Vec<Game>, sqlx::Error> {
+    let sql = format!(
+        "SELECT * FROM games WHERE {} ORDER BY title",
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, Game>(&sql).fetch_all(pool).await
+}
+
+/// Whether a game exists and the active profile allows it
+pub async fn is_game_visible(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let sql = format!(
+        "SELECT EXISTS(SELECT 1 FROM games WHERE id = ? AND {})",
+        PROFILE_FILTER_SQL
+    );
+    let (visible,): (bool,) = sqlx::query_as(&sql).bind(id).fetch_one(pool).await?;
+    Ok(visible)
+}
+
 pub async fn get_game_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Game>, sqlx::Error> {
     sqlx::query_as::<_, Game>("SELECT * FROM games WHERE id = ?")
         .bind(id)
@@ -130,10 +759,150 @@ pub async fn get_game_by_id(pool: &SqlitePool, id: i64) -> Result<Option<Game>,
         .await
 }
 
-pub async fn search_games(pool: &SqlitePool, query: &str) -> Result<Vec<Game>, sqlx::Error> {
+/// WHERE clause for [`GameFilter`]; binds ?1 platform, ?2 status, ?3 wrapper,
+/// ?4 Deck status, ?5 include hidden, ?6 release year, ?7 review bands (JSON),
+/// ?8 settings resolution (LIKE pattern), ?9 settings upscaler, ?10 settings
+/// FPS cap, ?11 genre, ?12 custom field key, ?13 custom field value (JSON),
+/// ?14 trading cards, ?15 Points Shop items
+const GAME_FILTER_SQL: &str = r#"
+    (?4 IS NULL OR games.deck_compat = ?4)
+    AND (?5 OR COALESCE(games.hidden, 0) = 0)
+    AND (?6 IS NULL OR substr(games.release_date_iso, 1, 4) = printf('%04d', ?6))
+    AND (?7 IS NULL OR games.review_band IN (SELECT value FROM json_each(?7)))
+    AND ((?1 IS NULL AND ?2 IS NULL AND ?3 IS NULL) OR EXISTS (
+        SELECT 1 FROM game_compat c
+        WHERE c.game_id = games.id
+            AND (?1 IS NULL OR c.platform = ?1)
+            AND (?2 IS NULL OR c.status = ?2)
+            AND (?3 IS NULL OR c.wrapper LIKE '%' || ?3 || '%')
+    ))
+    AND ((?8 IS NULL AND ?9 IS NULL AND ?10 IS NULL) OR EXISTS (
+        SELECT 1 FROM game_settings s
+        WHERE s.game_id = games.id
+            AND (?8 IS NULL OR json_extract(s.profile, '$.resolution') LIKE ?8)
+            AND (?9 IS NULL OR json_extract(s.profile, '$.upscaler') = ?9)
+            AND (?10 IS NULL OR json_extract(s.profile, '$.fps_cap') = ?10)
+    ))
+    AND (?11 IS NULL OR EXISTS (
+        SELECT 1 FROM json_each(
+            CASE WHEN json_valid(COALESCE(games.genres, games.inferred_genres))
+                THEN COALESCE(games.genres, games.inferred_genres) END
+        ) g
+        WHERE g.value = ?11 COLLATE NOCASE
+    ))
+    AND (?12 IS NULL OR (
+        json_type(games.custom_fields, '$."' || ?12 || '"') IS NOT NULL
+        AND (?13 IS NULL OR json_extract(games.custom_fields, '$."' || ?12 || '"')
+            = json_extract(?13, '$') COLLATE NOCASE)
+    ))
+    AND (?14 IS NULL OR games.trading_cards = ?14)
+    AND (?15 IS NULL OR (games.points_shop_items > 0) = ?15)
+"#;
+
+/// WHERE clause leaving out games the active profile doesn't allow (see
+/// profiles.rs); no binds, true for every game while no profile is active
+const PROFILE_FILTER_SQL: &str = r#"
+    NOT EXISTS (
+        SELECT 1 FROM profiles p
+        WHERE p.active = 1 AND NOT (
+            COALESCE(games.hidden, 0) = 0
+            AND (p.max_age IS NULL OR COALESCE(games.age_rating <= p.max_age, 0))
+            AND (p.allowed_franchises IS NULL OR COALESCE(
+                games.franchise IN (SELECT value FROM json_each(p.allowed_franchises)), 0
+            ))
+            AND (p.blocked_genres IS NULL OR NOT EXISTS (
+                SELECT 1 FROM json_each(
+                    CASE WHEN json_valid(COALESCE(games.genres, games.inferred_genres))
+                        THEN COALESCE(games.genres, games.inferred_genres) END
+                ) g
+                JOIN json_each(p.blocked_genres) b ON g.value = b.value COLLATE NOCASE
+            ))
+        )
+    )
+"#;
+
+/// Get one page of games in the filter's sort order
+pub async fn get_games_page(
+    pool: &SqlitePool,
+    filter: &GameFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    let sql = format!(
+        "SELECT * FROM games WHERE {} AND {} ORDER BY {} LIMIT ?16 OFFSET ?17",
+        GAME_FILTER_SQL,
+        PROFILE_FILTER_SQL,
+        filter.sort.order_by()
+    );
+    sqlx::query_as::<_, Game>(&sql)
+        .bind(&filter.compat_platform)
+        .bind(&filter.compat_status)
+        .bind(&filter.compat_wrapper)
+        .bind(&filter.deck_compat)
+        .bind(filter.include_hidden.unwrap_or(false))
+        .bind(filter.release_year)
+        .bind(filter.review_band.map(ReviewBandFilter::to_json))
+        .bind(
+            filter
+                .settings_resolution
+                .map(ResolutionFilter::like_pattern),
+        )
+        .bind(filter.settings_upscaler.map(Upscaler::as_str))
+        .bind(filter.settings_fps_cap)
+        .bind(&filter.genre)
+        .bind(&filter.custom_field)
+        .bind(&filter.custom_value)
+        .bind(filter.trading_cards)
+        .bind(filter.points_shop)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn count_games(pool: &SqlitePool, filter: &GameFilter) -> Result<i64, sqlx::Error> {
+    let sql = format!(
+        "SELECT COUNT(*) FROM games WHERE {} AND {}",
+        GAME_FILTER_SQL, PROFILE_FILTER_SQL
+    );
+    let count: (i64,) = sqlx::query_as(&sql)
+        .bind(&filter.compat_platform)
+        .bind(&filter.compat_status)
+        .bind(&filter.compat_wrapper)
+        .bind(&filter.deck_compat)
+        .bind(filter.include_hidden.unwrap_or(false))
+        .bind(filter.release_year)
+        .bind(filter.review_band.map(ReviewBandFilter::to_json))
+        .bind(
+            filter
+                .settings_resolution
+                .map(ResolutionFilter::like_pattern),
+        )
+        .bind(filter.settings_upscaler.map(Upscaler::as_str))
+        .bind(filter.settings_fps_cap)
+        .bind(&filter.genre)
+        .bind(&filter.custom_field)
+        .bind(&filter.custom_value)
+        .bind(filter.trading_cards)
+        .bind(filter.points_shop)
+        .fetch_one(pool)
+        .await?;
+    Ok(count.0)
+}
+
+pub async fn search_games(
+    pool: &SqlitePool,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<Game>, sqlx::Error> {
     let pattern = format!("%{}%", query);
-    sqlx::query_as::<_, Game>("SELECT * FROM games WHERE title LIKE ? ORDER BY title LIMIT 50")
+    let sql = format!(
+        "SELECT * FROM games WHERE title LIKE ? AND COALESCE(hidden, 0) = 0 AND {} ORDER BY title LIMIT ?",
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, Game>(&sql)
         .bind(pattern)
+        .bind(limit)
         .fetch_all(pool)
         .await
 }
@@ -141,14 +910,65 @@ pub async fn search_games(pool: &SqlitePool, query: &str) -> Result<Vec<Game>, s
 /// Get games that need enrichment:
 /// - Pending games (not yet matched to Steam)
 /// - Games missing local images (matched but image caching failed)
+///
+/// Games excluded by the enrichment blocklist are skipped.
 pub async fn get_games_needing_enrichment(pool: &SqlitePool) -> Result<Vec<Game>, sqlx::Error> {
     sqlx::query_as::<_, Game>(
-        "SELECT * FROM games WHERE (match_status = 'pending' OR steam_app_id IS NULL) OR (match_status = 'matched' AND (local_cover_path IS NULL OR local_background_path IS NULL)) ORDER BY title"
+        "SELECT * FROM games WHERE ((match_status = 'pending' OR steam_app_id IS NULL) OR (match_status = 'matched' AND (local_cover_path IS NULL OR local_background_path IS NULL))) AND COALESCE(excluded_from_enrichment, 0) = 0 ORDER BY title"
     )
     .fetch_all(pool)
     .await
 }
 
+/// Pending games and matches below `confidence` that enrichment may change
+pub async fn get_games_for_rematch(
+    pool: &SqlitePool,
+    confidence: f64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    let sql = format!(
+        "SELECT * FROM games WHERE {} ORDER BY title",
+        REMATCH_FILTER_SQL
+    );
+    sqlx::query_as::<_, Game>(&sql)
+        .bind(confidence)
+        .fetch_all(pool)
+        .await
+}
+
+/// Games waiting for a match review that the active profile allows
+pub async fn get_visible_games_for_rematch(
+    pool: &SqlitePool,
+    confidence: f64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    let sql = format!(
+        "SELECT * FROM games WHERE {} AND {} ORDER BY title",
+        REMATCH_FILTER_SQL, PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, Game>(&sql)
+        .bind(confidence)
+        .fetch_all(pool)
+        .await
+}
+
+/// WHERE clause for games enrichment should match again; binds ?1 confidence
+const REMATCH_FILTER_SQL: &str = r#"
+    (match_status = 'pending' OR steam_app_id IS NULL OR COALESCE(match_confidence, 0) < ?1
+        OR (folder_app_id IS NOT NULL AND steam_app_id IS NOT folder_app_id))
+    AND COALESCE(match_locked, 0) = 0
+    AND COALESCE(manually_edited, 0) = 0
+    AND COALESCE(excluded_from_enrichment, 0) = 0
+"#;
+
+pub async fn update_game_title(pool: &SqlitePool, id: i64, title: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE games SET title = ?, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?")
+        .bind(title)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn update_game_steam_data(
     pool: &SqlitePool,
     id: i64,
@@ -173,9 +993,10 @@ pub async fn update_game_steam_data(
             developers = COALESCE(?, developers),
             publishers = COALESCE(?, publishers),
             release_date = COALESCE(?, release_date),
+            release_date_iso = CASE WHEN ? THEN ? ELSE release_date_iso END,
             match_confidence = ?,
             match_status = 'matched',
-            updated_at = datetime('now')
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
         WHERE id = ?
         "#,
     )
@@ -187,6 +1008,8 @@ pub async fn update_game_steam_data(
     .bind(developers)
     .bind(publishers)
     .bind(release_date)
+    .bind(release_date.is_some())
+    .bind(release_date.and_then(freshness::release_date_iso))
     .bind(match_confidence)
     .bind(id)
     .execute(pool)
@@ -201,6 +1024,7 @@ pub async fn update_game_reviews(
     review_score: i64,
     review_count: i64,
     review_summary: &str,
+    review_band: Option<ReviewBand>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
@@ -208,13 +1032,15 @@ pub async fn update_game_reviews(
             review_score = ?,
             review_count = ?,
             review_summary = ?,
-            updated_at = datetime('now')
+            review_band = ?,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
         WHERE id = ?
         "#,
     )
     .bind(review_score)
     .bind(review_count)
     .bind(review_summary)
+    .bind(review_band.map(ReviewBand::as_str))
     .bind(id)
     .execute(pool)
     .await?;
@@ -222,63 +1048,157 @@ pub async fn update_game_reviews(
     Ok(())
 }
 
-/// Update game metadata from imported JSON file
-pub async fn update_game_from_import(
+/// Store the Steam Deck compatibility category of a game
+pub async fn update_game_deck_compat(
     pool: &SqlitePool,
     id: i64,
-    steam_app_id: Option<i64>,
-    summary: Option<&str>,
-    genres: Option<&str>,
-    developers: Option<&str>,
-    publishers: Option<&str>,
-    release_date: Option<&str>,
-    review_score: Option<i64>,
-    review_summary: Option<&str>,
-    hltb_main: Option<i64>,
-    hltb_extra: Option<i64>,
-    hltb_completionist: Option<i64>,
+    deck_compat: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE games SET deck_compat = ?, deck_compat_checked_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?",
+    )
+    .bind(deck_compat)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Store whether a game has Steam Trading Cards and how many Points Shop
+/// items; a value that couldn't be fetched keeps the stored one
+pub async fn update_game_steam_items(
+    pool: &SqlitePool,
+    id: i64,
+    trading_cards: Option<bool>,
+    points_shop_items: Option<i64>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE games SET
-            steam_app_id = COALESCE(?, steam_app_id),
-            summary = COALESCE(?, summary),
-            genres = COALESCE(?, genres),
-            developers = COALESCE(?, developers),
-            publishers = COALESCE(?, publishers),
-            release_date = COALESCE(?, release_date),
-            review_score = COALESCE(?, review_score),
-            review_summary = COALESCE(?, review_summary),
-            hltb_main_mins = COALESCE(?, hltb_main_mins),
-            hltb_extra_mins = COALESCE(?, hltb_extra_mins),
-            hltb_completionist_mins = COALESCE(?, hltb_completionist_mins),
-            match_status = CASE WHEN ? IS NOT NULL THEN 'matched' ELSE match_status END,
-            updated_at = datetime('now')
+            trading_cards = COALESCE(?, trading_cards),
+            points_shop_items = COALESCE(?, points_shop_items),
+            steam_items_checked_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
         WHERE id = ?
         "#,
     )
-    .bind(steam_app_id)
-    .bind(summary)
-    .bind(genres)
-    .bind(developers)
-    .bind(publishers)
-    .bind(release_date)
-    .bind(review_score)
-    .bind(review_summary)
-    .bind(hltb_main)
-    .bind(hltb_extra)
-    .bind(hltb_completionist)
-    .bind(steam_app_id)
+    .bind(trading_cards)
+    .bind(points_shop_items)
     .bind(id)
     .execute(pool)
     .await?;
+    Ok(())
+}
 
+/// Store the app id the scan found in a game's files (None = no such file)
+pub async fn update_game_folder_app_id(
+    pool: &SqlitePool,
+    id: i64,
+    found: Option<&FolderAppId>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE games SET folder_app_id = ?, folder_app_id_source = ? WHERE id = ?")
+        .bind(found.map(|f| f.app_id))
+        .bind(found.map(|f| f.source.as_str()))
+        .bind(id)
+        .execute(pool)
+        .await?;
     Ok(())
 }
 
-pub async fn get_stats(pool: &SqlitePool) -> Result<Stats, sqlx::Error> {
-    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM games")
-        .fetch_one(pool)
+/// Store the minimum age Steam lists for a game
+pub async fn update_game_age_rating(
+    pool: &SqlitePool,
+    id: i64,
+    age_rating: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE games SET age_rating = ? WHERE id = ?")
+        .bind(age_rating)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Store the base game's app id from Steam (None when the app isn't DLC)
+pub async fn update_game_fullgame_app_id(
+    pool: &SqlitePool,
+    id: i64,
+    fullgame_app_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE games SET steam_fullgame_app_id = ? WHERE id = ?")
+        .bind(fullgame_app_id)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Store the cover's blurhash and dominant color (see artwork.rs)
+pub async fn update_game_artwork(
+    pool: &SqlitePool,
+    id: i64,
+    blurhash: &str,
+    color: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE games SET cover_blurhash = ?, cover_color = ? WHERE id = ?")
+        .bind(blurhash)
+        .bind(color)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Ids of games with a cached cover but no blurhash yet
+pub async fn get_games_missing_artwork(pool: &SqlitePool) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT id FROM games WHERE local_cover_path IS NOT NULL AND cover_blurhash IS NULL ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Matched games whose Deck status is missing or older than `max_age_days`
+pub async fn get_games_needing_deck_compat(
+    pool: &SqlitePool,
+    max_age_days: i64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    sqlx::query_as::<_, Game>(
+        r#"
+        SELECT * FROM games
+        WHERE steam_app_id IS NOT NULL
+            AND (deck_compat_checked_at IS NULL
+                OR deck_compat_checked_at < strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-' || ? || ' days'))
+        ORDER BY deck_compat_checked_at IS NOT NULL, deck_compat_checked_at, title
+        "#,
+    )
+    .bind(max_age_days)
+    .fetch_all(pool)
+    .await
+}
+
+/// Matched games whose trading cards and Points Shop items weren't checked
+/// within `max_age_days`, never checked first
+pub async fn get_games_needing_steam_items(
+    pool: &SqlitePool,
+    max_age_days: i64,
+) -> Result<Vec<Game>, sqlx::Error> {
+    sqlx::query_as::<_, Game>(
+        r#"
+        SELECT * FROM games
+        WHERE steam_app_id IS NOT NULL
+            AND (steam_items_checked_at IS NULL
+                OR steam_items_checked_at < strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-' || ? || ' days'))
+        ORDER BY steam_items_checked_at IS NOT NULL, steam_items_checked_at, title
+        "#,
+    )
+    .bind(max_age_days)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_stats(pool: &SqlitePool) -> Result<Stats, sqlx::Error> {
+    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM games")
+        .fetch_one(pool)
         .await?;
 
     let matched: (i64,) =
@@ -287,7 +1207,7 @@ pub async fn get_stats(pool: &SqlitePool) -> Result<Stats, sqlx::Error> {
             .await?;
 
     let pending: (i64,) =
-        sqlx::query_as("SELECT COUNT(*) FROM games WHERE match_status = 'pending'")
+        sqlx::query_as("SELECT COUNT(*) FROM games WHERE match_status = 'pending' AND COALESCE(excluded_from_enrichment, 0) = 0")
             .fetch_one(pool)
             .await?;
 
@@ -316,7 +1236,7 @@ pub async fn update_game_local_images(
         UPDATE games SET
             local_cover_path = COALESCE(?, local_cover_path),
             local_background_path = COALESCE(?, local_background_path),
-            updated_at = datetime('now')
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
         WHERE id = ?
         "#,
     )
@@ -331,7 +1251,11 @@ pub async fn update_game_local_images(
 
 /// Get recently added games
 pub async fn get_recent_games(pool: &SqlitePool, limit: i64) -> Result<Vec<Game>, sqlx::Error> {
-    sqlx::query_as::<_, Game>("SELECT * FROM games ORDER BY created_at DESC LIMIT ?")
+    let sql = format!(
+        "SELECT * FROM games WHERE {} ORDER BY created_at DESC LIMIT ?",
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, Game>(&sql)
         .bind(limit)
         .fetch_all(pool)
         .await
@@ -353,6 +1277,7 @@ pub async fn get_game_folder_path(
 /// Update game metadata from user edits
 /// Returns the updated Game for dual-write to metadata.json
 /// Uses a transaction to ensure atomicity of UPDATE + SELECT
+#[allow(clippy::too_many_arguments)]
 pub async fn update_game_metadata(
     pool: &SqlitePool,
     id: i64,
@@ -366,6 +1291,14 @@ pub async fn update_game_metadata(
 ) -> Result<Game, sqlx::Error> {
     let mut tx = pool.begin().await?;
 
+    record_undo_operation(
+        &mut tx,
+        undo::KIND_EDIT,
+        &format!("Edit game {}", id),
+        &[id],
+    )
+    .await?;
+
     sqlx::query(
         r#"
         UPDATE games SET
@@ -375,9 +1308,10 @@ pub async fn update_game_metadata(
             developers = COALESCE(?, developers),
             publishers = COALESCE(?, publishers),
             release_date = COALESCE(?, release_date),
+            release_date_iso = CASE WHEN ? THEN ? ELSE release_date_iso END,
             review_score = COALESCE(?, review_score),
             manually_edited = 1,
-            updated_at = datetime('now')
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
         WHERE id = ?
         "#,
     )
@@ -387,6 +1321,8 @@ pub async fn update_game_metadata(
     .bind(developers)
     .bind(publishers)
     .bind(release_date)
+    .bind(release_date.is_some())
+    .bind(release_date.and_then(freshness::release_date_iso))
     .bind(review_score)
     .bind(id)
     .execute(&mut *tx)
@@ -401,3 +1337,2646 @@ pub async fn update_game_metadata(
     tx.commit().await?;
     Ok(game)
 }
+
+/// Update per-game launch and idle detection settings
+pub async fn update_play_settings(
+    pool: &SqlitePool,
+    id: i64,
+    launch_exe: Option<&str>,
+    idle_timeout_mins: Option<i64>,
+) -> Result<Game, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE games SET
+            launch_exe = ?,
+            idle_timeout_mins = ?,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(launch_exe)
+    .bind(idle_timeout_mins)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    let game = sqlx::query_as::<_, Game>("SELECT * FROM games WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(game)
+}
+
+/// Open a new play session for a game on a machine, in the active profile
+pub async fn start_play_session(
+    pool: &SqlitePool,
+    game_id: i64,
+    machine: &str,
+) -> Result<i64, sqlx::Error> {
+    let (id,): (i64,) = fetch_one_committed(
+        pool,
+        sqlx::query_as(
+            r#"
+            INSERT INTO play_sessions (game_id, machine, profile_id)
+            VALUES (?, ?, (SELECT id FROM profiles WHERE active = 1))
+            RETURNING id
+            "#,
+        )
+        .bind(game_id)
+        .bind(machine),
+    )
+    .await?;
+
+    Ok(id)
+}
+
+/// Games by their latest session in the active profile, newest first
+///
+/// `machine` limits the list to sessions on that machine; sessions recorded
+/// before machines were tracked count for every machine.
+pub async fn get_recently_played(
+    pool: &SqlitePool,
+    machine: Option<&str>,
+    limit: i64,
+) -> Result<Vec<PlayedGame>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT games.*, s.last_played_at, s.running FROM (
+            SELECT game_id, MAX(started_at) AS last_played_at,
+                MAX(ended_at IS NULL) AS running
+            FROM play_sessions
+            WHERE (?1 IS NULL OR machine = ?1 OR machine IS NULL)
+                AND profile_id IS (SELECT id FROM profiles WHERE active = 1)
+            GROUP BY game_id
+        ) s
+        JOIN games ON games.id = s.game_id
+        WHERE {}
+        ORDER BY s.last_played_at DESC, games.id DESC
+        LIMIT ?2
+        "#,
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, PlayedGame>(&sql)
+        .bind(machine)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+/// Get the currently open session for a game, if any
+pub async fn get_open_play_session(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Option<PlaySession>, sqlx::Error> {
+    sqlx::query_as::<_, PlaySession>(
+        "SELECT * FROM play_sessions WHERE game_id = ? AND ended_at IS NULL ORDER BY started_at DESC LIMIT 1",
+    )
+    .bind(game_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Close a play session and add its active time to the game's playtime
+///
+/// Any idle period still in progress is excluded. Returns the closed session.
+pub async fn end_play_session(pool: &SqlitePool, id: i64) -> Result<PlaySession, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE play_sessions SET
+            idle_secs = idle_secs + COALESCE(
+                CAST((julianday('now') - julianday(idle_since)) * 86400 AS INTEGER), 0),
+            idle_since = NULL,
+            ended_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ? AND ended_at IS NULL
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE play_sessions SET
+            active_mins = MAX(0, CAST(
+                ((julianday(ended_at) - julianday(started_at)) * 86400 - idle_secs) / 60
+                AS INTEGER))
+        WHERE id = ? AND active_mins IS NULL
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    let session = sqlx::query_as::<_, PlaySession>("SELECT * FROM play_sessions WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE games SET
+            playtime_mins = COALESCE(playtime_mins, 0) + ?,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(session.active_mins.unwrap_or(0))
+    .bind(session.game_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(session)
+}
+
+/// List play sessions for a game (newest first)
+pub async fn get_play_sessions(
+    pool: &SqlitePool,
+    game_id: i64,
+    limit: i64,
+) -> Result<Vec<PlaySession>, sqlx::Error> {
+    sqlx::query_as::<_, PlaySession>(
+        "SELECT * FROM play_sessions WHERE game_id = ? ORDER BY started_at DESC LIMIT ?",
+    )
+    .bind(game_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Get open sessions for games that have idle detection enabled
+pub async fn get_idle_tracked_sessions(
+    pool: &SqlitePool,
+) -> Result<Vec<IdleTrackedSession>, sqlx::Error> {
+    sqlx::query_as::<_, IdleTrackedSession>(
+        r#"
+        SELECT s.id, s.game_id, s.idle_since, g.idle_timeout_mins
+        FROM play_sessions s
+        JOIN games g ON g.id = s.game_id
+        WHERE s.ended_at IS NULL AND g.idle_timeout_mins IS NOT NULL
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Pause a session clock, backdating the pause to the last system input
+pub async fn pause_play_session(
+    pool: &SqlitePool,
+    id: i64,
+    idle_for_secs: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE play_sessions SET idle_since = strftime('%Y-%m-%dT%H:%M:%SZ', 'now', ?) WHERE id = ? AND idle_since IS NULL",
+    )
+    .bind(format!("-{} seconds", idle_for_secs))
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Resume a paused session clock from the last system input
+pub async fn resume_play_session(
+    pool: &SqlitePool,
+    id: i64,
+    idle_for_secs: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE play_sessions SET
+            idle_secs = idle_secs + MAX(0, CAST(
+                (julianday(strftime('%Y-%m-%dT%H:%M:%SZ', 'now', ?)) - julianday(idle_since)) * 86400 AS INTEGER)),
+            idle_since = NULL
+        WHERE id = ? AND idle_since IS NOT NULL
+        "#,
+    )
+    .bind(format!("-{} seconds", idle_for_secs))
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Aggregate closed play sessions per game within [start, end)
+pub async fn get_playtime_by_game(
+    pool: &SqlitePool,
+    start: &str,
+    end: &str,
+) -> Result<Vec<GamePlaytime>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT
+            games.id AS game_id,
+            games.title,
+            COALESCE(SUM(s.active_mins), 0) AS active_mins,
+            COUNT(s.id) AS sessions,
+            MAX(s.started_at) AS last_played
+        FROM play_sessions s
+        JOIN games ON games.id = s.game_id
+        WHERE s.ended_at IS NOT NULL
+          AND datetime(s.started_at) >= datetime(?)
+          AND datetime(s.started_at) < datetime(?)
+          AND {}
+        GROUP BY games.id
+        ORDER BY active_mins DESC, games.title
+        "#,
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, GamePlaytime>(&sql)
+        .bind(start)
+        .bind(end)
+        .fetch_all(pool)
+        .await
+}
+
+/// Count games whose first-ever play session started within [start, end)
+pub async fn count_games_first_played_between(
+    pool: &SqlitePool,
+    start: &str,
+    end: &str,
+) -> Result<i64, sqlx::Error> {
+    let count: (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM (
+            SELECT game_id, MIN(datetime(started_at)) AS first_played
+            FROM play_sessions
+            GROUP BY game_id
+        )
+        WHERE first_played >= datetime(?) AND first_played < datetime(?)
+        "#,
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count.0)
+}
+
+/// Count games per backlog status
+pub async fn get_backlog_progress(pool: &SqlitePool) -> Result<BacklogProgress, sqlx::Error> {
+    sqlx::query_as::<_, BacklogProgress>(
+        r#"
+        SELECT
+            COALESCE(SUM(CASE WHEN COALESCE(user_status, 'unplayed') = 'unplayed' THEN 1 ELSE 0 END), 0) AS unplayed,
+            COALESCE(SUM(CASE WHEN user_status = 'playing' THEN 1 ELSE 0 END), 0) AS playing,
+            COALESCE(SUM(CASE WHEN user_status = 'completed' THEN 1 ELSE 0 END), 0) AS completed,
+            COALESCE(SUM(CASE WHEN user_status = 'abandoned' THEN 1 ELSE 0 END), 0) AS abandoned,
+            COUNT(*) AS total
+        FROM games
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Games still to be played (unplayed or playing), excluding archived and hidden games
+pub async fn get_backlog_games(pool: &SqlitePool) -> Result<Vec<Game>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT * FROM games
+        WHERE COALESCE(user_status, 'unplayed') IN ('unplayed', 'playing')
+          AND COALESCE(archived, 0) = 0
+          AND COALESCE(hidden, 0) = 0
+          AND {}
+        ORDER BY created_at, id
+        "#,
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, Game>(&sql).fetch_all(pool).await
+}
+
+/// Totals of the whole library (hidden and archived games included, except
+/// in the backlog figures)
+pub async fn get_library_totals(pool: &SqlitePool) -> Result<LibraryTotals, sqlx::Error> {
+    sqlx::query_as::<_, LibraryTotals>(
+        r#"
+        WITH backlog AS (
+            SELECT hltb_main_mins FROM games
+            WHERE COALESCE(user_status, 'unplayed') IN ('unplayed', 'playing')
+              AND COALESCE(archived, 0) = 0
+              AND COALESCE(hidden, 0) = 0
+        )
+        SELECT
+            (SELECT COALESCE(SUM(size_bytes), 0) FROM games) AS size_bytes,
+            (SELECT COUNT(*) FROM backlog) AS backlog_games,
+            (SELECT COALESCE(SUM(hltb_main_mins), 0) FROM backlog WHERE hltb_main_mins > 0)
+                AS backlog_hltb_mins,
+            (SELECT COUNT(*) FROM backlog WHERE COALESCE(hltb_main_mins, 0) <= 0)
+                AS backlog_without_hltb,
+            (SELECT COUNT(*) FROM games WHERE COALESCE(hidden, 0) = 1) AS hidden_games,
+            (SELECT COUNT(*) FROM games WHERE COALESCE(archived, 0) = 1) AS archived_games
+        "#,
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Number of games per match status ("matched", "pending", ...)
+pub async fn count_games_by_match_status(
+    pool: &SqlitePool,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT match_status, COUNT(*) FROM games GROUP BY match_status ORDER BY match_status",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Start of the earliest closed play session, if any
+pub async fn get_first_session_start(pool: &SqlitePool) -> Result<Option<String>, sqlx::Error> {
+    let first: (Option<String>,) = sqlx::query_as(
+        "SELECT MIN(datetime(started_at)) FROM play_sessions WHERE ended_at IS NOT NULL",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(first.0)
+}
+
+/// Check whether the digest for a period was already sent
+pub async fn digest_already_sent(
+    pool: &SqlitePool,
+    period: &str,
+    period_start: &str,
+) -> Result<bool, sqlx::Error> {
+    let row: Option<(String,)> =
+        sqlx::query_as("SELECT sent_at FROM digest_log WHERE period = ? AND period_start = ?")
+            .bind(period)
+            .bind(period_start)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(row.is_some())
+}
+
+/// Record that the digest for a period was sent
+pub async fn record_digest_sent(
+    pool: &SqlitePool,
+    period: &str,
+    period_start: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO digest_log (period, period_start) VALUES (?, ?)")
+        .bind(period)
+        .bind(period_start)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// List synced Workshop items for a game (most recently updated first)
+pub async fn get_workshop_items(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Vec<WorkshopItem>, sqlx::Error> {
+    sqlx::query_as::<_, WorkshopItem>(
+        "SELECT * FROM workshop_items WHERE game_id = ? ORDER BY time_updated DESC, title",
+    )
+    .bind(game_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Replace a game's Workshop subscriptions with a fresh list from Steam
+///
+/// Existing items keep their history; `update_seen_at` is stamped when the
+/// item's `time_updated` changed since the last sync. Items no longer
+/// subscribed are removed.
+pub async fn sync_workshop_items(
+    pool: &SqlitePool,
+    game_id: i64,
+    items: &[SteamWorkshopItem],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for item in items {
+        sqlx::query(
+            r#"
+            INSERT INTO workshop_items (
+                game_id, published_file_id, title, description, preview_url,
+                file_size, time_created, time_updated
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(game_id, published_file_id) DO UPDATE SET
+                title = excluded.title,
+                description = excluded.description,
+                preview_url = excluded.preview_url,
+                file_size = excluded.file_size,
+                time_created = excluded.time_created,
+                update_seen_at = CASE
+                    WHEN workshop_items.time_updated IS NOT excluded.time_updated
+                    THEN strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+                    ELSE workshop_items.update_seen_at
+                END,
+                time_updated = excluded.time_updated,
+                synced_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            "#,
+        )
+        .bind(game_id)
+        .bind(&item.published_file_id)
+        .bind(&item.title)
+        .bind(&item.description)
+        .bind(&item.preview_url)
+        .bind(item.file_size)
+        .bind(&item.time_created)
+        .bind(&item.time_updated)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Drop items that are no longer subscribed
+    let ids: Vec<&str> = items.iter().map(|i| i.published_file_id.as_str()).collect();
+    sqlx::query(
+        "DELETE FROM workshop_items WHERE game_id = ? AND published_file_id NOT IN (SELECT value FROM json_each(?))",
+    )
+    .bind(game_id)
+    .bind(serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string()))
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace the stored library of one Steam friend
+pub async fn replace_friend_games(
+    pool: &SqlitePool,
+    friend_steam_id: &str,
+    games: &[(i64, i64)],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM steam_friend_games WHERE friend_steam_id = ?")
+        .bind(friend_steam_id)
+        .execute(&mut *tx)
+        .await?;
+
+    for (app_id, playtime_mins) in games {
+        sqlx::query(
+            "INSERT OR REPLACE INTO steam_friend_games (friend_steam_id, app_id, playtime_mins) VALUES (?, ?, ?)",
+        )
+        .bind(friend_steam_id)
+        .bind(app_id)
+        .bind(playtime_mins)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Drop stored libraries of accounts that are no longer friends
+pub async fn prune_friend_games(
+    pool: &SqlitePool,
+    friend_ids: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM steam_friend_games WHERE friend_steam_id NOT IN (SELECT value FROM json_each(?))",
+    )
+    .bind(serde_json::to_string(friend_ids).unwrap_or_else(|_| "[]".to_string()))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Recompute `games.friends_owned` from the stored friend libraries
+///
+/// Returns the number of games owned by at least one friend.
+pub async fn refresh_friends_owned(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE games SET friends_owned = (
+            SELECT COUNT(*) FROM steam_friend_games f WHERE f.app_id = games.steam_app_id
+        )
+        WHERE steam_app_id IS NOT NULL
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let row = sqlx::query("SELECT COUNT(*) FROM games WHERE friends_owned > 0")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.get::<i64, _>(0) as u64)
+}
+
+pub async fn get_game_compat(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Vec<GameCompat>, sqlx::Error> {
+    sqlx::query_as::<_, GameCompat>("SELECT * FROM game_compat WHERE game_id = ? ORDER BY platform")
+        .bind(game_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Insert or replace the compatibility report for one platform
+pub async fn upsert_game_compat(
+    pool: &SqlitePool,
+    game_id: i64,
+    platform: &str,
+    status: &str,
+    wrapper: Option<&str>,
+    launch_options: Option<&str>,
+    dxvk_flags: Option<&str>,
+) -> Result<GameCompat, sqlx::Error> {
+    fetch_one_committed(
+        pool,
+        sqlx::query_as::<_, GameCompat>(
+            r#"
+            INSERT INTO game_compat (game_id, platform, status, wrapper, launch_options, dxvk_flags)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(game_id, platform) DO UPDATE SET
+                status = excluded.status,
+                wrapper = excluded.wrapper,
+                launch_options = excluded.launch_options,
+                dxvk_flags = excluded.dxvk_flags,
+                updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            RETURNING *
+            "#,
+        )
+        .bind(game_id)
+        .bind(platform)
+        .bind(status)
+        .bind(wrapper)
+        .bind(launch_options)
+        .bind(dxvk_flags),
+    )
+    .await
+}
+
+pub async fn delete_game_compat(
+    pool: &SqlitePool,
+    game_id: i64,
+    platform: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM game_compat WHERE game_id = ? AND platform = ?")
+        .bind(game_id)
+        .bind(platform)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Store the genres a scan inferred for a game (NULL when there are none)
+pub async fn update_game_inferred_genres(
+    pool: &SqlitePool,
+    game_id: i64,
+    genres: &[String],
+) -> Result<(), sqlx::Error> {
+    let json = (!genres.is_empty()).then(|| serde_json::to_string(genres).unwrap_or_default());
+    sqlx::query("UPDATE games SET inferred_genres = ? WHERE id = ?")
+        .bind(json)
+        .bind(game_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Record a failed metadata.json write (the game stays dirty until one succeeds)
+pub async fn mark_metadata_dirty(
+    pool: &SqlitePool,
+    game_id: i64,
+    error: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE games SET
+            metadata_dirty_at = COALESCE(metadata_dirty_at, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+            metadata_write_error = ?,
+            metadata_write_attempts = COALESCE(metadata_write_attempts, 0) + 1
+        WHERE id = ?
+        "#,
+    )
+    .bind(error)
+    .bind(game_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Mark a game's metadata.json as matching the database again
+pub async fn clear_metadata_dirty(pool: &SqlitePool, game_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE games SET metadata_dirty_at = NULL, metadata_write_error = NULL,
+            metadata_write_attempts = NULL
+        WHERE id = ? AND metadata_dirty_at IS NOT NULL
+        "#,
+    )
+    .bind(game_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Games whose metadata.json write failed, oldest first
+pub async fn get_metadata_dirty_games(pool: &SqlitePool) -> Result<Vec<Game>, sqlx::Error> {
+    sqlx::query_as::<_, Game>(
+        "SELECT * FROM games WHERE metadata_dirty_at IS NOT NULL ORDER BY metadata_dirty_at, id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Dirty games whose writes failed at least `min_attempts` times
+pub async fn get_metadata_divergence(
+    pool: &SqlitePool,
+    min_attempts: i64,
+) -> Result<Vec<MetadataDivergence>, sqlx::Error> {
+    sqlx::query_as::<_, MetadataDivergence>(
+        r#"
+        SELECT id AS game_id, title, metadata_dirty_at AS dirty_since,
+            metadata_write_attempts AS attempts, metadata_write_error AS error
+        FROM games
+        WHERE metadata_dirty_at IS NOT NULL AND metadata_write_attempts >= ?
+        ORDER BY metadata_dirty_at, id
+        "#,
+    )
+    .bind(min_attempts)
+    .fetch_all(pool)
+    .await
+}
+
+/// Replace the runtimes recorded for a game with the ones found by a scan
+pub async fn replace_game_runtimes(
+    pool: &SqlitePool,
+    game_id: i64,
+    runtimes: &[Redist],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM game_runtimes WHERE game_id = ?")
+        .bind(game_id)
+        .execute(&mut *tx)
+        .await?;
+    for redist in runtimes {
+        sqlx::query(
+            "INSERT OR IGNORE INTO game_runtimes (game_id, runtime, version, arch, installer) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(game_id)
+        .bind(&redist.runtime)
+        .bind(&redist.version)
+        .bind(&redist.arch)
+        .bind(&redist.installer)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+pub async fn get_game_runtimes(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Vec<Redist>, sqlx::Error> {
+    sqlx::query_as::<_, Redist>(
+        "SELECT runtime, version, arch, installer FROM game_runtimes WHERE game_id = ? ORDER BY runtime, version, arch",
+    )
+    .bind(game_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Size records kept per game; the oldest are dropped beyond this
+const SIZE_HISTORY_LIMIT: i64 = 100;
+
+/// Record a game's folder size if it differs from the last record
+///
+/// Returns whether a record was added.
+pub async fn record_game_size(
+    pool: &SqlitePool,
+    game_id: i64,
+    size_bytes: i64,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO game_size_history (game_id, size_bytes)
+        SELECT ?1, ?2
+        WHERE ?2 IS NOT (
+            SELECT size_bytes FROM game_size_history
+            WHERE game_id = ?1
+            ORDER BY id DESC
+            LIMIT 1
+        )
+        "#,
+    )
+    .bind(game_id)
+    .bind(size_bytes)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM game_size_history
+        WHERE game_id = ?1 AND id NOT IN (
+            SELECT id FROM game_size_history WHERE game_id = ?1 ORDER BY id DESC LIMIT ?2
+        )
+        "#,
+    )
+    .bind(game_id)
+    .bind(SIZE_HISTORY_LIMIT)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Recorded folder sizes of a game, oldest first
+pub async fn get_game_size_history(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Vec<SizeRecord>, sqlx::Error> {
+    sqlx::query_as::<_, SizeRecord>(
+        r#"
+        SELECT size_bytes,
+               size_bytes - LAG(size_bytes) OVER (ORDER BY id) AS change_bytes,
+               recorded_at
+        FROM game_size_history
+        WHERE game_id = ?
+        ORDER BY id
+        "#,
+    )
+    .bind(game_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Runtime installers of all games, by runtime, version, architecture and title
+pub async fn get_library_runtimes(pool: &SqlitePool) -> Result<Vec<LibraryRedist>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT r.game_id, games.title, r.runtime, r.version, r.arch, r.installer
+        FROM game_runtimes r
+        JOIN games ON games.id = r.game_id
+        WHERE {}
+        ORDER BY r.runtime, r.version, r.arch, games.title
+        "#,
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, LibraryRedist>(&sql)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_game_settings(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Option<GameSettings>, sqlx::Error> {
+    let row = sqlx::query("SELECT profile, updated_at FROM game_settings WHERE game_id = ?")
+        .bind(game_id)
+        .fetch_optional(pool)
+        .await?;
+    row.map(|row| game_settings_from_row(game_id, &row))
+        .transpose()
+}
+
+/// Insert or replace a game's settings profile
+pub async fn upsert_game_settings(
+    pool: &SqlitePool,
+    game_id: i64,
+    profile: &SettingsProfile,
+) -> Result<GameSettings, sqlx::Error> {
+    let (updated_at,): (String,) = fetch_one_committed(
+        pool,
+        sqlx::query_as(
+            r#"
+                INSERT INTO game_settings (game_id, profile) VALUES (?, ?)
+                ON CONFLICT(game_id) DO UPDATE SET
+                    profile = excluded.profile,
+                    updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+                RETURNING updated_at
+                "#,
+        )
+        .bind(game_id)
+        .bind(serde_json::to_string(profile).unwrap_or_else(|_| "{}".to_string())),
+    )
+    .await?;
+    Ok(GameSettings {
+        game_id,
+        profile: profile.clone(),
+        updated_at,
+    })
+}
+
+pub async fn delete_game_settings(pool: &SqlitePool, game_id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM game_settings WHERE game_id = ?")
+        .bind(game_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+fn game_settings_from_row(game_id: i64, row: &SqliteRow) -> Result<GameSettings, sqlx::Error> {
+    let profile =
+        serde_json::from_str(row.get("profile")).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "profile".to_string(),
+            source: Box::new(e),
+        })?;
+    Ok(GameSettings {
+        game_id,
+        profile,
+        updated_at: row.get("updated_at"),
+    })
+}
+
+/// List wishlist entries, open ones first
+pub async fn get_wishlist(pool: &SqlitePool) -> Result<Vec<WishlistItem>, sqlx::Error> {
+    sqlx::query_as::<_, WishlistItem>(
+        "SELECT * FROM wishlist_items ORDER BY status = 'acquired', created_at DESC, id DESC",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_wishlist_item(
+    pool: &SqlitePool,
+    id: i64,
+) -> Result<Option<WishlistItem>, sqlx::Error> {
+    sqlx::query_as::<_, WishlistItem>("SELECT * FROM wishlist_items WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Wishlist entries currently holding a folder reservation
+pub async fn get_acquiring_wishlist(pool: &SqlitePool) -> Result<Vec<WishlistItem>, sqlx::Error> {
+    sqlx::query_as::<_, WishlistItem>(
+        "SELECT * FROM wishlist_items WHERE status = 'acquiring' ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn create_wishlist_item(
+    pool: &SqlitePool,
+    title: &str,
+    steam_app_id: Option<i64>,
+    notes: Option<&str>,
+) -> Result<WishlistItem, sqlx::Error> {
+    fetch_one_committed(
+        pool,
+        sqlx::query_as::<_, WishlistItem>(
+            "INSERT INTO wishlist_items (title, steam_app_id, notes) VALUES (?, ?, ?) RETURNING *",
+        )
+        .bind(title)
+        .bind(steam_app_id)
+        .bind(notes),
+    )
+    .await
+}
+
+/// Update a wishlist entry (None leaves a field unchanged)
+pub async fn update_wishlist_item(
+    pool: &SqlitePool,
+    id: i64,
+    title: Option<&str>,
+    steam_app_id: Option<i64>,
+    notes: Option<&str>,
+    status: Option<&str>,
+) -> Result<Option<WishlistItem>, sqlx::Error> {
+    fetch_optional_committed(
+        pool,
+        sqlx::query_as::<_, WishlistItem>(
+            r#"
+            UPDATE wishlist_items SET
+                title = COALESCE(?, title),
+                steam_app_id = COALESCE(?, steam_app_id),
+                notes = COALESCE(?, notes),
+                status = COALESCE(?, status),
+                updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(title)
+        .bind(steam_app_id)
+        .bind(notes)
+        .bind(status)
+        .bind(id),
+    )
+    .await
+}
+
+/// Mark an entry as acquiring and reserve a library folder name for it
+pub async fn reserve_wishlist_folder(
+    pool: &SqlitePool,
+    id: i64,
+    folder_name: &str,
+) -> Result<Option<WishlistItem>, sqlx::Error> {
+    fetch_optional_committed(
+        pool,
+        sqlx::query_as::<_, WishlistItem>(
+            r#"
+            UPDATE wishlist_items SET
+                status = 'acquiring',
+                target_folder_name = ?,
+                updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(folder_name)
+        .bind(id),
+    )
+    .await
+}
+
+/// Whether a folder name is already reserved by another wishlist entry
+pub async fn is_folder_reserved(
+    pool: &SqlitePool,
+    folder_name: &str,
+    except_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT COUNT(*) FROM wishlist_items WHERE status = 'acquiring' AND target_folder_name = ? COLLATE NOCASE AND id != ?",
+    )
+    .bind(folder_name)
+    .bind(except_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(row.get::<i64, _>(0) > 0)
+}
+
+/// Link an acquired wishlist entry to the library game that fulfilled it,
+/// carrying notes and price history over to the game
+pub async fn link_wishlist_item(
+    pool: &SqlitePool,
+    id: i64,
+    game_id: i64,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE wishlist_items SET
+            status = 'acquired',
+            game_id = ?,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(game_id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE games SET
+            notes = COALESCE(notes, (SELECT notes FROM wishlist_items WHERE id = ?)),
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(id)
+    .bind(game_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE wishlist_prices SET game_id = ? WHERE wishlist_id = ?")
+        .bind(game_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete a wishlist entry and its price history
+pub async fn delete_wishlist_item(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM wishlist_prices WHERE wishlist_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM wishlist_items WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn add_wishlist_price(
+    pool: &SqlitePool,
+    wishlist_id: i64,
+    price_cents: i64,
+    currency: &str,
+    store: Option<&str>,
+) -> Result<WishlistPrice, sqlx::Error> {
+    fetch_one_committed(
+        pool,
+        sqlx::query_as::<_, WishlistPrice>(
+            r#"
+            INSERT INTO wishlist_prices (wishlist_id, game_id, price_cents, currency, store)
+            VALUES (?, (SELECT game_id FROM wishlist_items WHERE id = ?), ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(wishlist_id)
+        .bind(wishlist_id)
+        .bind(price_cents)
+        .bind(currency)
+        .bind(store),
+    )
+    .await
+}
+
+pub async fn get_wishlist_prices(
+    pool: &SqlitePool,
+    wishlist_id: i64,
+) -> Result<Vec<WishlistPrice>, sqlx::Error> {
+    sqlx::query_as::<_, WishlistPrice>(
+        "SELECT * FROM wishlist_prices WHERE wishlist_id = ? ORDER BY recorded_at, id",
+    )
+    .bind(wishlist_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Price history carried over to a library game
+pub async fn get_game_prices(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Vec<WishlistPrice>, sqlx::Error> {
+    sqlx::query_as::<_, WishlistPrice>(
+        "SELECT * FROM wishlist_prices WHERE game_id = ? ORDER BY recorded_at, id",
+    )
+    .bind(game_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Insert or refresh an indexed media file
+pub async fn upsert_game_media(
+    pool: &SqlitePool,
+    game_id: i64,
+    path: &str,
+    kind: &str,
+    source: &str,
+    size_bytes: Option<i64>,
+    taken_at: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO game_media (game_id, path, kind, source, size_bytes, taken_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(path) DO UPDATE SET
+            game_id = excluded.game_id,
+            kind = excluded.kind,
+            source = excluded.source,
+            size_bytes = excluded.size_bytes,
+            taken_at = excluded.taken_at,
+            indexed_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        "#,
+    )
+    .bind(game_id)
+    .bind(path)
+    .bind(kind)
+    .bind(source)
+    .bind(size_bytes)
+    .bind(taken_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// List media for a game (newest first)
+pub async fn get_game_media(
+    pool: &SqlitePool,
+    game_id: i64,
+) -> Result<Vec<GameMedia>, sqlx::Error> {
+    sqlx::query_as::<_, GameMedia>(
+        "SELECT * FROM game_media WHERE game_id = ? ORDER BY taken_at DESC, id DESC",
+    )
+    .bind(game_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Get a single media entry
+pub async fn get_media_by_id(pool: &SqlitePool, id: i64) -> Result<Option<GameMedia>, sqlx::Error> {
+    sqlx::query_as::<_, GameMedia>("SELECT * FROM game_media WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// List all indexed media (for pruning deleted files)
+pub async fn get_all_media(pool: &SqlitePool) -> Result<Vec<GameMedia>, sqlx::Error> {
+    sqlx::query_as::<_, GameMedia>("SELECT * FROM game_media")
+        .fetch_all(pool)
+        .await
+}
+
+/// Remove a media entry
+pub async fn delete_game_media(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM game_media WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Find the game that was being played at a given time
+pub async fn find_game_played_at(
+    pool: &SqlitePool,
+    timestamp: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        r#"
+        SELECT game_id FROM play_sessions
+        WHERE datetime(started_at) <= datetime(?)
+          AND datetime(COALESCE(ended_at, 'now')) >= datetime(?)
+        ORDER BY started_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(timestamp)
+    .bind(timestamp)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|r| r.0))
+}
+
+/// Get a cached Steam search response: (results JSON, is_miss, fetched_at)
+pub async fn get_steam_search_cache(
+    pool: &SqlitePool,
+    query: &str,
+) -> Result<Option<(String, bool, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT results, is_miss, fetched_at FROM steam_search_cache WHERE query = ?")
+        .bind(query)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Store a Steam search response
+pub async fn put_steam_search_cache(
+    pool: &SqlitePool,
+    query: &str,
+    results: &str,
+    is_miss: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO steam_search_cache (query, results, is_miss, fetched_at)
+        VALUES (?, ?, ?, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+        ON CONFLICT(query) DO UPDATE SET
+            results = excluded.results,
+            is_miss = excluded.is_miss,
+            fetched_at = excluded.fetched_at
+        "#,
+    )
+    .bind(query)
+    .bind(results)
+    .bind(is_miss)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Current values of the importable columns of a game (None if it doesn't exist)
+pub async fn get_import_values(
+    pool: &SqlitePool,
+    id: i64,
+) -> Result<Option<HashMap<String, serde_json::Value>>, sqlx::Error> {
+    let Some(row) = sqlx::query("SELECT * FROM games WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let mut values = HashMap::new();
+    for (field, kind) in IMPORTABLE_FIELDS {
+        let value = match kind {
+            FieldKind::Text => row.try_get::<Option<String>, _>(*field)?.into(),
+            FieldKind::Integer => row.try_get::<Option<i64>, _>(*field)?.into(),
+        };
+        values.insert(field.to_string(), value);
+    }
+    Ok(Some(values))
+}
+
+/// Columns of the games table, in table order
+pub async fn get_game_columns(pool: &SqlitePool) -> Result<Vec<raw::Column>, sqlx::Error> {
+    let rows = sqlx::query("SELECT name, type, \"notnull\" FROM pragma_table_info('games')")
+        .fetch_all(pool)
+        .await?;
+    rows.iter()
+        .map(|row| {
+            Ok(raw::Column {
+                name: row.try_get("name")?,
+                kind: raw::ColumnKind::from_declared(&row.try_get::<String, _>("type")?),
+                not_null: row.try_get::<i64, _>("notnull")? != 0,
+            })
+        })
+        .collect()
+}
+
+/// Every column of a game as stored (None if it doesn't exist)
+pub async fn get_game_raw(pool: &SqlitePool, id: i64) -> Result<Option<raw::RawRow>, sqlx::Error> {
+    let Some(row) = sqlx::query("SELECT * FROM games WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let mut values = raw::RawRow::new();
+    for column in row.columns() {
+        let index = column.ordinal();
+        let raw_value = row.try_get_raw(index)?;
+        let value = if raw_value.is_null() {
+            serde_json::Value::Null
+        } else {
+            match raw_value.type_info().name() {
+                "INTEGER" => row.try_get_unchecked::<i64, _>(index)?.into(),
+                "REAL" => row.try_get_unchecked::<f64, _>(index)?.into(),
+                "BLOB" => serde_json::Value::Null,
+                _ => row.try_get_unchecked::<String, _>(index)?.into(),
+            }
+        };
+        values.insert(column.name().to_string(), value);
+    }
+    Ok(Some(values))
+}
+
+/// Write validated raw column changes to a game, snapshotting it for undo
+///
+/// Column names must come from `raw::validate`, which checks them against the table.
+pub async fn update_game_raw(
+    pool: &SqlitePool,
+    id: i64,
+    changes: &[(String, serde_json::Value)],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let names: Vec<&str> = changes.iter().map(|(name, _)| name.as_str()).collect();
+    let description = format!("Raw edit of game {}: {}", id, names.join(", "));
+    record_undo_operation(&mut tx, undo::KIND_RAW_EDIT, &description, &[id]).await?;
+
+    let assignments: Vec<String> = names
+        .iter()
+        .map(|name| format!("\"{}\" = ?", name))
+        .collect();
+    let sql = format!(
+        "UPDATE games SET {}, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?",
+        assignments.join(", ")
+    );
+    let mut query = sqlx::query(&sql);
+    for (_, value) in changes {
+        query = match value {
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => query.bind(i),
+                None => query.bind(n.as_f64()),
+            },
+            serde_json::Value::String(s) => query.bind(s.as_str()),
+            serde_json::Value::Null => query.bind(None::<String>),
+            other => query.bind(other.to_string()),
+        };
+    }
+    query.bind(id).execute(&mut *tx).await?;
+
+    tx.commit().await
+}
+
+/// Apply import changes to games in one transaction (all or nothing)
+///
+/// Field names must come from `IMPORTABLE_FIELDS`; anything else is rejected.
+pub async fn apply_import(
+    pool: &SqlitePool,
+    source: &str,
+    updates: &[(i64, Vec<(&str, &serde_json::Value)>)],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let ids: Vec<i64> = updates
+        .iter()
+        .filter(|(_, changes)| !changes.is_empty())
+        .map(|(id, _)| *id)
+        .collect();
+    if !ids.is_empty() {
+        let description = format!("Import from {}", source);
+        record_undo_operation(&mut tx, undo::KIND_IMPORT, &description, &ids).await?;
+    }
+
+    for (id, changes) in updates {
+        if changes.is_empty() {
+            continue;
+        }
+        if let Some((field, _)) = changes
+            .iter()
+            .find(|(f, _)| !IMPORTABLE_FIELDS.iter().any(|(name, _)| name == f))
+        {
+            return Err(sqlx::Error::ColumnNotFound(field.to_string()));
+        }
+
+        let assignments: Vec<String> = changes.iter().map(|(f, _)| format!("{} = ?", f)).collect();
+        // Setting a Steam app ID marks the game as matched, as enrichment would
+        let sets_app_id = changes.iter().any(|(f, _)| *f == "steam_app_id");
+        let sql = format!(
+            "UPDATE games SET {}{}, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?",
+            assignments.join(", "),
+            if sets_app_id {
+                ", match_status = 'matched'"
+            } else {
+                ""
+            }
+        );
+
+        let mut query = sqlx::query(&sql);
+        for (_, value) in changes {
+            query = match value {
+                serde_json::Value::Number(n) => query.bind(n.as_i64()),
+                serde_json::Value::String(s) => query.bind(s.as_str()),
+                serde_json::Value::Null => query.bind(None::<String>),
+                other => query.bind(other.to_string()),
+            };
+        }
+        query.bind(id).execute(&mut *tx).await?;
+    }
+
+    tx.commit().await
+}
+
+/// Store an import report, returning it with its id and timestamp
+pub async fn insert_import_report(
+    pool: &SqlitePool,
+    report: &ImportReportSummary,
+    rows_json: &str,
+) -> Result<ImportReportSummary, sqlx::Error> {
+    fetch_one_committed(
+        pool,
+        sqlx::query_as::<_, ImportReportSummary>(
+            r#"
+            INSERT INTO import_reports (
+                source, dry_run, conflict_policy, status,
+                imported, unchanged, skipped, not_found, failed, total, error, rows
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING id, source, dry_run, conflict_policy, status,
+                imported, unchanged, skipped, not_found, failed, total, error, created_at
+            "#,
+        )
+        .bind(&report.source)
+        .bind(report.dry_run)
+        .bind(&report.conflict_policy)
+        .bind(&report.status)
+        .bind(report.imported)
+        .bind(report.unchanged)
+        .bind(report.skipped)
+        .bind(report.not_found)
+        .bind(report.failed)
+        .bind(report.total)
+        .bind(&report.error)
+        .bind(rows_json),
+    )
+    .await
+}
+
+/// Recent import reports, newest first (without rows)
+pub async fn get_import_reports(
+    pool: &SqlitePool,
+    limit: i64,
+) -> Result<Vec<ImportReportSummary>, sqlx::Error> {
+    sqlx::query_as::<_, ImportReportSummary>(
+        r#"
+        SELECT id, source, dry_run, conflict_policy, status,
+            imported, unchanged, skipped, not_found, failed, total, error, created_at
+        FROM import_reports
+        ORDER BY id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// An import report with its rows JSON
+pub async fn get_import_report(
+    pool: &SqlitePool,
+    id: i64,
+) -> Result<Option<(ImportReportSummary, String)>, sqlx::Error> {
+    let Some(row) = sqlx::query("SELECT * FROM import_reports WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let summary = ImportReportSummary::from_row(&row)?;
+    Ok(Some((summary, row.try_get("rows")?)))
+}
+
+/// Store a scan report, returning its summary
+pub async fn insert_scan_report(
+    pool: &SqlitePool,
+    total_found: i64,
+    added_or_updated: i64,
+    wishlist_linked: i64,
+    skipped: i64,
+    skipped_folders_json: &str,
+) -> Result<ScanReportSummary, sqlx::Error> {
+    fetch_one_committed(
+        pool,
+        sqlx::query_as::<_, ScanReportSummary>(
+        r#"
+        INSERT INTO scan_reports (total_found, added_or_updated, wishlist_linked, skipped, skipped_folders)
+        VALUES (?, ?, ?, ?, ?)
+        RETURNING id, total_found, added_or_updated, wishlist_linked, skipped, created_at
+        "#,
+    )
+    .bind(total_found)
+    .bind(added_or_updated)
+    .bind(wishlist_linked)
+    .bind(skipped)
+    .bind(skipped_folders_json),
+    )
+    .await
+}
+
+/// Most recent scan reports without their skipped folders, newest first
+pub async fn get_scan_reports(
+    pool: &SqlitePool,
+    limit: i64,
+) -> Result<Vec<ScanReportSummary>, sqlx::Error> {
+    sqlx::query_as::<_, ScanReportSummary>(
+        r#"
+        SELECT id, total_found, added_or_updated, wishlist_linked, skipped, created_at
+        FROM scan_reports
+        ORDER BY id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// A scan report with its skipped folders JSON
+pub async fn get_scan_report(
+    pool: &SqlitePool,
+    id: i64,
+) -> Result<Option<(ScanReportSummary, String)>, sqlx::Error> {
+    let Some(row) = sqlx::query("SELECT * FROM scan_reports WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let summary = ScanReportSummary::from_row(&row)?;
+    Ok(Some((summary, row.try_get("skipped_folders")?)))
+}
+
+pub async fn get_blocklist(pool: &SqlitePool) -> Result<Vec<BlocklistEntry>, sqlx::Error> {
+    sqlx::query_as::<_, BlocklistEntry>("SELECT * FROM enrichment_blocklist ORDER BY id")
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn add_blocklist_entry(
+    pool: &SqlitePool,
+    pattern: Option<&str>,
+    game_id: Option<i64>,
+) -> Result<BlocklistEntry, sqlx::Error> {
+    fetch_one_committed(
+        pool,
+        sqlx::query_as::<_, BlocklistEntry>(
+            "INSERT INTO enrichment_blocklist (pattern, game_id) VALUES (?, ?) RETURNING *",
+        )
+        .bind(pattern)
+        .bind(game_id),
+    )
+    .await
+}
+
+pub async fn delete_blocklist_entry(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM enrichment_blocklist WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hide a game from the game list (or show it again)
+pub async fn set_game_hidden(
+    pool: &SqlitePool,
+    id: i64,
+    hidden: bool,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE games SET hidden = ?, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?")
+            .bind(hidden)
+            .bind(id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn count_excluded_games(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let (count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM games WHERE excluded_from_enrichment = 1")
+            .fetch_one(pool)
+            .await?;
+    Ok(count as usize)
+}
+
+/// Flag exactly these games as excluded from enrichment, clearing the rest
+pub async fn set_enrichment_exclusions(
+    pool: &SqlitePool,
+    game_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE games SET excluded_from_enrichment = 0 WHERE excluded_from_enrichment = 1")
+        .execute(&mut *tx)
+        .await?;
+    for id in game_ids {
+        sqlx::query("UPDATE games SET excluded_from_enrichment = 1 WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await
+}
+
+/// Add a notification to the inbox, dropping the oldest beyond the limit
+pub async fn insert_notification(
+    pool: &SqlitePool,
+    kind: &str,
+    title: &str,
+    message: &str,
+    game_id: Option<i64>,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO notifications (kind, title, message, game_id) VALUES (?, ?, ?, ?) RETURNING id",
+    )
+    .bind(kind)
+    .bind(title)
+    .bind(message)
+    .bind(game_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM notifications WHERE id <= ?")
+        .bind(id - notifications::INBOX_LIMIT)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// WHERE clause leaving out notifications about games the active profile
+/// doesn't allow; no binds
+fn notification_profile_filter() -> String {
+    format!(
+        "NOT EXISTS (SELECT 1 FROM games WHERE games.id = notifications.game_id AND NOT ({}))",
+        PROFILE_FILTER_SQL
+    )
+}
+
+/// Notifications the active profile allows, newest first
+pub async fn get_notifications(
+    pool: &SqlitePool,
+    unread_only: bool,
+    limit: i64,
+) -> Result<Vec<Notification>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT * FROM notifications
+        WHERE (? = 0 OR read_at IS NULL) AND {}
+        ORDER BY id DESC
+        LIMIT ?
+        "#,
+        notification_profile_filter()
+    );
+    sqlx::query_as::<_, Notification>(&sql)
+        .bind(unread_only)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+/// Record a job as running and return its id
+pub async fn start_job(pool: &SqlitePool, kind: &str, trigger: &str) -> Result<i64, sqlx::Error> {
+    let (id,): (i64,) = fetch_one_committed(
+        pool,
+        sqlx::query_as("INSERT INTO jobs (kind, trigger) VALUES (?, ?) RETURNING id")
+            .bind(kind)
+            .bind(trigger),
+    )
+    .await?;
+    Ok(id)
+}
+
+/// Record how a job ended ("completed" or "failed")
+pub async fn finish_job(
+    pool: &SqlitePool,
+    id: i64,
+    status: &str,
+    message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE jobs SET status = ?, message = ?,
+            finished_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(status)
+    .bind(message)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Most recent jobs, newest first
+pub async fn get_jobs(pool: &SqlitePool, limit: i64) -> Result<Vec<Job>, sqlx::Error> {
+    sqlx::query_as::<_, Job>("SELECT * FROM jobs ORDER BY id DESC LIMIT ?")
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn count_unread_notifications(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let sql = format!(
+        "SELECT COUNT(*) FROM notifications WHERE read_at IS NULL AND {}",
+        notification_profile_filter()
+    );
+    let (count,): (i64,) = sqlx::query_as(&sql).fetch_one(pool).await?;
+    Ok(count)
+}
+
+/// Whether an unread notification of this kind is already in the inbox
+pub async fn has_unread_notification(pool: &SqlitePool, kind: &str) -> Result<bool, sqlx::Error> {
+    let row: Option<(i64,)> =
+        sqlx::query_as("SELECT id FROM notifications WHERE kind = ? AND read_at IS NULL LIMIT 1")
+            .bind(kind)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.is_some())
+}
+
+/// Mark one notification read; false if it doesn't exist
+pub async fn mark_notification_read(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE notifications SET read_at = COALESCE(read_at, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')) WHERE id = ?",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark every unread notification read, returning how many there were
+pub async fn mark_all_notifications_read(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE notifications SET read_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE read_at IS NULL")
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn insert_device_token(
+    pool: &SqlitePool,
+    name: &str,
+    scope: &str,
+    token_hash: &str,
+) -> Result<DeviceToken, sqlx::Error> {
+    fetch_one_committed(
+        pool,
+        sqlx::query_as::<_, DeviceToken>(
+            "INSERT INTO device_tokens (name, scope, token_hash) VALUES (?, ?, ?) RETURNING *",
+        )
+        .bind(name)
+        .bind(scope)
+        .bind(token_hash),
+    )
+    .await
+}
+
+pub async fn get_device_tokens(pool: &SqlitePool) -> Result<Vec<DeviceToken>, sqlx::Error> {
+    sqlx::query_as::<_, DeviceToken>(
+        "SELECT * FROM device_tokens ORDER BY created_at DESC, id DESC",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Scope of the device token with this hash, recording that it was used
+///
+/// `last_used_at` is only rewritten once a minute to keep requests read-only.
+pub async fn use_device_token(
+    pool: &SqlitePool,
+    token_hash: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let scope: Option<(String,)> =
+        sqlx::query_as("SELECT scope FROM device_tokens WHERE token_hash = ?")
+            .bind(token_hash)
+            .fetch_optional(pool)
+            .await?;
+
+    if scope.is_some() {
+        sqlx::query(
+            r#"
+            UPDATE device_tokens SET last_used_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            WHERE token_hash = ?
+              AND (last_used_at IS NULL OR last_used_at < strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-1 minute'))
+            "#,
+        )
+        .bind(token_hash)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(scope.map(|s| s.0))
+}
+
+pub async fn delete_device_token(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM device_tokens WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Unplayed, non-archived games that may appear in the discovery queue
+pub async fn get_discovery_candidates(pool: &SqlitePool) -> Result<Vec<Game>, sqlx::Error> {
+    sqlx::query_as::<_, Game>(
+        r#"
+        SELECT * FROM games
+        WHERE COALESCE(user_status, 'unplayed') = 'unplayed'
+          AND COALESCE(archived, 0) = 0
+        ORDER BY id
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// The stored discovery queue for a day, in queue order
+pub async fn get_discovery_queue(pool: &SqlitePool, day: &str) -> Result<Vec<Game>, sqlx::Error> {
+    let sql = format!(
+        r#"
+        SELECT games.* FROM discovery_queue q
+        JOIN games ON games.id = q.game_id
+        WHERE q.day = ? AND {}
+        ORDER BY q.position
+        "#,
+        PROFILE_FILTER_SQL
+    );
+    sqlx::query_as::<_, Game>(&sql)
+        .bind(day)
+        .fetch_all(pool)
+        .await
+}
+
+/// Store the discovery queue for a day, dropping earlier days
+///
+/// A queue already stored for the day (by a concurrent request) is kept.
+pub async fn save_discovery_queue(
+    pool: &SqlitePool,
+    day: &str,
+    game_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM discovery_queue WHERE day <> ?")
+        .bind(day)
+        .execute(&mut *tx)
+        .await?;
+
+    for (position, game_id) in game_ids.iter().enumerate() {
+        sqlx::query(
+            "INSERT OR IGNORE INTO discovery_queue (day, position, game_id) VALUES (?, ?, ?)",
+        )
+        .bind(day)
+        .bind(position as i64)
+        .bind(game_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+/// Snapshot games into a new undo operation, returning its id
+///
+/// Runs on the caller's connection so the snapshot can share a transaction
+/// with the write it protects. Only the newest `undo::HISTORY_LIMIT`
+/// operations are kept.
+pub async fn record_undo_operation(
+    conn: &mut SqliteConnection,
+    kind: &str,
+    description: &str,
+    game_ids: &[i64],
+) -> Result<i64, sqlx::Error> {
+    let (operation_id,): (i64,) = sqlx::query_as(
+        "INSERT INTO undo_operations (kind, description) VALUES (?, ?) RETURNING id",
+    )
+    .bind(kind)
+    .bind(description)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let fields: Vec<String> = UNDO_COLUMNS
+        .iter()
+        .map(|c| format!("'{}', {}", c, c))
+        .collect();
+    let sql = format!(
+        "INSERT OR IGNORE INTO undo_snapshots (operation_id, game_id, data) \
+         SELECT ?, id, json_object({}) FROM games WHERE id = ?",
+        fields.join(", ")
+    );
+    for id in game_ids {
+        sqlx::query(&sql)
+            .bind(operation_id)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlx::query(
+        "UPDATE undo_operations SET game_count = \
+         (SELECT COUNT(*) FROM undo_snapshots WHERE operation_id = ?1) WHERE id = ?1",
+    )
+    .bind(operation_id)
+    .execute(&mut *conn)
+    .await?;
+
+    // Drop history beyond the limit
+    let cutoff = operation_id - undo::HISTORY_LIMIT;
+    sqlx::query("DELETE FROM undo_snapshots WHERE operation_id <= ?")
+        .bind(cutoff)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM undo_operations WHERE id <= ?")
+        .bind(cutoff)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(operation_id)
+}
+
+/// Snapshot games into a new undo operation in its own transaction
+pub async fn snapshot_games(
+    pool: &SqlitePool,
+    kind: &str,
+    description: &str,
+    game_ids: &[i64],
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let id = record_undo_operation(&mut tx, kind, description, game_ids).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Recent undo operations, newest first
+pub async fn get_undo_operations(
+    pool: &SqlitePool,
+    limit: i64,
+) -> Result<Vec<UndoOperation>, sqlx::Error> {
+    sqlx::query_as::<_, UndoOperation>("SELECT * FROM undo_operations ORDER BY id DESC LIMIT ?")
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn get_undo_operation(
+    pool: &SqlitePool,
+    id: i64,
+) -> Result<Option<UndoOperation>, sqlx::Error> {
+    sqlx::query_as::<_, UndoOperation>("SELECT * FROM undo_operations WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Revert operation `id` and every later one that hasn't been undone yet
+///
+/// Operations are reverted newest first in one transaction, so a game touched
+/// by several of them ends up as it was before `id`. Returns the reverted
+/// operations and the ids of the restored games.
+pub async fn revert_undo_operations(
+    pool: &SqlitePool,
+    id: i64,
+) -> Result<(Vec<UndoOperation>, Vec<i64>), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let mut operations = sqlx::query_as::<_, UndoOperation>(
+        "SELECT * FROM undo_operations WHERE id >= ? AND undone_at IS NULL ORDER BY id DESC",
+    )
+    .bind(id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let assignments: Vec<String> = UNDO_COLUMNS
+        .iter()
+        .map(|c| format!("{} = json_extract(?1, '$.{}')", c, c))
+        .collect();
+    let restore = format!(
+        "UPDATE games SET {}, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?2",
+        assignments.join(", ")
+    );
+
+    let mut game_ids = Vec::new();
+    for operation in &mut operations {
+        let snapshots: Vec<(i64, String)> =
+            sqlx::query_as("SELECT game_id, data FROM undo_snapshots WHERE operation_id = ?")
+                .bind(operation.id)
+                .fetch_all(&mut *tx)
+                .await?;
+
+        for (game_id, data) in snapshots {
+            sqlx::query(&restore)
+                .bind(&data)
+                .bind(game_id)
+                .execute(&mut *tx)
+                .await?;
+            if !game_ids.contains(&game_id) {
+                game_ids.push(game_id);
+            }
+        }
+
+        let (undone_at,): (String,) = sqlx::query_as(
+            "UPDATE undo_operations SET undone_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ? RETURNING undone_at",
+        )
+        .bind(operation.id)
+        .fetch_one(&mut *tx)
+        .await?;
+        operation.undone_at = Some(undone_at);
+    }
+
+    tx.commit().await?;
+    Ok((operations, game_ids))
+}
+
+/// Delete a game and everything recorded for it
+///
+/// Foreign keys aren't enforced on this connection, so dependent rows are
+/// removed explicitly.
+pub async fn delete_game(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    for table in [
+        "play_sessions",
+        "workshop_items",
+        "game_media",
+        "game_compat",
+        "game_settings",
+        "game_runtimes",
+        "game_size_history",
+        "undo_snapshots",
+        "discovery_queue",
+        "enrichment_blocklist",
+    ] {
+        sqlx::query(&format!("DELETE FROM {} WHERE game_id = ?", table))
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Links to the game go with it; its editions and DLC become standalone
+    sqlx::query("DELETE FROM link_proposals WHERE game_id = ? OR parent_game_id = ?")
+        .bind(id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE games SET parent_game_id = NULL WHERE parent_game_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Wishlist history and notifications outlive the game
+    for table in ["wishlist_items", "wishlist_prices", "notifications"] {
+        sqlx::query(&format!(
+            "UPDATE {} SET game_id = NULL WHERE game_id = ?",
+            table
+        ))
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("DELETE FROM games WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Claim a game for an archive/restore job
+///
+/// Returns false if another job is already running for it or the game is
+/// not in the expected archived state.
+pub async fn begin_archive_job(
+    pool: &SqlitePool,
+    id: i64,
+    status: &str,
+    expect_archived: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE games SET archive_status = ?, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+          AND COALESCE(archived, 0) = ?
+          AND (archive_status IS NULL OR archive_status LIKE 'failed%')
+        "#,
+    )
+    .bind(status)
+    .bind(id)
+    .bind(expect_archived as i64)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record the outcome of an archive/restore job that failed
+pub async fn fail_archive_job(pool: &SqlitePool, id: i64, error: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE games SET archive_status = ? WHERE id = ?")
+        .bind(format!("failed: {}", error))
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Mark a game as archived to cold storage
+pub async fn mark_game_archived(
+    pool: &SqlitePool,
+    id: i64,
+    archive_path: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE games SET
+            archived = 1,
+            archive_path = ?,
+            archived_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now'),
+            archive_status = NULL,
+            local_cover_path = NULL,
+            local_background_path = NULL,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(archive_path)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark a game as restored from cold storage
+pub async fn mark_game_restored(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE games SET
+            archived = 0,
+            archive_status = NULL,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Insert a fully matched made-up game for demo mode
+pub async fn insert_demo_game(
+    pool: &SqlitePool,
+    game: &DemoGame,
+    folder_path: &str,
+    review_summary: &str,
+) -> Result<i64, sqlx::Error> {
+    let folder_name = folder_path.rsplit('/').next().unwrap_or(folder_path);
+    let json = |values: &[&str]| serde_json::to_string(values).unwrap_or_default();
+
+    let (id,): (i64,) = fetch_one_committed(
+        pool,
+        sqlx::query_as(
+        r#"
+        INSERT INTO games (
+            folder_path, folder_name, title, summary, release_date, release_date_iso,
+            local_cover_path, local_background_path,
+            genres, developers, publishers,
+            review_score, review_count, review_summary, review_band,
+            size_bytes, match_confidence, match_status, user_status,
+            deck_compat, deck_compat_checked_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1.0, 'matched', ?, ?, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+        RETURNING id
+        "#,
+    )
+    .bind(folder_path)
+    .bind(folder_name)
+    .bind(game.title)
+    .bind(game.summary)
+    .bind(game.release_date)
+    .bind(freshness::release_date_iso(game.release_date))
+    .bind(format!("{}/.gamevault/cover.jpg", folder_path))
+    .bind(format!("{}/.gamevault/background.jpg", folder_path))
+    .bind(json(game.genres))
+    .bind(json(&[game.developer]))
+    .bind(json(&[game.publisher]))
+    .bind(game.review_score)
+    .bind(game.review_count)
+    .bind(review_summary)
+    .bind(ReviewBand::from_summary(review_summary).map(ReviewBand::as_str))
+    .bind((game.size_gb * 1024.0 * 1024.0 * 1024.0) as i64)
+    .bind(game.user_status)
+    .bind(game.deck_compat),
+    )
+    .await?;
+
+    Ok(id)
+}
+
+/// Record a finished play session that ended `days_ago` days ago (demo mode)
+pub async fn insert_demo_play_session(
+    pool: &SqlitePool,
+    game_id: i64,
+    days_ago: i64,
+    minutes: i64,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO play_sessions (game_id, started_at, ended_at, active_mins)
+        VALUES (
+            ?1,
+            strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-' || ?2 || ' days', '-' || ?3 || ' minutes'),
+            strftime('%Y-%m-%dT%H:%M:%SZ', 'now', '-' || ?2 || ' days'),
+            ?3
+        )
+        "#,
+    )
+    .bind(game_id)
+    .bind(days_ago)
+    .bind(minutes)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE games SET playtime_mins = COALESCE(playtime_mins, 0) + ? WHERE id = ?")
+        .bind(minutes)
+        .bind(game_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+// ============================================================================
+// Game links
+// ============================================================================
+
+const LINK_PROPOSAL_SELECT: &str = r#"
+    SELECT p.*, g.title AS game_title, parent.title AS parent_title
+    FROM link_proposals p
+    JOIN games g ON g.id = p.game_id
+    LEFT JOIN games parent ON parent.id = p.parent_game_id
+"#;
+
+/// Store a proposal unless the same link was proposed before
+///
+/// Returns whether it was added.
+pub async fn insert_link_proposal(
+    pool: &SqlitePool,
+    link: &ProposedLink,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO link_proposals (game_id, kind, parent_game_id, franchise, source, reason)
+        SELECT ?1, ?2, ?3, ?4, ?5, ?6
+        WHERE NOT EXISTS (
+            SELECT 1 FROM link_proposals
+            WHERE game_id = ?1 AND kind = ?2
+                AND parent_game_id IS ?3 AND franchise IS ?4 COLLATE NOCASE
+        )
+        "#,
+    )
+    .bind(link.game_id)
+    .bind(link.kind)
+    .bind(link.parent_game_id)
+    .bind(link.franchise.as_deref())
+    .bind(link.source)
+    .bind(&link.reason)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Proposals with a status, oldest first
+pub async fn get_link_proposals(
+    pool: &SqlitePool,
+    status: &str,
+) -> Result<Vec<LinkProposal>, sqlx::Error> {
+    // Both games of a proposal must be allowed by the active profile
+    sqlx::query_as::<_, LinkProposal>(&format!(
+        r#"{} WHERE p.status = ?
+            AND EXISTS (SELECT 1 FROM games WHERE games.id = p.game_id AND {profile})
+            AND (p.parent_game_id IS NULL OR EXISTS (
+                SELECT 1 FROM games WHERE games.id = p.parent_game_id AND {profile}
+            ))
+        ORDER BY p.id"#,
+        LINK_PROPOSAL_SELECT,
+        profile = PROFILE_FILTER_SQL
+    ))
+    .bind(status)
+    .fetch_all(pool)
+    .await
+}
+
+/// Confirm (applying the link) or reject a pending proposal
+///
+/// Confirming a link rejects the game's other pending proposals of the same
+/// kind. Returns false when no pending proposal has the id.
+pub async fn resolve_link_proposal(
+    pool: &SqlitePool,
+    id: i64,
+    confirm: bool,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let proposal: Option<(i64, String, Option<i64>, Option<String>)> = sqlx::query_as(
+        "SELECT game_id, kind, parent_game_id, franchise FROM link_proposals WHERE id = ? AND status = 'pending'",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((game_id, kind, parent_game_id, franchise)) = proposal else {
+        return Ok(false);
+    };
+
+    if confirm {
+        let column = if kind == linking::KIND_PARENT {
+            "parent_game_id = ?"
+        } else {
+            "franchise = ?"
+        };
+        let query = format!(
+            "UPDATE games SET {}, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?",
+            column
+        );
+        let update = sqlx::query(&query);
+        let update = if kind == linking::KIND_PARENT {
+            update.bind(parent_game_id)
+        } else {
+            update.bind(franchise)
+        };
+        update.bind(game_id).execute(&mut *tx).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE link_proposals SET status = 'rejected', resolved_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            WHERE game_id = ? AND kind = ? AND status = 'pending' AND id != ?
+            "#,
+        )
+        .bind(game_id)
+        .bind(&kind)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query("UPDATE link_proposals SET status = ?, resolved_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = ?")
+        .bind(if confirm { "confirmed" } else { "rejected" })
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Set a game's parent and franchise directly (None clears them)
+pub async fn set_game_links(
+    pool: &SqlitePool,
+    id: i64,
+    parent_game_id: Option<i64>,
+    franchise: Option<&str>,
+) -> Result<Option<Game>, sqlx::Error> {
+    fetch_optional_committed(
+        pool,
+        sqlx::query_as::<_, Game>(
+        r#"
+        UPDATE games SET parent_game_id = ?, franchise = ?, updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        RETURNING *
+        "#,
+    )
+    .bind(parent_game_id)
+    .bind(franchise)
+    .bind(id),
+    )
+    .await
+}
+
+// ============================================================================
+// Title mappings
+// ============================================================================
+
+/// Stored app id for a normalized title
+pub async fn get_title_mapping(pool: &SqlitePool, title: &str) -> Result<Option<i64>, sqlx::Error> {
+    let row: Option<(i64,)> =
+        sqlx::query_as("SELECT steam_app_id FROM title_mappings WHERE title = ?")
+            .bind(title)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.map(|r| r.0))
+}
+
+pub async fn get_title_mappings(pool: &SqlitePool) -> Result<Vec<TitleMapping>, sqlx::Error> {
+    sqlx::query_as::<_, TitleMapping>("SELECT * FROM title_mappings ORDER BY title")
+        .fetch_all(pool)
+        .await
+}
+
+/// Insert or replace mappings in one transaction
+pub async fn put_title_mappings(
+    pool: &SqlitePool,
+    mappings: &[(String, i64)],
+    source: &str,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for (title, steam_app_id) in mappings {
+        sqlx::query(
+            r#"
+            INSERT INTO title_mappings (title, steam_app_id, source) VALUES (?, ?, ?)
+            ON CONFLICT(title) DO UPDATE SET
+                steam_app_id = excluded.steam_app_id,
+                source = excluded.source,
+                updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+            "#,
+        )
+        .bind(title)
+        .bind(steam_app_id)
+        .bind(source)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Returns false when the title had no mapping
+pub async fn delete_title_mapping(pool: &SqlitePool, title: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM title_mappings WHERE title = ?")
+        .bind(title)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_profiles(pool: &SqlitePool) -> Result<Vec<Profile>, sqlx::Error> {
+    sqlx::query_as::<_, Profile>(
+        "SELECT id, name, max_age, blocked_genres, allowed_franchises, active, created_at FROM profiles ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns None when a profile with the name exists
+pub async fn insert_profile(
+    pool: &SqlitePool,
+    name: &str,
+    max_age: Option<i64>,
+    blocked_genres: Option<&str>,
+    allowed_franchises: Option<&str>,
+    pin_hash: &str,
+) -> Result<Option<Profile>, sqlx::Error> {
+    fetch_optional_committed(
+        pool,
+        sqlx::query_as::<_, Profile>(
+            r#"
+            INSERT INTO profiles (name, max_age, blocked_genres, allowed_franchises, pin_hash)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(name) DO NOTHING
+            RETURNING id, name, max_age, blocked_genres, allowed_franchises, active, created_at
+            "#,
+        )
+        .bind(name)
+        .bind(max_age)
+        .bind(blocked_genres)
+        .bind(allowed_franchises)
+        .bind(pin_hash),
+    )
+    .await
+}
+
+/// Returns false when no profile has the id
+pub async fn delete_profile(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM profiles WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The active profile's id and PIN hash
+pub async fn get_active_profile_pin(
+    pool: &SqlitePool,
+) -> Result<Option<(i64, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT id, pin_hash FROM profiles WHERE active = 1")
+        .fetch_optional(pool)
+        .await
+}
+
+/// Make a profile the active one (None = no restrictions)
+///
+/// Returns false when no profile has the id.
+pub async fn set_active_profile(pool: &SqlitePool, id: Option<i64>) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    if let Some(id) = id {
+        let exists: Option<(i64,)> = sqlx::query_as("SELECT id FROM profiles WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Ok(false);
+        }
+    }
+    sqlx::query("UPDATE profiles SET active = (id IS ?)")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn get_custom_fields(pool: &SqlitePool) -> Result<Vec<CustomField>, sqlx::Error> {
+    sqlx::query_as::<_, CustomField>("SELECT * FROM custom_fields ORDER BY label")
+        .fetch_all(pool)
+        .await
+}
+
+/// Returns false when a field with the key exists
+pub async fn insert_custom_field(
+    pool: &SqlitePool,
+    key: &str,
+    label: &str,
+    field_type: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO custom_fields (key, label, field_type) VALUES (?, ?, ?) ON CONFLICT(key) DO NOTHING",
+    )
+    .bind(key)
+    .bind(label)
+    .bind(field_type)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove a field and every game's value of it
+///
+/// Returns false when no field has the key.
+pub async fn delete_custom_field(pool: &SqlitePool, key: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query("DELETE FROM custom_fields WHERE key = ?")
+        .bind(key)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    sqlx::query(
+        r#"
+        UPDATE games SET custom_fields = NULLIF(json_remove(custom_fields, ?), '{}')
+        WHERE json_type(custom_fields, ?) IS NOT NULL
+        "#,
+    )
+    .bind(custom_fields::json_path(key))
+    .bind(custom_fields::json_path(key))
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Store a game's custom field values (JSON object, None = no values)
+/// Returns the updated Game for dual-write to metadata.json
+pub async fn update_game_custom_fields(
+    pool: &SqlitePool,
+    id: i64,
+    custom_fields: Option<&str>,
+) -> Result<Game, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    record_undo_operation(
+        &mut tx,
+        undo::KIND_EDIT,
+        &format!("Edit custom fields of game {}", id),
+        &[id],
+    )
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE games SET
+            custom_fields = ?,
+            updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(custom_fields)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    let game = sqlx::query_as::<_, Game>("SELECT * FROM games WHERE id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(game)
+}
+
+/// A migrated in-memory pool for tests
+///
+/// The database lives as long as the returned connection; keep it bound
+/// (`let (pool, _keepalive) = ...`) for the length of the test.
+#[cfg(test)]
+pub async fn test_pool() -> (SqlitePool, SqliteConnection) {
+    let (pool, keepalive) = crate::demo::connect().await.unwrap();
+    run_migrations(&pool).await.unwrap();
+    (pool, keepalive)
+}
+
+/// Databases for tests that need to behave like the server's
+#[cfg(test)]
+pub mod testing {
+    use std::path::Path;
+
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::{Connection, SqliteConnection, SqlitePool};
+
+    /// A migrated pool on a database file in `dir`, and a connection to the
+    /// same file outside the pool
+    ///
+    /// Unlike the in-memory database, this shows whether a write was
+    /// committed before it returned: the outside connection only sees
+    /// committed rows, and nothing it runs can commit a write pending on a
+    /// pool connection.
+    pub async fn connect_file(dir: &Path) -> (SqlitePool, SqliteConnection) {
+        let url = format!("sqlite:{}?mode=rwc", dir.join("gamevault.db").display());
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect(&url)
+            .await
+            .unwrap();
+        super::run_migrations(&pool).await.unwrap();
+        let outside = SqliteConnection::connect(&url).await.unwrap();
+        (pool, outside)
+    }
+
+    /// Whether the row with this id is committed
+    pub async fn committed(outside: &mut SqliteConnection, table: &str, id: i64) -> bool {
+        let sql = format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?)", table);
+        let (found,): (bool,) = sqlx::query_as(&sql)
+            .bind(id)
+            .fetch_one(outside)
+            .await
+            .unwrap();
+        found
+    }
+}
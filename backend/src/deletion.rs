@@ -0,0 +1,86 @@
+//! Guarded deletion of game files
+//!
+//! Deleting a game folder is a two-step operation: the first request returns
+//! a short-lived confirmation token, the second request must echo it back.
+//! Folders are moved to the OS recycle bin rather than deleted outright.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rand::{distributions::Alphanumeric, Rng};
+
+/// How long a confirmation token stays valid
+pub const CONFIRMATION_TTL: Duration = Duration::from_secs(300);
+
+const TOKEN_LENGTH: usize = 32;
+
+/// Pending delete confirmations, one per game
+#[derive(Default)]
+pub struct ConfirmationTokens {
+    pending: Mutex<HashMap<i64, (String, Instant)>>,
+}
+
+impl ConfirmationTokens {
+    /// Issue a new token for a game, replacing any earlier one
+    pub fn issue(&self, game_id: i64) -> String {
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.retain(|_, (_, issued)| issued.elapsed() < CONFIRMATION_TTL);
+            pending.insert(game_id, (token.clone(), Instant::now()));
+        }
+
+        token
+    }
+
+    /// Consume a token; true only if it matches the unexpired token for this game
+    pub fn consume(&self, game_id: i64, token: &str) -> bool {
+        let Ok(mut pending) = self.pending.lock() else {
+            return false;
+        };
+
+        match pending.remove(&game_id) {
+            Some((expected, issued)) => {
+                issued.elapsed() < CONFIRMATION_TTL && !token.is_empty() && expected == token
+            }
+            None => false,
+        }
+    }
+}
+
+/// Move a folder to the OS recycle bin / trash
+pub fn move_to_trash(path: &Path) -> Result<(), trash::Error> {
+    trash::delete(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmation_token_single_use() {
+        let tokens = ConfirmationTokens::default();
+        let token = tokens.issue(1);
+
+        assert_eq!(token.len(), TOKEN_LENGTH);
+        assert!(!tokens.consume(2, &token), "token is bound to its game");
+        // A failed attempt for another game doesn't burn this game's token
+        assert!(tokens.consume(1, &token));
+        assert!(!tokens.consume(1, &token), "token can only be used once");
+    }
+
+    #[test]
+    fn test_confirmation_token_wrong_value() {
+        let tokens = ConfirmationTokens::default();
+        tokens.issue(1);
+
+        assert!(!tokens.consume(1, "guess"));
+        assert!(!tokens.consume(1, ""));
+    }
+}
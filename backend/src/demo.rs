@@ -0,0 +1,303 @@
+//! Self-contained demo mode (`--demo`)
+//!
+//! Runs against an in-memory database seeded with made-up games instead of
+//! the configured library, so the UI can be tried out or a bug reproduced
+//! without exposing real paths. Nothing is read from or written to
+//! config.toml, and cover/background requests are answered with generated
+//! placeholder art.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{ConnectOptions, SqliteConnection, SqlitePool};
+
+use crate::config::AppConfig;
+use crate::db;
+
+/// Command line flag that enables demo mode
+pub const DEMO_FLAG: &str = "--demo";
+
+/// Library path shown for the demo games (never touched on disk)
+pub const DEMO_LIBRARY: &str = "Demo Library";
+
+/// A made-up game in the demo library
+pub struct DemoGame {
+    pub title: &'static str,
+    pub summary: &'static str,
+    pub genres: &'static [&'static str],
+    pub developer: &'static str,
+    pub publisher: &'static str,
+    pub release_date: &'static str,
+    pub review_score: i64,
+    pub review_count: i64,
+    pub size_gb: f64,
+    pub user_status: &'static str,
+    /// Recent play sessions as (days ago, minutes)
+    pub sessions: &'static [(i64, i64)],
+    pub deck_compat: &'static str,
+}
+
+const DEMO_GAMES: &[DemoGame] = &[
+    DemoGame {
+        title: "Starfall Tactics",
+        summary: "Command a ragtag fleet through a collapsing star cluster in turn-based battles.",
+        genres: &["Strategy", "Indie"],
+        developer: "Lantern Forge",
+        publisher: "Lantern Forge",
+        release_date: "Mar 14, 2019",
+        review_score: 91,
+        review_count: 18_240,
+        size_gb: 3.2,
+        user_status: "completed",
+        sessions: &[(20, 95), (18, 140), (12, 60)],
+        deck_compat: "verified",
+    },
+    DemoGame {
+        title: "Hollow Lantern",
+        summary: "A hand-drawn metroidvania about a lamplighter exploring a drowned city.",
+        genres: &["Action", "Adventure", "Indie"],
+        developer: "Mothwing Studio",
+        publisher: "Paper Boat",
+        release_date: "Oct 2, 2021",
+        review_score: 95,
+        review_count: 52_110,
+        size_gb: 8.7,
+        user_status: "playing",
+        sessions: &[(6, 45), (3, 120), (1, 30)],
+        deck_compat: "verified",
+    },
+    DemoGame {
+        title: "Rust Belt Racers",
+        summary: "Arcade racing on scrap-built cars across post-industrial highways.",
+        genres: &["Racing", "Sports"],
+        developer: "Gearbox Garage",
+        publisher: "Redline Interactive",
+        release_date: "Jun 21, 2017",
+        review_score: 72,
+        review_count: 4_350,
+        size_gb: 21.4,
+        user_status: "abandoned",
+        sessions: &[(40, 25)],
+        deck_compat: "playable",
+    },
+    DemoGame {
+        title: "The Quiet Orchard",
+        summary: "Tend an orchard, befriend the village and uncover why the bees went silent.",
+        genres: &["Simulation", "RPG", "Casual"],
+        developer: "Sunday Soil",
+        publisher: "Sunday Soil",
+        release_date: "Apr 9, 2022",
+        review_score: 88,
+        review_count: 9_870,
+        size_gb: 1.1,
+        user_status: "playing",
+        sessions: &[(9, 80), (2, 150)],
+        deck_compat: "verified",
+    },
+    DemoGame {
+        title: "Iron Meridian",
+        summary: "Open-world mech RPG with a branching campaign and deep part customization.",
+        genres: &["RPG", "Action"],
+        developer: "Northlight Works",
+        publisher: "Meridian Games",
+        release_date: "Nov 30, 2023",
+        review_score: 79,
+        review_count: 31_400,
+        size_gb: 96.5,
+        user_status: "unplayed",
+        sessions: &[],
+        deck_compat: "unsupported",
+    },
+    DemoGame {
+        title: "Pixel Dungeon Deluxe",
+        summary: "A roguelike dungeon crawler with hundreds of items and daily challenges.",
+        genres: &["RPG", "Indie"],
+        developer: "Two Bit Crew",
+        publisher: "Two Bit Crew",
+        release_date: "Jan 17, 2016",
+        review_score: 93,
+        review_count: 12_660,
+        size_gb: 0.4,
+        user_status: "completed",
+        sessions: &[(30, 50), (29, 70)],
+        deck_compat: "verified",
+    },
+    DemoGame {
+        title: "Cold Signal",
+        summary: "A first-person mystery set in an abandoned arctic radio station.",
+        genres: &["Adventure", "Indie"],
+        developer: "Whiteout",
+        publisher: "Paper Boat",
+        release_date: "Feb 28, 2020",
+        review_score: 84,
+        review_count: 6_020,
+        size_gb: 5.9,
+        user_status: "unplayed",
+        sessions: &[],
+        deck_compat: "playable",
+    },
+    DemoGame {
+        title: "Kingdoms of Ash and Ember",
+        summary: "Grand strategy across three centuries of a fractured empire.",
+        genres: &["Strategy", "Simulation"],
+        developer: "Crownfall",
+        publisher: "Meridian Games",
+        release_date: "Aug 5, 2018",
+        review_score: 68,
+        review_count: 15_900,
+        size_gb: 14.2,
+        user_status: "unplayed",
+        sessions: &[],
+        deck_compat: "unknown",
+    },
+];
+
+/// Whether demo mode was requested on the command line
+pub fn is_requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == DEMO_FLAG)
+}
+
+/// Built-in configuration for demo mode (the user's config.toml is ignored)
+pub fn demo_config() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.paths.game_library = PathBuf::from(DEMO_LIBRARY);
+    config.paths.database = "sqlite::memory:".to_string();
+    config
+}
+
+/// Open the in-memory demo database
+///
+/// Every pool connection shares the same in-memory database, which lives only
+/// as long as one connection is open. The returned connection keeps it alive
+/// (migrations detach the pool's only connection) and must be held until exit.
+pub async fn connect() -> Result<(SqlitePool, SqliteConnection), sqlx::Error> {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+    let keepalive = options.connect().await?;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await?;
+    Ok((pool, keepalive))
+}
+
+/// Fill the demo database with the made-up library
+pub async fn seed(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    for game in DEMO_GAMES {
+        let folder_path = format!("{}/{}", DEMO_LIBRARY, game.title);
+        let id = db::insert_demo_game(pool, game, &folder_path, review_summary(game.review_score))
+            .await?;
+
+        for (days_ago, minutes) in game.sessions {
+            db::insert_demo_play_session(pool, id, *days_ago, *minutes).await?;
+        }
+    }
+
+    db::create_wishlist_item(
+        pool,
+        "Skyline Couriers",
+        None,
+        Some("Wait for a sale under $15"),
+    )
+    .await?;
+
+    tracing::info!("Demo library seeded with {} games", DEMO_GAMES.len());
+    Ok(())
+}
+
+/// Steam-style review summary for a score
+fn review_summary(score: i64) -> &'static str {
+    match score {
+        95.. => "Overwhelmingly Positive",
+        85..=94 => "Very Positive",
+        80..=84 => "Positive",
+        70..=79 => "Mostly Positive",
+        40..=69 => "Mixed",
+        _ => "Mostly Negative",
+    }
+}
+
+/// Escape text for use inside SVG markup
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Generated placeholder art for a demo game (SVG)
+///
+/// Covers are portrait (600x900), backgrounds are wide (1920x620); the
+/// colours are picked from the game id so each game keeps its look.
+pub fn placeholder_art(game_id: i64, title: &str, background: bool) -> String {
+    const PALETTE: &[(&str, &str)] = &[
+        ("#1e3a8a", "#0f172a"),
+        ("#7c2d12", "#1c1917"),
+        ("#065f46", "#022c22"),
+        ("#6b21a8", "#1e1b4b"),
+        ("#9f1239", "#1f2937"),
+        ("#0e7490", "#082f49"),
+    ];
+    let (from, to) = PALETTE[game_id.rem_euclid(PALETTE.len() as i64) as usize];
+    let (width, height, font_size) = if background {
+        (1920, 620, 96)
+    } else {
+        (600, 900, 56)
+    };
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">
+<defs><linearGradient id="g" x1="0" y1="0" x2="1" y2="1">
+<stop offset="0" stop-color="{from}"/><stop offset="1" stop-color="{to}"/>
+</linearGradient></defs>
+<rect width="100%" height="100%" fill="url(#g)"/>
+<text x="50%" y="50%" fill="#f8fafc" fill-opacity="0.9" font-family="sans-serif" font-size="{font_size}" font-weight="bold" text-anchor="middle" dominant-baseline="middle">{title}</text>
+<text x="50%" y="92%" fill="#f8fafc" fill-opacity="0.5" font-family="sans-serif" font-size="24" text-anchor="middle">GameVault demo</text>
+</svg>"##,
+        w = width,
+        h = height,
+        from = from,
+        to = to,
+        font_size = font_size,
+        title = escape_xml(title),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder_art() {
+        let cover = placeholder_art(3, "Salt & <Sanctuary>", false);
+        assert!(cover.starts_with("<svg"));
+        assert!(cover.contains(r#"width="600" height="900""#));
+        assert!(cover.contains("Salt &amp; &lt;Sanctuary&gt;"));
+
+        let background = placeholder_art(3, "Salt", true);
+        assert!(background.contains(r#"width="1920""#));
+        assert_eq!(
+            placeholder_art(-1, "x", false),
+            placeholder_art(5, "x", false)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seed_demo_library() {
+        let (pool, _keepalive) = connect().await.unwrap();
+        db::run_migrations(&pool).await.unwrap();
+        seed(&pool).await.unwrap();
+
+        let games = db::get_all_games(&pool).await.unwrap();
+        assert_eq!(games.len(), DEMO_GAMES.len());
+        assert!(games
+            .iter()
+            .all(|g| g.folder_path.starts_with(DEMO_LIBRARY)));
+        assert!(games.iter().all(|g| g.match_status == "matched"));
+
+        let played = games.iter().find(|g| g.title == "Hollow Lantern").unwrap();
+        assert_eq!(played.playtime_mins, Some(195));
+    }
+}
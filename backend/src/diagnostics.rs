@@ -0,0 +1,353 @@
+//! Launch diagnostics
+//!
+//! When a launch fails (the executable can't be started, or it exits with an
+//! error within `EARLY_EXIT_WINDOW`) a diagnostic bundle is written to
+//! `.gamevault/diagnostics/` in the game folder: the resolved executable,
+//! its arguments, working directory and environment (secrets redacted), the
+//! DLLs from its import table that can't be found next to it, in the Windows
+//! system directories or on PATH, and the latest server log lines. The usual
+//! reasons a repack won't start, such as a missing `steam_api64.dll` or an
+//! uninstalled Visual C++ runtime (`MSVCP140.dll`), show up in the missing
+//! DLL list.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::models::Game;
+use crate::{crash, local_storage, notifications, timestamps};
+
+/// A game exiting with an error this soon after launch failed to start
+pub const EARLY_EXIT_WINDOW: Duration = Duration::from_secs(30);
+
+/// Bundles kept per game; older ones are deleted
+pub const MAX_BUNDLES: usize = 10;
+
+/// Log lines copied into a bundle
+const LOG_LINES: usize = 50;
+
+const BUNDLE_PREFIX: &str = "launch-";
+
+/// Environment variables whose names contain these are redacted
+const SECRET_MARKERS: &[&str] = &["KEY", "TOKEN", "SECRET", "PASSWORD", "PASSWD", "CREDENTIAL"];
+
+/// DLL name prefixes Windows resolves virtually (API sets)
+const VIRTUAL_DLL_PREFIXES: &[&str] = &["api-ms-win-", "ext-ms-"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchDiagnostics {
+    /// When the bundle was captured (UTC)
+    pub captured_at: String,
+    pub game_id: i64,
+    pub game_title: String,
+    /// Why the launch counted as failed
+    pub failure: String,
+    pub exit_code: Option<i32>,
+    /// How long the process ran before exiting
+    pub ran_secs: Option<u64>,
+    pub executable: String,
+    pub executable_size: Option<u64>,
+    pub working_dir: String,
+    pub args: Vec<String>,
+    /// "x86" or "x64" (None when the executable isn't a readable PE file)
+    pub architecture: Option<String>,
+    /// DLLs in the import table
+    pub imported_dlls: Vec<String>,
+    /// Imported DLLs that Windows won't find (None when there's no Windows
+    /// system directory to check against)
+    pub missing_dlls: Option<Vec<String>>,
+    pub environment: BTreeMap<String, String>,
+    /// Server log lines before the failure, oldest first
+    pub recent_logs: Vec<String>,
+}
+
+/// How a launch failed
+#[derive(Debug, Clone)]
+pub struct LaunchFailure {
+    pub reason: String,
+    pub exit_code: Option<i32>,
+    pub ran_for: Option<Duration>,
+}
+
+/// Architecture and imported DLL names of a PE executable
+pub fn read_imports(exe: &Path) -> Option<(&'static str, Vec<String>)> {
+    let bytes = std::fs::read(exe).ok()?;
+    let file = pelite::PeFile::from_bytes(&bytes).ok()?;
+    let architecture = match file {
+        pelite::Wrap::T32(_) => "x86",
+        pelite::Wrap::T64(_) => "x64",
+    };
+
+    let dlls = match file.imports() {
+        Ok(imports) => imports
+            .into_iter()
+            .filter_map(|desc| desc.dll_name().ok())
+            .map(|name| name.to_string())
+            .collect(),
+        // No import directory at all
+        Err(e) if e.is_null() => Vec::new(),
+        Err(_) => return None,
+    };
+    Some((architecture, dlls))
+}
+
+/// Windows directories searched for DLLs besides the executable's own and PATH
+fn system_dirs(architecture: Option<&str>) -> Vec<PathBuf> {
+    let Some(root) = std::env::var_os("SystemRoot").or_else(|| std::env::var_os("windir")) else {
+        return Vec::new();
+    };
+    let root = PathBuf::from(root);
+    // 32-bit programs on 64-bit Windows get SysWOW64 instead of System32
+    let wow64 = root.join("SysWOW64");
+    let system = if architecture == Some("x86") && wow64.is_dir() {
+        wow64
+    } else {
+        root.join("System32")
+    };
+    vec![system, root]
+}
+
+/// Lowercase file names in a directory
+fn file_names(dir: &Path) -> HashSet<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Imported DLLs found in none of the search directories
+pub fn find_missing_dlls(dlls: &[String], search_dirs: &[PathBuf]) -> Vec<String> {
+    let available: Vec<HashSet<String>> = search_dirs.iter().map(|d| file_names(d)).collect();
+    dlls.iter()
+        .filter(|dll| {
+            let lower = dll.to_lowercase();
+            !VIRTUAL_DLL_PREFIXES.iter().any(|p| lower.starts_with(p))
+                && !available.iter().any(|names| names.contains(&lower))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Process environment with secret values redacted
+fn environment() -> BTreeMap<String, String> {
+    std::env::vars()
+        .map(|(name, value)| {
+            let upper = name.to_uppercase();
+            if SECRET_MARKERS.iter().any(|m| upper.contains(m)) {
+                (name, "[redacted]".to_string())
+            } else {
+                (name, value)
+            }
+        })
+        .collect()
+}
+
+/// Collect a diagnostic bundle for a failed launch
+pub fn capture(
+    game: &Game,
+    exe: &Path,
+    args: &[String],
+    failure: &LaunchFailure,
+) -> LaunchDiagnostics {
+    let exe_dir = exe.parent().unwrap_or(Path::new(""));
+    let imports = read_imports(exe);
+    let architecture = imports.as_ref().map(|(arch, _)| *arch);
+
+    let missing_dlls = match (&imports, system_dirs(architecture)) {
+        (Some((_, dlls)), system) if !system.is_empty() => {
+            let mut search = vec![exe_dir.to_path_buf()];
+            search.extend(system);
+            if let Some(path) = std::env::var_os("PATH") {
+                search.extend(std::env::split_paths(&path));
+            }
+            Some(find_missing_dlls(dlls, &search))
+        }
+        _ => None,
+    };
+
+    LaunchDiagnostics {
+        captured_at: timestamps::now(),
+        game_id: game.id,
+        game_title: game.title.clone(),
+        failure: failure.reason.clone(),
+        exit_code: failure.exit_code,
+        ran_secs: failure.ran_for.map(|d| d.as_secs()),
+        executable: exe.to_string_lossy().to_string(),
+        executable_size: std::fs::metadata(exe).ok().map(|m| m.len()),
+        working_dir: exe_dir.to_string_lossy().to_string(),
+        args: args.to_vec(),
+        architecture: architecture.map(str::to_string),
+        imported_dlls: imports.map(|(_, dlls)| dlls).unwrap_or_default(),
+        missing_dlls,
+        environment: environment(),
+        recent_logs: crash::recent_logs(LOG_LINES),
+    }
+}
+
+/// Write a bundle to the game's diagnostics folder, keeping the newest `MAX_BUNDLES`
+pub fn save(game_folder: &str, diagnostics: &LaunchDiagnostics) -> std::io::Result<PathBuf> {
+    let dir = local_storage::get_diagnostics_dir(game_folder);
+    std::fs::create_dir_all(&dir)?;
+
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S%.6f");
+    let mut path = dir.join(format!("{}{}.json", BUNDLE_PREFIX, stamp));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(format!("{}{}-{}.json", BUNDLE_PREFIX, stamp, n));
+        n += 1;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(diagnostics)?)?;
+
+    for old in bundle_paths(&dir).into_iter().skip(MAX_BUNDLES) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Bundle files, newest first (names sort by capture time)
+fn bundle_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .is_some_and(|n| n.to_string_lossy().starts_with(BUNDLE_PREFIX))
+                        && p.extension().is_some_and(|e| e == "json")
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+/// Stored bundles for a game folder, newest first
+pub fn list(game_folder: &str) -> Vec<LaunchDiagnostics> {
+    bundle_paths(&local_storage::get_diagnostics_dir(game_folder))
+        .iter()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(path).ok()?;
+            serde_json::from_str(&text).ok()
+        })
+        .collect()
+}
+
+/// Capture and save a bundle for a failed launch, and say so in the inbox
+pub async fn record_failure(pool: &SqlitePool, game: Game, exe: PathBuf, failure: LaunchFailure) {
+    let captured = tokio::task::spawn_blocking(move || {
+        let diagnostics = capture(&game, &exe, &[], &failure);
+        let saved = save(&game.folder_path, &diagnostics);
+        (game, diagnostics, saved)
+    })
+    .await;
+
+    let (game, diagnostics, saved) = match captured {
+        Ok(captured) => captured,
+        Err(e) => {
+            tracing::warn!("Launch diagnostics task failed: {}", e);
+            return;
+        }
+    };
+
+    match &saved {
+        Ok(path) => tracing::info!("Launch diagnostics for {} saved to {:?}", game.title, path),
+        Err(e) => tracing::warn!(
+            "Failed to save launch diagnostics for {}: {}",
+            game.title,
+            e
+        ),
+    }
+
+    let mut message = format!("{} {}.", game.title, diagnostics.failure);
+    match diagnostics.missing_dlls.as_deref() {
+        Some([]) | None => {}
+        Some(missing) => message.push_str(&format!(" Missing DLLs: {}.", missing.join(", "))),
+    }
+    if saved.is_ok() {
+        message.push_str(" Diagnostics were saved.");
+    }
+    notifications::notify(
+        pool,
+        notifications::KIND_LAUNCH_FAILED,
+        "Game failed to start",
+        &message,
+        Some(game.id),
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn game(folder: &Path) -> Game {
+        Game {
+            folder_path: folder.to_string_lossy().into_owned(),
+            ..create_test_game(3, "Hades")
+        }
+    }
+
+    #[test]
+    fn test_find_missing_dlls() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let system_dir = tempfile::tempdir().unwrap();
+        std::fs::write(game_dir.path().join("steam_api64.dll"), b"").unwrap();
+        std::fs::write(system_dir.path().join("kernel32.dll"), b"").unwrap();
+
+        let dlls: Vec<String> = [
+            "KERNEL32.dll",
+            "steam_api64.dll",
+            "MSVCP140.dll",
+            "api-ms-win-crt-runtime-l1-1-0.dll",
+        ]
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+        let search = [
+            game_dir.path().to_path_buf(),
+            system_dir.path().to_path_buf(),
+        ];
+        assert_eq!(find_missing_dlls(&dlls, &search), ["MSVCP140.dll"]);
+    }
+
+    #[test]
+    fn test_save_and_list_bundles() {
+        let folder = tempfile::tempdir().unwrap();
+        let folder_path = folder.path().to_string_lossy().to_string();
+        let exe = folder.path().join("Hades.exe");
+        std::fs::write(&exe, b"not a PE file").unwrap();
+
+        let failure = LaunchFailure {
+            reason: "exited with code 1 after 2 seconds".to_string(),
+            exit_code: Some(1),
+            ran_for: Some(Duration::from_secs(2)),
+        };
+        let diagnostics = capture(&game(folder.path()), &exe, &[], &failure);
+        assert_eq!(diagnostics.architecture, None);
+        assert!(diagnostics.imported_dlls.is_empty());
+        assert_eq!(diagnostics.executable_size, Some(13));
+        assert!(diagnostics
+            .environment
+            .iter()
+            .all(|(name, value)| !name.to_uppercase().contains("TOKEN") || value == "[redacted]"));
+
+        for _ in 0..MAX_BUNDLES + 2 {
+            save(&folder_path, &diagnostics).unwrap();
+        }
+        let bundles = list(&folder_path);
+        assert_eq!(bundles.len(), MAX_BUNDLES);
+        assert_eq!(bundles[0].exit_code, Some(1));
+    }
+}
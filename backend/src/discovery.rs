@@ -0,0 +1,160 @@
+//! Daily discovery queue
+//!
+//! Every day (UTC) a queue of unplayed library games is drawn at random,
+//! seeded by the date. Better reviewed games are more likely to be picked and
+//! games sharing genres with earlier picks less likely, so the queue mixes
+//! genres. The first request of the day stores the queue, so every client in
+//! the household sees the same rotation until the next day.
+
+use std::collections::HashSet;
+
+use chrono::{NaiveDate, Utc};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    db,
+    models::{Game, GameSummary},
+};
+
+/// Games in each day's queue
+pub const QUEUE_SIZE: usize = 10;
+
+/// Review score assumed for games without reviews
+const DEFAULT_REVIEW_SCORE: i64 = 60;
+
+/// Weight multiplier per genre shared with games already in the queue
+const GENRE_REPEAT_FACTOR: f64 = 0.5;
+
+#[derive(Debug, Serialize)]
+pub struct DiscoveryQueue {
+    /// Day the queue belongs to (YYYY-MM-DD, UTC)
+    pub date: String,
+    pub games: Vec<GameSummary>,
+}
+
+/// Seed for a day's draw (YYYYMMDD)
+fn day_seed(day: NaiveDate) -> u64 {
+    day.format("%Y%m%d").to_string().parse().unwrap_or_default()
+}
+
+fn genres(game: &Game) -> Vec<String> {
+    game.genres
+        .as_deref()
+        .and_then(|s| serde_json::from_str::<Vec<String>>(s).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|g| g.to_lowercase())
+        .collect()
+}
+
+/// Draw up to `size` game ids, weighted by review score and genre variety
+///
+/// The same candidates and seed always give the same queue.
+pub fn draw_queue(candidates: &[Game], seed: u64, size: usize) -> Vec<i64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut remaining: Vec<(&Game, Vec<String>)> =
+        candidates.iter().map(|g| (g, genres(g))).collect();
+    let mut seen_genres: HashSet<String> = HashSet::new();
+    let mut queue = Vec::with_capacity(size);
+
+    while queue.len() < size && !remaining.is_empty() {
+        let weights: Vec<f64> = remaining
+            .iter()
+            .map(|(game, genres)| {
+                let score = game
+                    .review_score
+                    .unwrap_or(DEFAULT_REVIEW_SCORE)
+                    .clamp(0, 100) as f64
+                    / 100.0;
+                let repeats = genres.iter().filter(|g| seen_genres.contains(*g)).count();
+                (score * score + 0.05) * GENRE_REPEAT_FACTOR.powi(repeats as i32)
+            })
+            .collect();
+
+        let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        let mut index = weights.len() - 1;
+        for (i, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                index = i;
+                break;
+            }
+            target -= weight;
+        }
+
+        let (game, genres) = remaining.remove(index);
+        seen_genres.extend(genres);
+        queue.push(game.id);
+    }
+
+    queue
+}
+
+/// Today's queue, drawn and stored on the first request of the day
+pub async fn todays_queue(pool: &SqlitePool) -> Result<DiscoveryQueue, sqlx::Error> {
+    let today = Utc::now().date_naive();
+    let date = today.format("%Y-%m-%d").to_string();
+
+    let mut games = db::get_discovery_queue(pool, &date).await?;
+    if games.is_empty() {
+        let candidates = db::get_discovery_candidates(pool).await?;
+        let ids = draw_queue(&candidates, day_seed(today), QUEUE_SIZE);
+        db::save_discovery_queue(pool, &date, &ids).await?;
+        games = db::get_discovery_queue(pool, &date).await?;
+    }
+
+    Ok(DiscoveryQueue {
+        date,
+        games: games.into_iter().map(GameSummary::from).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn game(id: i64, review_score: Option<i64>, genres: &[&str]) -> Game {
+        Game {
+            review_score,
+            genres: Some(serde_json::to_string(genres).unwrap()),
+            ..create_test_game(id, &format!("Game {}", id))
+        }
+    }
+
+    #[test]
+    fn test_draw_queue_is_seeded() {
+        let candidates: Vec<Game> = (1..=30)
+            .map(|id| game(id, Some(id * 3), &["RPG"]))
+            .collect();
+
+        let queue = draw_queue(&candidates, 20240510, QUEUE_SIZE);
+        assert_eq!(queue.len(), QUEUE_SIZE);
+        assert_eq!(queue, draw_queue(&candidates, 20240510, QUEUE_SIZE));
+        assert_ne!(queue, draw_queue(&candidates, 20240511, QUEUE_SIZE));
+
+        let unique: HashSet<_> = queue.iter().collect();
+        assert_eq!(unique.len(), QUEUE_SIZE);
+        assert_eq!(draw_queue(&candidates[..3], 1, QUEUE_SIZE).len(), 3);
+    }
+
+    #[test]
+    fn test_draw_queue_prefers_variety() {
+        // Without the genre penalty the strategy game would make a queue of
+        // 3 out of 6 equally rated games half the time; with it, two thirds
+        let mut candidates: Vec<Game> = (1..=5).map(|id| game(id, Some(80), &["RPG"])).collect();
+        candidates.push(game(99, Some(80), &["Strategy"]));
+
+        let picked = (0..200)
+            .filter(|seed| draw_queue(&candidates, *seed, 3).contains(&99))
+            .count();
+        assert!(picked > 110, "strategy game picked only {} times", picked);
+    }
+
+    #[test]
+    fn test_day_seed() {
+        let day = NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        assert_eq!(day_seed(day), 20240510);
+    }
+}
@@ -0,0 +1,290 @@
+//! Image download queue
+//!
+//! Enrichment used to download each game's cover and background inline, one
+//! after the other, at whatever speed the connection allowed. Instead it now
+//! queues the images and moves on: a background worker downloads up to
+//! `[media] download_workers` games' images at once, stores the local paths
+//! and queues the covers for [artwork](crate::artwork) pre-generation.
+//!
+//! Every image download (including wishlist cover [prefetch](crate::prefetch))
+//! draws from one shared [`Bandwidth`] limit of `[media] download_kbps`, so
+//! enriching a few hundred games doesn't saturate the uplink however many
+//! downloads run in parallel.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use sqlx::SqlitePool;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::artwork::ArtworkQueue;
+use crate::config::MediaConfig;
+use crate::http::HttpClient;
+use crate::models::Game;
+use crate::{db, events, local_storage};
+
+/// Global download rate limit shared by every image download
+#[derive(Debug, Default)]
+pub struct Bandwidth {
+    /// 0 = unlimited
+    bytes_per_sec: u64,
+    /// When the bytes reserved so far will have gone through at the limit
+    next: Mutex<Option<Instant>>,
+}
+
+impl Bandwidth {
+    /// Limit to `kbps` KB/s (0 = unlimited)
+    pub fn new(kbps: u32) -> Self {
+        Bandwidth {
+            bytes_per_sec: u64::from(kbps) * 1024,
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Reserve `bytes` and return how long to wait before reading more
+    ///
+    /// Unused time isn't saved up, so an idle period doesn't allow a burst.
+    pub fn reserve(&self, now: Instant, bytes: usize) -> Duration {
+        if self.bytes_per_sec == 0 {
+            return Duration::ZERO;
+        }
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let start = next.map_or(now, |next| next.max(now));
+        let done = start + Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec as f64);
+        *next = Some(done);
+        done.saturating_duration_since(now)
+    }
+
+    /// Account for `bytes` just received, sleeping to stay under the limit
+    pub async fn consume(&self, bytes: usize) {
+        let wait = self.reserve(Instant::now(), bytes);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Images to cache into a game's `.gamevault/` folder
+#[derive(Debug, Clone)]
+pub struct ImageJob {
+    pub game_id: i64,
+    pub folder_path: String,
+    pub cover_url: Option<String>,
+    pub background_url: Option<String>,
+}
+
+/// Images of games whose cached cover or background file is missing
+///
+/// Covers a library copied without its `.gamevault/` folders, or cleaned up
+/// by hand. Games whose folder is gone are left out.
+pub fn missing_images(games: &[Game]) -> Vec<ImageJob> {
+    let missing = |url: &Option<String>, local: &Option<String>| {
+        url.clone()
+            .filter(|_| !local.as_deref().is_some_and(|p| Path::new(p).is_file()))
+    };
+    games
+        .iter()
+        .filter(|g| Path::new(&g.folder_path).is_dir())
+        .map(|g| ImageJob {
+            game_id: g.id,
+            folder_path: g.folder_path.clone(),
+            cover_url: missing(&g.cover_url, &g.local_cover_path),
+            background_url: missing(&g.background_url, &g.local_background_path),
+        })
+        .filter(|job| job.cover_url.is_some() || job.background_url.is_some())
+        .collect()
+}
+
+struct Worker {
+    pool: SqlitePool,
+    http: HttpClient,
+    media: MediaConfig,
+    bandwidth: Arc<Bandwidth>,
+    artwork: ArtworkQueue,
+}
+
+/// Queue of game images to download
+#[derive(Clone)]
+pub struct DownloadQueue {
+    sender: mpsc::UnboundedSender<ImageJob>,
+    bandwidth: Arc<Bandwidth>,
+}
+
+impl DownloadQueue {
+    /// Start the background worker
+    pub fn start(
+        pool: SqlitePool,
+        http: HttpClient,
+        media: MediaConfig,
+        artwork: ArtworkQueue,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let bandwidth = Arc::new(Bandwidth::new(media.download_kbps));
+        let workers = media.download_workers.max(1) as usize;
+        let worker = Worker {
+            pool,
+            http,
+            media,
+            bandwidth: bandwidth.clone(),
+            artwork,
+        };
+        tokio::spawn(run_worker(Arc::new(worker), workers, receiver));
+        Self { sender, bandwidth }
+    }
+
+    /// Queue a game's images (a game already being downloaded is skipped)
+    pub fn enqueue(&self, job: ImageJob) {
+        if job.cover_url.is_none() && job.background_url.is_none() {
+            return;
+        }
+        // Only fails once the worker is gone (shutdown)
+        let _ = self.sender.send(job);
+    }
+
+    /// The download limit, for downloads outside the queue
+    pub fn bandwidth(&self) -> Arc<Bandwidth> {
+        self.bandwidth.clone()
+    }
+}
+
+async fn run_worker(
+    worker: Arc<Worker>,
+    workers: usize,
+    mut receiver: mpsc::UnboundedReceiver<ImageJob>,
+) {
+    let permits = Arc::new(Semaphore::new(workers));
+    // Games with a download running; two downloads must not write one file
+    let active = Arc::new(Mutex::new(HashSet::new()));
+
+    while let Some(job) = receiver.recv().await {
+        if !active
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(job.game_id)
+        {
+            tracing::debug!("Images of game {} are already downloading", job.game_id);
+            continue;
+        }
+        let Ok(permit) = permits.clone().acquire_owned().await else {
+            return;
+        };
+
+        let worker = worker.clone();
+        let active = active.clone();
+        tokio::spawn(async move {
+            let game_id = job.game_id;
+            worker.download(job).await;
+            active
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&game_id);
+            drop(permit);
+        });
+    }
+}
+
+impl Worker {
+    async fn download(&self, job: ImageJob) {
+        let (local_cover, local_bg) = local_storage::cache_game_images(
+            &self.http,
+            &self.media,
+            &self.bandwidth,
+            &job.folder_path,
+            job.cover_url.as_deref(),
+            job.background_url.as_deref(),
+        )
+        .await;
+        if local_cover.is_none() && local_bg.is_none() {
+            return;
+        }
+
+        if let Err(e) = db::update_game_local_images(
+            &self.pool,
+            job.game_id,
+            local_cover.as_deref(),
+            local_bg.as_deref(),
+        )
+        .await
+        {
+            tracing::warn!(
+                "Failed to update local image paths for game {}: {}",
+                job.game_id,
+                e
+            );
+            return;
+        }
+        if local_cover.is_some() {
+            // Pre-generate thumbnails and placeholders before the grid asks for them
+            self.artwork.enqueue([job.game_id]);
+        }
+        events::publish_game(&self.pool, job.game_id, false).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bandwidth() {
+        let start = Instant::now();
+        let limit = Bandwidth::new(100);
+
+        // 50 KB at 100 KB/s takes half a second
+        assert_eq!(limit.reserve(start, 51200), Duration::from_millis(500));
+        // A second download queues up behind the first
+        assert_eq!(limit.reserve(start, 102400), Duration::from_millis(1500));
+        // Idle time isn't saved up
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limit.reserve(later, 10240), Duration::from_millis(100));
+
+        let unlimited = Bandwidth::new(0);
+        assert_eq!(unlimited.reserve(start, 1 << 30), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_missing_images() {
+        let (pool, _keepalive) = db::test_pool().await;
+        let library = tempfile::tempdir().unwrap();
+        let folder = |title: &str| library.path().join(title).to_string_lossy().to_string();
+        for title in ["Hades", "Celeste", "Removed"] {
+            if title != "Removed" {
+                std::fs::create_dir(folder(title)).unwrap();
+            }
+            let id = db::upsert_game(&pool, &folder(title), title, title, None, None)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE games SET cover_url = 'https://example.com/c.jpg', background_url = 'https://example.com/b.jpg' WHERE id = ?")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        // Celeste's cover is cached, its background was deleted
+        let cover = format!("{}/cover.jpg", folder("Celeste"));
+        std::fs::write(&cover, b"").unwrap();
+        sqlx::query("UPDATE games SET local_cover_path = ?, local_background_path = ? WHERE title = 'Celeste'")
+            .bind(&cover)
+            .bind(format!("{}/background.jpg", folder("Celeste")))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let games = db::get_all_games(&pool).await.unwrap();
+        let jobs = missing_images(&games);
+        let missing: Vec<_> = jobs
+            .iter()
+            .map(|j| {
+                let title = &games.iter().find(|g| g.id == j.game_id).unwrap().title;
+                (
+                    title.as_str(),
+                    j.cover_url.is_some(),
+                    j.background_url.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(missing, [("Celeste", false, true), ("Hades", true, true)]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Duplicate installation report
+//!
+//! The same game can end up in the library more than once (a store copy next
+//! to a repack, or an old folder that was never removed). Copies are grouped
+//! by Steam app id, and games without one by their normalized title. Each
+//! group suggests a copy to keep (most played, then matched, then largest);
+//! the others count as wasted space and can be hidden or deleted.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::models::{Game, GameLocation, WithLocation};
+
+/// One installed copy of a duplicated game
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCopy {
+    pub game_id: i64,
+    pub title: String,
+    pub size_bytes: Option<i64>,
+    pub match_status: String,
+    pub playtime_mins: Option<i64>,
+    pub hidden: bool,
+    /// Suggested copy to keep
+    pub keep: bool,
+}
+
+/// Copies of the same game
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub steam_app_id: Option<i64>,
+    pub title: String,
+    pub copies: Vec<WithLocation<DuplicateCopy>>,
+    pub total_bytes: i64,
+    /// Space freed by removing every copy except the suggested one
+    pub wasted_bytes: i64,
+}
+
+/// Lowercase letters and digits only, so "Hades II" and "hades-ii" agree
+pub fn normalize_title(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Order copies by how likely they are the one to keep (best first)
+fn keep_rank(game: &Game) -> (i64, bool, i64) {
+    (
+        game.playtime_mins.unwrap_or(0),
+        game.match_status == "matched",
+        game.size_bytes.unwrap_or(0),
+    )
+}
+
+/// Group games that are installed more than once, most wasted space first
+///
+/// `include_paths` adds each copy's folder (admin clients only).
+pub fn find_duplicates(games: &[Game], include_paths: bool) -> Vec<DuplicateGroup> {
+    let mut by_app_id: HashMap<i64, Vec<&Game>> = HashMap::new();
+    let mut by_title: HashMap<String, Vec<&Game>> = HashMap::new();
+    for game in games {
+        match game.steam_app_id {
+            Some(app_id) => by_app_id.entry(app_id).or_default().push(game),
+            None => {
+                let key = normalize_title(&game.title);
+                if !key.is_empty() {
+                    by_title.entry(key).or_default().push(game);
+                }
+            }
+        }
+    }
+
+    // A copy without an app id joins a group whose app id copies share its title
+    let mut groups: Vec<Vec<&Game>> = Vec::new();
+    for (_, mut copies) in by_app_id {
+        let titles: Vec<String> = copies.iter().map(|g| normalize_title(&g.title)).collect();
+        for title in titles {
+            if let Some(unmatched) = by_title.remove(&title) {
+                copies.extend(unmatched);
+            }
+        }
+        groups.push(copies);
+    }
+    groups.extend(by_title.into_values());
+
+    let mut report: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .filter(|copies| copies.len() > 1)
+        .map(|mut copies| {
+            copies.sort_by(|a, b| keep_rank(b).cmp(&keep_rank(a)).then(a.id.cmp(&b.id)));
+            let keep = copies[0];
+            let total_bytes = copies
+                .iter()
+                .map(|g| g.size_bytes.unwrap_or(0))
+                .sum::<i64>();
+
+            DuplicateGroup {
+                steam_app_id: copies.iter().find_map(|g| g.steam_app_id),
+                title: keep.title.clone(),
+                total_bytes,
+                wasted_bytes: total_bytes - keep.size_bytes.unwrap_or(0),
+                copies: copies
+                    .iter()
+                    .map(|g| WithLocation {
+                        item: DuplicateCopy {
+                            game_id: g.id,
+                            title: g.title.clone(),
+                            size_bytes: g.size_bytes,
+                            match_status: g.match_status.clone(),
+                            playtime_mins: g.playtime_mins,
+                            hidden: g.hidden.unwrap_or(0) != 0,
+                            keep: g.id == keep.id,
+                        },
+                        location: include_paths.then(|| GameLocation::from(*g)),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    report.sort_by(|a, b| {
+        b.wasted_bytes
+            .cmp(&a.wasted_bytes)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn game(id: i64, title: &str, app_id: Option<i64>, size: i64, playtime: i64) -> Game {
+        Game {
+            folder_path: format!("/games/{}", id),
+            steam_app_id: app_id,
+            size_bytes: Some(size),
+            playtime_mins: Some(playtime),
+            match_status: if app_id.is_some() {
+                "matched"
+            } else {
+                "pending"
+            }
+            .to_string(),
+            ..create_test_game(id, title)
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates_groups_by_app_id_and_title() {
+        let games = [
+            game(1, "Hades", Some(1145360), 15_000, 0),
+            game(2, "Hades", Some(1145360), 12_000, 300),
+            game(3, "HADES", None, 10_000, 0),
+            game(4, "Celeste", None, 1_000, 0),
+            game(5, "celeste!", None, 1_200, 0),
+            game(6, "Hollow Knight", Some(367520), 9_000, 0),
+        ];
+
+        let report = find_duplicates(&games, false);
+        assert_eq!(report.len(), 2);
+
+        let hades = &report[0];
+        assert_eq!(hades.steam_app_id, Some(1145360));
+        assert_eq!(hades.copies.len(), 3);
+        assert_eq!(hades.total_bytes, 37_000);
+        // The played copy is kept even though it isn't the largest
+        assert!(hades.copies[0].item.keep);
+        assert_eq!(hades.copies[0].item.game_id, 2);
+        assert_eq!(hades.wasted_bytes, 25_000);
+        assert!(hades.copies[0].location.is_none());
+
+        let celeste = &report[1];
+        assert_eq!(celeste.steam_app_id, None);
+        assert_eq!(celeste.copies[0].item.game_id, 5);
+        assert_eq!(celeste.wasted_bytes, 1_000);
+    }
+
+    #[test]
+    fn test_find_duplicates_includes_paths() {
+        let games = [game(1, "Hades", None, 1, 0), game(2, "Hades", None, 1, 0)];
+        let report = find_duplicates(&games, true);
+        assert_eq!(
+            report[0].copies[1].location.as_ref().unwrap().folder_path,
+            "/games/2"
+        );
+    }
+}
@@ -2,13 +2,26 @@
 //!
 //! Uses rust-embed to bundle the Next.js static export into the binary.
 //! Falls back to filesystem serving in development mode.
+//!
+//! Files without a content hash in their name get an ETag computed at
+//! startup, so unchanged files are answered with 304 Not Modified.
+//!
+//! A binary built before the frontend (no `frontend/out/index.html`) serves a
+//! built-in status page instead, with build instructions and server health.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
 
 use axum::{
     body::Body,
-    http::{header, StatusCode, Uri},
+    http::{header, HeaderMap, StatusCode, Uri},
     response::{IntoResponse, Response},
 };
 use rust_embed::RustEmbed;
+use sha2::{Digest, Sha256};
+
+/// Cache-Control for files whose URL changes with their content
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
 
 /// Embedded static assets from Next.js build
 /// The folder path is relative to the Cargo.toml (backend/) directory
@@ -17,9 +30,112 @@ use rust_embed::RustEmbed;
 #[prefix = ""]
 pub struct StaticAssets;
 
+/// Hex characters of the SHA-256 kept in an ETag
+const ETAG_HEX_LENGTH: usize = 16;
+
+/// ETags of embedded assets, computed once by [`init_asset_etags`]
+static ASSET_ETAGS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Whether the frontend is embedded, checked once by [`frontend_embedded`]
+static FRONTEND_EMBEDDED: OnceLock<bool> = OnceLock::new();
+
+/// Whether an asset is served with an ETag
+///
+/// `_next/static/` files have content hashes in their names and are cached
+/// forever; index.html is the entry point and always fetched fresh.
+fn uses_etag(path: &str) -> bool {
+    !is_hashed_asset(path) && path != "index.html"
+}
+
+fn is_hashed_asset(path: &str) -> bool {
+    path.starts_with("_next/static/") || path.contains("/_next/static/")
+}
+
+/// Hash the embedded assets once at startup
+///
+/// Each file is re-hashed and checked against the hash recorded at build
+/// time; a file that doesn't match is logged and served without an ETag.
+/// Returns the number of assets with an ETag.
+pub fn init_asset_etags() -> usize {
+    let etags = ASSET_ETAGS.get_or_init(|| {
+        let mut etags = HashMap::new();
+        for path in StaticAssets::iter().filter(|p| uses_etag(p)) {
+            let Some(file) = StaticAssets::get(&path) else {
+                continue;
+            };
+            let hash: [u8; 32] = Sha256::digest(&file.data).into();
+            if hash != file.metadata.sha256_hash() {
+                tracing::warn!("Embedded asset {} failed its integrity check", path);
+                continue;
+            }
+            let hex: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+            etags.insert(path.to_string(), hex[..ETAG_HEX_LENGTH].to_string());
+        }
+        etags
+    });
+    etags.len()
+}
+
+/// Whether an If-None-Match header value matches an ETag (weak or strong)
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.trim_start_matches("W/").trim_matches('"') == etag
+    })
+}
+
+/// Serve an embedded file, answering 304 when the client's copy is current
+///
+/// Requests that carry the asset's ETag as `?v=` are cache busted, so they
+/// can be cached for a year like the hashed `_next/static/` bundles.
+fn asset_response(
+    path: &str,
+    content_type: &str,
+    cache_control: &'static str,
+    data: Vec<u8>,
+    headers: &HeaderMap,
+    query: Option<&str>,
+) -> Response {
+    let etag = ASSET_ETAGS.get().and_then(|etags| etags.get(path));
+    let Some(etag) = etag else {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::CACHE_CONTROL, cache_control)
+            .body(Body::from(data))
+            .unwrap();
+    };
+
+    let versioned = query
+        .into_iter()
+        .flat_map(|q| q.split('&'))
+        .any(|pair| pair == format!("v={}", etag));
+    let cache_control = if versioned { IMMUTABLE } else { cache_control };
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| etag_matches(h, etag));
+
+    let builder = Response::builder()
+        .header(header::ETAG, format!("\"{}\"", etag))
+        .header(header::CACHE_CONTROL, cache_control);
+    if not_modified {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap();
+    }
+    builder
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(data))
+        .unwrap()
+}
+
 /// Serve embedded static files
-pub async fn serve_static(uri: Uri) -> impl IntoResponse {
+pub async fn serve_static(uri: Uri, headers: HeaderMap) -> impl IntoResponse {
     let path = uri.path().trim_start_matches('/');
+    let query = uri.query();
 
     // Handle root path
     let path = if path.is_empty() { "index.html" } else { path };
@@ -29,37 +145,58 @@ pub async fn serve_static(uri: Uri) -> impl IntoResponse {
     // Try exact path first
     if let Some(content) = StaticAssets::get(path) {
         let mime = mime_guess::from_path(path).first_or_octet_stream();
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, mime.as_ref())
-            .header(header::CACHE_CONTROL, get_cache_control(path))
-            .body(Body::from(content.data.into_owned()))
-            .unwrap();
+        return asset_response(
+            path,
+            mime.as_ref(),
+            get_cache_control(path),
+            content.data.into_owned(),
+            &headers,
+            query,
+        );
     }
 
     // Try with .html extension (Next.js static export format)
     let html_path = format!("{}.html", path.trim_end_matches('/'));
     if let Some(content) = StaticAssets::get(&html_path) {
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-            .header(header::CACHE_CONTROL, "no-cache")
-            .body(Body::from(content.data.into_owned()))
-            .unwrap();
+        return asset_response(
+            &html_path,
+            "text/html; charset=utf-8",
+            "no-cache",
+            content.data.into_owned(),
+            &headers,
+            query,
+        );
     }
 
     // Try with /index.html for directory paths
     let index_path = format!("{}/index.html", path.trim_end_matches('/'));
     if let Some(content) = StaticAssets::get(&index_path) {
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-            .header(header::CACHE_CONTROL, "no-cache")
-            .body(Body::from(content.data.into_owned()))
-            .unwrap();
+        return asset_response(
+            &index_path,
+            "text/html; charset=utf-8",
+            "no-cache",
+            content.data.into_owned(),
+            &headers,
+            query,
+        );
+    }
+
+    // Fallback to root index.html for SPA client-side routing. Unknown API
+    // routes and missing assets get a real 404 instead of the app shell, so
+    // they can't be mistaken for (or sniffed as) another content type.
+    let is_asset = path != "index.html"
+        && path
+            .rsplit('/')
+            .next()
+            .is_some_and(|name| name.contains('.'));
+    if path.starts_with("api/") || is_asset {
+        return not_found();
+    }
+
+    if !frontend_embedded() {
+        return status_page();
     }
 
-    // Fallback to root index.html for SPA client-side routing
     if let Some(content) = StaticAssets::get("index.html") {
         return Response::builder()
             .status(StatusCode::OK)
@@ -70,6 +207,10 @@ pub async fn serve_static(uri: Uri) -> impl IntoResponse {
     }
 
     // Nothing found
+    not_found()
+}
+
+fn not_found() -> Response {
     Response::builder()
         .status(StatusCode::NOT_FOUND)
         .header(header::CONTENT_TYPE, "text/plain")
@@ -79,13 +220,13 @@ pub async fn serve_static(uri: Uri) -> impl IntoResponse {
 
 /// Get appropriate cache control header based on file type
 fn get_cache_control(path: &str) -> &'static str {
-    // Static assets with hash in filename can be cached forever
-    if path.contains("/_next/static/") {
-        "public, max-age=31536000, immutable"
-    }
-    // Fonts can be cached
-    else if path.ends_with(".woff2") || path.ends_with(".woff") || path.ends_with(".ttf") {
-        "public, max-age=31536000, immutable"
+    // Static assets with hash in filename and fonts can be cached forever
+    if is_hashed_asset(path)
+        || path.ends_with(".woff2")
+        || path.ends_with(".woff")
+        || path.ends_with(".ttf")
+    {
+        IMMUTABLE
     }
     // Images can be cached
     else if path.ends_with(".png")
@@ -97,7 +238,7 @@ fn get_cache_control(path: &str) -> &'static str {
     {
         "public, max-age=86400"
     }
-    // HTML and other files should not be cached
+    // HTML and other files are revalidated (with their ETag) on every use
     else {
         "no-cache"
     }
@@ -108,8 +249,128 @@ pub fn has_embedded_assets() -> bool {
     StaticAssets::get("index.html").is_some()
 }
 
-/// List all embedded files (for debugging)
-#[allow(dead_code)]
-pub fn list_embedded_files() -> Vec<String> {
-    StaticAssets::iter().map(|f| f.to_string()).collect()
+/// Whether the frontend was built into this binary
+///
+/// Checked on first use (at startup); a missing frontend is logged once.
+pub fn frontend_embedded() -> bool {
+    *FRONTEND_EMBEDDED.get_or_init(|| {
+        let embedded = has_embedded_assets();
+        if !embedded {
+            tracing::warn!(
+                "Frontend is not embedded (frontend/out/index.html was missing at build time); \
+                 serving a status page instead"
+            );
+        }
+        embedded
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Page shown in place of the frontend when it wasn't built
+fn status_page() -> Response {
+    let health = crate::crash::health_details();
+    let last_crash = match &health.last_crash {
+        Some(crash) => format!(
+            "{} ({})",
+            escape_html(&crash.message),
+            escape_html(&crash.occurred_at)
+        ),
+        None => "none".to_string(),
+    };
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>GameVault</title>
+<style>
+body {{ font-family: system-ui, sans-serif; background: #111827; color: #e5e7eb; margin: 0; }}
+main {{ max-width: 40rem; margin: 4rem auto; padding: 0 1.5rem; }}
+h1 {{ font-size: 1.5rem; }}
+code, pre {{ background: #1f2937; border-radius: 4px; padding: 0.1rem 0.3rem; }}
+pre {{ padding: 0.75rem; overflow-x: auto; }}
+td {{ padding: 0.2rem 1rem 0.2rem 0; }}
+a {{ color: #60a5fa; }}
+</style>
+</head>
+<body>
+<main>
+<h1>GameVault is running</h1>
+<p>The server and API are up, but this build doesn't include the web interface.
+Build the frontend, then rebuild the backend so it gets embedded:</p>
+<pre>cd frontend
+npm install
+npm run build
+cd ../backend
+cargo build --release</pre>
+<h2>Server</h2>
+<table>
+<tr><td>Version</td><td>{version}</td></tr>
+<tr><td>Platform</td><td>{platform}</td></tr>
+<tr><td>Uptime</td><td>{uptime_mins} min</td></tr>
+<tr><td>Recent errors</td><td>{recent_errors}</td></tr>
+<tr><td>Last crash</td><td>{last_crash}</td></tr>
+</table>
+<p>API: <a href="/api/health">/api/health</a>, <a href="/api/health/details">/api/health/details</a></p>
+</main>
+</body>
+</html>
+"#,
+        version = escape_html(&health.version),
+        platform = escape_html(&health.platform),
+        uptime_mins = health.uptime_secs / 60,
+        recent_errors = health.recent_errors.len(),
+        last_crash = last_crash,
+    );
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(html))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matches() {
+        assert!(etag_matches("\"abc123\"", "abc123"));
+        assert!(etag_matches("W/\"abc123\"", "abc123"));
+        assert!(etag_matches("\"old\", \"abc123\"", "abc123"));
+        assert!(etag_matches("*", "abc123"));
+        assert!(!etag_matches("\"abc12\"", "abc123"));
+    }
+
+    #[test]
+    fn test_etag_and_cache_policy() {
+        assert!(!uses_etag("_next/static/chunks/main-1a2b3c.js"));
+        assert!(!uses_etag("index.html"));
+        assert!(uses_etag("games.html"));
+        assert!(uses_etag("favicon.ico"));
+        assert_eq!(get_cache_control("_next/static/css/app.css"), IMMUTABLE);
+        assert_eq!(get_cache_control("games.html"), "no-cache");
+    }
+
+    #[test]
+    fn test_status_page() {
+        let response = status_page();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(
+            escape_html("<b>\"R&D\"</b>"),
+            "&lt;b&gt;&quot;R&amp;D&quot;&lt;/b&gt;"
+        );
+    }
 }
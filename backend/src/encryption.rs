@@ -0,0 +1,188 @@
+//! Save backup encryption
+//!
+//! Backups can contain account tokens, so they can be encrypted with a
+//! passphrase or keyfile from `[backup]` in config.toml before they're synced
+//! to cloud storage. An encrypted backup is the original zip sealed with
+//! AES-256-GCM and saved next to it as `<name>.zip.enc`:
+//!
+//! ```text
+//! "GVENC001" | salt (16 bytes) | nonce (12 bytes) | ciphertext + tag
+//! ```
+//!
+//! The key is derived from the secret with Argon2id and the per-file salt;
+//! the header is authenticated along with the data.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use rand::RngCore;
+
+/// File extension appended to encrypted backups
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+const MAGIC: &[u8; 8] = b"GVENC001";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+/// Whether a path names an encrypted backup
+pub fn is_encrypted_path(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == ENCRYPTED_EXTENSION)
+}
+
+fn derive_key(secret: &[u8], salt: &[u8]) -> Result<Key<Aes256Gcm>, String> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::default()
+        .hash_password_into(secret, salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypt data with a passphrase or keyfile contents
+pub fn encrypt(secret: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+
+    let cipher = Aes256Gcm::new(&derive_key(secret, &salt)?);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &out,
+            },
+        )
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt data written by [`encrypt`]
+pub fn decrypt(secret: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+        return Err("Not an encrypted GameVault backup".to_string());
+    }
+
+    let (header, ciphertext) = data.split_at(HEADER_LEN);
+    let salt = &header[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = Nonce::from_slice(&header[MAGIC.len() + SALT_LEN..]);
+
+    let cipher = Aes256Gcm::new(&derive_key(secret, salt)?);
+    cipher
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| "Decryption failed (wrong key or damaged file)".to_string())
+}
+
+/// Encrypt a backup file to `<path>.enc` and remove the plaintext
+///
+/// The encrypted file is decrypted again and compared before the original is
+/// removed, so a failure never leaves only an unreadable copy.
+pub fn encrypt_file(path: &Path, secret: &[u8]) -> Result<PathBuf, String> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ENCRYPTED_EXTENSION);
+    let encrypted_path = PathBuf::from(name);
+
+    if encrypted_path.exists() {
+        return Err(format!(
+            "Encrypted backup already exists: {}",
+            encrypted_path.display()
+        ));
+    }
+
+    let plaintext = fs::read(path).map_err(|e| format!("Failed to read backup: {}", e))?;
+    let sealed = encrypt(secret, &plaintext)?;
+    if decrypt(secret, &sealed)? != plaintext {
+        return Err("Encrypted backup did not round-trip".to_string());
+    }
+
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let mut temp = tempfile::NamedTempFile::new_in(parent)
+        .map_err(|e| format!("Failed to create temporary file: {}", e))?;
+    temp.write_all(&sealed)
+        .and_then(|_| temp.as_file().sync_all())
+        .map_err(|e| format!("Failed to write encrypted backup: {}", e))?;
+    temp.persist(&encrypted_path)
+        .map_err(|e| format!("Failed to save encrypted backup: {}", e))?;
+
+    fs::remove_file(path).map_err(|e| {
+        format!(
+            "Encrypted backup written to {} but the original could not be removed: {}",
+            encrypted_path.display(),
+            e
+        )
+    })?;
+
+    Ok(encrypted_path)
+}
+
+/// Decrypt an encrypted backup into a temporary file (removed on drop)
+pub fn decrypt_to_temp(path: &Path, secret: &[u8]) -> Result<tempfile::NamedTempFile, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read backup: {}", e))?;
+    let plaintext = decrypt(secret, &data)?;
+
+    let mut temp = tempfile::Builder::new()
+        .prefix("gamevault-backup-")
+        .suffix(".zip")
+        .tempfile()
+        .map_err(|e| format!("Failed to create temporary file: {}", e))?;
+    temp.write_all(&plaintext)
+        .map_err(|e| format!("Failed to write temporary file: {}", e))?;
+    Ok(temp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let sealed = encrypt(b"correct horse", b"save data").unwrap();
+
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed.windows(9).any(|w| w == b"save data"));
+        assert_eq!(decrypt(b"correct horse", &sealed).unwrap(), b"save data");
+        assert!(decrypt(b"wrong", &sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        tampered[MAGIC.len()] ^= 1;
+        assert!(decrypt(b"correct horse", &tampered).is_err());
+        assert!(decrypt(b"correct horse", b"plain zip").is_err());
+    }
+
+    #[test]
+    fn test_encrypt_file_replaces_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("2024-05-10_2000.zip");
+        fs::write(&path, b"zip bytes").unwrap();
+
+        let encrypted = encrypt_file(&path, b"key").unwrap();
+        assert_eq!(
+            encrypted.file_name().unwrap(),
+            "2024-05-10_2000.zip.enc",
+            "original name is kept"
+        );
+        assert!(is_encrypted_path(&encrypted));
+        assert!(!path.exists());
+
+        let temp = decrypt_to_temp(&encrypted, b"key").unwrap();
+        assert_eq!(fs::read(temp.path()).unwrap(), b"zip bytes");
+    }
+}
@@ -0,0 +1,329 @@
+//! Live library events (GET /api/ws)
+//!
+//! Frontends open a WebSocket and receive JSON text frames, each an object
+//! with a `type`:
+//!
+//! - `hello` once on connect, with the protocol version and heartbeat interval
+//! - `game_added` / `game_updated` with the game summary, `game_deleted` with its id
+//! - `job_progress` while a scan or enrichment runs
+//! - `notification` when a notification lands in the inbox
+//! - `heartbeat` every `heartbeat_secs`
+//! - `resync` when the client fell behind and events were dropped; it should
+//!   refetch whatever it shows
+//!
+//! Events about a game the active restricted profile doesn't allow (see
+//! profiles.rs) are not sent.
+//!
+//! Clients may send `{"type":"ping"}` and get `{"type":"pong"}` back. A client
+//! that sends nothing (not even a pong to the server's ping frames) for three
+//! heartbeats is disconnected. Events are best effort: nothing is replayed
+//! after a reconnect.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tokio::sync::broadcast;
+
+use crate::db;
+use crate::models::GameSummary;
+
+/// Bumped on incompatible changes to the event format
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Time between heartbeats
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Silence after which a client is considered gone
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Events buffered per client before it is told to resync
+const CHANNEL_CAPACITY: usize = 256;
+
+pub const JOB_SCAN: &str = "scan";
+pub const JOB_ENRICH: &str = "enrich";
+pub const JOB_REMATCH: &str = "rematch";
+
+/// A message sent to clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Hello {
+        version: u32,
+        heartbeat_secs: u64,
+    },
+    Heartbeat,
+    Pong,
+    Resync,
+    GameAdded {
+        game: GameSummary,
+    },
+    GameUpdated {
+        game: GameSummary,
+    },
+    GameDeleted {
+        id: i64,
+    },
+    JobProgress {
+        job: &'static str,
+        done: usize,
+        total: usize,
+        finished: bool,
+    },
+    Notification {
+        id: i64,
+        kind: String,
+        title: String,
+        message: String,
+        game_id: Option<i64>,
+    },
+}
+
+impl Event {
+    /// The game an event shows details of, if any
+    fn game_id(&self) -> Option<i64> {
+        match self {
+            Event::GameAdded { game } | Event::GameUpdated { game } => Some(game.id),
+            Event::Notification { game_id, .. } => *game_id,
+            _ => None,
+        }
+    }
+}
+
+/// Whether a client may see an event under the active profile
+///
+/// Events are dropped when the check fails.
+async fn allowed(pool: &SqlitePool, event: &Event) -> bool {
+    let Some(id) = event.game_id() else {
+        return true;
+    };
+    match db::is_game_visible(pool, id).await {
+        Ok(visible) => visible,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to check visibility of game {} for clients: {}",
+                id,
+                e
+            );
+            false
+        }
+    }
+}
+
+/// A message received from a client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Ping,
+}
+
+static BUS: OnceLock<broadcast::Sender<Event>> = OnceLock::new();
+
+fn bus() -> &'static broadcast::Sender<Event> {
+    BUS.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+/// Send an event to every connected client
+pub fn publish(event: Event) {
+    // Fails only when nobody is listening
+    let _ = bus().send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    bus().subscribe()
+}
+
+/// Whether any client is connected
+fn has_subscribers() -> bool {
+    bus().receiver_count() > 0
+}
+
+/// Publish the current state of a game as added or updated
+///
+/// Skips the lookup when no client is connected; failures are logged.
+pub async fn publish_game(pool: &SqlitePool, id: i64, added: bool) {
+    if !has_subscribers() {
+        return;
+    }
+    match db::get_game_by_id(pool, id).await {
+        Ok(Some(game)) => {
+            let game = GameSummary::from(game);
+            publish(if added {
+                Event::GameAdded { game }
+            } else {
+                Event::GameUpdated { game }
+            });
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to load game {} for clients: {}", id, e),
+    }
+}
+
+/// Publish progress of a background job
+pub fn publish_progress(job: &'static str, done: usize, total: usize) {
+    publish(Event::JobProgress {
+        job,
+        done,
+        total,
+        finished: done >= total,
+    });
+}
+
+async fn send(socket: &mut WebSocket, event: &Event) -> bool {
+    match serde_json::to_string(event) {
+        Ok(text) => socket.send(Message::Text(text)).await.is_ok(),
+        Err(e) => {
+            tracing::warn!("Failed to serialize event: {}", e);
+            true
+        }
+    }
+}
+
+/// Serve one client until it disconnects or goes silent
+pub async fn serve(mut socket: WebSocket, pool: SqlitePool) {
+    let mut events = subscribe();
+    let hello = Event::Hello {
+        version: PROTOCOL_VERSION,
+        heartbeat_secs: HEARTBEAT_INTERVAL.as_secs(),
+    };
+    if !send(&mut socket, &hello).await {
+        return;
+    }
+
+    let mut heartbeat = tokio::time::interval_at(
+        tokio::time::Instant::now() + HEARTBEAT_INTERVAL,
+        HEARTBEAT_INTERVAL,
+    );
+    let mut last_seen = Instant::now();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("Client fell behind by {} events", skipped);
+                        Event::Resync
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if !allowed(&pool, &event).await {
+                    continue;
+                }
+                if !send(&mut socket, &event).await {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                let message = match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(message)) => message,
+                };
+                last_seen = Instant::now();
+                if let Message::Text(text) = message {
+                    if let Ok(ClientMessage::Ping) = serde_json::from_str(&text) {
+                        if !send(&mut socket, &Event::Pong).await {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    tracing::debug!("Closing silent WebSocket client");
+                    break;
+                }
+                if !send(&mut socket, &Event::Heartbeat).await
+                    || socket.send(Message::Ping(Vec::new())).await.is_err()
+                {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_event_format() {
+        let event = Event::Hello {
+            version: PROTOCOL_VERSION,
+            heartbeat_secs: 30,
+        };
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            json!({"type": "hello", "version": 1, "heartbeat_secs": 30})
+        );
+        assert_eq!(
+            serde_json::to_value(Event::GameDeleted { id: 7 }).unwrap(),
+            json!({"type": "game_deleted", "id": 7})
+        );
+        assert_eq!(
+            serde_json::to_value(Event::Resync).unwrap(),
+            json!({"type": "resync"})
+        );
+        assert!(matches!(
+            serde_json::from_str(r#"{"type":"ping"}"#),
+            Ok(ClientMessage::Ping)
+        ));
+        assert!(serde_json::from_str::<ClientMessage>(r#"{"type":"subscribe"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_events_about_hidden_games_not_allowed() {
+        let (pool, _keepalive) = db::test_pool().await;
+        let id = db::upsert_game(&pool, "/games/Doom", "Doom", "Doom", None, None)
+            .await
+            .unwrap();
+        let game = GameSummary::from(db::get_game_by_id(&pool, id).await.unwrap().unwrap());
+        let updated = Event::GameUpdated { game };
+        let notification = Event::Notification {
+            id: 1,
+            kind: "game_updated".into(),
+            title: "Doom".into(),
+            message: "Changed".into(),
+            game_id: Some(id),
+        };
+        assert!(allowed(&pool, &updated).await);
+        assert!(allowed(&pool, &notification).await);
+
+        sqlx::query("UPDATE games SET age_rating = 18")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO profiles (name, max_age, pin_hash, active) VALUES ('Kids', 12, '', 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(!allowed(&pool, &updated).await);
+        assert!(!allowed(&pool, &notification).await);
+        assert!(allowed(&pool, &Event::GameDeleted { id }).await);
+    }
+
+    #[tokio::test]
+    async fn test_publish() {
+        let mut events = subscribe();
+        publish_progress(JOB_SCAN, 3, 3);
+        // Other tests may publish on the same bus
+        loop {
+            if let Event::JobProgress {
+                job,
+                done,
+                finished,
+                ..
+            } = events.recv().await.unwrap()
+            {
+                assert_eq!((job, done, finished), (JOB_SCAN, 3, true));
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,507 @@
+//! Artwork and metadata export for external launcher frontends
+//!
+//! Copies (or hard-links) every cached cover and background into a
+//! directory laid out the way a launcher frontend looks for media, keyed by
+//! game title, and writes the frontend's metadata file next to it
+//! (`metadata.pegasus.txt` for Pegasus, `gamelist.xml` for EmulationStation)
+//! with titles, descriptions, release dates and play stats. An HTPC frontend
+//! can then reuse the library's curated art and metadata. Images that are
+//! already up to date are left alone, and nothing else in the target is
+//! touched, so the export can be re-run after each enrichment.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::config::ArtworkLayout;
+use crate::models::Game;
+use crate::{launcher, local_storage, media, wishlist};
+
+/// Pegasus collection name
+const PEGASUS_COLLECTION: &str = "GameVault";
+
+/// Extension of the shortcuts ES-DE lists (one per game in the ROM folder)
+const ESDE_SHORTCUT_EXTENSION: &str = "lnk";
+
+/// Kind of image exported
+#[derive(Debug, Clone, Copy)]
+enum ArtKind {
+    Cover,
+    Background,
+}
+
+impl ArtworkLayout {
+    /// Where the layout expects an image for a game
+    fn path(self, target: &Path, name: &str, kind: ArtKind) -> PathBuf {
+        let file = format!("{}.jpg", name);
+        match (self, kind) {
+            (ArtworkLayout::Esde, ArtKind::Cover) => target.join("covers").join(file),
+            (ArtworkLayout::Esde, ArtKind::Background) => target.join("fanart").join(file),
+            (ArtworkLayout::Pegasus, ArtKind::Cover) => target.join(name).join("boxFront.jpg"),
+            (ArtworkLayout::Pegasus, ArtKind::Background) => {
+                target.join(name).join("background.jpg")
+            }
+        }
+    }
+
+    /// File name of the layout's metadata file
+    fn metadata_file(self) -> &'static str {
+        match self {
+            ArtworkLayout::Esde => "gamelist.xml",
+            ArtworkLayout::Pegasus => "metadata.pegasus.txt",
+        }
+    }
+}
+
+/// A game to export with its cached images and play stats
+#[derive(Debug, Clone)]
+pub struct ExportGame {
+    pub game: Game,
+    pub cover: PathBuf,
+    pub background: PathBuf,
+    /// Play sessions recorded by GameVault
+    pub sessions: i64,
+    pub last_played: Option<String>,
+}
+
+impl ExportGame {
+    pub fn new(game: Game, sessions: i64, last_played: Option<String>) -> Self {
+        ExportGame {
+            cover: local_storage::get_cover_path(&game.folder_path),
+            background: local_storage::get_background_path(&game.folder_path),
+            game,
+            sessions,
+            last_played,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArtworkExportResult {
+    pub target: String,
+    pub layout: ArtworkLayout,
+    /// Metadata file written (`metadata.pegasus.txt` or `gamelist.xml`)
+    pub metadata_file: String,
+    /// Games with at least one image in the target
+    pub exported: usize,
+    /// Games without cached artwork
+    pub skipped: usize,
+    /// Images that couldn't be written
+    pub failed: usize,
+    /// Images copied or linked this run
+    pub written: usize,
+    /// Images already up to date
+    pub unchanged: usize,
+}
+
+/// File name for each game: its title made safe for file names
+///
+/// Titles that clash (ignoring case, as on Windows) get the game id appended
+/// to all but the first, so every game keeps a stable name across runs.
+fn export_names(games: &[ExportGame]) -> Vec<String> {
+    let mut order: Vec<usize> = (0..games.len()).collect();
+    order.sort_by_key(|&i| games[i].game.id);
+
+    let mut taken = HashSet::new();
+    let mut names = vec![String::new(); games.len()];
+    for i in order {
+        let game = &games[i].game;
+        let mut name = wishlist::reserved_folder_name(&game.title);
+        if name.is_empty() {
+            name = format!("Game {}", game.id);
+        }
+        if !taken.insert(name.to_lowercase()) {
+            name = format!("{} ({})", name, game.id);
+            taken.insert(name.to_lowercase());
+        }
+        names[i] = name;
+    }
+    names
+}
+
+/// Copy or hard-link one image; false when it was already up to date
+fn write_image(source: &Path, dest: &Path, link: bool) -> io::Result<bool> {
+    if media::is_up_to_date(source, dest) {
+        return Ok(false);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if dest.exists() {
+        fs::remove_file(dest)?;
+    }
+    // Hard links only work within one volume; copy otherwise
+    if !link || fs::hard_link(source, dest).is_err() {
+        fs::copy(source, dest)?;
+    }
+    Ok(true)
+}
+
+/// Lists are stored as JSON arrays
+fn json_list(value: Option<&str>) -> Vec<String> {
+    value
+        .and_then(|v| serde_json::from_str(v).ok())
+        .unwrap_or_default()
+}
+
+/// One Pegasus field; continuation lines are indented, blank lines become "."
+fn pegasus_field(out: &mut String, key: &str, value: &str) {
+    let mut lines = value.trim().lines().map(str::trim_end);
+    let Some(first) = lines.next().filter(|l| !l.is_empty()) else {
+        return;
+    };
+    out.push_str(&format!("{}: {}\n", key, first));
+    for line in lines {
+        let line = if line.trim().is_empty() { "." } else { line };
+        out.push_str(&format!("  {}\n", line));
+    }
+}
+
+/// Asset path as written in the metadata file
+fn asset_path(asset: &Path, metadata_dir: &Path) -> String {
+    asset
+        .strip_prefix(metadata_dir)
+        .unwrap_or(asset)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// `metadata.pegasus.txt` for a collection of the exported games
+fn pegasus_metadata(
+    games: &[ExportGame],
+    names: &[String],
+    artwork_dir: &Path,
+    metadata_dir: &Path,
+) -> String {
+    let mut out = String::new();
+    pegasus_field(&mut out, "collection", PEGASUS_COLLECTION);
+    pegasus_field(&mut out, "launch", "\"{file.path}\"");
+    pegasus_field(&mut out, "workdir", "{file.dir}");
+
+    for (export, name) in games.iter().zip(names) {
+        let game = &export.game;
+        out.push('\n');
+        pegasus_field(&mut out, "game", &game.title);
+        let exe = launcher::resolve_game_exe(&game.folder_path, game.launch_exe.as_deref());
+        if let Some(exe) = exe {
+            pegasus_field(&mut out, "file", &exe.to_string_lossy());
+        }
+        pegasus_field(
+            &mut out,
+            "developers",
+            &json_list(game.developers.as_deref()).join("\n"),
+        );
+        pegasus_field(
+            &mut out,
+            "publishers",
+            &json_list(game.publishers.as_deref()).join("\n"),
+        );
+        pegasus_field(
+            &mut out,
+            "genres",
+            &json_list(game.genres.as_deref()).join("\n"),
+        );
+        if let Some(release) = &game.release_date_iso {
+            pegasus_field(&mut out, "release", release);
+        }
+        if let Some(score) = game.review_score {
+            pegasus_field(&mut out, "rating", &format!("{}%", score));
+        }
+        if let Some(summary) = &game.summary {
+            pegasus_field(&mut out, "description", summary);
+        }
+
+        for (key, source, kind) in [
+            ("assets.boxFront", &export.cover, ArtKind::Cover),
+            ("assets.background", &export.background, ArtKind::Background),
+        ] {
+            if source.is_file() {
+                let asset = ArtworkLayout::Pegasus.path(artwork_dir, name, kind);
+                pegasus_field(&mut out, key, &asset_path(&asset, metadata_dir));
+            }
+        }
+
+        pegasus_field(&mut out, "x-gamevault-id", &game.id.to_string());
+        pegasus_field(
+            &mut out,
+            "x-gamevault-playtime-mins",
+            &game.playtime_mins.unwrap_or(0).to_string(),
+        );
+        pegasus_field(
+            &mut out,
+            "x-gamevault-sessions",
+            &export.sessions.to_string(),
+        );
+        if let Some(last_played) = &export.last_played {
+            pegasus_field(&mut out, "x-gamevault-last-played", last_played);
+        }
+        if let Some(status) = &game.user_status {
+            pegasus_field(&mut out, "x-gamevault-status", status);
+        }
+    }
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// ES-DE timestamp ("20201217T000000") from "YYYY-MM-DD" or "YYYY-MM-DD HH:MM:SS"
+fn esde_timestamp(value: &str) -> String {
+    let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+    let (date, time) = digits.split_at(digits.len().min(8));
+    format!("{}T{:0<6}", date, time)
+}
+
+/// One gamelist.xml element; empty values are left out
+fn xml_field(out: &mut String, tag: &str, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() {
+        out.push_str(&format!("\t\t<{}>{}</{}>\n", tag, xml_escape(value), tag));
+    }
+}
+
+/// `gamelist.xml` for the exported games
+fn esde_gamelist(games: &[ExportGame], names: &[String]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\"?>\n<gameList>\n");
+    for (export, name) in games.iter().zip(names) {
+        let game = &export.game;
+        out.push_str("\t<game>\n");
+        xml_field(
+            &mut out,
+            "path",
+            &format!("./{}.{}", name, ESDE_SHORTCUT_EXTENSION),
+        );
+        xml_field(&mut out, "name", &game.title);
+        xml_field(
+            &mut out,
+            "desc",
+            game.summary.as_deref().unwrap_or_default(),
+        );
+        if let Some(score) = game.review_score {
+            xml_field(&mut out, "rating", &format!("{:.2}", score as f64 / 100.0));
+        }
+        if let Some(release) = &game.release_date_iso {
+            xml_field(&mut out, "releasedate", &esde_timestamp(release));
+        }
+        xml_field(
+            &mut out,
+            "developer",
+            &json_list(game.developers.as_deref()).join(", "),
+        );
+        xml_field(
+            &mut out,
+            "publisher",
+            &json_list(game.publishers.as_deref()).join(", "),
+        );
+        xml_field(
+            &mut out,
+            "genre",
+            &json_list(game.genres.as_deref()).join(", "),
+        );
+        if export.sessions > 0 {
+            xml_field(&mut out, "playcount", &export.sessions.to_string());
+        }
+        if let Some(last_played) = &export.last_played {
+            xml_field(&mut out, "lastplayed", &esde_timestamp(last_played));
+        }
+        out.push_str("\t</game>\n");
+    }
+    out.push_str("</gameList>\n");
+    out
+}
+
+/// Export all games' cached artwork into `target` and write the metadata
+/// file into `metadata_dir`
+pub fn export_frontend(
+    games: &[ExportGame],
+    target: &Path,
+    metadata_dir: &Path,
+    layout: ArtworkLayout,
+    link: bool,
+) -> io::Result<ArtworkExportResult> {
+    let metadata_file = metadata_dir.join(layout.metadata_file());
+    let mut result = ArtworkExportResult {
+        target: target.to_string_lossy().to_string(),
+        layout,
+        metadata_file: metadata_file.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    let names = export_names(games);
+    for (export, name) in games.iter().zip(&names) {
+        let images = [
+            (ArtKind::Cover, &export.cover),
+            (ArtKind::Background, &export.background),
+        ];
+        let mut exported = false;
+        for (kind, image) in images {
+            if !image.is_file() {
+                continue;
+            }
+            match write_image(image, &layout.path(target, name, kind), link) {
+                Ok(written) => {
+                    exported = true;
+                    if written {
+                        result.written += 1;
+                    } else {
+                        result.unchanged += 1;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to export {:?} of '{}': {}",
+                        kind,
+                        export.game.title,
+                        e
+                    );
+                    result.failed += 1;
+                }
+            }
+        }
+
+        if exported {
+            result.exported += 1;
+        } else if !images.iter().any(|(_, image)| image.is_file()) {
+            result.skipped += 1;
+        }
+    }
+
+    let metadata = match layout {
+        ArtworkLayout::Pegasus => pegasus_metadata(games, &names, target, metadata_dir),
+        ArtworkLayout::Esde => esde_gamelist(games, &names),
+    };
+    fs::create_dir_all(metadata_dir)?;
+    let tmp = metadata_file.with_extension("tmp");
+    fs::write(&tmp, metadata)?;
+    fs::rename(&tmp, &metadata_file)?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    /// A game whose folder has a cover (and optionally a background) cached
+    fn export_game(dir: &Path, id: i64, title: &str, background: bool) -> ExportGame {
+        let folder = dir.join(format!("game{}", id));
+        let cache = folder.join(".gamevault");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("cover.jpg"), format!("cover {}", id)).unwrap();
+        if background {
+            fs::write(cache.join("background.jpg"), "background").unwrap();
+        }
+
+        let game = Game {
+            folder_path: folder.to_string_lossy().into_owned(),
+            folder_name: format!("game{}", id),
+            summary: Some(
+                "Defy the god of the dead.\n\nRoguelike <dungeon> crawler & more.".to_string(),
+            ),
+            developers: Some("[\"Supergiant Games\"]".to_string()),
+            genres: Some("[\"Action\", \"Indie\"]".to_string()),
+            release_date: Some("17 Sep, 2020".to_string()),
+            release_date_iso: Some("2020-09-17".to_string()),
+            review_score: Some(98),
+            playtime_mins: Some(1260),
+            ..create_test_game(id, title)
+        };
+        ExportGame::new(game, 4, Some("2024-05-09T20:14:02Z".to_string()))
+    }
+
+    #[test]
+    fn test_export_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let games = [
+            export_game(dir.path(), 9, "Hades", false),
+            export_game(dir.path(), 3, "HADES", false),
+            export_game(dir.path(), 4, "Half-Life 2: Episode One", false),
+            export_game(dir.path(), 5, "...", false),
+        ];
+        assert_eq!(
+            export_names(&games),
+            ["Hades (9)", "HADES", "Half-Life 2 Episode One", "Game 5"]
+        );
+    }
+
+    #[test]
+    fn test_export_artwork() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut games = vec![
+            export_game(dir.path(), 1, "Hades", true),
+            export_game(dir.path(), 2, "Celeste", false),
+            export_game(dir.path(), 3, "No Art", false),
+        ];
+        fs::remove_file(&games[2].cover).unwrap();
+
+        let target = dir.path().join("media");
+        let result = export_frontend(&games, &target, &target, ArtworkLayout::Esde, false).unwrap();
+        assert_eq!(
+            (
+                result.exported,
+                result.skipped,
+                result.written,
+                result.failed
+            ),
+            (2, 1, 3, 0)
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("covers/Celeste.jpg")).unwrap(),
+            "cover 2"
+        );
+        assert!(target.join("fanart/Hades.jpg").is_file());
+
+        let again = export_frontend(&games, &target, &target, ArtworkLayout::Esde, false).unwrap();
+        assert_eq!((again.written, again.unchanged), (0, 3));
+
+        games.truncate(1);
+        let result =
+            export_frontend(&games, &target, &target, ArtworkLayout::Pegasus, true).unwrap();
+        assert_eq!(result.written, 2);
+        assert!(target.join("Hades/boxFront.jpg").is_file());
+        assert!(target.join("Hades/background.jpg").is_file());
+    }
+
+    #[test]
+    fn test_pegasus_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let games = [export_game(dir.path(), 1, "Hades", false)];
+        let media = dir.path().join("media");
+        let text = pegasus_metadata(&games, &["Hades".to_string()], &media, dir.path());
+
+        assert!(text.starts_with("collection: GameVault\nlaunch: \"{file.path}\"\n"));
+        assert!(text.contains("\ngame: Hades\n"));
+        assert!(text.contains("genres: Action\n  Indie\n"));
+        assert!(text.contains("release: 2020-09-17\nrating: 98%\n"));
+        assert!(text.contains("description: Defy the god of the dead.\n  .\n  Roguelike"));
+        assert!(text.contains("assets.boxFront: media/Hades/boxFront.jpg\n"));
+        assert!(!text.contains("assets.background"));
+        assert!(text.contains("x-gamevault-playtime-mins: 1260\nx-gamevault-sessions: 4\n"));
+    }
+
+    #[test]
+    fn test_esde_gamelist() {
+        let dir = tempfile::tempdir().unwrap();
+        let games = [export_game(dir.path(), 1, "Hades", false)];
+        let xml = esde_gamelist(&games, &["Hades".to_string()]);
+
+        assert!(xml.contains("<path>./Hades.lnk</path>"));
+        assert!(xml.contains("Roguelike &lt;dungeon&gt; crawler &amp; more.</desc>"));
+        assert!(xml.contains("<rating>0.98</rating>"));
+        assert!(xml.contains("<releasedate>20200917T000000</releasedate>"));
+        assert!(xml.contains("<genre>Action, Indie</genre>"));
+        assert!(xml.contains("<playcount>4</playcount>"));
+        assert!(xml.contains("<lastplayed>20240509T201402</lastplayed>"));
+        assert!(xml.ends_with("</gameList>\n"));
+    }
+}
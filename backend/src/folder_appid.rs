@@ -0,0 +1,214 @@
+//! Steam app ids read from a game's own files
+//!
+//! Many game folders say which Steam app they are. Steam and most emulators
+//! read `steam_appid.txt` next to the executable (Goldberg keeps it in
+//! `steam_settings/`), other emulators take `AppId=` from an ini file
+//! (`steam_emu.ini`, `ColdClientLoader.ini`, `cream_api.ini`,
+//! `SteamConfig.ini`), and folders copied out of a Steam library can carry
+//! their `appmanifest_<id>.acf`.
+//!
+//! The scan records the id as `folder_app_id`, looking again when the folder
+//! changed size or no id was found before. Enrichment uses it ahead of
+//! any title search (only a stored title mapping, someone's confirmed
+//! answer, comes first), and the rematch pass corrects earlier title matches
+//! that disagree with it.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+/// Confidence of a match taken from the folder's files
+///
+/// Below a confirmed rematch or mapping (1.0), above any title match.
+pub const MATCH_CONFIDENCE: f64 = 0.99;
+
+/// How deep to look (Unreal games keep the file in `<Game>/Binaries/Win64/`)
+const MAX_DEPTH: usize = 4;
+
+/// Entries looked at before giving up, to keep scans of huge folders cheap
+const MAX_ENTRIES: usize = 5000;
+
+/// Emulator config files holding an `AppId=` line (lowercase)
+const INI_FILES: &[&str] = &[
+    "steam_emu.ini",
+    "coldclientloader.ini",
+    "cream_api.ini",
+    "steamconfig.ini",
+];
+
+/// Spacewar, the Steamworks sample app emulators fall back to
+const PLACEHOLDER_APP_ID: i64 = 480;
+
+/// An app id found in a game folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FolderAppId {
+    pub app_id: i64,
+    /// File it was read from, relative to the game folder
+    pub source: String,
+}
+
+/// Kinds of files, in the order they're trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SourceKind {
+    AppIdFile,
+    EmulatorIni,
+    AppManifest,
+}
+
+fn source_kind(file_name: &str) -> Option<SourceKind> {
+    if file_name == "steam_appid.txt" {
+        Some(SourceKind::AppIdFile)
+    } else if INI_FILES.contains(&file_name) {
+        Some(SourceKind::EmulatorIni)
+    } else if file_name.starts_with("appmanifest_") && file_name.ends_with(".acf") {
+        Some(SourceKind::AppManifest)
+    } else {
+        None
+    }
+}
+
+/// The app id a game folder names, if any
+///
+/// Several files are ranked by kind, then by how close they are to the top
+/// of the folder.
+pub fn detect(game_dir: &Path) -> Option<FolderAppId> {
+    let mut best: Option<(SourceKind, usize, FolderAppId)> = None;
+    for entry in WalkDir::new(game_dir)
+        .max_depth(MAX_DEPTH)
+        .into_iter()
+        .take(MAX_ENTRIES)
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+    {
+        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        let Some(kind) = source_kind(&file_name) else {
+            continue;
+        };
+        let rank = (kind, entry.depth());
+        if best.as_ref().is_some_and(|(k, d, _)| (*k, *d) <= rank) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let app_id = match kind {
+            SourceKind::AppIdFile => parse_app_id_file(&content),
+            SourceKind::EmulatorIni => parse_ini(&content),
+            SourceKind::AppManifest => parse_app_manifest(&content, &file_name),
+        };
+        let Some(app_id) = app_id.filter(|id| *id > 0 && *id != PLACEHOLDER_APP_ID) else {
+            continue;
+        };
+        let source = entry
+            .path()
+            .strip_prefix(game_dir)
+            .unwrap_or(entry.path())
+            .to_string_lossy()
+            .replace('\\', "/");
+        best = Some((rank.0, rank.1, FolderAppId { app_id, source }));
+    }
+    best.map(|(_, _, found)| found)
+}
+
+/// `steam_appid.txt`: the id on the first line
+fn parse_app_id_file(content: &str) -> Option<i64> {
+    content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// `AppId=123` (any case, spaces around `=` allowed)
+fn parse_ini(content: &str) -> Option<i64> {
+    content.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("appid")
+            .then(|| value.trim().parse().ok())?
+    })
+}
+
+/// `"appid"  "292030"`, or the id in the file name
+fn parse_app_manifest(content: &str, file_name: &str) -> Option<i64> {
+    static APP_ID: OnceLock<Regex> = OnceLock::new();
+    let re = APP_ID.get_or_init(|| Regex::new(r#"(?i)"appid"\s+"(\d+)""#).unwrap());
+    re.captures(content)
+        .and_then(|c| c[1].parse().ok())
+        .or_else(|| {
+            file_name
+                .strip_prefix("appmanifest_")?
+                .strip_suffix(".acf")?
+                .parse()
+                .ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, file: &str, content: &str) {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_app_id_file("\u{feff}292030\r\n"), Some(292030));
+        assert_eq!(parse_app_id_file("not an id"), None);
+        assert_eq!(
+            parse_ini("[Settings]\r\nComment=x\r\nAppId = 1145360\r\n"),
+            Some(1145360)
+        );
+        assert_eq!(parse_ini("[config]\nappid=abc\n"), None);
+        assert_eq!(
+            parse_app_manifest(
+                "\"AppState\"\n{\n\t\"appid\"\t\t\"570\"\n}",
+                "appmanifest_1.acf"
+            ),
+            Some(570)
+        );
+        assert_eq!(parse_app_manifest("", "appmanifest_620.acf"), Some(620));
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(detect(dir.path()), None);
+
+        // Emulator placeholder
+        write(dir.path(), "steam_settings/steam_appid.txt", "480");
+        assert_eq!(detect(dir.path()), None);
+
+        write(dir.path(), "appmanifest_292030.acf", "");
+        write(
+            dir.path(),
+            "Bin/steam_emu.ini",
+            "[Settings]\nAppId=292030\n",
+        );
+        assert_eq!(
+            detect(dir.path()),
+            Some(FolderAppId {
+                app_id: 292030,
+                source: "Bin/steam_emu.ini".into()
+            })
+        );
+
+        write(
+            dir.path(),
+            "Witcher/Binaries/Win64/steam_appid.txt",
+            "292030\n",
+        );
+        assert_eq!(
+            detect(dir.path()).unwrap().source,
+            "Witcher/Binaries/Win64/steam_appid.txt"
+        );
+    }
+}
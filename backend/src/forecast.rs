@@ -0,0 +1,210 @@
+//! Backlog burn-down forecast
+//!
+//! The backlog is every unplayed or in-progress game (archived and hidden
+//! games excluded). Each game still needs its HowLongToBeat main story time
+//! minus the time already played; games without an HLTB time are counted at
+//! the backlog's median. Dividing the total by the average weekly playtime
+//! from recent sessions gives the date the backlog would be cleared, and
+//! each recently added game shows how many days it pushed that date back.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::{db, models::Game, timestamps};
+
+/// Weeks of play sessions averaged for the weekly playtime
+pub const PLAYTIME_WINDOW_WEEKS: i64 = 12;
+
+/// Games added within this many days are listed as recent additions
+pub const RECENT_ADDITION_DAYS: i64 = 30;
+
+/// Time assumed for a game when no backlog game has an HLTB time
+const DEFAULT_ESTIMATE_MINS: i64 = 600;
+
+/// A recently added backlog game and its effect on the clear date
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastGame {
+    pub game_id: i64,
+    pub title: String,
+    pub added_at: String,
+    pub remaining_mins: i64,
+    /// No HLTB time; `remaining_mins` is the backlog estimate
+    pub estimated: bool,
+    /// Days the game adds to the forecast (None without recent playtime)
+    pub delay_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Forecast {
+    pub backlog_games: usize,
+    pub remaining_mins: i64,
+    /// Backlog games without an HLTB time
+    pub estimated_games: usize,
+    /// Time assumed for each of those games
+    pub estimate_mins: i64,
+    /// Average weekly playtime over recent sessions
+    pub weekly_mins: i64,
+    pub weeks_to_clear: Option<f64>,
+    /// Day the backlog would be cleared (YYYY-MM-DD, UTC)
+    pub clear_date: Option<String>,
+    /// Games added in the last 30 days, newest first
+    pub recent_additions: Vec<ForecastGame>,
+}
+
+/// Median HLTB main time of the games that have one
+fn median_main_mins(games: &[Game]) -> Option<i64> {
+    let mut times: Vec<i64> = games
+        .iter()
+        .filter_map(|g| g.hltb_main_mins)
+        .filter(|m| *m > 0)
+        .collect();
+    if times.is_empty() {
+        return None;
+    }
+    times.sort_unstable();
+    Some(times[times.len() / 2])
+}
+
+/// Days needed to play `mins` at `weekly_mins` a week
+fn days_for(mins: i64, weekly_mins: f64) -> Option<i64> {
+    (weekly_mins > 0.0).then(|| (mins as f64 / weekly_mins * 7.0).ceil() as i64)
+}
+
+/// Forecast the backlog clear date from `today`
+pub fn build_forecast(backlog: &[Game], weekly_mins: f64, today: NaiveDate) -> Forecast {
+    let estimate_mins = median_main_mins(backlog).unwrap_or(DEFAULT_ESTIMATE_MINS);
+    let remaining = |game: &Game| match game.hltb_main_mins.filter(|m| *m > 0) {
+        Some(main) => ((main - game.playtime_mins.unwrap_or(0)).max(0), false),
+        None => (estimate_mins, true),
+    };
+
+    let remaining_mins = backlog.iter().map(|g| remaining(g).0).sum::<i64>();
+    let estimated_games = backlog.iter().filter(|g| remaining(g).1).count();
+
+    let recent_since = (today - Duration::days(RECENT_ADDITION_DAYS)).to_string();
+    let mut recent_additions: Vec<ForecastGame> = backlog
+        .iter()
+        .filter(|g| g.created_at.as_str() >= recent_since.as_str())
+        .map(|g| {
+            let (mins, estimated) = remaining(g);
+            ForecastGame {
+                game_id: g.id,
+                title: g.title.clone(),
+                added_at: g.created_at.clone(),
+                remaining_mins: mins,
+                estimated,
+                delay_days: days_for(mins, weekly_mins),
+            }
+        })
+        .collect();
+    recent_additions.sort_by(|a, b| b.added_at.cmp(&a.added_at));
+
+    let weeks_to_clear =
+        (weekly_mins > 0.0).then(|| (remaining_mins as f64 / weekly_mins * 10.0).round() / 10.0);
+    let clear_date = days_for(remaining_mins, weekly_mins)
+        .map(|days| (today + Duration::days(days)).to_string());
+
+    Forecast {
+        backlog_games: backlog.len(),
+        remaining_mins,
+        estimated_games,
+        estimate_mins,
+        weekly_mins: weekly_mins.round() as i64,
+        weeks_to_clear,
+        clear_date,
+        recent_additions,
+    }
+}
+
+/// Average weekly playtime over the last weeks of sessions
+///
+/// The window starts at the first recorded session when that is more recent,
+/// so a new install isn't averaged over weeks it wasn't tracking.
+async fn weekly_playtime(pool: &SqlitePool, now: NaiveDateTime) -> Result<f64, sqlx::Error> {
+    let window_start = now - Duration::weeks(PLAYTIME_WINDOW_WEEKS);
+    let first_session = db::get_first_session_start(pool)
+        .await?
+        .and_then(|s| timestamps::parse_naive(&s));
+    let Some(first_session) = first_session else {
+        return Ok(0.0);
+    };
+    let start = first_session.max(window_start);
+
+    let total: i64 = db::get_playtime_by_game(
+        pool,
+        &timestamps::format_naive(start),
+        &timestamps::format_naive(now),
+    )
+    .await?
+    .iter()
+    .map(|g| g.active_mins)
+    .sum();
+
+    let weeks = ((now - start).num_days() as f64 / 7.0).max(1.0);
+    Ok(total as f64 / weeks)
+}
+
+/// Forecast for the current library
+pub async fn current_forecast(pool: &SqlitePool) -> Result<Forecast, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let backlog = db::get_backlog_games(pool).await?;
+    let weekly_mins = weekly_playtime(pool, now).await?;
+    Ok(build_forecast(&backlog, weekly_mins, now.date()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn game(id: i64, main: Option<i64>, played: i64, created_at: &str) -> Game {
+        Game {
+            hltb_main_mins: main,
+            playtime_mins: Some(played),
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            ..create_test_game(id, &format!("Game {}", id))
+        }
+    }
+
+    #[test]
+    fn test_build_forecast() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        let backlog = [
+            game(1, Some(600), 120, "2023-01-01T00:00:00Z"),
+            game(2, Some(1200), 0, "2023-06-01T00:00:00Z"),
+            game(3, Some(300), 400, "2024-05-01T12:00:00Z"),
+            game(4, None, 0, "2024-05-08T09:00:00Z"),
+        ];
+
+        let forecast = build_forecast(&backlog, 300.0, today);
+        assert_eq!(forecast.backlog_games, 4);
+        // 480 + 1200 + 0 (past HLTB time) + 600 (median estimate)
+        assert_eq!(forecast.estimate_mins, 600);
+        assert_eq!(forecast.estimated_games, 1);
+        assert_eq!(forecast.remaining_mins, 2280);
+        assert_eq!(forecast.weeks_to_clear, Some(7.6));
+        assert_eq!(forecast.clear_date.as_deref(), Some("2024-07-03"));
+
+        let added: Vec<i64> = forecast
+            .recent_additions
+            .iter()
+            .map(|g| g.game_id)
+            .collect();
+        assert_eq!(added, [4, 3]);
+        assert!(forecast.recent_additions[0].estimated);
+        assert_eq!(forecast.recent_additions[0].delay_days, Some(14));
+        assert_eq!(forecast.recent_additions[1].delay_days, Some(0));
+    }
+
+    #[test]
+    fn test_forecast_without_playtime() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        let forecast = build_forecast(&[game(1, None, 0, "2024-05-09T00:00:00Z")], 0.0, today);
+        assert_eq!(forecast.estimate_mins, DEFAULT_ESTIMATE_MINS);
+        assert_eq!(forecast.weeks_to_clear, None);
+        assert_eq!(forecast.clear_date, None);
+        assert_eq!(forecast.recent_additions[0].delay_days, None);
+    }
+}
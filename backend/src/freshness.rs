@@ -0,0 +1,219 @@
+//! "What's new" feed
+//!
+//! A game's `created_at` is when the scanner first found its folder, which
+//! says nothing about how new the game itself is. The feed splits recent
+//! activity in two: games released recently (by their store release date)
+//! and games added to the library recently that were released earlier, or
+//! whose release date is unknown.
+
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+use crate::models::{Game, GameSummary};
+
+/// Default window in days
+pub const DEFAULT_WINDOW_DAYS: i64 = 90;
+/// Longest window accepted
+pub const MAX_WINDOW_DAYS: i64 = 365;
+
+/// Default and largest number of games in each list
+pub const DEFAULT_LIMIT: usize = 20;
+pub const MAX_LIMIT: usize = 100;
+
+/// Formats of Steam's store dates and of user edits
+const RELEASE_DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%b %d, %Y",
+    "%d %b, %Y",
+    "%B %d, %Y",
+    "%d %B, %Y",
+];
+
+/// Parse a release date as stored; vague dates ("Q3 2024", "Coming soon") give None
+pub fn parse_release_date(date: &str) -> Option<NaiveDate> {
+    let date = date.trim();
+    RELEASE_DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+}
+
+/// `release_date_iso` column value (YYYY-MM-DD) for a stored release date
+pub fn release_date_iso(date: &str) -> Option<String> {
+    parse_release_date(date).map(|d| d.to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FreshGame {
+    #[serde(flatten)]
+    pub game: GameSummary,
+    /// Release date as stored (from Steam or an edit)
+    pub release_date: Option<String>,
+    /// Parsed release date (YYYY-MM-DD), when it is a full date
+    pub released_on: Option<String>,
+    /// When the scanner first found the game
+    pub detected_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewReleases {
+    pub days: i64,
+    /// Games released in the window, newest release first
+    pub released: Vec<FreshGame>,
+    /// Games detected in the window that were released before it, newest first
+    pub acquired: Vec<FreshGame>,
+}
+
+/// Split recent games into new releases and newly acquired older games
+///
+/// Hidden games are left out.
+pub fn build_feed(games: Vec<Game>, today: NaiveDate, days: i64, limit: usize) -> NewReleases {
+    let since = today - Duration::days(days);
+    let since_str = since.to_string();
+
+    let mut released = Vec::new();
+    let mut acquired = Vec::new();
+    for game in games.into_iter().filter(|g| g.hidden.unwrap_or(0) == 0) {
+        let release = game
+            .release_date_iso
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        let is_new_release = release.is_some_and(|d| d >= since && d <= today);
+        let is_new_detection = game.created_at.as_str() >= since_str.as_str();
+        if !is_new_release && !is_new_detection {
+            continue;
+        }
+
+        let fresh = FreshGame {
+            release_date: game.release_date.clone(),
+            released_on: game.release_date_iso.clone(),
+            detected_at: game.created_at.clone(),
+            game: GameSummary::from(game),
+        };
+        if is_new_release {
+            released.push((release, fresh));
+        } else if release.is_none_or(|d| d < since) {
+            acquired.push(fresh);
+        }
+    }
+
+    released.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| a.1.game.title.cmp(&b.1.game.title))
+    });
+    acquired.sort_by(|a, b| b.detected_at.cmp(&a.detected_at));
+
+    NewReleases {
+        days,
+        released: released.into_iter().map(|(_, g)| g).take(limit).collect(),
+        acquired: acquired.into_iter().take(limit).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::models::{create_test_game, GameFilter, GameSort};
+
+    fn game(id: i64, release_date: Option<&str>, created_at: &str) -> Game {
+        Game {
+            release_date: release_date.map(str::to_string),
+            release_date_iso: release_date.and_then(release_date_iso),
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            ..create_test_game(id, &format!("Game {}", id))
+        }
+    }
+
+    #[test]
+    fn test_parse_release_date() {
+        let expected = NaiveDate::from_ymd_opt(2015, 5, 18);
+        assert_eq!(parse_release_date("May 18, 2015"), expected);
+        assert_eq!(parse_release_date("18 May, 2015"), expected);
+        assert_eq!(parse_release_date("2015-05-18"), expected);
+        assert_eq!(parse_release_date("Q3 2024"), None);
+        assert_eq!(parse_release_date("Coming soon"), None);
+    }
+
+    #[test]
+    fn test_build_feed() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 10).unwrap();
+        let games = vec![
+            // Released last month, found long ago (preloaded)
+            game(1, Some("Apr 9, 2024"), "2023-01-01T00:00:00Z"),
+            // Released last week, found yesterday
+            game(2, Some("3 May, 2024"), "2024-05-09T10:00:00Z"),
+            // Old game found this week
+            game(3, Some("Jan 17, 2016"), "2024-05-08T09:00:00Z"),
+            // Unknown release date, found this week
+            game(4, Some("Coming soon"), "2024-05-07T09:00:00Z"),
+            // Old game found long ago
+            game(5, Some("Aug 5, 2018"), "2023-01-01T00:00:00Z"),
+            // Not released yet
+            game(6, Some("Dec 1, 2024"), "2023-01-01T00:00:00Z"),
+        ];
+
+        let feed = build_feed(games, today, 90, DEFAULT_LIMIT);
+        let ids = |list: &[FreshGame]| list.iter().map(|g| g.game.id).collect::<Vec<_>>();
+        assert_eq!(ids(&feed.released), [2, 1]);
+        assert_eq!(ids(&feed.acquired), [3, 4]);
+        assert_eq!(feed.released[0].released_on.as_deref(), Some("2024-05-03"));
+
+        let feed = build_feed(vec![game(1, None, "2024-05-09T00:00:00Z")], today, 90, 0);
+        assert!(feed.acquired.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_release_date_sort_and_backfill() {
+        let (pool, _keepalive) = db::test_pool().await;
+
+        for (title, release_date) in [
+            ("Hades", Some("17 Sep, 2020")),
+            ("Celeste", Some("Jan 25, 2018")),
+            ("Silksong", Some("Coming soon")),
+            ("Unmatched", None),
+            ("Cyberpunk 2077", Some("Dec 9, 2020")),
+        ] {
+            let id = db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            // Stored the way enrichment did before the ISO column existed
+            sqlx::query("UPDATE games SET release_date = ? WHERE id = ?")
+                .bind(release_date)
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        db::run_migrations(&pool).await.unwrap();
+
+        let titles = |games: Vec<Game>| games.into_iter().map(|g| g.title).collect::<Vec<_>>();
+        let mut filter = GameFilter {
+            sort: GameSort::ReleaseDate,
+            ..Default::default()
+        };
+        let games = db::get_games_page(&pool, &filter, 10, 0).await.unwrap();
+        assert_eq!(
+            titles(games),
+            [
+                "Cyberpunk 2077",
+                "Hades",
+                "Celeste",
+                "Silksong",
+                "Unmatched"
+            ]
+        );
+
+        filter.release_year = Some(2020);
+        let games = db::get_games_page(&pool, &filter, 10, 0).await.unwrap();
+        assert_eq!(titles(games), ["Cyberpunk 2077", "Hades"]);
+        assert_eq!(db::count_games(&pool, &filter).await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+//! Rough genres for games no provider can match
+//!
+//! Games without a Steam match have no genres, so filtering by genre leaves
+//! them out. Scans infer a few genres and tags from the folder name
+//! ("Farming Simulator 22" is a simulation) and from files bundled with the
+//! game: an engine or emulator that implies a genre (RPG Maker, Ren'Py,
+//! ScummVM) or wrappers for old DirectX versions that imply an older title.
+//!
+//! The result is stored apart from the provider's genres as
+//! `inferred_genres` and only used by the genre filter while a game has no
+//! real genres, so a guess never replaces or mixes with matched data.
+
+use std::path::Path;
+
+/// Tag for games that look like older titles
+pub const TAG_RETRO: &str = "Retro";
+
+/// Words in a folder name (lowercase) and the genres they suggest
+const NAME_RULES: &[(&[&str], &[&str])] = &[
+    (
+        &[
+            "simulator",
+            "simulation",
+            "sim",
+            "farming",
+            "truck",
+            "flight",
+        ],
+        &["Simulation"],
+    ),
+    (&["tycoon"], &["Simulation", "Strategy"]),
+    (&["racing", "racer", "rally", "drift", "kart"], &["Racing"]),
+    (
+        &[
+            "football", "soccer", "fifa", "nba", "nfl", "nhl", "golf", "tennis",
+        ],
+        &["Sports"],
+    ),
+    (&["strategy", "tactics", "rts"], &["Strategy"]),
+    (&["puzzle"], &["Puzzle"]),
+    (&["horror"], &["Horror"]),
+    (&["rpg"], &["RPG"]),
+    (&["shooter"], &["Action"]),
+    (&["adventure"], &["Adventure"]),
+    (&["platformer"], &["Platformer"]),
+    (&["vr"], &["VR"]),
+];
+
+/// Files or folders (relative to the game folder, lowercase) and what they
+/// suggest
+const FILE_RULES: &[(&[&str], &[&str])] = &[
+    // RPG Maker XP/VX/VX Ace archives and MV/MZ engine scripts
+    (
+        &[
+            "game.rgssad",
+            "game.rgss2a",
+            "game.rgss3a",
+            "www/js/rpg_core.js",
+            "js/rpg_core.js",
+            "js/rmmz_core.js",
+        ],
+        &["RPG"],
+    ),
+    (&["renpy"], &["Visual Novel"]),
+    (&["scummvm.exe"], &["Adventure", TAG_RETRO]),
+    (&["dosbox.exe", "dosbox"], &[TAG_RETRO]),
+    // Wrappers for DirectInput/DirectDraw/Direct3D 8 and earlier, which
+    // games of the early 2000s and before need to run well today
+    // (dinput8.dll is left out: modern mod loaders use it too)
+    (
+        &[
+            "dinput.dll",
+            "ddraw.dll",
+            "d3d8.dll",
+            "d3dimm.dll",
+            "dgvoodoo.conf",
+        ],
+        &[TAG_RETRO],
+    ),
+];
+
+/// Genres and tags suggested by a game's folder name and files, sorted
+pub fn infer(folder_name: &str, game_dir: &Path) -> Vec<String> {
+    let mut found: Vec<&str> = Vec::new();
+
+    let name = folder_name.to_lowercase();
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    for (keywords, genres) in NAME_RULES {
+        if words.iter().any(|w| keywords.contains(w)) {
+            found.extend_from_slice(genres);
+        }
+    }
+
+    let top_level: Vec<String> = std::fs::read_dir(game_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default();
+    for (files, genres) in FILE_RULES {
+        let present = files.iter().any(|file| match file.split_once('/') {
+            // Nested paths are checked directly (case as commonly shipped)
+            Some(_) => game_dir.join(file).exists(),
+            None => top_level.iter().any(|f| f == file),
+        });
+        if present {
+            found.extend_from_slice(genres);
+        }
+    }
+
+    let mut genres: Vec<String> = found.into_iter().map(String::from).collect();
+    genres.sort();
+    genres.dedup();
+    genres
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_from_name() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            infer("Farming Simulator 22 [FitGirl Repack]", dir.path()),
+            ["Simulation"]
+        );
+        assert_eq!(
+            infer("Prison.Tycoon.4", dir.path()),
+            ["Simulation", "Strategy"]
+        );
+        assert!(infer("Simple Things", dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_infer_from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["Game.exe", "DDRAW.dll", "www/js/rpg_core.js"] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        assert_eq!(infer("Some Game", dir.path()), ["RPG", TAG_RETRO]);
+
+        let modern = tempfile::tempdir().unwrap();
+        std::fs::write(modern.path().join("dinput8.dll"), b"").unwrap();
+        assert!(infer("Some Game", modern.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_genre_filter_falls_back_to_inferred() {
+        use crate::{db, models::GameFilter};
+
+        let (pool, _keepalive) = db::test_pool().await;
+        for title in [
+            "Euro Truck Simulator 2",
+            "Microsoft Flight Simulator",
+            "Doom",
+        ] {
+            let id = db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            db::update_game_inferred_genres(&pool, id, &infer(title, Path::new("/missing")))
+                .await
+                .unwrap();
+        }
+        // A provider's genres replace the guess
+        sqlx::query(
+            "UPDATE games SET genres = '[\"Action\"]' WHERE title = 'Microsoft Flight Simulator'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let titles = |genre: &str| {
+            let pool = pool.clone();
+            let filter = GameFilter {
+                genre: Some(genre.to_string()),
+                ..Default::default()
+            };
+            async move {
+                db::get_games_page(&pool, &filter, 10, 0)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|g| g.title)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(titles("simulation").await, ["Euro Truck Simulator 2"]);
+        assert_eq!(titles("Action").await, ["Microsoft Flight Simulator"]);
+        assert!(titles("Racing").await.is_empty());
+    }
+}
@@ -1,28 +1,153 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, Query, State},
+    extract::{ws::WebSocketUpgrade, ConnectInfo, Path, Query, State},
+    http::HeaderMap,
     Json,
 };
 use serde::Deserialize;
 
 use crate::{
+    archive, artwork, backup, blocklist,
     config::{self, AppConfig},
-    db, local_storage,
-    models::{ApiResponse, Game, GameSummary, Stats},
-    scanner, steam, AppState,
+    crash, custom_fields, db, deletion, demo, diagnostics, discovery, downloads, duplicates,
+    encryption, events, export, folder_appid, forecast, freshness, i18n, import, launcher, linking,
+    local_storage, mappings, match_review, matching, media, metadata_sync, metrics,
+    models::{
+        ApiResponse, BlocklistEntry, CustomField, DeviceToken, Game, GameCompat, GameDetail,
+        GameFilter, GameList, GameLocation, GameMedia, GamePlaytime, GameSettings, GameSummary,
+        ImportReportSummary, IncludePathsQuery, Job, LinkProposal, Notification, Page, PageQuery,
+        PlaySession, Profile, RecentlyPlayed, ScanReportSummary, Stats, TitleMapping,
+        UndoOperation, WishlistItem, WishlistPrice, WithLocation, WorkshopItem,
+    },
+    notifications, pairing, prefetch, profiles, raw, redist, rematch, reports,
+    reviews::ReviewBand,
+    scanner, session_tokens, sessions,
+    settings_profile::SettingsProfile,
+    site_export, steam, storage, timestamps, tracker_import, undo, wishlist, AppState,
 };
 
 pub async fn health() -> Json<ApiResponse<&'static str>> {
     Json(ApiResponse::success("OK"))
 }
 
-pub async fn list_games(State(state): State<Arc<AppState>>) -> Json<ApiResponse<Vec<GameSummary>>> {
-    match db::get_all_games(&state.db).await {
-        Ok(games) => {
-            let summaries: Vec<GameSummary> = games.into_iter().map(|g| g.into()).collect();
-            Json(ApiResponse::success(summaries))
+/// Version, uptime, the last crash report and recent errors (GET /health/details)
+pub async fn health_details(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<crash::HealthDetails>> {
+    let mut details = crash::health_details();
+    match metadata_sync::status(&state.db).await {
+        Ok(sync) => details.metadata_sync = sync,
+        Err(e) => tracing::warn!("Failed to read metadata sync state: {}", e),
+    }
+    Json(ApiResponse::success(details))
+}
+
+/// Whether a game listing should include folder paths
+///
+/// SECURITY: Folder paths are only returned when explicitly requested by an
+/// admin. Returns an error message when `include_paths` was requested without
+/// the admin key.
+fn include_paths(headers: &HeaderMap, query: &IncludePathsQuery) -> Result<bool, &'static str> {
+    if !query.include_paths {
+        return Ok(false);
+    }
+    if crate::is_admin_request(headers) {
+        Ok(true)
+    } else {
+        tracing::warn!("include_paths requested without a valid admin key");
+        Err("include_paths requires the admin API key")
+    }
+}
+
+/// List games one page at a time (GET /games?page=1&page_size=100)
+///
+/// Without `page` and `page_size` the games are returned as a bare array, so
+/// clients written before pagination keep working; that array is capped at
+/// `api.max_unpaged_games`. Optional filters and the sort order are the
+/// fields of `GameFilter`.
+pub async fn list_games(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<PageQuery>,
+    Query(filter): Query<GameFilter>,
+    Query(paths): Query<IncludePathsQuery>,
+) -> Json<ApiResponse<GameList<WithLocation<GameSummary>>>> {
+    let include = match include_paths(&headers, &paths) {
+        Ok(include) => include,
+        Err(message) => return Json(ApiResponse::error(message)),
+    };
+
+    let (page, page_size) = state.config.api.page_bounds(query.page, query.page_size);
+    let offset = (page as i64 - 1) * page_size as i64;
+
+    let filter = GameFilter {
+        compat_platform: filter.compat_platform.map(|p| normalize_platform(&p)),
+        compat_status: filter.compat_status.map(|s| s.trim().to_lowercase()),
+        compat_wrapper: filter.compat_wrapper.filter(|w| !w.trim().is_empty()),
+        deck_compat: filter.deck_compat.map(|d| d.trim().to_lowercase()),
+        include_hidden: filter.include_hidden,
+        release_year: filter.release_year,
+        review_band: filter.review_band,
+        settings_resolution: filter.settings_resolution,
+        settings_upscaler: filter.settings_upscaler,
+        settings_fps_cap: filter.settings_fps_cap,
+        genre: filter
+            .genre
+            .map(|g| g.trim().to_string())
+            .filter(|g| !g.is_empty()),
+        custom_field: filter.custom_field.filter(|k| !k.trim().is_empty()),
+        custom_value: filter.custom_value,
+        trading_cards: filter.trading_cards,
+        points_shop: filter.points_shop,
+        sort: filter.sort,
+    };
+    let filter = match custom_field_filter(&state, filter).await {
+        Ok(filter) => filter,
+        Err(message) => return Json(ApiResponse::error(message)),
+    };
+
+    let with_locations = |games: Vec<Game>| -> Vec<WithLocation<GameSummary>> {
+        games
+            .into_iter()
+            .map(|g| {
+                let location = include.then(|| GameLocation::from(&g));
+                WithLocation {
+                    item: g.into(),
+                    location,
+                }
+            })
+            .collect()
+    };
+
+    if query.page.is_none() && query.page_size.is_none() {
+        let limit = state.config.api.unpaged_limit() as i64;
+        return match db::get_games_page(&state.db, &filter, limit, 0).await {
+            Ok(games) => Json(ApiResponse::success(GameList::All(with_locations(games)))),
+            Err(e) => {
+                tracing::error!("Failed to list games: {}", e);
+                Json(ApiResponse::error("Internal server error"))
+            }
+        };
+    }
+
+    let total = match db::count_games(&state.db, &filter).await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to count games: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
         }
+    };
+
+    match db::get_games_page(&state.db, &filter, page_size as i64, offset).await {
+        Ok(games) => Json(ApiResponse::success(GameList::Page(Page {
+            items: with_locations(games),
+            page,
+            page_size,
+            total,
+        }))),
         Err(e) => {
             tracing::error!("Failed to list games: {}", e);
             Json(ApiResponse::error("Internal server error"))
@@ -30,12 +155,86 @@ pub async fn list_games(State(state): State<Arc<AppState>>) -> Json<ApiResponse<
     }
 }
 
+/// Resolve the custom field filter: the key must name a field, and the value
+/// becomes a JSON literal of the field's type
+async fn custom_field_filter(
+    state: &AppState,
+    filter: GameFilter,
+) -> Result<GameFilter, &'static str> {
+    let Some(key) = filter.custom_field.as_deref().map(str::trim) else {
+        return Ok(filter);
+    };
+    let fields = db::get_custom_fields(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to load custom fields: {}", e);
+        "Internal server error"
+    })?;
+    let field_type = fields
+        .iter()
+        .find(|f| f.key == key)
+        .and_then(|f| custom_fields::FieldType::parse(&f.field_type))
+        .ok_or("Unknown custom field")?;
+    let custom_value = match filter.custom_value.as_deref() {
+        Some(value) => Some(
+            field_type
+                .filter_literal(value)
+                .ok_or("custom_value doesn't fit the field's type")?,
+        ),
+        None => None,
+    };
+    Ok(GameFilter {
+        custom_field: Some(key.to_string()),
+        custom_value,
+        ..filter
+    })
+}
+
+/// "Game not found" unless the game exists and the active profile allows it,
+/// for handlers that take a game id but don't load the game itself
+async fn ensure_visible<T>(state: &AppState, id: i64) -> Result<(), Json<ApiResponse<T>>> {
+    match db::is_game_visible(&state.db, id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Json(ApiResponse::error("Game not found"))),
+        Err(e) => {
+            tracing::error!("Failed to check visibility of game {}: {}", id, e);
+            Err(Json(ApiResponse::error("Database error")))
+        }
+    }
+}
+
+/// A game, unless the active profile hides it (see profiles.rs)
+async fn visible_game(state: &AppState, id: i64) -> Result<Option<Game>, sqlx::Error> {
+    if !db::is_game_visible(&state.db, id).await? {
+        return Ok(None);
+    }
+    db::get_game_by_id(&state.db, id).await
+}
+
 pub async fn get_game(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Json<ApiResponse<Game>> {
-    match db::get_game_by_id(&state.db, id).await {
-        Ok(Some(game)) => Json(ApiResponse::success(game)),
+    Query(paths): Query<IncludePathsQuery>,
+) -> Json<ApiResponse<WithLocation<GameDetail>>> {
+    let include = match include_paths(&headers, &paths) {
+        Ok(include) => include,
+        Err(message) => return Json(ApiResponse::error(message)),
+    };
+
+    match visible_game(&state, id).await {
+        Ok(Some(game)) => {
+            let size_history = match db::get_game_size_history(&state.db, id).await {
+                Ok(history) => history,
+                Err(e) => {
+                    tracing::error!("Failed to get size history of game {}: {}", id, e);
+                    return Json(ApiResponse::error("Internal server error"));
+                }
+            };
+            let location = include.then(|| GameLocation::from(&game));
+            Json(ApiResponse::success(WithLocation {
+                item: GameDetail { game, size_history },
+                location,
+            }))
+        }
         Ok(None) => Json(ApiResponse::error("Game not found")),
         Err(e) => {
             tracing::error!("Failed to get game {}: {}", id, e);
@@ -70,7 +269,8 @@ pub async fn search_games(
         return Json(ApiResponse::error("Search query too long"));
     }
 
-    match db::search_games(&state.db, query_trimmed).await {
+    let limit = state.config.api.search_limit() as i64;
+    match db::search_games(&state.db, query_trimmed, limit).await {
         Ok(games) => {
             let summaries: Vec<GameSummary> = games.into_iter().map(|g| g.into()).collect();
             Json(ApiResponse::success(summaries))
@@ -82,46 +282,258 @@ pub async fn search_games(
     }
 }
 
-pub async fn scan_games(State(state): State<Arc<AppState>>) -> Json<ApiResponse<ScanResult>> {
+/// Folders scanned between progress events
+const SCAN_PROGRESS_INTERVAL: usize = 25;
+
+pub async fn scan_games(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<scanner::ScanReport>> {
+    Json(ApiResponse::success(run_scan(&state).await))
+}
+
+/// Scan the library folder (also run on startup, see startup.rs)
+///
+/// The report is stored with every folder left out and why.
+pub async fn run_scan(state: &AppState) -> scanner::ScanReport {
     tracing::info!("Starting game scan of {}", state.games_path);
 
-    let games = scanner::scan_games_directory(&state.games_path);
+    let scanner::DirectoryScan { games, mut skipped } =
+        scanner::scan_games_directory(&state.games_path);
     let total = games.len();
     let mut added = 0;
+    let mut wishlist_linked = 0;
+
+    let reservations = db::get_acquiring_wishlist(&state.db)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to load wishlist reservations: {}", e);
+            Vec::new()
+        });
+
+    // Known games, to notice games whose files changed since the last scan
+    let known: HashMap<String, Game> = db::get_all_games(&state.db)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to load known games: {}", e);
+            Vec::new()
+        })
+        .into_iter()
+        .map(|g| (g.folder_path.clone(), g))
+        .collect();
+    let mut new_games = 0;
+    let mut updated = 0;
+
+    for (done, game) in games.into_iter().enumerate() {
+        if done % SCAN_PROGRESS_INTERVAL == 0 {
+            events::publish_progress(events::JOB_SCAN, done, total);
+        }
+        let reservation = wishlist::find_reservation(&reservations, &game.folder_name);
+
+        // A reserved folder that is still empty is a download that hasn't started
+        if let Some(item) = reservation
+            .filter(|_| wishlist::is_empty_folder(std::path::Path::new(&game.folder_path)))
+        {
+            skipped.push(scanner::SkippedFolder::new(
+                game.folder_name,
+                scanner::SkipReason::PendingDownload,
+                Some(format!("Wishlist entry '{}'", item.title)),
+            ));
+            continue;
+        }
 
-    for game in games {
-        match db::upsert_game(
+        let game_id = match db::upsert_game(
             &state.db,
             &game.folder_path,
             &game.folder_name,
             &game.clean_title,
             game.size_bytes,
+            game.year_hint,
         )
         .await
         {
-            Ok(_) => added += 1,
+            Ok(id) => {
+                added += 1;
+                id
+            }
             Err(e) => {
                 tracing::warn!("Failed to upsert game '{}': {}", game.clean_title, e);
+                skipped.push(scanner::SkippedFolder::new(
+                    game.folder_name,
+                    scanner::SkipReason::Failed,
+                    Some(e.to_string()),
+                ));
+                continue;
+            }
+        };
+
+        if let Err(e) = db::replace_game_runtimes(&state.db, game_id, &game.runtimes).await {
+            tracing::warn!("Failed to record runtimes of '{}': {}", game.clean_title, e);
+        }
+        if let Err(e) =
+            db::update_game_inferred_genres(&state.db, game_id, &game.inferred_genres).await
+        {
+            tracing::warn!(
+                "Failed to record inferred genres of '{}': {}",
+                game.clean_title,
+                e
+            );
+        }
+        let size_changed = match game.install_size_bytes {
+            Some(size) => db::record_game_size(&state.db, game_id, size)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to record size of '{}': {}", game.clean_title, e);
+                    true
+                }),
+            None => true,
+        };
+
+        // Looking for an app id walks the folder, so it's skipped while the
+        // folder is the same size as last time and its id is already known
+        let known_game = known.get(&game.folder_path);
+        if size_changed || known_game.is_none_or(|g| g.folder_app_id.is_none()) {
+            let found = folder_appid::detect(std::path::Path::new(&game.folder_path));
+            if let Err(e) = db::update_game_folder_app_id(&state.db, game_id, found.as_ref()).await
+            {
+                tracing::warn!("Failed to record app id of '{}': {}", game.clean_title, e);
+            }
+        }
+
+        match (known_game.map(|g| g.size_bytes), game.size_bytes) {
+            (None, _) => {
+                new_games += 1;
+                events::publish_game(&state.db, game_id, true).await;
+            }
+            (Some(Some(old)), Some(new)) if old != new => {
+                updated += 1;
+                events::publish_game(&state.db, game_id, false).await;
+                let message = format!(
+                    "{} changed size on disk ({} -> {} bytes), it may have been updated",
+                    game.clean_title, old, new
+                );
+                notifications::notify(
+                    &state.db,
+                    notifications::KIND_GAME_UPDATED,
+                    "Game update detected",
+                    &message,
+                    Some(game_id),
+                )
+                .await;
+            }
+            _ => {}
+        }
+
+        if let Some(item) = reservation {
+            match db::link_wishlist_item(&state.db, item.id, game_id).await {
+                Ok(()) => {
+                    tracing::info!(
+                        "Linked wishlist entry '{}' to {}",
+                        item.title,
+                        game.folder_name
+                    );
+                    wishlist_linked += 1;
+                }
+                Err(e) => tracing::warn!("Failed to link wishlist entry {}: {}", item.id, e),
             }
         }
     }
 
+    events::publish_progress(events::JOB_SCAN, total, total);
     tracing::info!(
-        "Scan complete: {} games found, {} added/updated",
+        "Scan complete: {} games found, {} added/updated, {} wishlist entries linked",
         total,
-        added
+        added,
+        wishlist_linked
     );
 
-    Json(ApiResponse::success(ScanResult {
-        total_found: total,
-        added_or_updated: added,
-    }))
+    if let Err(e) = blocklist::apply(&state.db).await {
+        tracing::warn!("Failed to apply enrichment blocklist: {}", e);
+    }
+
+    notifications::notify(
+        &state.db,
+        notifications::KIND_SCAN_COMPLETE,
+        "Library scan complete",
+        &format!(
+            "{} games found, {} new, {} changed on disk",
+            total, new_games, updated
+        ),
+        None,
+    )
+    .await;
+    notifications::check_disk_space(
+        &state.db,
+        std::path::Path::new(&state.games_path),
+        state.config.notifications.disk_low_gb,
+    )
+    .await;
+    match_review::notify_after_scan(&state.db, &state.http, &state.config, new_games).await;
+
+    let skipped_json = serde_json::to_string(&skipped).unwrap_or_else(|_| "[]".to_string());
+    let summary = db::insert_scan_report(
+        &state.db,
+        total as i64,
+        added as i64,
+        wishlist_linked as i64,
+        skipped.len() as i64,
+        &skipped_json,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        tracing::warn!("Failed to store scan report: {}", e);
+        ScanReportSummary {
+            id: 0,
+            total_found: total as i64,
+            added_or_updated: added as i64,
+            wishlist_linked: wishlist_linked as i64,
+            skipped: skipped.len() as i64,
+            created_at: timestamps::now(),
+        }
+    });
+    scanner::ScanReport {
+        summary,
+        skipped_folders: skipped,
+    }
 }
 
-#[derive(serde::Serialize)]
-pub struct ScanResult {
-    total_found: usize,
-    added_or_updated: usize,
+/// How many scan reports GET /scans returns
+const SCAN_REPORT_LIST_LIMIT: i64 = 50;
+
+/// List recent scan reports (GET /scans)
+pub async fn list_scan_reports(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<ScanReportSummary>>> {
+    match db::get_scan_reports(&state.db, SCAN_REPORT_LIST_LIMIT).await {
+        Ok(reports) => Json(ApiResponse::success(reports)),
+        Err(e) => {
+            tracing::error!("Failed to list scan reports: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Get one scan report with the folders it skipped (GET /scans/{id})
+pub async fn get_scan_report(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<scanner::ScanReport>> {
+    match db::get_scan_report(&state.db, id).await {
+        Ok(Some((summary, skipped_json))) => {
+            let skipped_folders = serde_json::from_str(&skipped_json).unwrap_or_else(|e| {
+                tracing::warn!("Scan report {} has unreadable skipped folders: {}", id, e);
+                Vec::new()
+            });
+            Json(ApiResponse::success(scanner::ScanReport {
+                summary,
+                skipped_folders,
+            }))
+        }
+        Ok(None) => Json(ApiResponse::error("Scan report not found")),
+        Err(e) => {
+            tracing::error!("Failed to get scan report {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
 }
 
 pub async fn enrich_games(State(state): State<Arc<AppState>>) -> Json<ApiResponse<EnrichResult>> {
@@ -135,16 +547,52 @@ pub async fn enrich_games(State(state): State<Arc<AppState>>) -> Json<ApiRespons
         }
     };
 
-    let client = reqwest::Client::new();
+    let client = state.http.clone();
     let mut enriched = 0;
     let mut failed = 0;
 
     // Process up to ENRICHMENT_BATCH_SIZE games per request to avoid timeouts
-    for game in games.iter().take(ENRICHMENT_BATCH_SIZE) {
+    let batch: Vec<i64> = games
+        .iter()
+        .take(ENRICHMENT_BATCH_SIZE)
+        .map(|g| g.id)
+        .collect();
+    if !batch.is_empty() {
+        let description = format!("Enrich {} game(s) from Steam", batch.len());
+        if let Err(e) = db::snapshot_games(&state.db, undo::KIND_ENRICH, &description, &batch).await
+        {
+            tracing::error!("Failed to snapshot games before enrichment: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    }
+
+    for (done, game) in games.iter().take(ENRICHMENT_BATCH_SIZE).enumerate() {
+        events::publish_progress(events::JOB_ENRICH, done, batch.len());
         tracing::info!("Enriching: {}", game.title);
 
-        // Search for Steam App ID
-        let (app_id, confidence) = match steam::search_steam_app(&client, &game.title).await {
+        // A stored mapping (confirmed rematch or imported pack) wins over searching
+        let mapped = mappings::lookup(&state.db, &game.title).await;
+        if mapped == Some(0) {
+            tracing::info!("Skipping {}: mapped as not on Steam", game.title);
+            failed += 1;
+            continue;
+        }
+
+        // Then an app id named by the folder's files, then a title search
+        let found = match (mapped, game.folder_app_id) {
+            (Some(id), _) => Some((id, 1.0)),
+            (None, Some(id)) => Some((id, folder_appid::MATCH_CONFIDENCE)),
+            (None, None) => {
+                steam::search_steam_app(
+                    &client,
+                    &state.search_cache,
+                    &game.title,
+                    game.year_hint.map(|y| y as i32),
+                )
+                .await
+            }
+        };
+        let (app_id, confidence) = match found {
             Some((id, conf)) => (id, conf),
             None => {
                 failed += 1;
@@ -152,89 +600,16 @@ pub async fn enrich_games(State(state): State<Arc<AppState>>) -> Json<ApiRespons
             }
         };
 
-        // Rate limit
-        tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
-
-        // Fetch details
-        let details = steam::fetch_steam_details(&client, app_id).await;
-
-        // Rate limit
-        tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
-
-        // Fetch reviews
-        let reviews = steam::fetch_steam_reviews(&client, app_id).await;
-
-        // Update database
-        if let Some(d) = details {
-            let genres_json = d
-                .genres
-                .map(|g| serde_json::to_string(&g).unwrap_or_default());
-            let devs_json = d
-                .developers
-                .map(|g| serde_json::to_string(&g).unwrap_or_default());
-            let pubs_json = d
-                .publishers
-                .map(|g| serde_json::to_string(&g).unwrap_or_default());
-
-            if let Err(e) = db::update_game_steam_data(
-                &state.db,
-                game.id,
-                app_id,
-                d.description.as_deref(),
-                d.header_image.as_deref(),
-                d.background.as_deref(),
-                genres_json.as_deref(),
-                devs_json.as_deref(),
-                pubs_json.as_deref(),
-                d.release_date.as_deref(),
-                confidence,
-            )
-            .await
-            {
-                tracing::warn!("Failed to update game {}: {}", game.id, e);
-                failed += 1;
-                continue;
-            }
-
-            // Cache images locally in the game folder
-            let (local_cover, local_bg) = local_storage::cache_game_images(
-                &client,
-                &game.folder_path,
-                d.header_image.as_deref(),
-                d.background.as_deref(),
-            )
-            .await;
-
-            // Update database with local image paths
-            if local_cover.is_some() || local_bg.is_some() {
-                if let Err(e) = db::update_game_local_images(
-                    &state.db,
-                    game.id,
-                    local_cover.as_deref(),
-                    local_bg.as_deref(),
-                )
-                .await
-                {
-                    tracing::warn!(
-                        "Failed to update local image paths for game {}: {}",
-                        game.id,
-                        e
-                    );
-                }
-            }
-        }
-
-        if let Some(r) = reviews {
-            if let Err(e) =
-                db::update_game_reviews(&state.db, game.id, r.score, r.count, &r.summary).await
-            {
-                tracing::warn!("Failed to update reviews for game {}: {}", game.id, e);
-            }
+        if !apply_steam_match(&state, game, app_id, confidence).await {
+            failed += 1;
+            continue;
         }
 
         enriched += 1;
+        events::publish_game(&state.db, game.id, false).await;
         tracing::info!("Enriched: {} (Steam App ID: {})", game.title, app_id);
     }
+    events::publish_progress(events::JOB_ENRICH, batch.len(), batch.len());
 
     tracing::info!(
         "Enrichment complete: {} enriched, {} failed",
@@ -242,61 +617,497 @@ pub async fn enrich_games(State(state): State<Arc<AppState>>) -> Json<ApiRespons
         failed
     );
 
+    // Post-enrichment pass: newly matched games may be editions, DLC or sequels
+    let link_proposals = if enriched > 0 {
+        linking::detect(&state.db).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to detect game links: {}", e);
+            0
+        })
+    } else {
+        0
+    };
+
     Json(ApiResponse::success(EnrichResult {
         enriched,
         failed,
         remaining: games.len().saturating_sub(ENRICHMENT_BATCH_SIZE),
         total: games.len(),
+        link_proposals,
     }))
 }
 
+/// Fetch a matched game's Steam details, reviews and Deck status and store
+/// them, as enrichment does
+///
+/// Images are queued for download. Returns false if the details couldn't be
+/// stored.
+async fn apply_steam_match(state: &AppState, game: &Game, app_id: i64, confidence: f64) -> bool {
+    // Rate limit
+    tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
+
+    // Fetch details
+    let details = steam::fetch_steam_details(&state.http, app_id).await;
+
+    // Rate limit
+    tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
+
+    // Fetch reviews
+    let reviews = steam::fetch_steam_reviews(&state.http, app_id).await;
+
+    let trading_cards = details.as_ref().map(|d| d.trading_cards);
+
+    // Update database
+    if let Some(d) = details {
+        let genres_json = d
+            .genres
+            .map(|g| serde_json::to_string(&g).unwrap_or_default());
+        let devs_json = d
+            .developers
+            .map(|g| serde_json::to_string(&g).unwrap_or_default());
+        let pubs_json = d
+            .publishers
+            .map(|g| serde_json::to_string(&g).unwrap_or_default());
+
+        if let Err(e) = db::update_game_steam_data(
+            &state.db,
+            game.id,
+            app_id,
+            d.description.as_deref(),
+            d.header_image.as_deref(),
+            d.background.as_deref(),
+            genres_json.as_deref(),
+            devs_json.as_deref(),
+            pubs_json.as_deref(),
+            d.release_date.as_deref(),
+            confidence,
+        )
+        .await
+        {
+            tracing::warn!("Failed to update game {}: {}", game.id, e);
+            return false;
+        }
+
+        if let Err(e) = db::update_game_fullgame_app_id(&state.db, game.id, d.fullgame_app_id).await
+        {
+            tracing::warn!("Failed to update base game for game {}: {}", game.id, e);
+        }
+        if let Err(e) = db::update_game_age_rating(&state.db, game.id, d.age_rating).await {
+            tracing::warn!("Failed to update age rating for game {}: {}", game.id, e);
+        }
+
+        if game.match_status != "matched" && confidence < matching::MATCH_REVIEW_CONFIDENCE {
+            let message = format!(
+                "{} was matched to Steam App ID {} with {:.0}% confidence",
+                game.title,
+                app_id,
+                confidence * 100.0
+            );
+            notifications::notify(
+                &state.db,
+                notifications::KIND_MATCH_REVIEW,
+                "Match needs review",
+                &message,
+                Some(game.id),
+            )
+            .await;
+        }
+
+        // Cache images locally in the game folder
+        state.downloads.enqueue(downloads::ImageJob {
+            game_id: game.id,
+            folder_path: game.folder_path.clone(),
+            cover_url: d.header_image,
+            background_url: d.background,
+        });
+    }
+
+    if let Some(r) = reviews {
+        if let Err(e) =
+            db::update_game_reviews(&state.db, game.id, r.score, r.count, &r.summary, r.band).await
+        {
+            tracing::warn!("Failed to update reviews for game {}: {}", game.id, e);
+        }
+    }
+
+    // Rate limit
+    tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
+
+    if let Some(deck) = steam::fetch_deck_compatibility(&state.http, app_id).await {
+        if let Err(e) = db::update_game_deck_compat(&state.db, game.id, deck).await {
+            tracing::warn!("Failed to update Deck status for game {}: {}", game.id, e);
+        }
+    }
+
+    // Rate limit
+    tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
+
+    let points_shop_items = steam::fetch_points_shop_items(&state.http, app_id).await;
+    if let Err(e) =
+        db::update_game_steam_items(&state.db, game.id, trading_cards, points_shop_items).await
+    {
+        tracing::warn!("Failed to update Steam items for game {}: {}", game.id, e);
+    }
+
+    true
+}
+
 #[derive(serde::Serialize)]
 pub struct EnrichResult {
     enriched: usize,
     failed: usize,
     remaining: usize,
     total: usize,
+    /// New edition/DLC/franchise links awaiting confirmation
+    link_proposals: usize,
 }
 
-pub async fn get_stats(State(state): State<Arc<AppState>>) -> Json<ApiResponse<Stats>> {
-    match db::get_stats(&state.db).await {
-        Ok(stats) => Json(ApiResponse::success(stats)),
+/// Re-clean pending and low-confidence games and match again the ones whose
+/// title changed or now has a mapping (POST /maintenance/rematch-pending)
+pub async fn rematch_pending(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<rematch::RematchReport>> {
+    let (checked, candidates) = match rematch::candidates(&state.db).await {
+        Ok(c) => c,
         Err(e) => {
-            tracing::error!("Failed to get stats: {}", e);
-            Json(ApiResponse::error("Internal server error"))
+            tracing::error!("Failed to find games to rematch: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    };
+
+    let batch: Vec<i64> = candidates
+        .iter()
+        .take(ENRICHMENT_BATCH_SIZE)
+        .map(|c| c.game.id)
+        .collect();
+    if !batch.is_empty() {
+        let description = format!("Re-evaluate matches of {} game(s)", batch.len());
+        if let Err(e) =
+            db::snapshot_games(&state.db, undo::KIND_REMATCH_PENDING, &description, &batch).await
+        {
+            tracing::error!("Failed to snapshot games before rematching: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
         }
     }
-}
 
-/// Get recently added games
-pub async fn get_recent_games(
-    State(state): State<Arc<AppState>>,
-) -> Json<ApiResponse<Vec<GameSummary>>> {
-    match db::get_recent_games(&state.db, 10).await {
-        Ok(games) => {
-            let summaries: Vec<GameSummary> = games.into_iter().map(|g| g.into()).collect();
-            Json(ApiResponse::success(summaries))
+    let mut report = rematch::RematchReport {
+        checked,
+        remaining: candidates.len().saturating_sub(ENRICHMENT_BATCH_SIZE),
+        ..Default::default()
+    };
+
+    for (done, candidate) in candidates.iter().take(ENRICHMENT_BATCH_SIZE).enumerate() {
+        events::publish_progress(events::JOB_REMATCH, done, batch.len());
+        let game = &candidate.game;
+        tracing::info!("Re-evaluating match: {} -> {}", game.title, candidate.title);
+
+        let found = match (candidate.mapped_app_id, game.folder_app_id) {
+            (Some(id), _) => Some((id, 1.0)),
+            (None, Some(id)) => Some((id, folder_appid::MATCH_CONFIDENCE)),
+            (None, None) => {
+                steam::search_steam_app(
+                    &state.http,
+                    &state.search_cache,
+                    &candidate.title,
+                    game.year_hint.map(|y| y as i32),
+                )
+                .await
+            }
+        };
+
+        let outcome = match found {
+            None => rematch::RematchOutcome::NotFound,
+            Some((0, _)) => rematch::RematchOutcome::NotOnSteam,
+            Some((_, confidence)) if !rematch::is_improvement(game, confidence) => {
+                rematch::RematchOutcome::NotImproved
+            }
+            Some((app_id, confidence)) => {
+                tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS))
+                    .await;
+                match apply_steam_match(&state, game, app_id, confidence).await {
+                    true => rematch::RematchOutcome::Improved,
+                    false => rematch::RematchOutcome::Failed,
+                }
+            }
+        };
+
+        // A failed fetch is retried next time, so the old title stays until then
+        let retitle = outcome != rematch::RematchOutcome::Failed && candidate.title != game.title;
+        if retitle {
+            if let Err(e) = db::update_game_title(&state.db, game.id, &candidate.title).await {
+                tracing::warn!("Failed to update title of game {}: {}", game.id, e);
+            }
         }
-        Err(e) => {
-            tracing::error!("Failed to get recent games: {}", e);
-            Json(ApiResponse::error("Internal server error"))
+        if outcome == rematch::RematchOutcome::Improved {
+            report.improved += 1;
         }
+        if retitle || outcome == rematch::RematchOutcome::Improved {
+            events::publish_game(&state.db, game.id, false).await;
+        }
+
+        report.reevaluated += 1;
+        report.games.push(rematch::RematchEntry {
+            game_id: game.id,
+            old_title: game.title.clone(),
+            new_title: candidate.title.clone(),
+            reason: candidate.reason,
+            old_app_id: game.steam_app_id,
+            old_confidence: game.match_confidence,
+            new_app_id: found.map(|(id, _)| id),
+            new_confidence: found.map(|(_, confidence)| confidence),
+            outcome,
+        });
     }
+    events::publish_progress(events::JOB_REMATCH, batch.len(), batch.len());
+
+    tracing::info!(
+        "Match re-evaluation complete: {} re-evaluated, {} improved",
+        report.reevaluated,
+        report.improved
+    );
+
+    Json(ApiResponse::success(report))
 }
 
-/// SECURITY: Validate that a path is within the allowed games directory
-/// Returns the canonicalized path if valid, None if path traversal detected
-fn validate_path_within_games(
-    games_path: &str,
-    file_path: &std::path::Path,
-) -> Option<std::path::PathBuf> {
-    // Canonicalize the games directory (resolve symlinks, normalize)
-    let games_canonical = match std::fs::canonicalize(games_path) {
-        Ok(p) => p,
-        Err(_) => return None,
-    };
+/// Re-check Deck status after this many days (Valve re-tests games)
+const DECK_COMPAT_MAX_AGE_DAYS: i64 = 30;
 
-    // Canonicalize the target file path
+#[derive(serde::Serialize)]
+pub struct DeckRefreshResult {
+    updated: usize,
+    failed: usize,
+    remaining: usize,
+}
+
+/// Fetch Steam Deck status for matched games that lack a recent one
+/// (POST /steam/deck/refresh). Processes one enrichment batch per call.
+pub async fn refresh_deck_compat(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<DeckRefreshResult>> {
+    let games = match db::get_games_needing_deck_compat(&state.db, DECK_COMPAT_MAX_AGE_DAYS).await {
+        Ok(g) => g,
+        Err(e) => {
+            tracing::error!("Failed to get games needing Deck status: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    };
+
+    let client = state.http.clone();
+    let mut updated = 0;
+    let mut failed = 0;
+
+    for (i, game) in games.iter().take(ENRICHMENT_BATCH_SIZE).enumerate() {
+        let Some(app_id) = game.steam_app_id else {
+            continue;
+        };
+
+        if i > 0 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
+        }
+
+        match steam::fetch_deck_compatibility(&client, app_id).await {
+            Some(deck) => match db::update_game_deck_compat(&state.db, game.id, deck).await {
+                Ok(()) => updated += 1,
+                Err(e) => {
+                    tracing::warn!("Failed to update Deck status for game {}: {}", game.id, e);
+                    failed += 1;
+                }
+            },
+            None => failed += 1,
+        }
+    }
+
+    Json(ApiResponse::success(DeckRefreshResult {
+        updated,
+        failed,
+        remaining: games.len().saturating_sub(ENRICHMENT_BATCH_SIZE),
+    }))
+}
+
+/// Re-check trading cards and Points Shop items after this many days
+/// (both are often added after release)
+const STEAM_ITEMS_MAX_AGE_DAYS: i64 = 30;
+
+#[derive(serde::Serialize)]
+pub struct SteamItemsRefreshResult {
+    updated: usize,
+    failed: usize,
+    remaining: usize,
+}
+
+/// Check matched games for Steam Trading Cards and Points Shop items
+/// (POST /steam/items/refresh). Processes one enrichment batch per call.
+pub async fn refresh_steam_items(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<SteamItemsRefreshResult>> {
+    let games = match db::get_games_needing_steam_items(&state.db, STEAM_ITEMS_MAX_AGE_DAYS).await {
+        Ok(g) => g,
+        Err(e) => {
+            tracing::error!("Failed to get games needing Steam items: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    };
+
+    let client = state.http.clone();
+    let mut updated = 0;
+    let mut failed = 0;
+
+    for (i, game) in games.iter().take(ENRICHMENT_BATCH_SIZE).enumerate() {
+        let Some(app_id) = game.steam_app_id else {
+            continue;
+        };
+
+        if i > 0 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
+        }
+        let trading_cards = steam::fetch_steam_details(&client, app_id)
+            .await
+            .map(|d| d.trading_cards);
+        tokio::time::sleep(tokio::time::Duration::from_millis(STEAM_API_RATE_LIMIT_MS)).await;
+        let points_shop_items = steam::fetch_points_shop_items(&client, app_id).await;
+
+        if trading_cards.is_none() && points_shop_items.is_none() {
+            failed += 1;
+            continue;
+        }
+        match db::update_game_steam_items(&state.db, game.id, trading_cards, points_shop_items)
+            .await
+        {
+            Ok(()) => updated += 1,
+            Err(e) => {
+                tracing::warn!("Failed to update Steam items for game {}: {}", game.id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    Json(ApiResponse::success(SteamItemsRefreshResult {
+        updated,
+        failed,
+        remaining: games.len().saturating_sub(ENRICHMENT_BATCH_SIZE),
+    }))
+}
+
+pub async fn get_stats(State(state): State<Arc<AppState>>) -> Json<ApiResponse<Stats>> {
+    match db::get_stats(&state.db).await {
+        Ok(stats) => Json(ApiResponse::success(stats)),
+        Err(e) => {
+            tracing::error!("Failed to get stats: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Get the games the scanner found most recently
+pub async fn get_recent_games(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<GameSummary>>> {
+    match db::get_recent_games(&state.db, 10).await {
+        Ok(games) => {
+            let summaries: Vec<GameSummary> = games.into_iter().map(|g| g.into()).collect();
+            Json(ApiResponse::success(summaries))
+        }
+        Err(e) => {
+            tracing::error!("Failed to get recent games: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RecentlyPlayedQuery {
+    pub limit: Option<usize>,
+    /// Include sessions on every machine, not just this one
+    #[serde(default)]
+    pub all_machines: bool,
+}
+
+/// Games by their latest play session (GET /games/recently-played)
+pub async fn get_recently_played(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecentlyPlayedQuery>,
+) -> Json<ApiResponse<Vec<RecentlyPlayed>>> {
+    let limit = query
+        .limit
+        .unwrap_or(sessions::RECENTLY_PLAYED_LIMIT)
+        .clamp(1, sessions::MAX_RECENTLY_PLAYED);
+    let machine = (!query.all_machines).then(sessions::machine_name);
+
+    match db::get_recently_played(&state.db, machine, limit as i64).await {
+        Ok(games) => Json(ApiResponse::success(
+            games.into_iter().map(RecentlyPlayed::from).collect(),
+        )),
+        Err(e) => {
+            tracing::error!("Failed to get recently played games: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Games waiting for a match review (GET /games/match-review)
+pub async fn get_match_review_queue(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<match_review::ReviewQueue>> {
+    match match_review::queue(&state.db).await {
+        Ok(queue) => Json(ApiResponse::success(queue)),
+        Err(e) => {
+            tracing::error!("Failed to get match review queue: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Query parameters for the new releases feed
+#[derive(Deserialize)]
+pub struct NewReleasesQuery {
+    #[serde(default = "default_new_releases_days")]
+    pub days: i64,
+    #[serde(default = "default_new_releases_limit")]
+    pub limit: usize,
+}
+
+fn default_new_releases_days() -> i64 {
+    freshness::DEFAULT_WINDOW_DAYS
+}
+
+fn default_new_releases_limit() -> usize {
+    freshness::DEFAULT_LIMIT
+}
+
+/// Recently released games vs recently acquired older ones (GET /games/new-releases)
+pub async fn get_new_releases(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<NewReleasesQuery>,
+) -> Json<ApiResponse<freshness::NewReleases>> {
+    let days = query.days.clamp(1, freshness::MAX_WINDOW_DAYS);
+    let limit = query.limit.clamp(1, freshness::MAX_LIMIT);
+
+    match db::get_visible_games(&state.db).await {
+        Ok(games) => {
+            let today = chrono::Utc::now().date_naive();
+            Json(ApiResponse::success(freshness::build_feed(
+                games, today, days, limit,
+            )))
+        }
+        Err(e) => {
+            tracing::error!("Failed to get games for new releases: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// SECURITY: Validate that a path is within the allowed games directory
+/// Returns the canonicalized path if valid, None if path traversal detected
+fn validate_path_within_games(
+    games_path: &str,
+    file_path: &std::path::Path,
+) -> Option<std::path::PathBuf> {
+    // Canonicalize the games directory (resolve symlinks, normalize)
+    let games_canonical = match std::fs::canonicalize(games_path) {
+        Ok(p) => p,
+        Err(_) => return None,
+    };
+
+    // Canonicalize the target file path
     let file_canonical = match std::fs::canonicalize(file_path) {
         Ok(p) => p,
         Err(_) => return None,
@@ -315,14 +1126,41 @@ fn validate_path_within_games(
     }
 }
 
+#[derive(Deserialize)]
+pub struct CoverQuery {
+    /// Thumbnail size ("small" or "medium"); the full image when absent
+    pub size: Option<String>,
+}
+
 /// Serve a game's cover image from local storage
 pub async fn serve_game_cover(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
+    Query(query): Query<CoverQuery>,
 ) -> axum::response::Response {
     use axum::http::{header, StatusCode};
     use axum::response::IntoResponse;
 
+    match db::is_game_visible(&state.db, id).await {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::NOT_FOUND, "Game not found").into_response(),
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    }
+
+    if state.demo {
+        return demo_placeholder_art(&state, id, false).await;
+    }
+
+    let size = match query.size.as_deref() {
+        None => None,
+        Some(name) => match artwork::cover_size(name) {
+            Some(edge) => Some((name.to_string(), edge)),
+            None => return (StatusCode::BAD_REQUEST, "Unknown cover size").into_response(),
+        },
+    };
+
     // Get game folder path
     let folder_path = match db::get_game_folder_path(&state.db, id).await {
         Ok(Some(path)) => path,
@@ -349,8 +1187,33 @@ pub async fn serve_game_cover(
         }
     };
 
+    // Thumbnails are usually pre-generated after enrichment (see artwork.rs)
+    let served_path = match size {
+        None => validated_path,
+        Some((name, edge)) => {
+            let thumbnail = artwork::cover_thumbnail_path(&state.config.cache_path(), id, &name);
+            let thumb_clone = thumbnail.clone();
+            let generated = tokio::task::spawn_blocking(move || {
+                artwork::ensure_cover_thumbnail(&validated_path, &thumb_clone, edge)
+            })
+            .await;
+
+            match generated {
+                Ok(Ok(())) => thumbnail,
+                Ok(Err(e)) => {
+                    tracing::warn!("Failed to create cover thumbnail for game {}: {}", id, e);
+                    return (StatusCode::UNPROCESSABLE_ENTITY, "Unsupported image").into_response();
+                }
+                Err(_) => {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "Thumbnail task failed")
+                        .into_response()
+                }
+            }
+        }
+    };
+
     // Read and serve the image
-    match std::fs::read(&validated_path) {
+    match std::fs::read(&served_path) {
         Ok(bytes) => (
             StatusCode::OK,
             [(header::CONTENT_TYPE, "image/jpeg")],
@@ -369,6 +1232,18 @@ pub async fn serve_game_background(
     use axum::http::{header, StatusCode};
     use axum::response::IntoResponse;
 
+    match db::is_game_visible(&state.db, id).await {
+        Ok(true) => {}
+        Ok(false) => return (StatusCode::NOT_FOUND, "Game not found").into_response(),
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    }
+
+    if state.demo {
+        return demo_placeholder_art(&state, id, true).await;
+    }
+
     // Get game folder path
     let folder_path = match db::get_game_folder_path(&state.db, id).await {
         Ok(Some(path)) => path,
@@ -407,11 +1282,35 @@ pub async fn serve_game_background(
     }
 }
 
+/// Generated art for a demo game (demo mode has no image files)
+async fn demo_placeholder_art(
+    state: &AppState,
+    id: i64,
+    background: bool,
+) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+
+    match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(game)) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "image/svg+xml")],
+            demo::placeholder_art(id, &game.title, background),
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Game not found").into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
 /// Check if a game folder is writable (for backup functionality)
 pub async fn check_folder_writable(
     State(state): State<Arc<AppState>>,
     Path(id): Path<i64>,
 ) -> Json<ApiResponse<FolderStatus>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
     let folder_path = match db::get_game_folder_path(&state.db, id).await {
         Ok(Some(path)) => path,
         Ok(None) => {
@@ -444,6 +1343,152 @@ pub struct FolderStatus {
     pub backups: Vec<local_storage::BackupInfo>,
 }
 
+/// Query parameters for backup verification
+#[derive(Deserialize)]
+pub struct VerifyBackupQuery {
+    /// Also extract the backup to a temporary directory
+    #[serde(default = "default_true")]
+    pub extract: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Verify a save backup's integrity (POST /games/{id}/backup/{filename}/verify)
+pub async fn verify_backup(
+    State(state): State<Arc<AppState>>,
+    Path((id, filename)): Path<(i64, String)>,
+    Query(query): Query<VerifyBackupQuery>,
+) -> Json<ApiResponse<backup::BackupVerification>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let folder_path = match db::get_game_folder_path(&state.db, id).await {
+        Ok(Some(path)) => path,
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game folder: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    };
+
+    let backup_path = match local_storage::find_backup(&folder_path, &filename) {
+        Some(p) => p,
+        None => return Json(ApiResponse::error("Backup not found")),
+    };
+
+    // A missing or unreadable key only matters for encrypted backups
+    let secret = state.config.backup_secret().unwrap_or_else(|e| {
+        tracing::warn!("{}", e);
+        None
+    });
+
+    match tokio::task::spawn_blocking(move || {
+        backup::verify_backup(&backup_path, query.extract, secret.as_deref())
+    })
+    .await
+    {
+        Ok(result) => {
+            if !result.valid {
+                tracing::warn!(
+                    "Backup {} for game {} failed verification: {:?}",
+                    filename,
+                    id,
+                    result.errors
+                );
+            }
+            Json(ApiResponse::success(result))
+        }
+        Err(e) => {
+            tracing::error!("Backup verification task failed: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Encrypt a save backup with the configured key (POST /games/{id}/backup/{filename}/encrypt)
+///
+/// Writes `<filename>.enc` and removes the plaintext backup.
+pub async fn encrypt_backup(
+    State(state): State<Arc<AppState>>,
+    Path((id, filename)): Path<(i64, String)>,
+) -> Json<ApiResponse<local_storage::BackupInfo>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let secret = match state.config.backup_secret() {
+        Ok(Some(secret)) => secret,
+        Ok(None) => return Json(ApiResponse::error("Backup encryption is not configured")),
+        Err(e) => {
+            tracing::error!("{}", e);
+            return Json(ApiResponse::error(
+                "Backup encryption key could not be read",
+            ));
+        }
+    };
+
+    let folder_path = match db::get_game_folder_path(&state.db, id).await {
+        Ok(Some(path)) => path,
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game folder: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    };
+
+    let backup_path = match local_storage::find_backup(&folder_path, &filename) {
+        Some(p) => p,
+        None => return Json(ApiResponse::error("Backup not found")),
+    };
+    if encryption::is_encrypted_path(&backup_path) {
+        return Json(ApiResponse::error("Backup is already encrypted"));
+    }
+
+    // Don't seal a backup that's already damaged
+    let result = tokio::task::spawn_blocking(move || {
+        let verification = backup::verify_backup(&backup_path, false, None);
+        if !verification.valid {
+            return Err(format!(
+                "Backup failed verification: {}",
+                verification.errors.join("; ")
+            ));
+        }
+        encryption::encrypt_file(&backup_path, &secret)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(encrypted_path)) => {
+            let name = encrypted_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            tracing::info!("Encrypted backup {} for game {}", filename, id);
+            match local_storage::list_backups(&folder_path)
+                .into_iter()
+                .find(|b| b.filename == name)
+            {
+                Some(info) => Json(ApiResponse::success(info)),
+                None => Json(ApiResponse::error("Internal server error")),
+            }
+        }
+        Ok(Err(e)) => {
+            tracing::warn!(
+                "Failed to encrypt backup {} for game {}: {}",
+                filename,
+                id,
+                e
+            );
+            Json(ApiResponse::error(e))
+        }
+        Err(e) => {
+            tracing::error!("Backup encryption task failed: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
 /// Export metadata for all matched games to their .gamevault folders
 pub async fn export_all_metadata(
     State(state): State<Arc<AppState>>,
@@ -504,112 +1549,507 @@ pub struct ExportResult {
     pub total: usize,
 }
 
-/// Import metadata from .gamevault/metadata.json files into database
-pub async fn import_all_metadata(
-    State(state): State<Arc<AppState>>,
-) -> Json<ApiResponse<ImportResult>> {
-    tracing::info!("Starting metadata import");
+/// Query parameters for the artwork export
+#[derive(Deserialize)]
+pub struct ArtworkExportQuery {
+    /// Layout to write; `export.artwork_layout` when absent
+    pub layout: Option<config::ArtworkLayout>,
+    /// Hard-link images instead of copying them (falls back to copying)
+    #[serde(default)]
+    pub link: bool,
+}
 
-    // Get all games
-    let games = match db::get_all_games(&state.db).await {
-        Ok(g) => g,
+/// Copy cached covers and backgrounds into a launcher frontend's media layout
+/// and write its metadata file (POST /export/artwork)
+pub async fn export_artwork(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ArtworkExportQuery>,
+) -> Json<ApiResponse<export::ArtworkExportResult>> {
+    let target = match state.config.artwork_export_target() {
+        Some(t) if t.is_dir() => t,
+        Some(_) => return Json(ApiResponse::error("Artwork export target does not exist")),
+        None => return Json(ApiResponse::error("No artwork export target configured")),
+    };
+    let metadata_dir = state.config.export_metadata_dir().unwrap_or(target.clone());
+    let layout = query.layout.unwrap_or(state.config.export.artwork_layout);
+
+    let loaded = async {
+        let games = db::get_visible_games(&state.db).await?;
+        let playtime =
+            db::get_playtime_by_game(&state.db, "1970-01-01T00:00:00Z", "9999-12-31T23:59:59Z")
+                .await?;
+        Ok::<_, sqlx::Error>((games, playtime))
+    };
+    let games: Vec<export::ExportGame> = match loaded.await {
+        Ok((games, playtime)) => {
+            let playtime: HashMap<i64, GamePlaytime> =
+                playtime.into_iter().map(|p| (p.game_id, p)).collect();
+            games
+                .into_iter()
+                .filter(|g| g.hidden.unwrap_or(0) == 0)
+                .map(|g| {
+                    let played = playtime.get(&g.id);
+                    export::ExportGame::new(
+                        g,
+                        played.map_or(0, |p| p.sessions),
+                        played.map(|p| p.last_played.clone()),
+                    )
+                })
+                .collect()
+        }
         Err(e) => {
-            tracing::error!("Failed to get games: {}", e);
-            return Json(ApiResponse::error("Internal server error"));
+            tracing::error!("Failed to get games for artwork export: {}", e);
+            return Json(ApiResponse::error("Database error"));
         }
     };
 
-    let mut imported = 0;
-    let mut skipped = 0;
-    let mut not_found = 0;
-    let mut failed = 0;
+    let link = query.link;
+    match tokio::task::spawn_blocking(move || {
+        export::export_frontend(&games, &target, &metadata_dir, layout, link)
+    })
+    .await
+    {
+        Ok(Ok(result)) => {
+            tracing::info!(
+                "Artwork export: {} games exported, {} images written, {} failed, metadata in {}",
+                result.exported,
+                result.written,
+                result.failed,
+                result.metadata_file
+            );
+            Json(ApiResponse::success(result))
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Failed to write frontend metadata: {}", e);
+            Json(ApiResponse::error("Failed to write frontend metadata"))
+        }
+        Err(e) => {
+            tracing::error!("Artwork export task failed: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
 
-    for game in &games {
-        match local_storage::import_game_metadata(game) {
-            local_storage::ImportResult::Imported(metadata) => {
-                // Convert Vec<String> to JSON strings for database
-                let genres_json = metadata
-                    .genres
-                    .as_ref()
-                    .map(|g| serde_json::to_string(g).unwrap_or_default());
-                let devs_json = metadata
-                    .developers
-                    .as_ref()
-                    .map(|d| serde_json::to_string(d).unwrap_or_default());
-                let pubs_json = metadata
-                    .publishers
-                    .as_ref()
-                    .map(|p| serde_json::to_string(p).unwrap_or_default());
-
-                // Extract HLTB data
-                let (hltb_main, hltb_extra, hltb_comp) = metadata
-                    .hltb
-                    .map(|h| (h.main_mins, h.extra_mins, h.completionist_mins))
-                    .unwrap_or((None, None, None));
-
-                // Update database
-                if let Err(e) = db::update_game_from_import(
-                    &state.db,
-                    game.id,
-                    metadata.steam_app_id,
-                    metadata.summary.as_deref(),
-                    genres_json.as_deref(),
-                    devs_json.as_deref(),
-                    pubs_json.as_deref(),
-                    metadata.release_date.as_deref(),
-                    metadata.review_score,
-                    metadata.review_summary.as_deref(),
-                    hltb_main,
-                    hltb_extra,
-                    hltb_comp,
-                )
-                .await
-                {
-                    tracing::warn!("Failed to import metadata for '{}': {}", game.title, e);
-                    failed += 1;
-                } else {
-                    imported += 1;
-                    tracing::info!("Imported metadata for: {}", game.title);
+/// Import metadata from `.gamevault/metadata.json` files (POST /import)
+///
+/// Query: `dry_run` to only preview, `conflict` = overwrite | fill_missing | skip.
+pub async fn import_all_metadata(
+    State(state): State<Arc<AppState>>,
+    Query(options): Query<import::ImportOptions>,
+) -> Json<ApiResponse<import::ImportReport>> {
+    match run_metadata_import(&state, options).await {
+        Ok(report) => Json(ApiResponse::success(report)),
+        Err(e) => {
+            tracing::error!("Import failed: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Import every game's metadata.json (also run on startup, see startup.rs)
+pub async fn run_metadata_import(
+    state: &AppState,
+    options: import::ImportOptions,
+) -> Result<import::ImportReport, sqlx::Error> {
+    tracing::info!(
+        "Starting metadata import (dry run: {}, conflicts: {})",
+        options.dry_run,
+        options.conflict.as_str()
+    );
+
+    let games = db::get_all_games(&state.db).await?;
+
+    let rows = games
+        .iter()
+        .map(|game| {
+            let key = game.title.clone();
+            match local_storage::import_game_metadata(game) {
+                local_storage::ImportResult::Imported(metadata) => {
+                    // Lists are stored as JSON strings
+                    let to_json = |v: &Vec<String>| serde_json::to_string(v).unwrap_or_default();
+                    let hltb = metadata.hltb.as_ref();
+
+                    import::ImportRow::new(key, Some(game.id))
+                        .set("steam_app_id", metadata.steam_app_id)
+                        .set("summary", metadata.summary.clone())
+                        .set("genres", metadata.genres.as_ref().map(to_json))
+                        .set("developers", metadata.developers.as_ref().map(to_json))
+                        .set("publishers", metadata.publishers.as_ref().map(to_json))
+                        .set("release_date", metadata.release_date.clone())
+                        .set(
+                            "release_date_iso",
+                            metadata
+                                .release_date
+                                .as_deref()
+                                .and_then(freshness::release_date_iso),
+                        )
+                        .set("review_score", metadata.review_score)
+                        .set("review_summary", metadata.review_summary.clone())
+                        .set(
+                            "review_band",
+                            metadata
+                                .review_summary
+                                .as_deref()
+                                .and_then(ReviewBand::from_summary)
+                                .map(ReviewBand::as_str),
+                        )
+                        .set("hltb_main_mins", hltb.and_then(|h| h.main_mins))
+                        .set("hltb_extra_mins", hltb.and_then(|h| h.extra_mins))
+                        .set(
+                            "hltb_completionist_mins",
+                            hltb.and_then(|h| h.completionist_mins),
+                        )
+                        .set(
+                            "custom_fields",
+                            metadata
+                                .custom_fields
+                                .filter(|values| !values.is_empty())
+                                .map(|values| serde_json::Value::Object(values).to_string()),
+                        )
+                }
+                local_storage::ImportResult::Skipped { reason } => {
+                    import::ImportRow::skipped(key, Some(game.id), reason)
+                }
+                local_storage::ImportResult::NotFound => {
+                    import::ImportRow::not_found(key, Some(game.id), "No metadata.json")
+                }
+                local_storage::ImportResult::Failed { error } => {
+                    tracing::warn!("Failed to read metadata for '{}': {}", game.title, error);
+                    import::ImportRow::failed(key, Some(game.id), error)
                 }
             }
-            local_storage::ImportResult::Skipped { reason } => {
-                tracing::debug!("Skipped '{}': {}", game.title, reason);
-                skipped += 1;
-            }
-            local_storage::ImportResult::NotFound => {
-                not_found += 1;
-            }
-            local_storage::ImportResult::Failed { error } => {
-                tracing::warn!("Failed to read metadata for '{}': {}", game.title, error);
-                failed += 1;
-            }
+        })
+        .collect();
+
+    let report = import::run(&state.db, "metadata_json", rows, options).await?;
+    let s = &report.summary;
+    tracing::info!(
+        "Import {} ({}): {} imported, {} unchanged, {} skipped, {} not found, {} failed",
+        s.id,
+        s.status,
+        s.imported,
+        s.unchanged,
+        s.skipped,
+        s.not_found,
+        s.failed
+    );
+    Ok(report)
+}
+
+/// How many import reports GET /imports returns
+const IMPORT_REPORT_LIST_LIMIT: i64 = 50;
+
+/// List recent import reports (GET /imports)
+pub async fn list_import_reports(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<ImportReportSummary>>> {
+    match db::get_import_reports(&state.db, IMPORT_REPORT_LIST_LIMIT).await {
+        Ok(reports) => Json(ApiResponse::success(reports)),
+        Err(e) => {
+            tracing::error!("Failed to list import reports: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Get one import report with its rows (GET /imports/{id})
+pub async fn get_import_report(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<import::ImportReport>> {
+    match db::get_import_report(&state.db, id).await {
+        Ok(Some((summary, rows_json))) => {
+            let rows = serde_json::from_str(&rows_json).unwrap_or_else(|e| {
+                tracing::warn!("Import report {} has unreadable rows: {}", id, e);
+                Vec::new()
+            });
+            Json(ApiResponse::success(import::ImportReport { summary, rows }))
+        }
+        Ok(None) => Json(ApiResponse::error("Import report not found")),
+        Err(e) => {
+            tracing::error!("Failed to get import report {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// How many jobs GET /jobs returns
+const JOB_LIST_LIMIT: i64 = 50;
+
+/// List recent background jobs, such as startup tasks (GET /jobs)
+pub async fn list_jobs(State(state): State<Arc<AppState>>) -> Json<ApiResponse<Vec<Job>>> {
+    match db::get_jobs(&state.db, JOB_LIST_LIMIT).await {
+        Ok(jobs) => Json(ApiResponse::success(jobs)),
+        Err(e) => {
+            tracing::error!("Failed to list jobs: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// List title mappings (GET /mappings)
+pub async fn list_title_mappings(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<TitleMapping>>> {
+    match db::get_title_mappings(&state.db).await {
+        Ok(mappings) => Json(ApiResponse::success(mappings)),
+        Err(e) => {
+            tracing::error!("Failed to list title mappings: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Query parameters for exporting a mapping pack
+#[derive(Deserialize)]
+pub struct ExportMappingsQuery {
+    pub name: Option<String>,
+}
+
+/// Export title mappings as a shareable pack (GET /mappings/export)
+pub async fn export_mapping_pack(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExportMappingsQuery>,
+) -> Json<ApiResponse<mappings::MappingPack>> {
+    match db::get_title_mappings(&state.db).await {
+        Ok(list) => Json(ApiResponse::success(mappings::build_pack(
+            &list, query.name,
+        ))),
+        Err(e) => {
+            tracing::error!("Failed to export title mappings: {}", e);
+            Json(ApiResponse::error("Database error"))
         }
     }
+}
+
+/// Request body for a Backloggd/Grouvee import
+#[derive(Deserialize)]
+pub struct TrackerImportRequest {
+    /// The exported CSV file's contents
+    pub csv: String,
+    /// Titles settled by hand: game id, or null to leave the row out
+    #[serde(default)]
+    pub resolve: HashMap<String, Option<i64>>,
+}
+
+/// Import statuses, ratings and finish dates from a tracker's CSV export
+/// (POST /import/{backloggd|grouvee})
+pub async fn import_tracker(
+    State(state): State<Arc<AppState>>,
+    Path(source): Path<tracker_import::TrackerSource>,
+    Query(options): Query<import::ImportOptions>,
+    Json(payload): Json<TrackerImportRequest>,
+) -> Json<ApiResponse<import::ImportReport>> {
+    let entries = match tracker_import::parse(source, &payload.csv) {
+        Ok(entries) => entries,
+        Err(message) => return Json(ApiResponse::error(message)),
+    };
+    let games = match db::get_all_games(&state.db).await {
+        Ok(games) => games,
+        Err(e) => {
+            tracing::error!("Failed to get games: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    };
 
     tracing::info!(
-        "Import complete: {} imported, {} skipped, {} not found, {} failed",
-        imported,
-        skipped,
-        not_found,
-        failed
+        "Importing {} {} entries (dry run: {}, conflicts: {})",
+        entries.len(),
+        source.as_str(),
+        options.dry_run,
+        options.conflict.as_str()
     );
+    let index = tracker_import::TitleIndex::new(&games);
+    let rows = tracker_import::rows(entries, &index, &payload.resolve);
 
-    Json(ApiResponse::success(ImportResult {
-        imported,
-        skipped,
-        not_found,
-        failed,
-        total: games.len(),
-    }))
+    match import::run(&state.db, source.as_str(), rows, options).await {
+        Ok(report) => Json(ApiResponse::success(report)),
+        Err(e) => {
+            tracing::error!("{} import failed: {}", source.as_str(), e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Import a mapping pack (POST /mappings/import)
+pub async fn import_mapping_pack(
+    State(state): State<Arc<AppState>>,
+    Query(options): Query<import::ImportOptions>,
+    Json(pack): Json<mappings::MappingPack>,
+) -> Json<ApiResponse<import::ImportReport>> {
+    if let Err(message) = mappings::validate_pack(&pack) {
+        return Json(ApiResponse::error(message));
+    }
+
+    tracing::info!(
+        "Importing mapping pack {} (dry run: {}, conflicts: {})",
+        pack.name.as_deref().unwrap_or("(unnamed)"),
+        options.dry_run,
+        options.conflict.as_str()
+    );
+
+    match mappings::import_pack(&state.db, &pack, options).await {
+        Ok(report) => Json(ApiResponse::success(report)),
+        Err(e) => {
+            tracing::error!("Mapping pack import failed: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Request body for removing a title mapping
+#[derive(Deserialize)]
+pub struct DeleteTitleMappingRequest {
+    pub title: String,
+}
+
+/// Remove a title mapping (POST /mappings/delete)
+pub async fn delete_title_mapping(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DeleteTitleMappingRequest>,
+) -> Json<ApiResponse<()>> {
+    let title = crate::search_cache::normalize_query(&payload.title);
+    match db::delete_title_mapping(&state.db, &title).await {
+        Ok(true) => Json(ApiResponse::success(())),
+        Ok(false) => Json(ApiResponse::error("Title mapping not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete title mapping '{}': {}", title, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// List recent undoable operations (GET /undo)
+pub async fn list_undo_operations(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<UndoOperation>>> {
+    match db::get_undo_operations(&state.db, undo::HISTORY_LIMIT).await {
+        Ok(operations) => Json(ApiResponse::success(operations)),
+        Err(e) => {
+            tracing::error!("Failed to list undo operations: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Revert an operation and every later one (POST /undo/{operation_id})
+pub async fn undo_operation(
+    State(state): State<Arc<AppState>>,
+    Path(operation_id): Path<i64>,
+) -> Json<ApiResponse<undo::UndoResult>> {
+    match db::get_undo_operation(&state.db, operation_id).await {
+        Ok(Some(op)) if op.undone_at.is_some() => {
+            return Json(ApiResponse::error("Operation has already been undone"))
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return Json(ApiResponse::error("Undo operation not found")),
+        Err(e) => {
+            tracing::error!("Failed to get undo operation {}: {}", operation_id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    match undo::revert(&state.db, operation_id).await {
+        Ok(result) => Json(ApiResponse::success(result)),
+        Err(e) => {
+            tracing::error!("Failed to undo operation {}: {}", operation_id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
 }
 
+/// The enrichment blocklist and how many games it currently excludes
 #[derive(serde::Serialize)]
-pub struct ImportResult {
-    pub imported: usize,
-    pub skipped: usize,
-    pub not_found: usize,
-    pub failed: usize,
-    pub total: usize,
+pub struct BlocklistState {
+    pub entries: Vec<BlocklistEntry>,
+    pub excluded_games: usize,
+}
+
+/// Current blocklist, re-evaluating the library first when it changed
+async fn blocklist_state(state: &AppState, reapply: bool) -> Json<ApiResponse<BlocklistState>> {
+    let excluded_games = if reapply {
+        blocklist::apply(&state.db).await
+    } else {
+        db::count_excluded_games(&state.db).await
+    };
+    let excluded_games = match excluded_games {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!("Failed to apply enrichment blocklist: {}", e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    match db::get_blocklist(&state.db).await {
+        Ok(entries) => Json(ApiResponse::success(BlocklistState {
+            entries,
+            excluded_games,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to list enrichment blocklist: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// List the enrichment blocklist (GET /enrichment/blocklist)
+pub async fn get_enrichment_blocklist(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<BlocklistState>> {
+    blocklist_state(&state, false).await
+}
+
+/// Request body for a blocklist entry: exactly one of pattern or game_id
+#[derive(Deserialize)]
+pub struct AddBlocklistEntryRequest {
+    pub pattern: Option<String>,
+    pub game_id: Option<i64>,
+}
+
+/// Add a blocklist entry and re-evaluate the library (POST /enrichment/blocklist)
+pub async fn add_enrichment_blocklist_entry(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AddBlocklistEntryRequest>,
+) -> Json<ApiResponse<BlocklistState>> {
+    let pattern = payload.pattern.as_deref().map(str::trim);
+    match (pattern, payload.game_id) {
+        (Some(pattern), None) => {
+            if let Err(message) = blocklist::validate_pattern(pattern) {
+                return Json(ApiResponse::error(message));
+            }
+        }
+        (None, Some(game_id)) => match db::get_game_by_id(&state.db, game_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return Json(ApiResponse::error("Game not found")),
+            Err(e) => {
+                tracing::error!("Failed to get game {}: {}", game_id, e);
+                return Json(ApiResponse::error("Database error"));
+            }
+        },
+        _ => return Json(ApiResponse::error("Provide either a pattern or a game_id")),
+    }
+
+    if let Err(e) = db::add_blocklist_entry(&state.db, pattern, payload.game_id).await {
+        tracing::error!("Failed to add blocklist entry: {}", e);
+        return Json(ApiResponse::error("Database error"));
+    }
+    blocklist_state(&state, true).await
+}
+
+/// Remove a blocklist entry and re-evaluate the library
+/// (POST /enrichment/blocklist/{id}/delete)
+pub async fn delete_enrichment_blocklist_entry(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<BlocklistState>> {
+    match db::delete_blocklist_entry(&state.db, id).await {
+        Ok(true) => blocklist_state(&state, true).await,
+        Ok(false) => Json(ApiResponse::error("Blocklist entry not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete blocklist entry {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
 }
 
 /// Request body for re-matching a game to a different Steam entry
@@ -658,6 +2098,9 @@ pub async fn rematch_game(
     Path(id): Path<i64>,
     Json(payload): Json<RematchGameRequest>,
 ) -> Json<ApiResponse<RematchResult>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
     tracing::info!("Rematch game {} with input: {}", id, payload.steam_input);
 
     // Parse Steam App ID from input
@@ -679,7 +2122,7 @@ pub async fn rematch_game(
     }
 
     // Fetch Steam details
-    let client = reqwest::Client::new();
+    let client = state.http.clone();
     let details = steam::fetch_steam_details(&client, steam_app_id).await;
 
     if details.is_none() {
@@ -717,6 +2160,9 @@ pub async fn confirm_rematch(
     Path(id): Path<i64>,
     Json(payload): Json<RematchGameRequest>,
 ) -> Json<ApiResponse<Game>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
     tracing::info!(
         "Confirming rematch for game {} with input: {}",
         id,
@@ -742,7 +2188,7 @@ pub async fn confirm_rematch(
     };
 
     // Fetch Steam details
-    let client = reqwest::Client::new();
+    let client = state.http.clone();
     let details = steam::fetch_steam_details(&client, steam_app_id).await;
 
     if details.is_none() {
@@ -766,6 +2212,12 @@ pub async fn confirm_rematch(
         .publishers
         .map(|g| serde_json::to_string(&g).unwrap_or_default());
 
+    let description = format!("Rematch game {} to Steam App ID {}", id, steam_app_id);
+    if let Err(e) = db::snapshot_games(&state.db, undo::KIND_REMATCH, &description, &[id]).await {
+        tracing::error!("Failed to snapshot game {} before rematch: {}", id, e);
+        return Json(ApiResponse::error("Database error"));
+    }
+
     if let Err(e) = db::update_game_steam_data(
         &state.db,
         id,
@@ -785,30 +2237,32 @@ pub async fn confirm_rematch(
         return Json(ApiResponse::error("Failed to update game"));
     }
 
+    if let Err(e) = db::update_game_age_rating(&state.db, id, d.age_rating).await {
+        tracing::warn!("Failed to update age rating for game {}: {}", id, e);
+    }
+    let points_shop_items = steam::fetch_points_shop_items(&state.http, steam_app_id).await;
+    if let Err(e) =
+        db::update_game_steam_items(&state.db, id, Some(d.trading_cards), points_shop_items).await
+    {
+        tracing::warn!("Failed to update Steam items for game {}: {}", id, e);
+    }
+
     // Update reviews if available
     if let Some(r) = reviews {
-        if let Err(e) = db::update_game_reviews(&state.db, id, r.score, r.count, &r.summary).await {
+        if let Err(e) =
+            db::update_game_reviews(&state.db, id, r.score, r.count, &r.summary, r.band).await
+        {
             tracing::warn!("Failed to update reviews: {}", e);
         }
     }
 
     // Cache images locally
-    let (local_cover, local_bg) = local_storage::cache_game_images(
-        &client,
-        &game.folder_path,
-        d.header_image.as_deref(),
-        d.background.as_deref(),
-    )
-    .await;
-
-    if local_cover.is_some() || local_bg.is_some() {
-        if let Err(e) =
-            db::update_game_local_images(&state.db, id, local_cover.as_deref(), local_bg.as_deref())
-                .await
-        {
-            tracing::warn!("Failed to update local image paths: {}", e);
-        }
-    }
+    state.downloads.enqueue(downloads::ImageJob {
+        game_id: id,
+        folder_path: game.folder_path.clone(),
+        cover_url: d.header_image,
+        background_url: d.background,
+    });
 
     // Fetch updated game
     let updated_game = match db::get_game_by_id(&state.db, id).await {
@@ -821,14 +2275,49 @@ pub async fn confirm_rematch(
     };
 
     // Dual-write to metadata.json
-    if let Err(e) = local_storage::save_game_metadata(&updated_game) {
-        tracing::warn!("Failed to save metadata.json: {}", e);
-    }
+    metadata_sync::save(&state.db, &updated_game).await;
+
+    // Remember the correction for other copies and future scans
+    mappings::record(&state.db, &game.title, steam_app_id).await;
 
     tracing::info!("Rematched game {} to Steam App ID {}", id, steam_app_id);
+    events::publish(events::Event::GameUpdated {
+        game: updated_game.clone().into(),
+    });
     Json(ApiResponse::success(updated_game))
 }
 
+#[derive(Deserialize)]
+pub struct MatchDebugQuery {
+    pub title: String,
+    /// Overrides the year hint read from the title
+    pub year: Option<i32>,
+}
+
+/// Explain how enrichment would match a title (GET /debug/match?title=)
+pub async fn debug_match(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MatchDebugQuery>,
+) -> Json<ApiResponse<matching::MatchExplanation>> {
+    let title = query.title.trim();
+    if title.len() < MIN_SEARCH_QUERY_LENGTH {
+        return Json(ApiResponse::error("Title too short"));
+    }
+    if title.len() > MAX_SEARCH_QUERY_LENGTH {
+        return Json(ApiResponse::error("Title too long"));
+    }
+
+    let explanation = matching::explain(
+        &state.db,
+        &state.http,
+        &state.search_cache,
+        title,
+        query.year,
+    )
+    .await;
+    Json(ApiResponse::success(explanation))
+}
+
 /// Request body for updating game metadata
 #[derive(Deserialize)]
 pub struct UpdateGameRequest {
@@ -848,6 +2337,9 @@ pub async fn update_game(
     Path(id): Path<i64>,
     Json(payload): Json<UpdateGameRequest>,
 ) -> Json<ApiResponse<Game>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
     tracing::info!("Updating game {}", id);
 
     // Convert Vec<String> to JSON strings for DB storage
@@ -882,13 +2374,14 @@ pub async fn update_game(
         }
     };
 
-    // Dual-write to metadata.json (don't fail if file write fails)
-    if let Err(e) = local_storage::save_game_metadata(&game) {
-        tracing::warn!("Failed to save metadata.json for game {}: {}", id, e);
-        // Continue - DB update succeeded, which is the primary storage
-    }
+    // Dual-write to metadata.json (a failed write is retried later; the DB
+    // update succeeded, which is the primary storage)
+    metadata_sync::save(&state.db, &game).await;
 
     tracing::info!("Updated game: {} (id={})", game.title, id);
+    events::publish(events::Event::GameUpdated {
+        game: game.clone().into(),
+    });
     Json(ApiResponse::success(game))
 }
 
@@ -901,6 +2394,11 @@ pub async fn update_game(
 pub struct ConfigResponse {
     pub paths: ConfigPathsResponse,
     pub server: ConfigServerResponse,
+    /// Changes with every write of config.toml; sent back with PUT
+    pub revision: String,
+    /// Settings environment variables override (their values above are the
+    /// ones in effect)
+    pub overrides: Vec<config::EnvOverride>,
 }
 
 #[derive(serde::Serialize)]
@@ -919,9 +2417,51 @@ pub struct ConfigServerResponse {
 }
 
 /// Get current configuration (GET /api/config)
-pub async fn get_config() -> Json<ApiResponse<ConfigResponse>> {
+///
+/// The revision is also sent as the `ETag` header.
+pub async fn get_config(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    use axum::http::header;
+    use axum::response::IntoResponse;
+
+    let revision = config::config_revision(&config::get_config_path());
+    let etag = [(header::ETAG, format!("\"{}\"", revision))];
+
+    if state.demo {
+        let cfg = &state.config;
+        return (
+            etag,
+            Json(ApiResponse::success(ConfigResponse {
+                paths: ConfigPathsResponse {
+                    game_library: state.games_path.clone(),
+                    cache: cfg.paths.cache.to_string_lossy().to_string(),
+                    game_library_exists: true,
+                    cache_exists: true,
+                },
+                server: ConfigServerResponse {
+                    port: cfg.server.port,
+                    auto_open_browser: cfg.server.auto_open_browser,
+                    bind_address: cfg.server.bind_address.clone(),
+                },
+                revision,
+                overrides: Vec::new(),
+            })),
+        )
+            .into_response();
+    }
+
     match AppConfig::load() {
-        Ok(cfg) => {
+        Ok(mut cfg) => {
+            // Legacy variables are read on startup rather than by load()
+            if let Ok(path) = std::env::var("GAMES_PATH") {
+                cfg.paths.game_library = path.into();
+            }
+            if let Some(port) = std::env::var("PORT").ok().and_then(|p| p.parse().ok()) {
+                cfg.server.port = port;
+            }
+            if let Ok(host) = std::env::var("HOST") {
+                cfg.server.bind_address = host;
+            }
+
             // Get original config values (for display)
             let original_game_library = cfg.paths.game_library.to_string_lossy().to_string();
             let original_cache = cfg.paths.cache.to_string_lossy().to_string();
@@ -952,12 +2492,17 @@ pub async fn get_config() -> Json<ApiResponse<ConfigResponse>> {
                     auto_open_browser: cfg.server.auto_open_browser,
                     bind_address: cfg.server.bind_address.clone(),
                 },
+                revision,
+                overrides: config::env_overrides(),
             };
-            Json(ApiResponse::success(response))
+            (etag, Json(ApiResponse::success(response))).into_response()
         }
         Err(e) => {
             tracing::error!("Failed to load config: {}", e);
-            Json(ApiResponse::error("Failed to load configuration"))
+            Json(ApiResponse::<ConfigResponse>::error(
+                "Failed to load configuration",
+            ))
+            .into_response()
         }
     }
 }
@@ -969,6 +2514,8 @@ pub struct ConfigUpdateRequest {
     pub cache: String,
     pub port: u16,
     pub auto_open_browser: bool,
+    /// Revision the client read (or an `If-Match` header)
+    pub revision: Option<String>,
 }
 
 /// Response structure for PUT /api/config
@@ -977,18 +2524,32 @@ pub struct ConfigUpdateResponse {
     pub success: bool,
     pub restart_required: bool,
     pub message: String,
+    /// Revision of the written config.toml, for the next update
+    pub revision: String,
 }
 
 /// Update configuration (PUT /api/config)
+///
+/// A write based on an older revision than config.toml's is refused with
+/// 409 Conflict.
 pub async fn update_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(payload): Json<ConfigUpdateRequest>,
-) -> Json<ApiResponse<ConfigUpdateResponse>> {
+) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+
+    let error = |message: &str| Json(ApiResponse::<ConfigUpdateResponse>::error(message));
+
+    if state.demo {
+        return error("Configuration can't be changed in demo mode").into_response();
+    }
+
     // Validate game library path
     let game_path = std::path::PathBuf::from(&payload.game_library);
     if !game_path.is_dir() {
-        return Json(ApiResponse::error(
-            "Game library path does not exist or is not a directory",
-        ));
+        return error("Game library path does not exist or is not a directory").into_response();
     }
 
     // SECURITY: Canonicalize path to resolve symlinks and prevent symlink attacks
@@ -996,67 +2557,98 @@ pub async fn update_config(
         Ok(p) => p,
         Err(e) => {
             tracing::warn!("Failed to canonicalize game library path: {}", e);
-            return Json(ApiResponse::error("Invalid game library path"));
+            return error("Invalid game library path").into_response();
         }
     };
 
     // SECURITY: Verify it's still a directory after canonicalization
     if !game_path.is_dir() {
-        return Json(ApiResponse::error(
-            "Game library path is not a valid directory",
-        ));
+        return error("Game library path is not a valid directory").into_response();
     }
 
     // Validate port range
-    if payload.port < 1024 || payload.port > 65535 {
-        return Json(ApiResponse::error("Port must be between 1024 and 65535"));
-    }
-
-    // Load current config to check for restart-requiring changes
-    let current_config = AppConfig::load().ok();
-    let restart_required = current_config
-        .as_ref()
-        .map(|c| c.server.port != payload.port)
-        .unwrap_or(false);
-
-    // Build new config
-    let new_config = AppConfig {
-        paths: config::PathsConfig {
-            game_library: game_path,
-            database: current_config
-                .as_ref()
-                .map(|c| c.paths.database.clone())
-                .unwrap_or_else(|| "sqlite:./data/gamevault.db?mode=rwc".to_string()),
-            cache: std::path::PathBuf::from(&payload.cache),
-        },
-        server: config::ServerConfig {
-            port: payload.port,
-            auto_open_browser: payload.auto_open_browser,
-            bind_address: current_config
-                .as_ref()
-                .map(|c| c.server.bind_address.clone())
-                .unwrap_or_else(|| "127.0.0.1".to_string()),
-        },
-    };
+    if payload.port < 1024 {
+        return error("Port must be between 1024 and 65535").into_response();
+    }
+
+    // An If-Match header wins over the body ("W/" and quotes are ignored)
+    let revision = headers
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.trim()
+                .trim_start_matches("W/")
+                .trim_matches('"')
+                .to_string()
+        })
+        .or(payload.revision);
+
+    // Sections not managed by the settings UI are preserved
+    let mut restart_required = false;
+    let written =
+        config::update_config_file(&config::get_config_path(), revision.as_deref(), |current| {
+            restart_required = current.server.port != payload.port;
+            AppConfig {
+                paths: config::PathsConfig {
+                    game_library: game_path,
+                    database: current.paths.database.clone(),
+                    cache: std::path::PathBuf::from(&payload.cache),
+                },
+                server: config::ServerConfig {
+                    port: payload.port,
+                    auto_open_browser: payload.auto_open_browser,
+                    ..current.server.clone()
+                },
+                ..current
+            }
+        });
 
-    // Write config atomically
-    match config::write_config(&new_config) {
-        Ok(_) => {
-            let message = if restart_required {
+    match written {
+        Ok(revision) => {
+            let mut message = if restart_required {
                 "Configuration saved. Restart required for port change.".to_string()
             } else {
                 "Configuration saved successfully.".to_string()
             };
+            let overridden: Vec<String> = config::env_overrides()
+                .into_iter()
+                .filter(|o| {
+                    matches!(
+                        o.key.as_str(),
+                        "paths.game_library"
+                            | "paths.cache"
+                            | "server.port"
+                            | "server.auto_open_browser"
+                    )
+                })
+                .map(|o| o.variable)
+                .collect();
+            if !overridden.is_empty() {
+                message.push_str(&format!(
+                    " Overridden by environment variables: {}.",
+                    overridden.join(", ")
+                ));
+            }
 
             Json(ApiResponse::success(ConfigUpdateResponse {
                 success: true,
                 restart_required,
                 message,
+                revision,
             }))
+            .into_response()
         }
-        Err(e) => {
+        Err(config::ConfigWriteError::Stale) => {
+            tracing::warn!("Refused a config update based on an old revision");
+            (
+                StatusCode::CONFLICT,
+                error("Configuration was changed elsewhere; reload it and try again"),
+            )
+                .into_response()
+        }
+        Err(config::ConfigWriteError::Io(e)) => {
             tracing::error!("Failed to save config: {}", e);
-            Json(ApiResponse::error("Failed to save configuration"))
+            error("Failed to save configuration").into_response()
         }
     }
 }
@@ -1091,8 +2683,11 @@ pub async fn restart_server() -> Json<ApiResponse<&'static str>> {
     tokio::spawn(async move {
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-        // Spawn new process
-        match std::process::Command::new(&exe_path).spawn() {
+        // Spawn new process with the same arguments (e.g. --demo)
+        match std::process::Command::new(&exe_path)
+            .args(std::env::args_os().skip(1))
+            .spawn()
+        {
             Ok(_) => {
                 tracing::info!("New instance spawned, exiting current process");
             }
@@ -1109,7 +2704,18 @@ pub async fn restart_server() -> Json<ApiResponse<&'static str>> {
 }
 
 /// Check if game library path is configured (GET /api/config/status)
-pub async fn get_config_status() -> Json<ApiResponse<ConfigStatusResponse>> {
+pub async fn get_config_status(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<ConfigStatusResponse>> {
+    // The demo library needs no setup
+    if state.demo {
+        return Json(ApiResponse::success(ConfigStatusResponse {
+            needs_setup: false,
+            game_library_configured: true,
+            game_library_path: state.games_path.clone(),
+        }));
+    }
+
     match AppConfig::load() {
         Ok(cfg) => {
             // Get original config value (not resolved)
@@ -1141,3 +2747,2676 @@ pub struct ConfigStatusResponse {
     pub game_library_configured: bool,
     pub game_library_path: String,
 }
+
+// ============================================================================
+// Launching & Play Sessions
+// ============================================================================
+
+/// Maximum idle timeout accepted for idle detection (minutes)
+const MAX_IDLE_TIMEOUT_MINS: i64 = 240;
+
+/// Response for launching a game or starting a session
+#[derive(serde::Serialize)]
+pub struct LaunchResult {
+    pub session_id: i64,
+    pub executable: Option<String>,
+}
+
+/// Launch a game's executable and start tracking a play session (POST /games/{id}/launch)
+pub async fn launch_game(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<LaunchResult>> {
+    let game = match visible_game(&state, id).await {
+        Ok(Some(g)) => g,
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    match db::get_open_play_session(&state.db, id).await {
+        Ok(Some(_)) => return Json(ApiResponse::error("Game is already running")),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to check sessions for game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    let exe = match launcher::resolve_game_exe(&game.folder_path, game.launch_exe.as_deref()) {
+        Some(exe) => exe,
+        None => {
+            return Json(ApiResponse::error(
+                "No executable found. Set one in the game's play settings.",
+            ))
+        }
+    };
+
+    // SECURITY: Only launch executables inside the games directory
+    let exe = match validate_path_within_games(&state.games_path, &exe) {
+        Some(p) => p,
+        None => return Json(ApiResponse::error("Access denied")),
+    };
+
+    match launcher::launch_game(&state.db, &game, &exe).await {
+        Ok(session_id) => Json(ApiResponse::success(LaunchResult {
+            session_id,
+            executable: exe.file_name().map(|n| n.to_string_lossy().to_string()),
+        })),
+        Err(e) => {
+            tracing::warn!("Failed to launch game {}: {}", id, e);
+            Json(ApiResponse::error(
+                "Failed to launch game. Launch diagnostics were saved.",
+            ))
+        }
+    }
+}
+
+/// Diagnostic bundles from the game's failed launches, newest first (GET /games/{id}/diagnostics)
+pub async fn get_launch_diagnostics(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<diagnostics::LaunchDiagnostics>>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let folder_path = match db::get_game_folder_path(&state.db, id).await {
+        Ok(Some(path)) => path,
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    match tokio::task::spawn_blocking(move || diagnostics::list(&folder_path)).await {
+        Ok(bundles) => Json(ApiResponse::success(bundles)),
+        Err(e) => {
+            tracing::error!("Failed to read launch diagnostics for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Start a play session manually for a game launched outside GameVault (POST /games/{id}/sessions)
+pub async fn start_play_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<LaunchResult>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+
+    match db::get_open_play_session(&state.db, id).await {
+        Ok(Some(_)) => return Json(ApiResponse::error("A session is already running")),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::error!("Failed to check sessions for game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    match db::start_play_session(&state.db, id, sessions::machine_name()).await {
+        Ok(session_id) => Json(ApiResponse::success(LaunchResult {
+            session_id,
+            executable: None,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to start session for game {}: {}", id, e);
+            Json(ApiResponse::error("Failed to start session"))
+        }
+    }
+}
+
+/// Stop the running play session for a game (POST /games/{id}/sessions/stop)
+pub async fn stop_play_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<PlaySession>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let session = match db::get_open_play_session(&state.db, id).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return Json(ApiResponse::error("No running session")),
+        Err(e) => {
+            tracing::error!("Failed to get session for game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    match db::end_play_session(&state.db, session.id).await {
+        Ok(session) => Json(ApiResponse::success(session)),
+        Err(e) => {
+            tracing::error!("Failed to end session {}: {}", session.id, e);
+            Json(ApiResponse::error("Failed to stop session"))
+        }
+    }
+}
+
+/// List recent play sessions for a game (GET /games/{id}/sessions)
+pub async fn list_play_sessions(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<PlaySession>>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::get_play_sessions(&state.db, id, 50).await {
+        Ok(sessions) => Json(ApiResponse::success(sessions)),
+        Err(e) => {
+            tracing::error!("Failed to list sessions for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Request body for per-game launch and idle detection settings
+#[derive(Deserialize)]
+pub struct PlaySettingsRequest {
+    /// Executable relative to the game folder (None = auto-detect)
+    pub launch_exe: Option<String>,
+    /// Minutes without system input before the session clock pauses (None = disabled)
+    pub idle_timeout_mins: Option<i64>,
+}
+
+/// Update launch and idle detection settings (PUT /games/{id}/play-settings)
+pub async fn update_play_settings(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(payload): Json<PlaySettingsRequest>,
+) -> Json<ApiResponse<Game>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    if let Some(mins) = payload.idle_timeout_mins {
+        if !(1..=MAX_IDLE_TIMEOUT_MINS).contains(&mins) {
+            return Json(ApiResponse::error(format!(
+                "Idle timeout must be between 1 and {} minutes",
+                MAX_IDLE_TIMEOUT_MINS
+            )));
+        }
+    }
+
+    let launch_exe = payload
+        .launch_exe
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| !e.is_empty());
+
+    if let Some(exe) = launch_exe {
+        let folder_path = match db::get_game_folder_path(&state.db, id).await {
+            Ok(Some(path)) => path,
+            Ok(None) => return Json(ApiResponse::error("Game not found")),
+            Err(e) => {
+                tracing::error!("Failed to get game folder: {}", e);
+                return Json(ApiResponse::error("Internal server error"));
+            }
+        };
+
+        // SECURITY: Executable must exist inside the game folder
+        let exe_path = std::path::Path::new(&folder_path).join(exe);
+        let within_folder = std::fs::canonicalize(&exe_path)
+            .ok()
+            .zip(std::fs::canonicalize(&folder_path).ok())
+            .is_some_and(|(exe, folder)| exe.starts_with(folder) && exe.is_file());
+        if !within_folder {
+            return Json(ApiResponse::error(
+                "Executable not found inside the game folder",
+            ));
+        }
+    }
+
+    match db::update_play_settings(&state.db, id, launch_exe, payload.idle_timeout_mins).await {
+        Ok(game) => Json(ApiResponse::success(game)),
+        Err(sqlx::Error::RowNotFound) => Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to update play settings for game {}: {}", id, e);
+            Json(ApiResponse::error("Failed to update play settings"))
+        }
+    }
+}
+
+// ============================================================================
+// Reports
+// ============================================================================
+
+/// Query parameters for playtime reports
+#[derive(Deserialize)]
+pub struct PlaytimeReportQuery {
+    /// "week" or "month"
+    #[serde(default = "default_report_period")]
+    pub period: String,
+    /// Periods back from the current one (0 = current)
+    #[serde(default)]
+    pub offset: u32,
+}
+
+fn default_report_period() -> String {
+    "week".to_string()
+}
+
+/// Get a weekly or monthly playtime report (GET /reports/playtime?period=week|month)
+pub async fn get_playtime_report(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PlaytimeReportQuery>,
+) -> Json<ApiResponse<reports::PlaytimeReport>> {
+    let period = match reports::ReportPeriod::parse(&query.period) {
+        Some(p) => p,
+        None => return Json(ApiResponse::error("Period must be 'week' or 'month'")),
+    };
+
+    match reports::build_playtime_report(&state.db, period, query.offset).await {
+        Ok(report) => Json(ApiResponse::success(report)),
+        Err(e) => {
+            tracing::error!("Failed to build playtime report: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Runtimes needed across the library (GET /reports/runtimes)
+pub async fn get_runtime_report(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<redist::RuntimeRequirement>>> {
+    match db::get_library_runtimes(&state.db).await {
+        Ok(rows) => Json(ApiResponse::success(redist::requirements(rows))),
+        Err(e) => {
+            tracing::error!("Failed to build runtime report: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Sanitized catalog for static site generators (GET /export/site-data)
+pub async fn export_site_data(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<site_export::SiteData>> {
+    match db::get_visible_games(&state.db).await {
+        Ok(games) => Json(ApiResponse::success(site_export::build(
+            games,
+            timestamps::now(),
+        ))),
+        Err(e) => {
+            tracing::error!("Failed to build site data: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Maximum notifications returned by GET /notifications
+const NOTIFICATION_LIST_LIMIT: i64 = 50;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct NotificationQuery {
+    /// Only unread notifications
+    pub unread: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct NotificationInbox {
+    /// Unread notifications in total (for a badge), regardless of filters
+    pub unread_count: i64,
+    pub items: Vec<Notification>,
+}
+
+/// List inbox notifications, newest first (GET /notifications)
+pub async fn list_notifications(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<NotificationQuery>,
+) -> Json<ApiResponse<NotificationInbox>> {
+    let unread_count = match db::count_unread_notifications(&state.db).await {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!("Failed to count notifications: {}", e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    match db::get_notifications(&state.db, query.unread, NOTIFICATION_LIST_LIMIT).await {
+        Ok(items) => Json(ApiResponse::success(NotificationInbox {
+            unread_count,
+            items,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to list notifications: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Mark a notification read (POST /notifications/{id}/read)
+pub async fn mark_notification_read(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<&'static str>> {
+    match db::mark_notification_read(&state.db, id).await {
+        Ok(true) => Json(ApiResponse::success("OK")),
+        Ok(false) => Json(ApiResponse::error("Notification not found")),
+        Err(e) => {
+            tracing::error!("Failed to mark notification {} read: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Mark every notification read (POST /notifications/read-all)
+pub async fn mark_all_notifications_read(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<u64>> {
+    match db::mark_all_notifications_read(&state.db).await {
+        Ok(count) => Json(ApiResponse::success(count)),
+        Err(e) => {
+            tracing::error!("Failed to mark notifications read: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Today's discovery queue of unplayed games (GET /discovery)
+pub async fn get_discovery_queue(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<discovery::DiscoveryQueue>> {
+    match discovery::todays_queue(&state.db).await {
+        Ok(queue) => Json(ApiResponse::success(queue)),
+        Err(e) => {
+            tracing::error!("Failed to build discovery queue: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Backlog burn-down forecast (GET /stats/forecast)
+pub async fn get_backlog_forecast(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<forecast::Forecast>> {
+    match forecast::current_forecast(&state.db).await {
+        Ok(forecast) => Json(ApiResponse::success(forecast)),
+        Err(e) => {
+            tracing::error!("Failed to build backlog forecast: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Query parameters for the storage tier report
+#[derive(Deserialize)]
+pub struct StorageStatsQuery {
+    #[serde(default = "default_storage_window_days")]
+    pub window_days: i64,
+    #[serde(default = "default_storage_idle_days")]
+    pub idle_days: i64,
+}
+
+fn default_storage_window_days() -> i64 {
+    storage::DEFAULT_WINDOW_DAYS
+}
+
+fn default_storage_idle_days() -> i64 {
+    storage::DEFAULT_IDLE_DAYS
+}
+
+/// Library stats per storage tier and games worth moving (GET /stats/storage)
+pub async fn get_storage_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StorageStatsQuery>,
+) -> Json<ApiResponse<storage::StorageReport>> {
+    let window_days = query.window_days.clamp(1, storage::MAX_DAYS);
+    let idle_days = query.idle_days.clamp(1, storage::MAX_DAYS);
+
+    match storage::report(&state.db, &state.config.storage, window_days, idle_days).await {
+        Ok(report) => Json(ApiResponse::success(report)),
+        Err(e) => {
+            tracing::error!("Failed to build storage report: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Library gauges for Prometheus (GET /metrics)
+pub async fn get_metrics(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+
+    match metrics::collect(&state.db).await {
+        Ok(library) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, metrics::CONTENT_TYPE)],
+            metrics::render(&library),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to collect metrics: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// Labels for server-provided values in the client's language (GET /i18n)
+pub async fn get_translations(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Json<ApiResponse<i18n::Translations>> {
+    let accept_language = headers
+        .get(axum::http::header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok());
+    let language = i18n::negotiate(&state.config.i18n.language, accept_language);
+    Json(ApiResponse::success(i18n::translations(language)))
+}
+
+/// Live library events over a WebSocket (GET /ws)
+pub async fn library_events(
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+) -> axum::response::Response {
+    let pool = state.db.clone();
+    ws.on_upgrade(move |socket| events::serve(socket, pool))
+}
+
+/// Games installed more than once (GET /duplicates)
+///
+/// `?include_paths=true` adds each copy's folder for admin clients.
+pub async fn get_duplicates(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(paths): Query<IncludePathsQuery>,
+) -> Json<ApiResponse<Vec<duplicates::DuplicateGroup>>> {
+    let include = match include_paths(&headers, &paths) {
+        Ok(include) => include,
+        Err(e) => return Json(ApiResponse::error(e)),
+    };
+
+    match db::get_visible_games(&state.db).await {
+        Ok(games) => Json(ApiResponse::success(duplicates::find_duplicates(
+            &games, include,
+        ))),
+        Err(e) => {
+            tracing::error!("Failed to load games for duplicate report: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Request body for hiding a game
+#[derive(Deserialize)]
+pub struct HideGameRequest {
+    pub hidden: bool,
+}
+
+/// Hide a game from the game list and search, or show it again (POST /games/{id}/hide)
+pub async fn hide_game(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(payload): Json<HideGameRequest>,
+) -> Json<ApiResponse<Game>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::set_game_hidden(&state.db, id, payload.hidden).await {
+        Ok(true) => {}
+        Ok(false) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to hide game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(game)) => {
+            events::publish(events::Event::GameUpdated {
+                game: game.clone().into(),
+            });
+            Json(ApiResponse::success(game))
+        }
+        Ok(None) => Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to reload game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Longest franchise name accepted
+const MAX_FRANCHISE_LENGTH: usize = 100;
+
+/// Request body for setting a game's links (null clears a link)
+#[derive(Deserialize)]
+pub struct SetGameLinksRequest {
+    pub parent_game_id: Option<i64>,
+    pub franchise: Option<String>,
+}
+
+/// Set a game's base game and franchise (POST /games/{id}/links)
+pub async fn set_game_links(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SetGameLinksRequest>,
+) -> Json<ApiResponse<Game>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let franchise = payload
+        .franchise
+        .as_deref()
+        .map(str::trim)
+        .filter(|f| !f.is_empty());
+    if franchise.is_some_and(|f| f.len() > MAX_FRANCHISE_LENGTH) {
+        return Json(ApiResponse::error(format!(
+            "Franchise must be at most {} characters",
+            MAX_FRANCHISE_LENGTH
+        )));
+    }
+
+    if let Some(parent_id) = payload.parent_game_id {
+        if parent_id == id {
+            return Json(ApiResponse::error("A game can't be its own base game"));
+        }
+        match db::get_game_by_id(&state.db, parent_id).await {
+            Ok(Some(parent)) if parent.parent_game_id == Some(id) => {
+                return Json(ApiResponse::error("The base game is linked to this game"));
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => return Json(ApiResponse::error("Base game not found")),
+            Err(e) => {
+                tracing::error!("Failed to get game {}: {}", parent_id, e);
+                return Json(ApiResponse::error("Database error"));
+            }
+        }
+    }
+
+    match db::set_game_links(&state.db, id, payload.parent_game_id, franchise).await {
+        Ok(Some(game)) => Json(ApiResponse::success(game)),
+        Ok(None) => Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to set links for game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Query parameters for listing link proposals
+#[derive(Deserialize)]
+pub struct LinkProposalsQuery {
+    #[serde(default = "default_link_status")]
+    pub status: String,
+}
+
+fn default_link_status() -> String {
+    "pending".to_string()
+}
+
+/// Proposed edition/DLC and franchise links (GET /links/proposals)
+pub async fn list_link_proposals(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LinkProposalsQuery>,
+) -> Json<ApiResponse<Vec<LinkProposal>>> {
+    if !["pending", "confirmed", "rejected"].contains(&query.status.as_str()) {
+        return Json(ApiResponse::error(
+            "Status must be 'pending', 'confirmed' or 'rejected'",
+        ));
+    }
+
+    match db::get_link_proposals(&state.db, &query.status).await {
+        Ok(proposals) => Json(ApiResponse::success(proposals)),
+        Err(e) => {
+            tracing::error!("Failed to get link proposals: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct DetectLinksResult {
+    added: usize,
+}
+
+/// Look for edition/DLC and franchise links now (POST /links/detect)
+pub async fn detect_links(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<DetectLinksResult>> {
+    match linking::detect(&state.db).await {
+        Ok(added) => Json(ApiResponse::success(DetectLinksResult { added })),
+        Err(e) => {
+            tracing::error!("Failed to detect game links: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct PrewarmResult {
+    queued: usize,
+}
+
+/// Queue thumbnails and placeholders for cached covers that lack them (POST /artwork/prewarm)
+///
+/// Enrichment queues its games itself; this covers images cached earlier.
+pub async fn prewarm_artwork(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<PrewarmResult>> {
+    match db::get_games_missing_artwork(&state.db).await {
+        Ok(ids) => {
+            let queued = ids.len();
+            state.artwork.enqueue(ids);
+            Json(ApiResponse::success(PrewarmResult { queued }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to list games missing artwork: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+async fn resolve_link_proposal(state: &AppState, id: i64, confirm: bool) -> Json<ApiResponse<()>> {
+    match db::resolve_link_proposal(&state.db, id, confirm).await {
+        Ok(true) => Json(ApiResponse::success(())),
+        Ok(false) => Json(ApiResponse::error("Pending link proposal not found")),
+        Err(e) => {
+            tracing::error!("Failed to resolve link proposal {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Apply a proposed link (POST /links/proposals/{id}/confirm)
+pub async fn confirm_link_proposal(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<()>> {
+    resolve_link_proposal(&state, id, true).await
+}
+
+/// Dismiss a proposed link for good (POST /links/proposals/{id}/reject)
+pub async fn reject_link_proposal(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<()>> {
+    resolve_link_proposal(&state, id, false).await
+}
+
+/// Send the playtime digest to the notification webhook now (POST /reports/playtime/digest)
+pub async fn send_playtime_digest(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PlaytimeReportQuery>,
+) -> Json<ApiResponse<reports::PlaytimeReport>> {
+    let webhook_url = &state.config.notifications.webhook_url;
+    if webhook_url.is_empty() {
+        return Json(ApiResponse::error("No notification webhook configured"));
+    }
+
+    let period = match reports::ReportPeriod::parse(&query.period) {
+        Some(p) => p,
+        None => return Json(ApiResponse::error("Period must be 'week' or 'month'")),
+    };
+
+    let report = match reports::build_playtime_report(&state.db, period, query.offset).await {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("Failed to build playtime report: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    };
+
+    let client = state.http.clone();
+    match notifications::send_webhook(&client, webhook_url, &reports::digest_event(&report)).await {
+        Ok(_) => Json(ApiResponse::success(report)),
+        Err(e) => {
+            tracing::warn!("Failed to send playtime digest: {}", e);
+            Json(ApiResponse::error("Failed to send digest"))
+        }
+    }
+}
+
+// ============================================================================
+// Steam Workshop
+// ============================================================================
+
+/// Result of syncing Workshop subscriptions for a game
+#[derive(serde::Serialize)]
+pub struct WorkshopSyncResult {
+    /// Newly subscribed items
+    pub added: usize,
+    /// Items updated on Steam since the last sync
+    pub updated: usize,
+    /// Items no longer subscribed
+    pub removed: usize,
+    pub items: Vec<WorkshopItem>,
+}
+
+/// List synced Workshop items for a game (GET /games/{id}/workshop)
+pub async fn list_workshop_items(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<WorkshopItem>>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::get_workshop_items(&state.db, id).await {
+        Ok(items) => Json(ApiResponse::success(items)),
+        Err(e) => {
+            tracing::error!("Failed to list Workshop items for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Fetch the user's subscribed Workshop items from Steam (POST /games/{id}/workshop/sync)
+pub async fn sync_workshop_items(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<WorkshopSyncResult>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let steam_config = &state.config.steam;
+    if !steam_config.is_configured() {
+        return Json(ApiResponse::error(
+            "Steam API key and SteamID are not configured",
+        ));
+    }
+
+    let game = match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(g)) => g,
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    let app_id = match game.steam_app_id {
+        Some(app_id) if app_id > 0 => app_id,
+        _ => return Json(ApiResponse::error("Game is not matched to a Steam app")),
+    };
+
+    let client = state.http.clone();
+    let fetched = match steam::fetch_subscribed_workshop_items(
+        &client,
+        &steam_config.api_key,
+        &steam_config.steam_id,
+        app_id,
+    )
+    .await
+    {
+        Some(items) => items,
+        None => {
+            return Json(ApiResponse::error(
+                "Failed to fetch Workshop items from Steam",
+            ))
+        }
+    };
+
+    let existing = match db::get_workshop_items(&state.db, id).await {
+        Ok(items) => items,
+        Err(e) => {
+            tracing::error!("Failed to list Workshop items for game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    let added = fetched
+        .iter()
+        .filter(|f| {
+            !existing
+                .iter()
+                .any(|e| e.published_file_id == f.published_file_id)
+        })
+        .count();
+    let updated = fetched
+        .iter()
+        .filter(|f| {
+            existing.iter().any(|e| {
+                e.published_file_id == f.published_file_id && e.time_updated != f.time_updated
+            })
+        })
+        .count();
+    let removed = existing
+        .iter()
+        .filter(|e| {
+            !fetched
+                .iter()
+                .any(|f| f.published_file_id == e.published_file_id)
+        })
+        .count();
+
+    if let Err(e) = db::sync_workshop_items(&state.db, id, &fetched).await {
+        tracing::error!("Failed to save Workshop items for game {}: {}", id, e);
+        return Json(ApiResponse::error("Failed to save Workshop items"));
+    }
+
+    match db::get_workshop_items(&state.db, id).await {
+        Ok(items) => Json(ApiResponse::success(WorkshopSyncResult {
+            added,
+            updated,
+            removed,
+            items,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to list Workshop items for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+// ============================================================================
+// Steam Friends
+// ============================================================================
+
+/// Delay between per-friend library requests to the Steam Web API
+const FRIEND_SYNC_DELAY_MS: u64 = 100;
+
+/// Summary of a friends library sync
+#[derive(serde::Serialize)]
+pub struct FriendsSyncResult {
+    pub friends: usize,
+    /// Friends whose game details are private (not counted)
+    pub private_profiles: usize,
+    /// Friends whose library couldn't be fetched (their last synced games
+    /// are kept)
+    pub failed_profiles: usize,
+    /// Library games owned by at least one friend
+    pub games_owned_by_friends: u64,
+}
+
+/// Pull friends' owned games and refresh per-game "friends who own this"
+/// counts (POST /steam/friends/sync)
+pub async fn sync_steam_friends(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<FriendsSyncResult>> {
+    let steam_config = &state.config.steam;
+    if !steam_config.is_configured() {
+        return Json(ApiResponse::error(
+            "Steam API key and SteamID are not configured",
+        ));
+    }
+
+    let client = state.http.clone();
+    let friend_ids =
+        match steam::fetch_friend_ids(&client, &steam_config.api_key, &steam_config.steam_id).await
+        {
+            Some(ids) => ids,
+            None => {
+                return Json(ApiResponse::error(
+                    "Failed to fetch friend list from Steam (is it public?)",
+                ))
+            }
+        };
+
+    let mut private_profiles = 0;
+    let mut failed_profiles = 0;
+    for (i, friend_id) in friend_ids.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(FRIEND_SYNC_DELAY_MS)).await;
+        }
+
+        let games = match steam::fetch_owned_games(&client, &steam_config.api_key, friend_id).await
+        {
+            Some(steam::OwnedGames::Public(games)) => games,
+            // Private libraries keep no rows, so they don't count as owning anything
+            Some(steam::OwnedGames::Private) => {
+                private_profiles += 1;
+                Vec::new()
+            }
+            // A failed request says nothing about the library: keep the last sync
+            None => {
+                failed_profiles += 1;
+                continue;
+            }
+        };
+
+        if let Err(e) = db::replace_friend_games(&state.db, friend_id, &games).await {
+            tracing::error!("Failed to save games of friend {}: {}", friend_id, e);
+            return Json(ApiResponse::error("Failed to save friend libraries"));
+        }
+    }
+
+    if let Err(e) = db::prune_friend_games(&state.db, &friend_ids).await {
+        tracing::error!("Failed to prune former friends: {}", e);
+        return Json(ApiResponse::error("Database error"));
+    }
+
+    match db::refresh_friends_owned(&state.db).await {
+        Ok(games_owned_by_friends) => Json(ApiResponse::success(FriendsSyncResult {
+            friends: friend_ids.len(),
+            private_profiles,
+            failed_profiles,
+            games_owned_by_friends,
+        })),
+        Err(e) => {
+            tracing::error!("Failed to update friend ownership counts: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+// ============================================================================
+// Compatibility Reports
+// ============================================================================
+
+/// Allowed compatibility statuses
+const COMPAT_STATUSES: &[&str] = &["works", "tweaks", "broken"];
+
+/// Lowercase a platform name and use underscores ("Steam Deck" -> "steam_deck")
+fn normalize_platform(platform: &str) -> String {
+    platform
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase()
+}
+
+/// Trim an optional text field, treating blank as unset
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// List a game's compatibility reports (GET /games/{id}/compat)
+pub async fn list_game_compat(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<GameCompat>>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::get_game_compat(&state.db, id).await {
+        Ok(reports) => Json(ApiResponse::success(reports)),
+        Err(e) => {
+            tracing::error!("Failed to list compatibility for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Request body for a compatibility report
+#[derive(Deserialize)]
+pub struct GameCompatRequest {
+    pub status: String,
+    pub wrapper: Option<String>,
+    pub launch_options: Option<String>,
+    pub dxvk_flags: Option<String>,
+}
+
+/// Set the compatibility report for one platform (PUT /games/{id}/compat/{platform})
+pub async fn update_game_compat(
+    State(state): State<Arc<AppState>>,
+    Path((id, platform)): Path<(i64, String)>,
+    Json(payload): Json<GameCompatRequest>,
+) -> Json<ApiResponse<GameCompat>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let platform = normalize_platform(&platform);
+    if platform.is_empty()
+        || platform.len() > 32
+        || !platform
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Json(ApiResponse::error("Invalid platform name"));
+    }
+
+    let status = payload.status.trim().to_lowercase();
+    if !COMPAT_STATUSES.contains(&status.as_str()) {
+        return Json(ApiResponse::error(
+            "Status must be 'works', 'tweaks' or 'broken'",
+        ));
+    }
+
+    match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    let wrapper = non_empty(payload.wrapper);
+    let launch_options = non_empty(payload.launch_options);
+    let dxvk_flags = non_empty(payload.dxvk_flags);
+
+    match db::upsert_game_compat(
+        &state.db,
+        id,
+        &platform,
+        &status,
+        wrapper.as_deref(),
+        launch_options.as_deref(),
+        dxvk_flags.as_deref(),
+    )
+    .await
+    {
+        Ok(report) => Json(ApiResponse::success(report)),
+        Err(e) => {
+            tracing::error!("Failed to save compatibility for game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Remove the compatibility report for one platform
+/// (POST /games/{id}/compat/{platform}/delete)
+pub async fn delete_game_compat(
+    State(state): State<Arc<AppState>>,
+    Path((id, platform)): Path<(i64, String)>,
+) -> Json<ApiResponse<()>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::delete_game_compat(&state.db, id, &normalize_platform(&platform)).await {
+        Ok(true) => Json(ApiResponse::success(())),
+        Ok(false) => Json(ApiResponse::error("Compatibility report not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete compatibility for game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// List the runtime installers found in a game's folder (GET /games/{id}/runtimes)
+pub async fn list_game_runtimes(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<redist::Redist>>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::get_game_runtimes(&state.db, id).await {
+        Ok(runtimes) => Json(ApiResponse::success(runtimes)),
+        Err(e) => {
+            tracing::error!("Failed to list runtimes for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+// ============================================================================
+// Settings Profiles
+// ============================================================================
+
+/// Get a game's settings profile (GET /games/{id}/settings)
+pub async fn get_game_settings(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<GameSettings>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::get_game_settings(&state.db, id).await {
+        Ok(Some(settings)) => Json(ApiResponse::success(settings)),
+        Ok(None) => Json(ApiResponse::error("No settings profile for this game")),
+        Err(e) => {
+            tracing::error!("Failed to get settings for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Set a game's settings profile (PUT /games/{id}/settings)
+///
+/// The body is the whole profile; it replaces the stored one.
+pub async fn update_game_settings(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SettingsProfile>,
+) -> Json<ApiResponse<GameSettings>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let profile = match payload.validate() {
+        Ok(profile) => profile,
+        Err(message) => return Json(ApiResponse::error(message)),
+    };
+
+    match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    match db::upsert_game_settings(&state.db, id, &profile).await {
+        Ok(settings) => Json(ApiResponse::success(settings)),
+        Err(e) => {
+            tracing::error!("Failed to save settings for game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Remove a game's settings profile (POST /games/{id}/settings/delete)
+pub async fn delete_game_settings(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<()>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::delete_game_settings(&state.db, id).await {
+        Ok(true) => Json(ApiResponse::success(())),
+        Ok(false) => Json(ApiResponse::error("No settings profile for this game")),
+        Err(e) => {
+            tracing::error!("Failed to delete settings for game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+// ============================================================================
+// Wishlist
+// ============================================================================
+
+/// List wishlist entries (GET /wishlist)
+pub async fn list_wishlist(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<WishlistItem>>> {
+    match db::get_wishlist(&state.db).await {
+        Ok(items) => Json(ApiResponse::success(items)),
+        Err(e) => {
+            tracing::error!("Failed to list wishlist: {}", e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Serve a wishlist entry's prefetched cover (GET /wishlist/{id}/cover)
+///
+/// 404 until the prefetch worker has downloaded it; never fetches on demand,
+/// so browsing the wishlist stays within the prefetch budget.
+pub async fn serve_wishlist_cover(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+
+    let app_id = match db::get_wishlist_item(&state.db, id).await {
+        Ok(Some(item)) => item.steam_app_id,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Wishlist entry not found").into_response(),
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    let Some(app_id) = app_id else {
+        return (StatusCode::NOT_FOUND, "Wishlist entry has no Steam app").into_response();
+    };
+
+    match std::fs::read(prefetch::cover_path(&state.config.cache_path(), app_id)) {
+        Ok(bytes) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "image/jpeg")],
+            bytes,
+        )
+            .into_response(),
+        Err(_) => {
+            // Queued again in case an earlier download failed
+            state.prefetch.enqueue([app_id]);
+            (StatusCode::NOT_FOUND, "Cover not cached yet").into_response()
+        }
+    }
+}
+
+/// Request body for creating or updating a wishlist entry
+#[derive(Deserialize)]
+pub struct WishlistItemRequest {
+    pub title: Option<String>,
+    pub steam_app_id: Option<i64>,
+    pub notes: Option<String>,
+    pub status: Option<String>,
+}
+
+/// Add a game to the wishlist (POST /wishlist)
+pub async fn create_wishlist_item(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<WishlistItemRequest>,
+) -> Json<ApiResponse<WishlistItem>> {
+    let title = match payload.title.as_deref().map(str::trim) {
+        Some(t) if !t.is_empty() => t,
+        _ => return Json(ApiResponse::error("Title is required")),
+    };
+
+    match db::create_wishlist_item(
+        &state.db,
+        title,
+        payload.steam_app_id,
+        payload.notes.as_deref(),
+    )
+    .await
+    {
+        Ok(item) => {
+            state.prefetch.enqueue(item.steam_app_id);
+            Json(ApiResponse::success(item))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create wishlist entry: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Update a wishlist entry (PUT /wishlist/{id})
+pub async fn update_wishlist_item(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(payload): Json<WishlistItemRequest>,
+) -> Json<ApiResponse<WishlistItem>> {
+    if let Some(status) = payload.status.as_deref() {
+        // Acquiring needs a folder reservation, so it goes through /acquire
+        if !wishlist::is_valid_status(status) || status == wishlist::STATUS_ACQUIRING {
+            return Json(ApiResponse::error(
+                "Status must be 'wanted' or 'acquired' (use /acquire to start acquiring)",
+            ));
+        }
+    }
+
+    let title = payload.title.as_deref().map(str::trim);
+    if title.is_some_and(str::is_empty) {
+        return Json(ApiResponse::error("Title cannot be empty"));
+    }
+
+    match db::update_wishlist_item(
+        &state.db,
+        id,
+        title,
+        payload.steam_app_id,
+        payload.notes.as_deref(),
+        payload.status.as_deref(),
+    )
+    .await
+    {
+        Ok(Some(item)) => {
+            if payload.steam_app_id.is_some() {
+                state.prefetch.enqueue(item.steam_app_id);
+            }
+            Json(ApiResponse::success(item))
+        }
+        Ok(None) => Json(ApiResponse::error("Wishlist entry not found")),
+        Err(e) => {
+            tracing::error!("Failed to update wishlist entry {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Remove a wishlist entry and its price history (POST /wishlist/{id}/delete)
+pub async fn delete_wishlist_item(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<()>> {
+    match db::delete_wishlist_item(&state.db, id).await {
+        Ok(true) => Json(ApiResponse::success(())),
+        Ok(false) => Json(ApiResponse::error("Wishlist entry not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete wishlist entry {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Request body for starting an acquisition
+#[derive(Deserialize, Default)]
+pub struct AcquireRequest {
+    /// Folder name to reserve (defaults to one derived from the title)
+    pub folder_name: Option<String>,
+    /// Also create the (empty) folder in the game library
+    #[serde(default)]
+    pub create_folder: bool,
+}
+
+/// Mark a wishlist entry as acquiring and reserve its library folder
+/// (POST /wishlist/{id}/acquire)
+pub async fn acquire_wishlist_item(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    payload: Option<Json<AcquireRequest>>,
+) -> Json<ApiResponse<WishlistItem>> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+
+    let item = match db::get_wishlist_item(&state.db, id).await {
+        Ok(Some(item)) => item,
+        Ok(None) => return Json(ApiResponse::error("Wishlist entry not found")),
+        Err(e) => {
+            tracing::error!("Failed to get wishlist entry {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    if item.status == wishlist::STATUS_ACQUIRED {
+        return Json(ApiResponse::error("Wishlist entry is already acquired"));
+    }
+
+    let folder_name = payload
+        .folder_name
+        .unwrap_or_else(|| wishlist::reserved_folder_name(&item.title));
+    // SECURITY: A single folder name, never a path
+    if !wishlist::is_valid_folder_name(&folder_name) {
+        return Json(ApiResponse::error("Invalid folder name"));
+    }
+
+    let folder_path = std::path::Path::new(&state.games_path).join(&folder_name);
+    let folder_exists = folder_path.exists();
+    if folder_exists && !wishlist::is_empty_folder(&folder_path) {
+        return Json(ApiResponse::error(
+            "A folder with that name already exists in the library",
+        ));
+    }
+
+    match db::is_folder_reserved(&state.db, &folder_name, id).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return Json(ApiResponse::error(
+                "Folder name is reserved by another wishlist entry",
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Failed to check folder reservations: {}", e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    if payload.create_folder && !folder_exists {
+        if let Err(e) = std::fs::create_dir(&folder_path) {
+            tracing::error!("Failed to create folder {:?}: {}", folder_path, e);
+            return Json(ApiResponse::error("Failed to create folder"));
+        }
+    }
+
+    match db::reserve_wishlist_folder(&state.db, id, &folder_name).await {
+        Ok(Some(item)) => Json(ApiResponse::success(item)),
+        Ok(None) => Json(ApiResponse::error("Wishlist entry not found")),
+        Err(e) => {
+            tracing::error!("Failed to reserve folder for wishlist entry {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Request body for recording a price
+#[derive(Deserialize)]
+pub struct AddPriceRequest {
+    pub price_cents: i64,
+    pub currency: String,
+    pub store: Option<String>,
+}
+
+/// Record a price point for a wishlist entry (POST /wishlist/{id}/prices)
+pub async fn add_wishlist_price(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(payload): Json<AddPriceRequest>,
+) -> Json<ApiResponse<WishlistPrice>> {
+    let currency = payload.currency.trim().to_uppercase();
+    if payload.price_cents < 0 || currency.len() != 3 {
+        return Json(ApiResponse::error(
+            "Price must be non-negative and currency a 3-letter code",
+        ));
+    }
+
+    match db::get_wishlist_item(&state.db, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Json(ApiResponse::error("Wishlist entry not found")),
+        Err(e) => {
+            tracing::error!("Failed to get wishlist entry {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    match db::add_wishlist_price(
+        &state.db,
+        id,
+        payload.price_cents,
+        &currency,
+        payload.store.as_deref(),
+    )
+    .await
+    {
+        Ok(price) => Json(ApiResponse::success(price)),
+        Err(e) => {
+            tracing::error!("Failed to record price for wishlist entry {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Price history of a wishlist entry (GET /wishlist/{id}/prices)
+pub async fn list_wishlist_prices(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<WishlistPrice>>> {
+    match db::get_wishlist_prices(&state.db, id).await {
+        Ok(prices) => Json(ApiResponse::success(prices)),
+        Err(e) => {
+            tracing::error!("Failed to list prices for wishlist entry {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Price history carried over from the wishlist (GET /games/{id}/prices)
+pub async fn list_game_prices(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<WishlistPrice>>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::get_game_prices(&state.db, id).await {
+        Ok(prices) => Json(ApiResponse::success(prices)),
+        Err(e) => {
+            tracing::error!("Failed to list prices for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+// ============================================================================
+// Screenshots & Clips
+// ============================================================================
+
+/// Re-index per-game media folders and the screenshots directory (POST /media/scan)
+pub async fn scan_media(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<media::MediaIndexResult>> {
+    let screenshots_dir = state.config.screenshots_path();
+
+    match media::index_media(&state.db, screenshots_dir.as_deref()).await {
+        Ok(result) => Json(ApiResponse::success(result)),
+        Err(e) => {
+            tracing::error!("Media index failed: {}", e);
+            Json(ApiResponse::error("Media index failed"))
+        }
+    }
+}
+
+/// List screenshots and clips for a game (GET /games/{id}/media)
+pub async fn list_game_media(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<Vec<GameMedia>>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    match db::get_game_media(&state.db, id).await {
+        Ok(items) => Json(ApiResponse::success(items)),
+        Err(e) => {
+            tracing::error!("Failed to list media for game {}: {}", id, e);
+            Json(ApiResponse::error("Internal server error"))
+        }
+    }
+}
+
+/// Look up a media entry and validate its file is inside an indexed location
+async fn resolve_media_file(
+    state: &AppState,
+    media_id: i64,
+) -> Result<(GameMedia, std::path::PathBuf), axum::response::Response> {
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    let item = match db::get_media_by_id(&state.db, media_id).await {
+        Ok(Some(m)) => m,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, "Media not found").into_response()),
+        Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()),
+    };
+    match db::is_game_visible(&state.db, item.game_id).await {
+        Ok(true) => {}
+        Ok(false) => return Err((StatusCode::NOT_FOUND, "Media not found").into_response()),
+        Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()),
+    }
+
+    let file_path = std::path::Path::new(&item.path);
+    if !file_path.is_file() {
+        return Err((StatusCode::NOT_FOUND, "Media file not found").into_response());
+    }
+
+    // SECURITY: Only serve files from the games directory or the screenshots directory
+    let validated = match item.source.as_str() {
+        media::SOURCE_SCREENSHOTS_DIR => state.config.screenshots_path().and_then(|dir| {
+            let dir = std::fs::canonicalize(dir).ok()?;
+            let file = std::fs::canonicalize(file_path).ok()?;
+            file.starts_with(&dir).then_some(file)
+        }),
+        _ => validate_path_within_games(&state.games_path, file_path),
+    };
+
+    match validated {
+        Some(path) => Ok((item, path)),
+        None => Err((StatusCode::FORBIDDEN, "Access denied").into_response()),
+    }
+}
+
+/// Serve a screenshot or clip (GET /media/{id}/file) - supports range requests for video
+pub async fn serve_media_file(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    request: axum::extract::Request,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let (_, path) = match resolve_media_file(&state, id).await {
+        Ok(resolved) => resolved,
+        Err(response) => return response,
+    };
+
+    match tower_http::services::ServeFile::new(path)
+        .try_call(request)
+        .await
+    {
+        Ok(response) => response.into_response(),
+        Err(e) => {
+            tracing::warn!("Failed to serve media {}: {}", id, e);
+            (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read media",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Serve a cached thumbnail for an image (GET /media/{id}/thumbnail)
+pub async fn serve_media_thumbnail(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+
+    let (item, path) = match resolve_media_file(&state, id).await {
+        Ok(resolved) => resolved,
+        Err(response) => return response,
+    };
+
+    if item.kind != "image" {
+        return (StatusCode::NOT_FOUND, "No thumbnail for this media type").into_response();
+    }
+
+    let thumbnail = media::thumbnail_path(&state.config.cache_path(), id);
+    let thumb_clone = thumbnail.clone();
+    let generated =
+        tokio::task::spawn_blocking(move || media::ensure_thumbnail(&path, &thumb_clone)).await;
+
+    match generated {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            tracing::warn!("Failed to create thumbnail for media {}: {}", id, e);
+            return (StatusCode::UNPROCESSABLE_ENTITY, "Unsupported image").into_response();
+        }
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Thumbnail task failed").into_response()
+        }
+    }
+
+    match std::fs::read(&thumbnail) {
+        Ok(bytes) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "image/jpeg")],
+            bytes,
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to read thumbnail",
+        )
+            .into_response(),
+    }
+}
+
+// ============================================================================
+// Deleting Game Files
+// ============================================================================
+
+/// Request body for deleting a game's files
+#[derive(Deserialize, Default)]
+pub struct DeleteFilesRequest {
+    /// Token from the first (unconfirmed) request
+    pub confirm_token: Option<String>,
+}
+
+/// Response for deleting a game's files
+#[derive(serde::Serialize)]
+pub struct DeleteFilesResponse {
+    /// "confirm_required" or "deleted"
+    pub status: &'static str,
+    pub title: String,
+    pub size_bytes: Option<i64>,
+    pub confirm_token: Option<String>,
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Move a game's folder to the recycle bin and remove it from the library
+/// (POST /games/{id}/delete-files)
+///
+/// Call once without a token to get a confirmation token, then again with
+/// `{"confirm_token": "..."}` within five minutes to delete.
+pub async fn delete_game_files(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    payload: Option<Json<DeleteFilesRequest>>,
+) -> Json<ApiResponse<DeleteFilesResponse>> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+
+    let game = match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(g)) => g,
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    // SECURITY: Only folders strictly inside the games directory can be deleted
+    let folder = std::path::Path::new(&game.folder_path);
+    let folder = match validate_path_within_games(&state.games_path, folder) {
+        Some(p) if p.is_dir() => p,
+        _ => return Json(ApiResponse::error("Game folder not found or access denied")),
+    };
+    let is_library_root = std::fs::canonicalize(&state.games_path)
+        .map(|root| root == folder)
+        .unwrap_or(true);
+    if is_library_root {
+        return Json(ApiResponse::error(
+            "Refusing to delete the game library root",
+        ));
+    }
+
+    let Some(token) = payload.confirm_token.filter(|t| !t.is_empty()) else {
+        let token = state.delete_confirmations.issue(id);
+        return Json(ApiResponse::success(DeleteFilesResponse {
+            status: "confirm_required",
+            title: game.title,
+            size_bytes: game.size_bytes,
+            confirm_token: Some(token),
+            expires_in_secs: Some(deletion::CONFIRMATION_TTL.as_secs()),
+        }));
+    };
+
+    if !state.delete_confirmations.consume(id, &token) {
+        return Json(ApiResponse::error("Invalid or expired confirmation token"));
+    }
+
+    tracing::warn!(
+        "Moving game {} ('{}') to the recycle bin: {:?}",
+        id,
+        game.title,
+        folder
+    );
+
+    let trash_path = folder.clone();
+    match tokio::task::spawn_blocking(move || deletion::move_to_trash(&trash_path)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            tracing::error!("Failed to move {:?} to the recycle bin: {}", folder, e);
+            return Json(ApiResponse::error(
+                "Failed to move folder to the recycle bin",
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Delete task failed: {}", e);
+            return Json(ApiResponse::error("Internal server error"));
+        }
+    }
+
+    if let Err(e) = db::delete_game(&state.db, id).await {
+        tracing::error!("Folder trashed but failed to remove game {}: {}", id, e);
+        return Json(ApiResponse::error(
+            "Folder moved to the recycle bin, but the library entry could not be removed",
+        ));
+    }
+    events::publish(events::Event::GameDeleted { id });
+
+    Json(ApiResponse::success(DeleteFilesResponse {
+        status: "deleted",
+        title: game.title,
+        size_bytes: game.size_bytes,
+        confirm_token: None,
+        expires_in_secs: None,
+    }))
+}
+
+// ============================================================================
+// Cold Storage Archival
+// ============================================================================
+
+/// Request body for archiving games
+#[derive(Deserialize)]
+pub struct ArchiveGamesRequest {
+    pub game_ids: Vec<i64>,
+}
+
+/// Per-game outcome of queueing an archive/restore job
+#[derive(serde::Serialize)]
+pub struct ArchiveQueueResult {
+    pub game_id: i64,
+    pub queued: bool,
+    pub error: Option<String>,
+}
+
+/// Compress selected games to the archive target and remove the originals
+/// (POST /archive)
+///
+/// Jobs run in the background one game at a time; progress is reported
+/// through each game's `archive_status`.
+pub async fn archive_games(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ArchiveGamesRequest>,
+) -> Json<ApiResponse<Vec<ArchiveQueueResult>>> {
+    let target = match state.config.archive_target() {
+        Some(t) if t.is_dir() => t,
+        Some(_) => return Json(ApiResponse::error("Archive target does not exist")),
+        None => return Json(ApiResponse::error("No archive target configured")),
+    };
+
+    let mut results = Vec::new();
+    let mut jobs = Vec::new();
+
+    for id in payload.game_ids {
+        let queued = |error: Option<&str>| ArchiveQueueResult {
+            game_id: id,
+            queued: error.is_none(),
+            error: error.map(String::from),
+        };
+
+        let game = match db::get_game_by_id(&state.db, id).await {
+            Ok(Some(g)) => g,
+            Ok(None) => {
+                results.push(queued(Some("Game not found")));
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("Failed to get game {}: {}", id, e);
+                results.push(queued(Some("Database error")));
+                continue;
+            }
+        };
+
+        // SECURITY: Only folders inside the games directory can be archived
+        let folder = match validate_path_within_games(
+            &state.games_path,
+            std::path::Path::new(&game.folder_path),
+        ) {
+            Some(p) if p.is_dir() => p,
+            _ => {
+                results.push(queued(Some("Game folder not found or access denied")));
+                continue;
+            }
+        };
+
+        match db::begin_archive_job(&state.db, id, "archiving", false).await {
+            Ok(true) => {}
+            Ok(false) => {
+                results.push(queued(Some("Game is already archived or busy")));
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("Failed to queue archive for game {}: {}", id, e);
+                results.push(queued(Some("Database error")));
+                continue;
+            }
+        }
+
+        let file_name = archive::archive_file_name(id, &game.folder_name);
+        jobs.push((id, folder, file_name));
+        results.push(queued(None));
+    }
+
+    let pool = state.db.clone();
+    tokio::spawn(async move {
+        for (id, folder, file_name) in jobs {
+            let target = target.clone();
+            tracing::info!("Archiving game {} from {:?}", id, folder);
+
+            let outcome = tokio::task::spawn_blocking(move || {
+                archive::archive_game_folder(&folder, &target, &file_name)
+            })
+            .await
+            .unwrap_or_else(|e| Err(format!("Archive task failed: {}", e)));
+
+            let saved = match outcome {
+                Ok(path) => {
+                    tracing::info!("Archived game {} to {:?}", id, path);
+                    db::mark_game_archived(&pool, id, &path.to_string_lossy()).await
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to archive game {}: {}", id, e);
+                    db::fail_archive_job(&pool, id, &e).await
+                }
+            };
+            if let Err(e) = saved {
+                tracing::error!("Failed to record archive result for game {}: {}", id, e);
+            }
+        }
+    });
+
+    Json(ApiResponse::success(results))
+}
+
+/// Request body for restoring an archived game
+#[derive(Deserialize, Default)]
+pub struct RestoreGameRequest {
+    /// Delete the archive after a successful restore
+    #[serde(default)]
+    pub delete_archive: bool,
+}
+
+/// Extract an archived game back to its original folder (POST /games/{id}/restore)
+pub async fn restore_game(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    payload: Option<Json<RestoreGameRequest>>,
+) -> Json<ApiResponse<ArchiveQueueResult>> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+
+    let game = match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(g)) => g,
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+
+    let archive_path = match game.archive_path.as_deref() {
+        Some(p) if game.archived == Some(1) && std::path::Path::new(p).is_file() => {
+            std::path::PathBuf::from(p)
+        }
+        _ => return Json(ApiResponse::error("Game has no archive to restore")),
+    };
+
+    // SECURITY: Restore only to a location inside the games directory
+    let folder = std::path::PathBuf::from(&game.folder_path);
+    let games_root = match std::fs::canonicalize(&state.games_path) {
+        Ok(root) => root,
+        Err(_) => return Json(ApiResponse::error("Game library not found")),
+    };
+    let parent_ok = folder
+        .parent()
+        .and_then(|p| std::fs::canonicalize(p).ok())
+        .is_some_and(|p| p.starts_with(&games_root));
+    if !parent_ok {
+        return Json(ApiResponse::error("Access denied"));
+    }
+
+    match db::begin_archive_job(&state.db, id, "restoring", true).await {
+        Ok(true) => {}
+        Ok(false) => return Json(ApiResponse::error("Game is busy")),
+        Err(e) => {
+            tracing::error!("Failed to queue restore for game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    let pool = state.db.clone();
+    tokio::spawn(async move {
+        tracing::info!("Restoring game {} to {:?}", id, folder);
+
+        let (archive_clone, folder_clone) = (archive_path.clone(), folder.clone());
+        let outcome = tokio::task::spawn_blocking(move || {
+            archive::restore_game_folder(&archive_clone, &folder_clone)
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Restore task failed: {}", e)));
+
+        if let Err(e) = outcome {
+            tracing::warn!("Failed to restore game {}: {}", id, e);
+            if let Err(e) = db::fail_archive_job(&pool, id, &e).await {
+                tracing::error!("Failed to record restore failure for game {}: {}", id, e);
+            }
+            return;
+        }
+
+        if let Err(e) = db::mark_game_restored(&pool, id).await {
+            tracing::error!("Failed to mark game {} restored: {}", id, e);
+            return;
+        }
+
+        // Cached images come back with the folder
+        let folder_str = folder.to_string_lossy();
+        let cover = local_storage::get_cover_path(&folder_str);
+        let background = local_storage::get_background_path(&folder_str);
+        let cover = cover.is_file().then(|| cover.to_string_lossy().to_string());
+        let background = background
+            .is_file()
+            .then(|| background.to_string_lossy().to_string());
+        if cover.is_some() || background.is_some() {
+            if let Err(e) =
+                db::update_game_local_images(&pool, id, cover.as_deref(), background.as_deref())
+                    .await
+            {
+                tracing::warn!("Failed to restore image paths for game {}: {}", id, e);
+            }
+        }
+
+        if payload.delete_archive {
+            if let Err(e) = std::fs::remove_file(&archive_path) {
+                tracing::warn!("Failed to delete archive {:?}: {}", archive_path, e);
+            }
+        }
+
+        tracing::info!("Restored game {}", id);
+    });
+
+    Json(ApiResponse::success(ArchiveQueueResult {
+        game_id: id,
+        queued: true,
+        error: None,
+    }))
+}
+
+// ============================================================================
+// Raw Rows
+// ============================================================================
+
+/// Every column of a game, including hidden ones (GET /admin/games/{id}/raw)
+pub async fn get_raw_game(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<raw::RawRow>> {
+    match db::get_game_raw(&state.db, id).await {
+        Ok(Some(row)) => Json(ApiResponse::success(row)),
+        Ok(None) => Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to read raw row of game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Set columns of a game directly (PUT /admin/games/{id}/raw)
+///
+/// The body is an object of column names to new values; nothing is written
+/// unless every value passes validation.
+pub async fn update_raw_game(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(edit): Json<raw::RawRow>,
+) -> Json<ApiResponse<raw::RawRow>> {
+    match db::get_game_by_id(&state.db, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to get game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    }
+
+    let columns = match db::get_game_columns(&state.db).await {
+        Ok(columns) => columns,
+        Err(e) => {
+            tracing::error!("Failed to read game columns: {}", e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+    let changes = match raw::validate(&columns, &edit, std::path::Path::new(&state.games_path)) {
+        Ok(changes) => changes,
+        Err(e) => return Json(ApiResponse::error(e)),
+    };
+
+    if let Err(e) = db::update_game_raw(&state.db, id, &changes).await {
+        if e.as_database_error()
+            .is_some_and(|e| e.is_unique_violation())
+        {
+            return Json(ApiResponse::error("Another game already uses that folder"));
+        }
+        tracing::error!("Failed to update raw row of game {}: {}", id, e);
+        return Json(ApiResponse::error("Database error"));
+    }
+    let names: Vec<&str> = changes.iter().map(|(name, _)| name.as_str()).collect();
+    tracing::warn!("Raw edit of game {}: {}", id, names.join(", "));
+
+    if let Ok(Some(game)) = db::get_game_by_id(&state.db, id).await {
+        metadata_sync::save(&state.db, &game).await;
+        events::publish(events::Event::GameUpdated { game: game.into() });
+    }
+
+    match db::get_game_raw(&state.db, id).await {
+        Ok(Some(row)) => Json(ApiResponse::success(row)),
+        Ok(None) => Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to reload raw row of game {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+// ============================================================================
+// Device Pairing
+// ============================================================================
+
+/// Request body for starting device pairing
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct StartPairingRequest {
+    /// Scope granted to the paired device (default "read")
+    pub scope: pairing::Scope,
+}
+
+#[derive(serde::Serialize)]
+pub struct PairingCode {
+    pub code: String,
+    pub scope: &'static str,
+    pub expires_in_secs: u64,
+}
+
+/// Issue a pairing code for a companion app (POST /pairing/start)
+///
+/// The code also appears in the in-app inbox, so it can be read on the host.
+pub async fn start_pairing(
+    State(state): State<Arc<AppState>>,
+    payload: Option<Json<StartPairingRequest>>,
+) -> Json<ApiResponse<PairingCode>> {
+    let scope = payload.map(|Json(p)| p).unwrap_or_default().scope;
+    let code = pairing::start(&state.db, &state.pairing, scope).await;
+
+    Json(ApiResponse::success(PairingCode {
+        code,
+        scope: scope.as_str(),
+        expires_in_secs: pairing::CODE_TTL.as_secs(),
+    }))
+}
+
+/// Request body for a web UI session token
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SessionTokenRequest {
+    /// Scope of the token (default "read")
+    pub scope: pairing::Scope,
+}
+
+#[derive(serde::Serialize)]
+pub struct SessionToken {
+    pub token: String,
+    pub scope: &'static str,
+    pub expires_in_secs: u64,
+}
+
+/// Issue a short-lived token to the web UI (POST /session/token)
+///
+/// Only answers requests made directly from localhost, see session_tokens.rs.
+pub async fn issue_session_token(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    payload: Option<Json<SessionTokenRequest>>,
+) -> Json<ApiResponse<SessionToken>> {
+    if !session_tokens::is_local_request(&peer, &headers) {
+        tracing::warn!("Session token requested from non-local client {}", peer);
+        return Json(ApiResponse::error(
+            "Session tokens are only issued to localhost",
+        ));
+    }
+    let minutes = state.config.auth.session_token_mins;
+    if minutes == 0 {
+        return Json(ApiResponse::error("Session tokens are disabled"));
+    }
+
+    let scope = payload.map(|Json(p)| p).unwrap_or_default().scope;
+    let ttl = std::time::Duration::from_secs(minutes * 60);
+    let token = state.session_tokens.issue(scope, ttl);
+    Json(ApiResponse::success(SessionToken {
+        token,
+        scope: scope.as_str(),
+        expires_in_secs: ttl.as_secs(),
+    }))
+}
+
+/// Revoke the session token sent with the request (POST /session/revoke)
+pub async fn revoke_session_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Json<ApiResponse<()>> {
+    if state.session_tokens.revoke(&headers) {
+        Json(ApiResponse::success(()))
+    } else {
+        Json(ApiResponse::error("Unknown session token"))
+    }
+}
+
+/// Request body for exchanging a pairing code
+#[derive(Deserialize)]
+pub struct ClaimPairingRequest {
+    pub code: String,
+    /// Shown in the device list, e.g. "Living room TV"
+    pub device_name: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct PairedDevice {
+    /// Device token; only returned once
+    pub token: String,
+    pub device: DeviceToken,
+}
+
+/// Exchange a pairing code for a device token (POST /pairing/claim)
+pub async fn claim_pairing(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ClaimPairingRequest>,
+) -> Json<ApiResponse<PairedDevice>> {
+    let name = payload.device_name.trim();
+    if name.is_empty() || name.len() > pairing::MAX_DEVICE_NAME_LENGTH {
+        return Json(ApiResponse::error(format!(
+            "Device name must be 1-{} characters",
+            pairing::MAX_DEVICE_NAME_LENGTH
+        )));
+    }
+
+    let Some(scope) = state.pairing.claim(&payload.code) else {
+        tracing::warn!("Invalid pairing code submitted for device '{}'", name);
+        return Json(ApiResponse::error("Invalid or expired pairing code"));
+    };
+
+    let token = pairing::generate_token();
+    match db::insert_device_token(
+        &state.db,
+        name,
+        scope.as_str(),
+        &pairing::hash_token(&token),
+    )
+    .await
+    {
+        Ok(device) => {
+            tracing::info!("Paired device '{}' ({} access)", name, scope.as_str());
+            Json(ApiResponse::success(PairedDevice { token, device }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to store device token: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// List paired devices (GET /devices)
+pub async fn list_devices(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<DeviceToken>>> {
+    match db::get_device_tokens(&state.db).await {
+        Ok(devices) => Json(ApiResponse::success(devices)),
+        Err(e) => {
+            tracing::error!("Failed to list devices: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Revoke a paired device's token (POST /devices/{id}/delete)
+pub async fn revoke_device(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<()>> {
+    match db::delete_device_token(&state.db, id).await {
+        Ok(true) => {
+            tracing::info!("Revoked device {}", id);
+            Json(ApiResponse::success(()))
+        }
+        Ok(false) => Json(ApiResponse::error("Device not found")),
+        Err(e) => {
+            tracing::error!("Failed to revoke device {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Restricted profiles, the active one flagged (GET /profiles)
+pub async fn list_profiles(State(state): State<Arc<AppState>>) -> Json<ApiResponse<Vec<Profile>>> {
+    match db::get_profiles(&state.db).await {
+        Ok(profiles) => Json(ApiResponse::success(profiles)),
+        Err(e) => {
+            tracing::error!("Failed to list profiles: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Create a restricted profile (POST /profiles)
+pub async fn create_profile(
+    State(state): State<Arc<AppState>>,
+    Json(profile): Json<profiles::NewProfile>,
+) -> Json<ApiResponse<Profile>> {
+    match profiles::create(&state.db, profile).await {
+        Ok(profile) => {
+            tracing::info!("Created profile '{}'", profile.name);
+            Json(ApiResponse::success(profile))
+        }
+        Err(e) => Json(ApiResponse::error(e.message())),
+    }
+}
+
+/// Delete a restricted profile (POST /profiles/{id}/delete)
+pub async fn delete_profile(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Json<ApiResponse<()>> {
+    match db::delete_profile(&state.db, id).await {
+        Ok(true) => {
+            tracing::info!("Deleted profile {}", id);
+            Json(ApiResponse::success(()))
+        }
+        Ok(false) => Json(ApiResponse::error("Profile not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete profile {}: {}", id, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SwitchProfileRequest {
+    /// None = no restrictions
+    pub profile_id: Option<i64>,
+    /// PIN of the active profile, needed to leave it
+    pub pin: Option<String>,
+}
+
+/// Switch the active profile (POST /profiles/switch)
+pub async fn switch_profile(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SwitchProfileRequest>,
+) -> Json<ApiResponse<()>> {
+    match profiles::switch(&state.db, request.profile_id, request.pin.as_deref()).await {
+        Ok(()) => {
+            match request.profile_id {
+                Some(id) => tracing::info!("Switched to profile {}", id),
+                None => tracing::info!("Switched to the unrestricted library"),
+            }
+            Json(ApiResponse::success(()))
+        }
+        Err(e) => {
+            if e == profiles::ProfileError::WrongPin {
+                tracing::warn!("Profile switch refused: wrong PIN");
+            }
+            Json(ApiResponse::error(e.message()))
+        }
+    }
+}
+
+// ============================================================================
+// Custom fields
+// ============================================================================
+
+/// List the declared custom fields (GET /custom-fields)
+pub async fn list_custom_fields(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<CustomField>>> {
+    match db::get_custom_fields(&state.db).await {
+        Ok(fields) => Json(ApiResponse::success(fields)),
+        Err(e) => {
+            tracing::error!("Failed to list custom fields: {}", e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Declare a custom field (POST /custom-fields)
+pub async fn create_custom_field(
+    State(state): State<Arc<AppState>>,
+    Json(field): Json<custom_fields::NewField>,
+) -> Json<ApiResponse<()>> {
+    let field = match field.validate() {
+        Ok(field) => field,
+        Err(message) => return Json(ApiResponse::error(message)),
+    };
+    match db::insert_custom_field(
+        &state.db,
+        &field.key,
+        &field.label,
+        field.field_type.as_str(),
+    )
+    .await
+    {
+        Ok(true) => {
+            tracing::info!("Created custom field '{}'", field.key);
+            Json(ApiResponse::success(()))
+        }
+        Ok(false) => Json(ApiResponse::error("A custom field with this key exists")),
+        Err(e) => {
+            tracing::error!("Failed to create custom field '{}': {}", field.key, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Remove a custom field and its values (POST /custom-fields/{key}/delete)
+pub async fn delete_custom_field(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+) -> Json<ApiResponse<()>> {
+    match db::delete_custom_field(&state.db, &key).await {
+        Ok(true) => {
+            tracing::info!("Deleted custom field '{}'", key);
+            Json(ApiResponse::success(()))
+        }
+        Ok(false) => Json(ApiResponse::error("Custom field not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete custom field '{}': {}", key, e);
+            Json(ApiResponse::error("Database error"))
+        }
+    }
+}
+
+/// Set a game's custom field values (PUT /games/{id}/custom-fields)
+///
+/// The body maps field keys to values; null removes a value, keys left out
+/// keep theirs.
+pub async fn update_game_custom_fields(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    Json(updates): Json<serde_json::Map<String, serde_json::Value>>,
+) -> Json<ApiResponse<Game>> {
+    if let Err(response) = ensure_visible(&state, id).await {
+        return response;
+    }
+    let (game, fields) = match tokio::try_join!(
+        db::get_game_by_id(&state.db, id),
+        db::get_custom_fields(&state.db)
+    ) {
+        Ok((Some(game), fields)) => (game, fields),
+        Ok((None, _)) => return Json(ApiResponse::error("Game not found")),
+        Err(e) => {
+            tracing::error!("Failed to load custom fields of game {}: {}", id, e);
+            return Json(ApiResponse::error("Database error"));
+        }
+    };
+    let values = match custom_fields::merge(game.custom_fields.as_deref(), &fields, updates) {
+        Ok(values) => values,
+        Err(message) => return Json(ApiResponse::error(message)),
+    };
+
+    let game = match db::update_game_custom_fields(&state.db, id, values.as_deref()).await {
+        Ok(game) => game,
+        Err(e) => {
+            tracing::error!("Failed to update custom fields of game {}: {}", id, e);
+            return Json(ApiResponse::error("Failed to update game"));
+        }
+    };
+    metadata_sync::save(&state.db, &game).await;
+
+    tracing::info!("Updated custom fields of game {}", id);
+    events::publish(events::Event::GameUpdated {
+        game: game.clone().into(),
+    });
+    Json(ApiResponse::success(game))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State for calling handlers directly, on an in-memory library
+    async fn test_state(pool: sqlx::SqlitePool) -> Arc<AppState> {
+        let config = AppConfig::default();
+        let http = crate::http::HttpClient::default();
+        let artwork = artwork::ArtworkQueue::start(pool.clone(), std::env::temp_dir());
+        let downloads = downloads::DownloadQueue::start(
+            pool.clone(),
+            http.clone(),
+            config.media.clone(),
+            artwork.clone(),
+        );
+        Arc::new(AppState {
+            search_cache: crate::search_cache::SteamSearchCache::new(pool.clone()),
+            delete_confirmations: deletion::ConfirmationTokens::default(),
+            pairing: pairing::PairingCodes::default(),
+            session_tokens: session_tokens::SessionTokens::default(),
+            prefetch: prefetch::PrefetchQueue::default(),
+            artwork,
+            downloads,
+            http,
+            demo: false,
+            db: pool,
+            games_path: "/games".to_string(),
+            config,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_list_games_unpaged_returns_array() {
+        let (pool, _keepalive) = db::test_pool().await;
+        for title in ["Doom", "Hades", "Celeste"] {
+            db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let state = test_state(pool).await;
+        let list = |page, page_size| {
+            list_games(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(PageQuery { page, page_size }),
+                Query(GameFilter::default()),
+                Query(IncludePathsQuery::default()),
+            )
+        };
+
+        let all = serde_json::to_value(list(None, None).await.0.data.unwrap()).unwrap();
+        let titles: Vec<_> = all
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|g| g["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles, ["Celeste", "Doom", "Hades"]);
+
+        let page = serde_json::to_value(list(None, Some(2)).await.0.data.unwrap()).unwrap();
+        assert_eq!(page["items"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            (page["page"].as_u64(), page["total"].as_i64()),
+            (Some(1), Some(3))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_games_unpaged_is_capped() {
+        let (pool, _keepalive) = db::test_pool().await;
+        for title in ["Doom", "Hades", "Celeste"] {
+            db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let mut state = Arc::into_inner(test_state(pool).await).unwrap();
+        state.config.api.max_unpaged_games = 2;
+
+        let all = list_games(
+            State(Arc::new(state)),
+            HeaderMap::new(),
+            Query(PageQuery::default()),
+            Query(GameFilter::default()),
+            Query(IncludePathsQuery::default()),
+        )
+        .await;
+        let all = serde_json::to_value(all.0.data.unwrap()).unwrap();
+        assert_eq!(all.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_scan_probes_app_id_only_when_folder_changed() {
+        let library = tempfile::tempdir().unwrap();
+        let game_dir = library.path().join("Hades");
+        std::fs::create_dir(&game_dir).unwrap();
+        std::fs::write(game_dir.join("steam_appid.txt"), "1145360").unwrap();
+
+        let (pool, _keepalive) = db::test_pool().await;
+        let mut state = Arc::into_inner(test_state(pool.clone()).await).unwrap();
+        state.games_path = library.path().to_string_lossy().to_string();
+        let folder_app_id = || async {
+            let games = db::get_all_games(&pool).await.unwrap();
+            games[0].folder_app_id
+        };
+
+        run_scan(&state).await;
+        assert_eq!(folder_app_id().await, Some(1145360));
+
+        // Same size: the stored id is kept without walking the folder
+        std::fs::write(game_dir.join("steam_appid.txt"), "1145350").unwrap();
+        run_scan(&state).await;
+        assert_eq!(folder_app_id().await, Some(1145360));
+
+        std::fs::write(game_dir.join("Hades.exe"), "update").unwrap();
+        run_scan(&state).await;
+        assert_eq!(folder_app_id().await, Some(1145350));
+    }
+
+    #[tokio::test]
+    async fn test_restricted_profile_applies_to_every_game_endpoint() {
+        let (pool, _keepalive) = db::test_pool().await;
+        // Two copies each of a game rated 18 and one rated 0
+        let mut ids = Vec::new();
+        for (folder, title) in [
+            ("Doom", "Doom"),
+            ("Doom (2)", "Doom"),
+            ("Minecraft", "Minecraft"),
+            ("Minecraft (2)", "Minecraft"),
+        ] {
+            ids.push(
+                db::upsert_game(
+                    &pool,
+                    &format!("/games/{}", folder),
+                    folder,
+                    title,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        sqlx::query(
+            r#"
+            UPDATE games SET age_rating = 18 WHERE title = 'Doom';
+            UPDATE games SET age_rating = 0 WHERE title = 'Minecraft';
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let doom = ids[0];
+        db::insert_notification(&pool, "game_updated", "Doom", "Changed", Some(doom))
+            .await
+            .unwrap();
+        db::insert_notification(&pool, "scan_complete", "Scan", "Done", None)
+            .await
+            .unwrap();
+
+        let state = test_state(pool.clone()).await;
+        let no_paths = || {
+            Query(IncludePathsQuery {
+                include_paths: false,
+            })
+        };
+        let site_titles = |state: Arc<AppState>| async move {
+            export_site_data(State(state))
+                .await
+                .0
+                .data
+                .unwrap()
+                .games
+                .into_iter()
+                .map(|g| g.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(site_titles(state.clone()).await.len(), 4);
+        assert_eq!(
+            get_duplicates(State(state.clone()), HeaderMap::new(), no_paths())
+                .await
+                .0
+                .data
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let kids = profiles::create(
+            &pool,
+            profiles::NewProfile {
+                name: "Kids".into(),
+                pin: "1234".into(),
+                max_age: Some(12),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        profiles::switch(&pool, Some(kids.id), None).await.unwrap();
+
+        assert_eq!(site_titles(state.clone()).await, ["Minecraft", "Minecraft"]);
+        let groups = get_duplicates(State(state.clone()), HeaderMap::new(), no_paths())
+            .await
+            .0
+            .data
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].title, "Minecraft");
+        let review = get_match_review_queue(State(state.clone()))
+            .await
+            .0
+            .data
+            .unwrap();
+        assert_eq!(review.games.len(), 2);
+        assert!(review.games.iter().all(|g| g.title == "Minecraft"));
+        let inbox = list_notifications(State(state.clone()), Query(NotificationQuery::default()))
+            .await
+            .0
+            .data
+            .unwrap();
+        assert_eq!(inbox.unread_count, 1);
+        assert_eq!(inbox.items[0].kind, "scan_complete");
+
+        // Endpoints taking a game id answer as if it didn't exist
+        let not_found = Some("Game not found".to_string());
+        assert_eq!(
+            list_play_sessions(State(state.clone()), Path(doom))
+                .await
+                .0
+                .error,
+            not_found
+        );
+        assert_eq!(
+            list_game_media(State(state.clone()), Path(doom))
+                .await
+                .0
+                .error,
+            not_found
+        );
+        assert_eq!(
+            list_game_compat(State(state.clone()), Path(doom))
+                .await
+                .0
+                .error,
+            not_found
+        );
+        assert_eq!(
+            get_game_settings(State(state.clone()), Path(doom))
+                .await
+                .0
+                .error,
+            not_found
+        );
+        assert!(
+            list_play_sessions(State(state.clone()), Path(ids[2]))
+                .await
+                .0
+                .success
+        );
+    }
+}
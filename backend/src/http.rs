@@ -0,0 +1,123 @@
+//! Shared HTTP client for external APIs
+//!
+//! All outbound requests go through one [`HttpClient`] built from the
+//! `[network]` config section: it sends the configured User-Agent and adds
+//! per-provider headers (API keys and the like) to requests for that host.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, RequestBuilder,
+};
+
+use crate::config::NetworkConfig;
+
+/// reqwest client with per-host default headers
+#[derive(Clone, Default)]
+pub struct HttpClient {
+    inner: Client,
+    provider_headers: Arc<HashMap<String, HeaderMap>>,
+}
+
+impl HttpClient {
+    /// Build the client from config; invalid headers are logged and skipped
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        let mut builder = Client::builder();
+        if !config.user_agent.trim().is_empty() {
+            builder = builder.user_agent(config.user_agent.trim());
+        }
+
+        let inner = builder.build().unwrap_or_else(|e| {
+            tracing::warn!("Invalid network config ({}), using default HTTP client", e);
+            Client::new()
+        });
+
+        HttpClient {
+            inner,
+            provider_headers: Arc::new(parse_provider_headers(&config.headers)),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.with_provider_headers(url, self.inner.get(url))
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.with_provider_headers(url, self.inner.post(url))
+    }
+
+    fn with_provider_headers(&self, url: &str, request: RequestBuilder) -> RequestBuilder {
+        let headers = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .and_then(|host| self.provider_headers.get(&host));
+
+        match headers {
+            Some(headers) => request.headers(headers.clone()),
+            None => request,
+        }
+    }
+}
+
+/// Convert `{ host = { header = value } }` into header maps keyed by lowercase host
+fn parse_provider_headers(
+    config: &HashMap<String, HashMap<String, String>>,
+) -> HashMap<String, HeaderMap> {
+    let mut providers = HashMap::new();
+
+    for (host, headers) in config {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            match (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                (Ok(name), Ok(mut value)) => {
+                    value.set_sensitive(true);
+                    map.insert(name, value);
+                }
+                _ => tracing::warn!("Ignoring invalid header {:?} for {}", name, host),
+            }
+        }
+        if !map.is_empty() {
+            providers.insert(host.trim().to_lowercase(), map);
+        }
+    }
+
+    providers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_headers_by_host() {
+        let config = NetworkConfig {
+            user_agent: "GameVault-Test/1.0".to_string(),
+            headers: HashMap::from([(
+                "API.Example.com".to_string(),
+                HashMap::from([
+                    ("client-id".to_string(), "abc".to_string()),
+                    ("bad header".to_string(), "x".to_string()),
+                ]),
+            )]),
+        };
+        let client = HttpClient::from_config(&config);
+
+        let request = client
+            .get("https://api.example.com/v4/games")
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["client-id"], "abc");
+        assert_eq!(request.headers().len(), 1);
+
+        let other = client
+            .get("https://store.steampowered.com/")
+            .build()
+            .unwrap();
+        assert!(other.headers().get("client-id").is_none());
+    }
+}
@@ -0,0 +1,164 @@
+//! Translations of server-provided values
+//!
+//! Statuses, genres, review summaries and the other enumerations the API
+//! returns are English keys. GET /api/i18n returns the display labels for one
+//! language, so a localized frontend doesn't have to hardcode its own mapping
+//! of server strings. The language is `[i18n] language` when set, otherwise
+//! the best match for the request's `Accept-Language` header, falling back to
+//! English.
+//!
+//! Tables live in `backend/translations/<language>.json` and are compiled in.
+//! Every table has the same categories and keys as `en.json`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+/// Language used when nothing else matches
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Compiled-in tables (language code, JSON)
+const TABLE_SOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("../translations/en.json")),
+    ("de", include_str!("../translations/de.json")),
+    ("fr", include_str!("../translations/fr.json")),
+];
+
+/// Category ("user_status", "genres", ...) -> key -> label
+pub type Tables = BTreeMap<String, BTreeMap<String, String>>;
+
+static TABLES: OnceLock<HashMap<&'static str, Tables>> = OnceLock::new();
+
+fn tables() -> &'static HashMap<&'static str, Tables> {
+    TABLES.get_or_init(|| {
+        TABLE_SOURCES
+            .iter()
+            .map(|(language, json)| {
+                let tables = serde_json::from_str(json)
+                    .unwrap_or_else(|e| panic!("Invalid translations/{}.json: {}", language, e));
+                (*language, tables)
+            })
+            .collect()
+    })
+}
+
+/// Labels for one language
+#[derive(Debug, Clone, Serialize)]
+pub struct Translations {
+    pub language: &'static str,
+    /// Every language with a table
+    pub available: Vec<&'static str>,
+    pub tables: &'static Tables,
+}
+
+/// Languages with a table
+pub fn available() -> Vec<&'static str> {
+    TABLE_SOURCES
+        .iter()
+        .map(|(language, _)| *language)
+        .collect()
+}
+
+/// Supported language for a tag like "de-AT" or "FR"
+fn supported(tag: &str) -> Option<&'static str> {
+    let primary = tag.split(['-', '_']).next()?.trim().to_lowercase();
+    TABLE_SOURCES
+        .iter()
+        .map(|(language, _)| *language)
+        .find(|language| *language == primary)
+}
+
+/// Pick the language: the configured one, else the best `Accept-Language` match
+pub fn negotiate(configured: &str, accept_language: Option<&str>) -> &'static str {
+    if !configured.trim().is_empty() {
+        match supported(configured) {
+            Some(language) => return language,
+            None => tracing::warn!(
+                "No translations for configured language {:?}, using Accept-Language",
+                configured
+            ),
+        }
+    }
+
+    // "de-DE,de;q=0.9,en;q=0.8": highest quality first, header order breaks ties
+    let mut ranges: Vec<(f32, &str)> = accept_language
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (quality > 0.0).then_some((quality, tag))
+        })
+        .collect();
+    ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    ranges
+        .iter()
+        .find_map(|(_, tag)| supported(tag))
+        .unwrap_or(DEFAULT_LANGUAGE)
+}
+
+/// Labels for a supported language (English for anything else)
+pub fn translations(language: &str) -> Translations {
+    let language = supported(language).unwrap_or(DEFAULT_LANGUAGE);
+    Translations {
+        language,
+        available: available(),
+        tables: &tables()[language],
+    }
+}
+
+/// English key whose label in any language matches `label`, ignoring case
+pub fn find_key(category: &str, label: &str) -> Option<&'static str> {
+    let label = label.trim().to_lowercase();
+    tables()
+        .values()
+        .filter_map(|tables| tables.get(category))
+        .flatten()
+        .find(|(_, l)| l.to_lowercase() == label)
+        .map(|(key, _)| key.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("", None), "en");
+        assert_eq!(negotiate("", Some("de-DE,de;q=0.9,en;q=0.8")), "de");
+        assert_eq!(negotiate("", Some("en;q=0.5, fr-CA")), "fr");
+        assert_eq!(negotiate("", Some("ja, de;q=0.3")), "de");
+        assert_eq!(negotiate("", Some("ja, de;q=0")), "en");
+        assert_eq!(negotiate("", Some("*")), "en");
+        assert_eq!(negotiate("de", Some("fr")), "de");
+        assert_eq!(negotiate("klingon", Some("fr")), "fr");
+    }
+
+    #[test]
+    fn test_tables_match_english() {
+        let english = translations("en").tables;
+        for language in available() {
+            let tables = translations(language).tables;
+            for (category, labels) in english {
+                let keys = |t: &BTreeMap<String, String>| t.keys().cloned().collect::<Vec<_>>();
+                assert_eq!(
+                    tables.get(category).map(keys),
+                    Some(keys(labels)),
+                    "{}.json, {}",
+                    language,
+                    category
+                );
+            }
+            assert_eq!(tables.len(), english.len(), "{}.json", language);
+        }
+        assert_eq!(
+            translations("de").tables["user_status"]["completed"],
+            "Abgeschlossen"
+        );
+    }
+}
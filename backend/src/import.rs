@@ -0,0 +1,442 @@
+//! Shared import pipeline
+//!
+//! Importers (metadata.json today; store and launcher libraries later) only
+//! read their source and turn each record into an [`ImportRow`]: the target
+//! game plus the columns to set. The pipeline then
+//!
+//! 1. compares every row with the game's current values and applies the
+//!    requested [`ConflictPolicy`],
+//! 2. stops there for a dry run, or applies all changes in one transaction
+//!    (rolled back entirely if any update fails),
+//! 3. stores the outcome as an import report that can be fetched later.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use crate::db;
+use crate::models::ImportReportSummary;
+
+/// Column types an importer may write
+#[derive(Debug, Clone, Copy)]
+pub enum FieldKind {
+    Text,
+    Integer,
+}
+
+/// Game columns importers are allowed to set
+pub const IMPORTABLE_FIELDS: &[(&str, FieldKind)] = &[
+    ("steam_app_id", FieldKind::Integer),
+    ("summary", FieldKind::Text),
+    ("genres", FieldKind::Text),
+    ("developers", FieldKind::Text),
+    ("publishers", FieldKind::Text),
+    ("release_date", FieldKind::Text),
+    ("release_date_iso", FieldKind::Text),
+    ("review_score", FieldKind::Integer),
+    ("review_summary", FieldKind::Text),
+    ("review_band", FieldKind::Text),
+    ("hltb_main_mins", FieldKind::Integer),
+    ("hltb_extra_mins", FieldKind::Integer),
+    ("hltb_completionist_mins", FieldKind::Integer),
+    ("user_status", FieldKind::Text),
+    ("playtime_mins", FieldKind::Integer),
+    ("user_rating", FieldKind::Integer),
+    ("finished_at", FieldKind::Text),
+    ("custom_fields", FieldKind::Text),
+];
+
+/// What to do when a row would replace a value the game already has
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Imported values replace existing ones
+    #[default]
+    Overwrite,
+    /// Only fill fields that are empty; existing values are kept
+    FillMissing,
+    /// Leave the whole row alone if any field conflicts
+    Skip,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::FillMissing => "fill_missing",
+            ConflictPolicy::Skip => "skip",
+        }
+    }
+}
+
+/// Query parameters accepted by every import endpoint
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct ImportOptions {
+    /// Compute and store the report without changing any game
+    pub dry_run: bool,
+    pub conflict: ConflictPolicy,
+}
+
+/// Outcome of one row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowAction {
+    Import,
+    Unchanged,
+    Skipped,
+    NotFound,
+    Failed,
+}
+
+/// One record read by an importer
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    /// Identifies the record in the source (shown in the report)
+    pub key: String,
+    /// Library game the record belongs to, if one was found
+    pub game_id: Option<i64>,
+    fields: Vec<(&'static str, Value)>,
+    /// Outcome already decided by the importer (unreadable or stale record)
+    preset: Option<(RowAction, String)>,
+    /// Games the record may belong to, when the importer couldn't decide
+    candidates: Vec<MatchCandidate>,
+}
+
+impl ImportRow {
+    pub fn new(key: impl Into<String>, game_id: Option<i64>) -> Self {
+        ImportRow {
+            key: key.into(),
+            game_id,
+            fields: Vec::new(),
+            preset: None,
+            candidates: Vec::new(),
+        }
+    }
+
+    /// Set a column; `None` leaves it out of the import
+    pub fn set(mut self, field: &'static str, value: Option<impl Into<Value>>) -> Self {
+        debug_assert!(
+            IMPORTABLE_FIELDS.iter().any(|(name, _)| *name == field),
+            "{} is not importable",
+            field
+        );
+        if let Some(value) = value {
+            self.fields.push((field, value.into()));
+        }
+        self
+    }
+
+    /// A record the importer decided not to use
+    pub fn skipped(
+        key: impl Into<String>,
+        game_id: Option<i64>,
+        reason: impl Into<String>,
+    ) -> Self {
+        ImportRow {
+            preset: Some((RowAction::Skipped, reason.into())),
+            ..ImportRow::new(key, game_id)
+        }
+    }
+
+    /// A record with no source data or no matching game
+    pub fn not_found(
+        key: impl Into<String>,
+        game_id: Option<i64>,
+        reason: impl Into<String>,
+    ) -> Self {
+        ImportRow {
+            preset: Some((RowAction::NotFound, reason.into())),
+            ..ImportRow::new(key, game_id)
+        }
+    }
+
+    /// A record matched by title that fits no game well enough, with the
+    /// closest games so it can be resolved by hand
+    pub fn unresolved(
+        key: impl Into<String>,
+        reason: impl Into<String>,
+        candidates: Vec<MatchCandidate>,
+    ) -> Self {
+        ImportRow {
+            candidates,
+            ..ImportRow::not_found(key, None, reason)
+        }
+    }
+
+    /// A record the importer couldn't read
+    pub fn failed(key: impl Into<String>, game_id: Option<i64>, error: impl Into<String>) -> Self {
+        ImportRow {
+            preset: Some((RowAction::Failed, error.into())),
+            ..ImportRow::new(key, game_id)
+        }
+    }
+}
+
+/// A library game a record may belong to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchCandidate {
+    pub game_id: i64,
+    pub title: String,
+    /// Title similarity, 0-1
+    pub score: f64,
+}
+
+/// A single column change in a row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+    /// The game already had a different, non-empty value
+    pub conflict: bool,
+}
+
+/// Result for one row, as stored in the report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowResult {
+    pub key: String,
+    pub game_id: Option<i64>,
+    pub action: RowAction,
+    /// Changes applied (or that would be applied in a dry run)
+    pub changes: Vec<FieldChange>,
+    pub message: Option<String>,
+    /// Closest games for a record that couldn't be matched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<MatchCandidate>,
+}
+
+/// A stored import report with its rows
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    #[serde(flatten)]
+    pub summary: ImportReportSummary,
+    pub rows: Vec<RowResult>,
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        _ => false,
+    }
+}
+
+/// Decide what to do with a row given the game's current values
+pub fn plan_row(
+    row: ImportRow,
+    current: Option<&HashMap<String, Value>>,
+    policy: ConflictPolicy,
+) -> RowResult {
+    let mut result = RowResult {
+        key: row.key,
+        game_id: row.game_id,
+        action: RowAction::Import,
+        changes: Vec::new(),
+        message: None,
+        candidates: row.candidates,
+    };
+
+    if let Some((action, message)) = row.preset {
+        result.action = action;
+        result.message = Some(message);
+        return result;
+    }
+
+    let Some(current) = current else {
+        result.action = RowAction::NotFound;
+        result.message = Some("No matching game in the library".to_string());
+        return result;
+    };
+
+    for (field, new) in row.fields {
+        if new.is_null() {
+            continue;
+        }
+        let old = current.get(field).cloned().unwrap_or(Value::Null);
+        if old == new {
+            continue;
+        }
+        result.changes.push(FieldChange {
+            field: field.to_string(),
+            conflict: !is_empty(&old),
+            old,
+            new,
+        });
+    }
+
+    let conflicts: Vec<&str> = result
+        .changes
+        .iter()
+        .filter(|c| c.conflict)
+        .map(|c| c.field.as_str())
+        .collect();
+
+    match policy {
+        ConflictPolicy::Skip if !conflicts.is_empty() => {
+            result.message = Some(format!("Conflicts with existing {}", conflicts.join(", ")));
+            result.action = RowAction::Skipped;
+            return result;
+        }
+        ConflictPolicy::FillMissing => result.changes.retain(|c| !c.conflict),
+        _ => {}
+    }
+
+    if result.changes.is_empty() {
+        result.action = RowAction::Unchanged;
+    }
+    result
+}
+
+/// Run rows from `source` through the pipeline and store the report
+pub async fn run(
+    pool: &SqlitePool,
+    source: &str,
+    rows: Vec<ImportRow>,
+    options: ImportOptions,
+) -> Result<ImportReport, sqlx::Error> {
+    let mut results = Vec::with_capacity(rows.len());
+    for row in rows {
+        let current = match row.game_id {
+            Some(id) if row.preset.is_none() => db::get_import_values(pool, id).await?,
+            _ => None,
+        };
+        results.push(plan_row(row, current.as_ref(), options.conflict));
+    }
+
+    let mut status = if options.dry_run {
+        "dry_run"
+    } else {
+        "applied"
+    };
+    let mut error = None;
+
+    if !options.dry_run {
+        let updates: Vec<(i64, Vec<(&str, &Value)>)> = results
+            .iter()
+            .filter(|r| r.action == RowAction::Import)
+            .filter_map(|r| {
+                let changes = r.changes.iter().map(|c| (c.field.as_str(), &c.new));
+                r.game_id.map(|id| (id, changes.collect()))
+            })
+            .collect();
+
+        if let Err(e) = db::apply_import(pool, source, &updates).await {
+            tracing::error!("Import from {} rolled back: {}", source, e);
+            status = "rolled_back";
+            error = Some(e.to_string());
+            for row in results.iter_mut().filter(|r| r.action == RowAction::Import) {
+                row.action = RowAction::Failed;
+                row.message = Some("Rolled back".to_string());
+            }
+        }
+    }
+
+    store_report(pool, source, options, status, error, results).await
+}
+
+/// Store the outcome of an import as a report
+///
+/// For importers that plan and apply their rows themselves because they
+/// don't write game columns (e.g. mapping packs).
+pub async fn store_report(
+    pool: &SqlitePool,
+    source: &str,
+    options: ImportOptions,
+    status: &str,
+    error: Option<String>,
+    results: Vec<RowResult>,
+) -> Result<ImportReport, sqlx::Error> {
+    let count = |action: RowAction| results.iter().filter(|r| r.action == action).count() as i64;
+    let summary = ImportReportSummary {
+        id: 0,
+        source: source.to_string(),
+        dry_run: options.dry_run,
+        conflict_policy: options.conflict.as_str().to_string(),
+        status: status.to_string(),
+        imported: count(RowAction::Import),
+        unchanged: count(RowAction::Unchanged),
+        skipped: count(RowAction::Skipped),
+        not_found: count(RowAction::NotFound),
+        failed: count(RowAction::Failed),
+        total: results.len() as i64,
+        error,
+        created_at: String::new(),
+    };
+
+    let rows_json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
+    let summary = db::insert_import_report(pool, &summary, &rows_json).await?;
+
+    Ok(ImportReport {
+        summary,
+        rows: results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn current() -> HashMap<String, Value> {
+        HashMap::from([
+            ("summary".to_string(), json!("Existing summary")),
+            ("release_date".to_string(), Value::Null),
+            ("review_score".to_string(), json!(80)),
+        ])
+    }
+
+    fn row() -> ImportRow {
+        ImportRow::new("Hades", Some(1))
+            .set("summary", Some("New summary"))
+            .set("release_date", Some("Sep 17, 2020"))
+            .set("review_score", Some(80))
+    }
+
+    fn fields(result: &RowResult) -> Vec<&str> {
+        result.changes.iter().map(|c| c.field.as_str()).collect()
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let current = current();
+
+        let overwrite = plan_row(row(), Some(&current), ConflictPolicy::Overwrite);
+        assert_eq!(overwrite.action, RowAction::Import);
+        assert_eq!(fields(&overwrite), ["summary", "release_date"]);
+        assert!(overwrite.changes[0].conflict);
+
+        let fill = plan_row(row(), Some(&current), ConflictPolicy::FillMissing);
+        assert_eq!(fill.action, RowAction::Import);
+        assert_eq!(fields(&fill), ["release_date"]);
+
+        let skip = plan_row(row(), Some(&current), ConflictPolicy::Skip);
+        assert_eq!(skip.action, RowAction::Skipped);
+        assert_eq!(
+            skip.message.as_deref(),
+            Some("Conflicts with existing summary")
+        );
+    }
+
+    #[test]
+    fn test_plan_row_outcomes() {
+        let current = current();
+
+        let same = ImportRow::new("Hades", Some(1)).set("review_score", Some(80));
+        assert_eq!(
+            plan_row(same, Some(&current), ConflictPolicy::Overwrite).action,
+            RowAction::Unchanged
+        );
+        assert_eq!(
+            plan_row(row(), None, ConflictPolicy::Overwrite).action,
+            RowAction::NotFound
+        );
+
+        let failed = ImportRow::failed("Broken", Some(2), "invalid JSON");
+        let result = plan_row(failed, Some(&current), ConflictPolicy::Overwrite);
+        assert_eq!(result.action, RowAction::Failed);
+        assert_eq!(result.message.as_deref(), Some("invalid JSON"));
+    }
+}
@@ -0,0 +1,182 @@
+//! Game launching
+//!
+//! Resolves the executable for a game folder (user override or heuristic)
+//! and spawns it, closing the play session when the process exits. Failed
+//! starts are written up by `diagnostics`.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use sqlx::SqlitePool;
+use walkdir::WalkDir;
+
+use crate::diagnostics::{self, LaunchFailure};
+use crate::{db, models::Game, sessions};
+
+/// Executable names that are almost never the game itself
+const NON_GAME_EXE_PATTERNS: &[&str] = &[
+    "unins",
+    "setup",
+    "install",
+    "redist",
+    "vcredist",
+    "vc_redist",
+    "dxsetup",
+    "dotnet",
+    "crashhandler",
+    "crashreport",
+    "crashpad",
+    "ue4prereq",
+    "easyanticheat",
+    "battleye",
+    "launcherpatcher",
+    "updater",
+    "quicksfv",
+];
+
+/// Check whether an executable file name looks like a helper rather than the game
+fn is_helper_exe(file_name: &str) -> bool {
+    let lower = file_name.to_lowercase();
+    NON_GAME_EXE_PATTERNS.iter().any(|p| lower.contains(p))
+}
+
+/// Pick the most likely game executable from candidate (relative path, size) pairs
+///
+/// Prefers shallow executables, then larger ones.
+pub fn pick_game_exe(candidates: &[(PathBuf, u64)]) -> Option<PathBuf> {
+    candidates
+        .iter()
+        .filter(|(path, _)| {
+            path.file_name()
+                .map(|n| !is_helper_exe(&n.to_string_lossy()))
+                .unwrap_or(false)
+        })
+        .min_by_key(|(path, size)| (path.components().count(), std::cmp::Reverse(*size)))
+        .map(|(path, _)| path.clone())
+}
+
+/// Resolve the executable to launch for a game folder
+///
+/// `launch_exe` is the user override, relative to the game folder.
+pub fn resolve_game_exe(game_folder: &str, launch_exe: Option<&str>) -> Option<PathBuf> {
+    let folder = Path::new(game_folder);
+
+    if let Some(exe) = launch_exe.filter(|e| !e.trim().is_empty()) {
+        let path = folder.join(exe);
+        return path.is_file().then_some(path);
+    }
+
+    // Only look a few levels deep - repacks usually keep the exe near the top
+    let candidates: Vec<(PathBuf, u64)> = WalkDir::new(folder)
+        .max_depth(3)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_type().is_file()
+                && e.path()
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"))
+        })
+        .filter(|e| !e.path().components().any(|c| c.as_os_str() == ".gamevault"))
+        .filter_map(|e| {
+            let size = e.metadata().ok()?.len();
+            let rel = e.path().strip_prefix(folder).ok()?.to_path_buf();
+            Some((rel, size))
+        })
+        .collect();
+
+    pick_game_exe(&candidates).map(|rel| folder.join(rel))
+}
+
+/// Spawn a game executable and track its session until the process exits
+///
+/// Returns the play session ID. A failed start (spawn error, or an error exit
+/// within `diagnostics::EARLY_EXIT_WINDOW`) saves launch diagnostics.
+pub async fn launch_game(pool: &SqlitePool, game: &Game, exe: &Path) -> anyhow::Result<i64> {
+    let game_id = game.id;
+    let working_dir = exe
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Executable has no parent directory"))?;
+
+    let spawned = tokio::process::Command::new(exe)
+        .current_dir(working_dir)
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            let failure = LaunchFailure {
+                reason: format!("could not be started: {}", e),
+                exit_code: None,
+                ran_for: None,
+            };
+            diagnostics::record_failure(pool, game.clone(), exe.to_path_buf(), failure).await;
+            return Err(e.into());
+        }
+    };
+    let started = Instant::now();
+
+    tracing::info!("Launched game {}: {:?}", game_id, exe);
+
+    let session_id = db::start_play_session(pool, game_id, sessions::machine_name()).await?;
+
+    let pool = pool.clone();
+    let (game, exe) = (game.clone(), exe.to_path_buf());
+    tokio::spawn(async move {
+        match child.wait().await {
+            Ok(status) => {
+                tracing::info!("Game {} exited with {}", game_id, status);
+                let ran_for = started.elapsed();
+                if !status.success() && ran_for < diagnostics::EARLY_EXIT_WINDOW {
+                    let exit = match status.code() {
+                        Some(code) => format!("code {}", code),
+                        None => status.to_string(),
+                    };
+                    let failure = LaunchFailure {
+                        reason: format!("exited with {} after {} seconds", exit, ran_for.as_secs()),
+                        exit_code: status.code(),
+                        ran_for: Some(ran_for),
+                    };
+                    diagnostics::record_failure(&pool, game, exe, failure).await;
+                }
+            }
+            Err(e) => tracing::warn!("Failed to wait for game {}: {}", game_id, e),
+        }
+
+        if let Err(e) = db::end_play_session(&pool, session_id).await {
+            tracing::warn!("Failed to close play session {}: {}", session_id, e);
+        }
+    });
+
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_game_exe() {
+        let candidates = vec![
+            (PathBuf::from("unins000.exe"), 2_000_000),
+            (PathBuf::from("Game.exe"), 50_000_000),
+            (PathBuf::from("Launcher.exe"), 5_000_000),
+            (PathBuf::from("_CommonRedist/vcredist_x64.exe"), 90_000_000),
+            (
+                PathBuf::from("bin/x64/Game-Win64-Shipping.exe"),
+                200_000_000,
+            ),
+        ];
+
+        assert_eq!(pick_game_exe(&candidates), Some(PathBuf::from("Game.exe")));
+    }
+
+    #[test]
+    fn test_pick_game_exe_only_helpers() {
+        let candidates = vec![
+            (PathBuf::from("setup.exe"), 1_000),
+            (PathBuf::from("unins000.exe"), 1_000),
+        ];
+
+        assert_eq!(pick_game_exe(&candidates), None);
+    }
+}
@@ -0,0 +1,353 @@
+//! Edition, DLC and franchise linking
+//!
+//! After enrichment, the library is scanned for games that belong together:
+//! DLC whose Steam store page names a base game in the library, editions
+//! ("Skyrim Special Edition", "Hades - Game of the Year") of a base game,
+//! add-ons titled "<base game> - <add-on>", and series that share a name
+//! ("Dark Souls", "Dark Souls III"). Nothing is linked automatically; each
+//! finding is stored as a proposal that must be confirmed, and a rejected
+//! proposal is never made again.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use sqlx::SqlitePool;
+
+use crate::{db, duplicates::normalize_title, models::Game};
+
+pub const KIND_PARENT: &str = "parent";
+pub const KIND_FRANCHISE: &str = "franchise";
+
+pub const SOURCE_STEAM: &str = "steam";
+pub const SOURCE_NAME: &str = "name";
+
+/// Words that make a title an edition of another game
+const EDITION_SUFFIX: &str = r"(?i)^(.+?)\s*[:\-–—]?\s+(?:the\s+)?((?:game of the year|goty|definitive|digital deluxe|deluxe|ultimate|complete|gold|premium|enhanced|anniversary|collector'?s|special|legendary|platinum|remastered|director'?s cut)(?:\s+edition)?)$";
+
+/// Trailing sequel number ("2", "III")
+const SEQUEL_NUMBER: &str = r"(?i)\s+(?:\d+|[ivx]+)$";
+
+/// A link found by the heuristics
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProposedLink {
+    pub game_id: i64,
+    pub kind: &'static str,
+    pub parent_game_id: Option<i64>,
+    pub franchise: Option<String>,
+    pub source: &'static str,
+    pub reason: String,
+}
+
+/// Title without an edition suffix, and the suffix
+fn split_edition<'a>(title: &'a str, edition: &Regex) -> Option<(&'a str, &'a str)> {
+    let caps = edition.captures(title.trim())?;
+    Some((caps.get(1)?.as_str(), caps.get(2)?.as_str()))
+}
+
+/// Series name: the title before any subtitle, without a sequel number
+fn series_name(title: &str, edition: &Regex, sequel: &Regex) -> String {
+    let base = split_edition(title, edition).map_or(title, |(base, _)| base);
+    let head = base
+        .split(':')
+        .next()
+        .unwrap_or(base)
+        .split(" - ")
+        .next()
+        .unwrap_or(base)
+        .trim();
+    sequel.replace(head, "").trim().to_string()
+}
+
+/// Find the base game of an edition or add-on
+fn find_parent<'a>(
+    game: &Game,
+    by_app_id: &HashMap<i64, &'a Game>,
+    by_title: &HashMap<String, &'a Game>,
+    edition: &Regex,
+) -> Option<(&'a Game, &'static str, String)> {
+    if let Some(parent) = game
+        .steam_fullgame_app_id
+        .filter(|app_id| Some(*app_id) != game.steam_app_id)
+        .and_then(|app_id| by_app_id.get(&app_id))
+        .filter(|parent| parent.id != game.id)
+    {
+        let reason = format!("Steam lists it as DLC for {}", parent.title);
+        return Some((parent, SOURCE_STEAM, reason));
+    }
+
+    // Copies of the same app are duplicates, not editions
+    let distinct_app = |parent: &Game| {
+        parent.id != game.id
+            && (game.steam_app_id.is_none() || parent.steam_app_id != game.steam_app_id)
+    };
+
+    if let Some((base, suffix)) = split_edition(&game.title, edition) {
+        if let Some(parent) = by_title
+            .get(&normalize_title(base))
+            .filter(|p| distinct_app(p))
+        {
+            let reason = format!("{} of {}", suffix, parent.title);
+            return Some((parent, SOURCE_NAME, reason));
+        }
+    }
+
+    // "<base game> - <add-on>": the longest matching base wins
+    let mut prefix = game.title.as_str();
+    while let Some(pos) = prefix.rfind(" - ").or_else(|| prefix.rfind(" – ")) {
+        prefix = &prefix[..pos];
+        if let Some(parent) = by_title
+            .get(&normalize_title(prefix))
+            .filter(|p| distinct_app(p))
+        {
+            let reason = format!("Title starts with {}", parent.title);
+            return Some((parent, SOURCE_NAME, reason));
+        }
+    }
+
+    None
+}
+
+/// Propose links for games that aren't linked yet
+pub fn propose_links(games: &[Game]) -> Vec<ProposedLink> {
+    let edition = Regex::new(EDITION_SUFFIX).unwrap();
+    let sequel = Regex::new(SEQUEL_NUMBER).unwrap();
+
+    let mut sorted: Vec<&Game> = games.iter().collect();
+    sorted.sort_by_key(|g| g.id);
+
+    // Lowest id wins when several games share an app id or title
+    let mut by_app_id: HashMap<i64, &Game> = HashMap::new();
+    let mut by_title: HashMap<String, &Game> = HashMap::new();
+    for game in &sorted {
+        if let Some(app_id) = game.steam_app_id {
+            by_app_id.entry(app_id).or_insert(game);
+        }
+        by_title.entry(normalize_title(&game.title)).or_insert(game);
+    }
+
+    let mut proposals = Vec::new();
+    let mut has_parent: Vec<i64> = Vec::new();
+    for game in &sorted {
+        if game.parent_game_id.is_some() {
+            has_parent.push(game.id);
+            continue;
+        }
+        if let Some((parent, source, reason)) = find_parent(game, &by_app_id, &by_title, &edition) {
+            has_parent.push(game.id);
+            proposals.push(ProposedLink {
+                game_id: game.id,
+                kind: KIND_PARENT,
+                parent_game_id: Some(parent.id),
+                franchise: None,
+                source,
+                reason,
+            });
+        }
+    }
+
+    // Franchises: base games sharing a series name under different titles
+    let mut series: HashMap<String, Vec<(&Game, String)>> = HashMap::new();
+    for game in sorted.iter().filter(|g| !has_parent.contains(&g.id)) {
+        let name = series_name(&game.title, &edition, &sequel);
+        let key = normalize_title(&name);
+        if key.len() >= 3 {
+            series.entry(key).or_default().push((game, name));
+        }
+    }
+
+    let mut franchises: Vec<Vec<(&Game, String)>> = series.into_values().collect();
+    franchises.sort_by_key(|members| members[0].0.id);
+    for members in franchises {
+        // Editions of one game don't make a franchise either
+        let mut titles: Vec<String> = members
+            .iter()
+            .map(|(g, _)| {
+                normalize_title(
+                    split_edition(&g.title, &edition).map_or(&g.title, |(base, _)| base),
+                )
+            })
+            .collect();
+        titles.sort();
+        titles.dedup();
+        if titles.len() < 2 {
+            continue;
+        }
+
+        // Keep a franchise name that was already confirmed for a member
+        let name = members
+            .iter()
+            .find_map(|(g, _)| g.franchise.clone())
+            .unwrap_or_else(|| {
+                members
+                    .iter()
+                    .map(|(_, name)| name.clone())
+                    .min_by_key(|name| name.len())
+                    .unwrap_or_default()
+            });
+        let others = |id: i64| {
+            members
+                .iter()
+                .filter(|(g, _)| g.id != id)
+                .map(|(g, _)| g.title.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        for (game, _) in members.iter().filter(|(g, _)| g.franchise.is_none()) {
+            proposals.push(ProposedLink {
+                game_id: game.id,
+                kind: KIND_FRANCHISE,
+                parent_game_id: None,
+                franchise: Some(name.clone()),
+                source: SOURCE_NAME,
+                reason: format!("Shares the series name with {}", others(game.id)),
+            });
+        }
+    }
+
+    proposals
+}
+
+/// Store new proposals for the current library
+///
+/// Returns the number of proposals added; ones already pending, confirmed or
+/// rejected are skipped.
+pub async fn detect(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let games = db::get_all_games(pool).await?;
+    let mut added = 0;
+    for link in propose_links(&games) {
+        if db::insert_link_proposal(pool, &link).await? {
+            added += 1;
+        }
+    }
+    if added > 0 {
+        tracing::info!("Proposed {} new game link(s)", added);
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn game(id: i64, title: &str, app_id: Option<i64>, fullgame: Option<i64>) -> Game {
+        Game {
+            folder_path: format!("/games/{}", id),
+            steam_app_id: app_id,
+            steam_fullgame_app_id: fullgame,
+            ..create_test_game(id, title)
+        }
+    }
+
+    fn parent_of(proposals: &[ProposedLink], id: i64) -> Option<(i64, &'static str)> {
+        proposals
+            .iter()
+            .find(|p| p.game_id == id && p.kind == KIND_PARENT)
+            .map(|p| (p.parent_game_id.unwrap(), p.source))
+    }
+
+    fn franchise_of(proposals: &[ProposedLink], id: i64) -> Option<&str> {
+        proposals
+            .iter()
+            .find(|p| p.game_id == id && p.kind == KIND_FRANCHISE)
+            .and_then(|p| p.franchise.as_deref())
+    }
+
+    #[test]
+    fn test_propose_parents() {
+        let games = [
+            game(1, "The Elder Scrolls V: Skyrim", Some(72850), None),
+            game(
+                2,
+                "The Elder Scrolls V: Skyrim Special Edition",
+                Some(489830),
+                None,
+            ),
+            game(3, "Skyrim Dawnguard", Some(211720), Some(72850)),
+            game(4, "Hades", Some(1145360), None),
+            game(5, "Hades - Original Soundtrack", None, None),
+            game(6, "Hades", None, None),
+            game(7, "Hades - Game of the Year Edition", Some(1145360), None),
+        ];
+
+        let proposals = propose_links(&games);
+        assert_eq!(parent_of(&proposals, 2), Some((1, SOURCE_NAME)));
+        assert_eq!(parent_of(&proposals, 3), Some((1, SOURCE_STEAM)));
+        assert_eq!(parent_of(&proposals, 5), Some((4, SOURCE_NAME)));
+        // A second copy of the same game isn't an edition of it
+        assert_eq!(parent_of(&proposals, 6), None);
+        assert_eq!(parent_of(&proposals, 7), None);
+        assert_eq!(parent_of(&proposals, 1), None);
+    }
+
+    #[test]
+    fn test_propose_franchises() {
+        let mut games = vec![
+            game(1, "Dark Souls: Remastered", None, None),
+            game(2, "Dark Souls II", None, None),
+            game(3, "DARK SOULS III", None, None),
+            game(4, "Portal", None, None),
+            game(5, "Celeste", None, None),
+            game(6, "Celeste", None, None),
+        ];
+        games[1].franchise = Some("Souls".to_string());
+
+        let proposals = propose_links(&games);
+        assert_eq!(franchise_of(&proposals, 1), Some("Souls"));
+        assert_eq!(franchise_of(&proposals, 2), None, "already linked");
+        assert_eq!(franchise_of(&proposals, 3), Some("Souls"));
+        assert_eq!(franchise_of(&proposals, 4), None);
+        // Duplicate copies don't make a franchise
+        assert_eq!(franchise_of(&proposals, 5), None);
+
+        let games = [
+            game(1, "Hades", None, None),
+            game(2, "Hades II", None, None),
+        ];
+        assert_eq!(franchise_of(&propose_links(&games), 2), Some("Hades"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_and_confirm() {
+        let (pool, _keepalive) = db::test_pool().await;
+
+        let base = db::upsert_game(&pool, "/games/Hades", "Hades", "Hades", None, None)
+            .await
+            .unwrap();
+        let edition = db::upsert_game(
+            &pool,
+            "/games/Hades GOTY",
+            "Hades GOTY",
+            "Hades GOTY",
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(detect(&pool).await.unwrap(), 1);
+        assert_eq!(detect(&pool).await.unwrap(), 0, "proposals aren't repeated");
+
+        let proposal = &db::get_link_proposals(&pool, "pending").await.unwrap()[0];
+        assert_eq!(proposal.game_id, edition);
+        assert_eq!(proposal.parent_title.as_deref(), Some("Hades"));
+
+        assert!(db::resolve_link_proposal(&pool, proposal.id, true)
+            .await
+            .unwrap());
+        let game = db::get_game_by_id(&pool, edition).await.unwrap().unwrap();
+        assert_eq!(game.parent_game_id, Some(base));
+        assert!(!db::resolve_link_proposal(&pool, proposal.id, false)
+            .await
+            .unwrap());
+
+        db::delete_game(&pool, base).await.unwrap();
+        let game = db::get_game_by_id(&pool, edition).await.unwrap().unwrap();
+        assert_eq!(game.parent_game_id, None);
+        assert!(db::get_link_proposals(&pool, "confirmed")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -2,14 +2,21 @@ use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use chrono::Utc;
-use reqwest::Client;
+use crate::http::HttpClient;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
 
+use crate::config::MediaConfig;
+use crate::downloads::Bandwidth;
+use crate::encryption;
 use crate::models::Game;
+use crate::timestamps;
 
 /// Directory name for GameVault data within each game folder
 const GAMEVAULT_DIR: &str = ".gamevault";
 const SAVES_DIR: &str = "saves";
+const MEDIA_DIR: &str = "media";
+const DIAGNOSTICS_DIR: &str = "diagnostics";
 
 /// Check if a game folder is writable
 pub fn is_folder_writable(game_folder: &str) -> bool {
@@ -33,10 +40,7 @@ pub fn is_folder_writable(game_folder: &str) -> bool {
         }
     } else {
         // Try to create the directory
-        match fs::create_dir(&gamevault_path) {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        fs::create_dir(&gamevault_path).is_ok()
     }
 }
 
@@ -47,13 +51,6 @@ pub fn ensure_gamevault_dir(game_folder: &str) -> Result<PathBuf, std::io::Error
     Ok(gamevault_path)
 }
 
-/// Ensure saves directory exists within .gamevault
-pub fn ensure_saves_dir(game_folder: &str) -> Result<PathBuf, std::io::Error> {
-    let saves_path = Path::new(game_folder).join(GAMEVAULT_DIR).join(SAVES_DIR);
-    fs::create_dir_all(&saves_path)?;
-    Ok(saves_path)
-}
-
 /// Get the path where cover image should be stored
 pub fn get_cover_path(game_folder: &str) -> PathBuf {
     Path::new(game_folder).join(GAMEVAULT_DIR).join("cover.jpg")
@@ -66,9 +63,48 @@ pub fn get_background_path(game_folder: &str) -> PathBuf {
         .join("background.jpg")
 }
 
+/// Get the per-game launch diagnostics directory
+pub fn get_diagnostics_dir(game_folder: &str) -> PathBuf {
+    Path::new(game_folder)
+        .join(GAMEVAULT_DIR)
+        .join(DIAGNOSTICS_DIR)
+}
+
+/// Get the per-game screenshots/clips directory
+pub fn get_media_dir(game_folder: &str) -> PathBuf {
+    Path::new(game_folder).join(GAMEVAULT_DIR).join(MEDIA_DIR)
+}
+
+/// Re-encode an image as JPEG, shrinking it to fit `max_dimension` (0 = any size)
+///
+/// Returns `None` when the result wouldn't be smaller than the original, so
+/// already lean images are kept as they are.
+pub fn optimize_image(
+    bytes: &[u8],
+    quality: u8,
+    max_dimension: u32,
+) -> Result<Option<Vec<u8>>, image::ImageError> {
+    let mut img = image::load_from_memory(bytes)?;
+    if max_dimension > 0 && img.width().max(img.height()) > max_dimension {
+        img = img.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    }
+
+    let mut out = Vec::new();
+    let encoder = JpegEncoder::new_with_quality(&mut out, quality.clamp(1, 100));
+    img.to_rgb8().write_with_encoder(encoder)?;
+
+    Ok((out.len() < bytes.len()).then_some(out))
+}
+
 /// Download and save an image to local storage
+///
+/// The body is read under the shared download limit. With
+/// `media.optimize_images` set the image is re-encoded before it's written;
+/// if that fails the original is saved instead.
 pub async fn download_and_save_image(
-    client: &Client,
+    client: &HttpClient,
+    media: &MediaConfig,
+    bandwidth: &Bandwidth,
     url: &str,
     dest_path: &Path,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -85,7 +121,7 @@ pub async fn download_and_save_image(
 
     tracing::info!("Downloading image: {} -> {:?}", url, dest_path);
 
-    let response = client
+    let mut response = client
         .get(url)
         .timeout(std::time::Duration::from_secs(30))
         .send()
@@ -95,7 +131,38 @@ pub async fn download_and_save_image(
         return Err(format!("HTTP error: {}", response.status()).into());
     }
 
-    let bytes = response.bytes().await?;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        bandwidth.consume(chunk.len()).await;
+    }
+
+    if media.optimize_images {
+        let original = std::mem::take(&mut bytes);
+        let (quality, max_dimension) = (media.image_quality, media.image_max_dimension);
+        let (original, optimized) = tokio::task::spawn_blocking(move || {
+            let optimized = optimize_image(&original, quality, max_dimension);
+            (original, optimized)
+        })
+        .await?;
+
+        bytes = match optimized {
+            Ok(Some(optimized)) => {
+                tracing::debug!(
+                    "Optimized image {:?}: {} -> {} bytes",
+                    dest_path,
+                    original.len(),
+                    optimized.len()
+                );
+                optimized
+            }
+            Ok(None) => original,
+            Err(e) => {
+                tracing::warn!("Failed to optimize image {}, keeping original: {}", url, e);
+                original
+            }
+        };
+    }
 
     // Write to file
     let mut file = fs::File::create(dest_path)?;
@@ -107,7 +174,9 @@ pub async fn download_and_save_image(
 
 /// Cache cover and background images for a game
 pub async fn cache_game_images(
-    client: &Client,
+    client: &HttpClient,
+    media: &MediaConfig,
+    bandwidth: &Bandwidth,
     game_folder: &str,
     cover_url: Option<&str>,
     background_url: Option<&str>,
@@ -127,7 +196,7 @@ pub async fn cache_game_images(
     // Download cover image
     if let Some(url) = cover_url {
         let cover_path = get_cover_path(game_folder);
-        match download_and_save_image(client, url, &cover_path).await {
+        match download_and_save_image(client, media, bandwidth, url, &cover_path).await {
             Ok(_) => {
                 local_cover = Some(cover_path.to_string_lossy().to_string());
             }
@@ -140,7 +209,7 @@ pub async fn cache_game_images(
     // Download background image
     if let Some(url) = background_url {
         let bg_path = get_background_path(game_folder);
-        match download_and_save_image(client, url, &bg_path).await {
+        match download_and_save_image(client, media, bandwidth, url, &bg_path).await {
             Ok(_) => {
                 local_background = Some(bg_path.to_string_lossy().to_string());
             }
@@ -161,13 +230,15 @@ pub fn list_backups(game_folder: &str) -> Vec<BackupInfo> {
     if let Ok(entries) = fs::read_dir(&saves_path) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.extension().map_or(false, |e| e == "zip") {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if name.ends_with(".zip") || name.ends_with(".zip.enc") {
                 if let Ok(metadata) = entry.metadata() {
                     backups.push(BackupInfo {
-                        filename: path
-                            .file_name()
-                            .map(|n| n.to_string_lossy().to_string())
-                            .unwrap_or_default(),
+                        encrypted: encryption::is_encrypted_path(&path),
+                        filename: name,
                         path: path.to_string_lossy().to_string(),
                         size_bytes: metadata.len() as i64,
                         created_at: metadata
@@ -183,16 +254,36 @@ pub fn list_backups(game_folder: &str) -> Vec<BackupInfo> {
     }
 
     // Sort by created_at descending (newest first)
-    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
     backups
 }
 
+/// Resolve a backup by file name within a game's saves directory
+///
+/// SECURITY: Only plain `.zip` / `.zip.enc` file names that are listed in the
+/// saves directory are accepted, so the name can't be used to reach other files.
+pub fn find_backup(game_folder: &str, filename: &str) -> Option<PathBuf> {
+    if filename.contains(['/', '\\'])
+        || filename.contains("..")
+        || !(filename.ends_with(".zip") || filename.ends_with(".zip.enc"))
+    {
+        return None;
+    }
+
+    list_backups(game_folder)
+        .into_iter()
+        .find(|b| b.filename == filename)
+        .map(|b| PathBuf::from(b.path))
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct BackupInfo {
     pub filename: String,
     pub path: String,
     pub size_bytes: i64,
     pub created_at: i64,
+    /// Encrypted with the configured backup key (`.zip.enc`)
+    pub encrypted: bool,
 }
 
 /// Metadata structure for JSON export
@@ -210,6 +301,9 @@ pub struct ExportedMetadata {
     pub review_score: Option<i64>,
     pub review_summary: Option<String>,
     pub hltb: Option<HltbData>,
+    /// Values of user-defined fields by key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
     pub exported_at: String,
     pub manually_edited: bool,
 }
@@ -224,7 +318,6 @@ pub struct HltbData {
 /// Imported metadata structure (mirrors ExportedMetadata but with Deserialize)
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct ImportedMetadata {
-    pub title: String,
     pub steam_app_id: Option<i64>,
     pub summary: Option<String>,
     pub genres: Option<Vec<String>>,
@@ -234,10 +327,13 @@ pub struct ImportedMetadata {
     pub review_score: Option<i64>,
     pub review_summary: Option<String>,
     pub hltb: Option<HltbData>,
+    #[serde(default)]
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
     pub exported_at: String,
 }
 
 /// Result of importing metadata for a single game
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum ImportResult {
     Imported(ImportedMetadata),
@@ -280,13 +376,8 @@ pub fn import_game_metadata(game: &Game) -> ImportResult {
     };
 
     // Parse timestamps for comparison
-    let json_exported_at = chrono::DateTime::parse_from_rfc3339(&metadata.exported_at)
-        .map(|dt| dt.with_timezone(&Utc))
-        .ok();
-
-    let db_updated_at = chrono::DateTime::parse_from_rfc3339(&game.updated_at)
-        .ok()
-        .map(|dt| dt.with_timezone(&Utc));
+    let json_exported_at = timestamps::parse(&metadata.exported_at);
+    let db_updated_at = timestamps::parse(&game.updated_at);
 
     // Compare timestamps - only import if JSON is newer or DB has no timestamp
     match (json_exported_at, db_updated_at) {
@@ -304,6 +395,16 @@ pub fn import_game_metadata(game: &Game) -> ImportResult {
     ImportResult::Imported(metadata)
 }
 
+/// Write a file through a temporary file and a rename, so a failed write
+/// never leaves a truncated file behind
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
+}
+
 /// Get the path where metadata JSON should be stored
 pub fn get_metadata_path(game_folder: &str) -> PathBuf {
     Path::new(game_folder)
@@ -338,6 +439,10 @@ pub fn export_game_metadata(
         .publishers
         .as_ref()
         .and_then(|s| serde_json::from_str(s).ok());
+    let custom_fields: Option<serde_json::Map<String, serde_json::Value>> = game
+        .custom_fields
+        .as_ref()
+        .and_then(|s| serde_json::from_str(s).ok());
 
     // Build HLTB data if any field is present
     let hltb = if game.hltb_main_mins.is_some()
@@ -366,7 +471,8 @@ pub fn export_game_metadata(
         review_score: game.review_score,
         review_summary: game.review_summary.clone(),
         hltb,
-        exported_at: Utc::now().to_rfc3339(),
+        custom_fields,
+        exported_at: timestamps::now(),
         manually_edited: game.manually_edited.unwrap_or(0) == 1,
     };
 
@@ -375,7 +481,7 @@ pub fn export_game_metadata(
 
     // Write to file
     let metadata_path = get_metadata_path(folder_path);
-    fs::write(&metadata_path, &json)?;
+    write_atomic(&metadata_path, json.as_bytes())?;
 
     tracing::info!(
         "Exported metadata: {:?} ({} bytes)",
@@ -386,17 +492,14 @@ pub fn export_game_metadata(
 }
 
 /// Save game metadata after user edit (dual-write from DB)
-/// This is called after update_game_metadata to keep JSON in sync
+/// This is called after update_game_metadata to keep JSON in sync; callers
+/// go through metadata_sync::save so a failed write is retried later
 pub fn save_game_metadata(game: &Game) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let folder_path = &game.folder_path;
 
-    // Check if folder is writable
+    // Check if folder is writable (an offline share counts as not writable)
     if !is_folder_writable(folder_path) {
-        tracing::warn!(
-            "Game folder not writable, skipping metadata save: {}",
-            folder_path
-        );
-        return Ok(()); // Don't fail the request, just skip file write
+        return Err(format!("Game folder not writable: {}", folder_path).into());
     }
 
     // Ensure .gamevault directory exists
@@ -415,6 +518,10 @@ pub fn save_game_metadata(game: &Game) -> Result<(), Box<dyn std::error::Error +
         .publishers
         .as_ref()
         .and_then(|s| serde_json::from_str(s).ok());
+    let custom_fields: Option<serde_json::Map<String, serde_json::Value>> = game
+        .custom_fields
+        .as_ref()
+        .and_then(|s| serde_json::from_str(s).ok());
 
     // Build HLTB data if any field is present
     let hltb = if game.hltb_main_mins.is_some()
@@ -443,7 +550,8 @@ pub fn save_game_metadata(game: &Game) -> Result<(), Box<dyn std::error::Error +
         review_score: game.review_score,
         review_summary: game.review_summary.clone(),
         hltb,
-        exported_at: Utc::now().to_rfc3339(),
+        custom_fields,
+        exported_at: timestamps::now(),
         manually_edited: true, // Always true when saving from user edit
     };
 
@@ -452,7 +560,7 @@ pub fn save_game_metadata(game: &Game) -> Result<(), Box<dyn std::error::Error +
 
     // Write to file
     let metadata_path = get_metadata_path(folder_path);
-    fs::write(&metadata_path, &json)?;
+    write_atomic(&metadata_path, json.as_bytes())?;
 
     tracing::info!(
         "Saved game metadata: {:?} ({} bytes)",
@@ -503,12 +611,13 @@ mod tests {
             review_score: Some(85),
             review_summary: Some("Very Positive".to_string()),
             hltb: None,
+            custom_fields: None,
             exported_at: "2024-01-01T00:00:00Z".to_string(),
             manually_edited: false,
         };
 
         assert_eq!(metadata.schema_version, 2);
-        assert_eq!(metadata.manually_edited, false);
+        assert!(!metadata.manually_edited);
     }
 
     #[test]
@@ -529,6 +638,7 @@ mod tests {
                 extra_mins: Some(1200),
                 completionist_mins: Some(2400),
             }),
+            custom_fields: None,
             exported_at: "2024-01-01T00:00:00Z".to_string(),
             manually_edited: true,
         };
@@ -572,6 +682,36 @@ mod tests {
             hltb_completionist_mins: Some(2400),
             save_path_pattern: None,
             manually_edited: Some(1),
+            launch_exe: None,
+            idle_timeout_mins: None,
+            archived: None,
+            archive_path: None,
+            archived_at: None,
+            archive_status: None,
+            friends_owned: None,
+            notes: None,
+            deck_compat: None,
+            deck_compat_checked_at: None,
+            trading_cards: None,
+            points_shop_items: None,
+            steam_items_checked_at: None,
+            year_hint: None,
+            excluded_from_enrichment: None,
+            hidden: None,
+            parent_game_id: None,
+            franchise: None,
+            steam_fullgame_app_id: None,
+            cover_blurhash: None,
+            cover_color: None,
+            release_date_iso: Some("2024-01-15".to_string()),
+            review_band: None,
+            inferred_genres: None,
+            age_rating: None,
+            folder_app_id: None,
+            folder_app_id_source: None,
+            custom_fields: None,
+            user_rating: None,
+            finished_at: None,
             created_at: "2024-01-01".to_string(),
             updated_at: "2024-01-01".to_string(),
         }
@@ -605,4 +745,28 @@ mod tests {
         let is_edited = unedited_game.manually_edited.unwrap_or(0) == 1;
         assert!(!is_edited);
     }
+
+    #[test]
+    fn test_optimize_image() {
+        // A noisy PNG compresses poorly, like a large Steam background
+        let img = image::RgbImage::from_fn(800, 400, |x, y| {
+            image::Rgb([
+                (x * 7 % 256) as u8,
+                (y * 13 % 256) as u8,
+                ((x ^ y) % 256) as u8,
+            ])
+        });
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let optimized = optimize_image(&png, 80, 400).unwrap().unwrap();
+        assert!(optimized.len() < png.len());
+        let decoded = image::load_from_memory(&optimized).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (400, 200));
+
+        // Re-encoding the result at higher quality doesn't shrink it
+        assert!(optimize_image(&optimized, 100, 0).unwrap().is_none());
+        assert!(optimize_image(b"not an image", 80, 0).is_err());
+    }
 }
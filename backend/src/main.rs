@@ -4,23 +4,78 @@
     windows_subsystem = "windows"
 )]
 
+mod archive;
+mod artwork;
+mod backup;
+mod blocklist;
 mod config;
+mod crash;
+mod custom_fields;
 mod db;
+mod deletion;
+mod demo;
+mod diagnostics;
+mod discovery;
+mod downloads;
+mod duplicates;
 mod embedded;
+mod encryption;
+mod events;
+mod export;
+mod folder_appid;
+mod forecast;
+mod freshness;
+mod genre_inference;
 mod handlers;
+mod http;
+mod i18n;
+mod import;
+mod launcher;
+mod linking;
 mod local_storage;
+mod mappings;
+mod match_review;
+mod matching;
+mod media;
+mod metadata_sync;
+mod metrics;
 mod models;
+mod notifications;
+mod pairing;
+mod prefetch;
+mod profiles;
+mod protocol;
+mod raw;
+mod redist;
+mod rematch;
+mod reports;
+mod reviews;
 mod scanner;
+mod search_cache;
+mod security;
+mod session_tokens;
+mod sessions;
+mod settings_profile;
+mod site_export;
+mod startup;
 mod steam;
+mod storage;
+mod timestamps;
+mod tracker_import;
 mod tray;
+mod undo;
+mod wishlist;
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::{
-    body::Body,
-    extract::Request,
-    http::{header::CONTENT_TYPE, HeaderValue, Method, StatusCode},
-    middleware,
+    extract::{Request, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::{get, post, put},
     Router,
@@ -30,37 +85,77 @@ use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::{
-    config::{ensure_directories, AppConfig},
+    config::{ensure_directories, AppConfig, AuthLevel, RouteGroup},
     embedded::serve_static,
 };
 
 pub struct AppState {
     pub db: sqlx::SqlitePool,
     pub games_path: String,
+    pub config: AppConfig,
+    pub search_cache: search_cache::SteamSearchCache,
+    pub delete_confirmations: deletion::ConfirmationTokens,
+    /// Pending device pairing code
+    pub pairing: pairing::PairingCodes,
+    /// Short-lived tokens issued to the web UI
+    pub session_tokens: session_tokens::SessionTokens,
+    /// Background cover thumbnail/blurhash generation
+    pub artwork: artwork::ArtworkQueue,
+    /// Cover and background downloads for enriched games
+    pub downloads: downloads::DownloadQueue,
+    /// Budgeted cover downloads for wishlist entries
+    pub prefetch: prefetch::PrefetchQueue,
+    pub http: http::HttpClient,
+    /// Running with `--demo` (in-memory made-up library)
+    pub demo: bool,
 }
 
-/// SECURITY: Optional API key authentication middleware
-/// Set API_KEY env var to enable authentication on sensitive endpoints
-async fn auth_middleware(request: Request, next: axum::middleware::Next) -> Response {
+/// SECURITY: Authentication for a route group, per the `[auth]` policy
+async fn auth_middleware(
+    State((state, group)): State<(Arc<AppState>, RouteGroup)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match state.config.auth.level(group) {
+        AuthLevel::None => next.run(request).await,
+        AuthLevel::ApiKey => require_api_key(&state, group, request, next).await,
+        AuthLevel::Admin => require_admin_key(request, next).await,
+    }
+}
+
+/// SECURITY: Optional API key authentication
+/// Set API_KEY env var to enable it. The admin key, paired devices and web UI
+/// session tokens are accepted as well: any scope on read routes, "control"
+/// elsewhere.
+async fn require_api_key(
+    state: &AppState,
+    group: RouteGroup,
+    request: Request,
+    next: Next,
+) -> Response {
     // If no API_KEY is configured, allow all requests (backwards compatible)
     let api_key = match std::env::var("API_KEY") {
         Ok(key) if !key.is_empty() => key,
         _ => return next.run(request).await,
     };
 
-    // Check Authorization header
-    let auth_header = request
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok());
+    // Check Authorization header (raw API key also accepted without Bearer prefix)
+    if has_key(request.headers(), &api_key) || is_admin_request(request.headers()) {
+        return next.run(request).await;
+    }
 
-    match auth_header {
-        Some(header) if header == format!("Bearer {}", api_key) => next.run(request).await,
-        Some(header) if header == api_key => {
-            // Also accept raw API key without Bearer prefix
-            next.run(request).await
+    let scope = match state.session_tokens.request_scope(request.headers()) {
+        Some(scope) => Some(scope),
+        None => pairing::request_scope(&state.db, request.headers()).await,
+    };
+    match scope {
+        Some(pairing::Scope::Control) => next.run(request).await,
+        Some(pairing::Scope::Read) if group == RouteGroup::Read => next.run(request).await,
+        Some(pairing::Scope::Read) => {
+            tracing::warn!("Read-only token used on a protected endpoint");
+            (StatusCode::FORBIDDEN, "Forbidden: token is read-only").into_response()
         }
-        _ => {
+        None => {
             tracing::warn!("Unauthorized API request - invalid or missing API key");
             (
                 StatusCode::UNAUTHORIZED,
@@ -71,6 +166,50 @@ async fn auth_middleware(request: Request, next: axum::middleware::Next) -> Resp
     }
 }
 
+/// The configured admin key (ADMIN_API_KEY), if any
+fn admin_api_key() -> Option<String> {
+    std::env::var("ADMIN_API_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+}
+
+/// Whether the Authorization header carries `key` (with or without "Bearer ")
+fn has_key(headers: &HeaderMap, key: &str) -> bool {
+    headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|header| header == format!("Bearer {}", key) || header == key)
+}
+
+/// Whether a request is authenticated as admin (never true without ADMIN_API_KEY)
+pub fn is_admin_request(headers: &HeaderMap) -> bool {
+    admin_api_key().is_some_and(|key| has_key(headers, &key))
+}
+
+/// SECURITY: Admin authentication for destructive endpoints
+/// Requires ADMIN_API_KEY env var - admin endpoints are disabled when it is unset
+async fn require_admin_key(request: Request, next: Next) -> Response {
+    let Some(admin_key) = admin_api_key() else {
+        tracing::warn!("Admin endpoint called but ADMIN_API_KEY is not configured");
+        return (
+            StatusCode::FORBIDDEN,
+            "Forbidden: admin endpoints require ADMIN_API_KEY to be set",
+        )
+            .into_response();
+    };
+
+    if has_key(request.headers(), &admin_key) {
+        next.run(request).await
+    } else {
+        tracing::warn!("Unauthorized admin request - invalid or missing admin key");
+        (
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized: Invalid or missing admin key",
+        )
+            .into_response()
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize logging
@@ -79,23 +218,56 @@ async fn main() -> anyhow::Result<()> {
             std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
         ))
         .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(crash::LogCapture),
+        )
         .init();
 
+    // Crash reports go to logs/ next to the executable
+    crash::init(config::get_exe_directory().join("logs"));
+
     tracing::info!("Starting GameVault server...");
 
+    // Install steps (--register/--unregister) exit right away
+    let command = protocol::requested();
+    match command {
+        Some(protocol::Command::Register { shell }) => return protocol::register(shell),
+        Some(protocol::Command::Unregister) => return protocol::unregister(),
+        _ => {}
+    }
+
+    // Demo mode ignores config.toml and the real library entirely
+    let demo = demo::is_requested();
+
     // Load configuration from config.toml or environment
-    let app_config = AppConfig::load().unwrap_or_else(|e| {
-        tracing::warn!("Failed to load config, using defaults: {}", e);
-        AppConfig::load().expect("Default config should always work")
-    });
+    let app_config = if demo {
+        tracing::info!("Demo mode: using a made-up in-memory library");
+        demo::demo_config()
+    } else {
+        AppConfig::load().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load config, using defaults: {}", e);
+            AppConfig::default()
+        })
+    };
 
     // Ensure required directories exist (data, cache, logs)
-    ensure_directories(&app_config)?;
+    if !demo {
+        ensure_directories(&app_config)?;
+    }
 
     // Get configuration values (supports both config file and env vars for backwards compat)
-    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| app_config.database_url());
-    let games_path = std::env::var("GAMES_PATH")
-        .unwrap_or_else(|_| app_config.games_path().to_string_lossy().to_string());
+    let database_url = std::env::var("DATABASE_URL")
+        .ok()
+        .filter(|_| !demo)
+        .unwrap_or_else(|| app_config.database_url());
+    let games_path = if demo {
+        demo::DEMO_LIBRARY.to_string()
+    } else {
+        std::env::var("GAMES_PATH")
+            .unwrap_or_else(|_| app_config.games_path().to_string_lossy().to_string())
+    };
     let port = std::env::var("PORT")
         .ok()
         .and_then(|p| p.parse().ok())
@@ -103,14 +275,35 @@ async fn main() -> anyhow::Result<()> {
     let host = std::env::var("HOST").unwrap_or_else(|_| app_config.server.bind_address.clone());
     let auto_open_browser = app_config.server.auto_open_browser;
 
+    // A gamevault:// link or "Add to GameVault" opens a page of the UI,
+    // in the running instance if there is one
+    let open_link = command.map(|command| match command {
+        protocol::Command::Add(folder) => {
+            protocol::add_folder_link(&folder, std::path::Path::new(&games_path))
+        }
+        protocol::Command::Open(link) => link,
+        _ => protocol::Link::Home,
+    });
+    if let Some(link) = &open_link {
+        if protocol::open_in_running_instance(port, link) {
+            return Ok(());
+        }
+    }
+
     tracing::info!("Database URL: {}", database_url);
     tracing::info!("Games path: {}", games_path);
 
-    // Create database pool
-    let pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await?;
+    // Create database pool (demo mode keeps its in-memory database alive until exit)
+    let (pool, _demo_keepalive) = if demo {
+        let (pool, keepalive) = demo::connect().await?;
+        (pool, Some(keepalive))
+    } else {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect(&database_url)
+            .await?;
+        (pool, None)
+    };
 
     tracing::info!("Database connected");
 
@@ -118,12 +311,88 @@ async fn main() -> anyhow::Result<()> {
     db::run_migrations(&pool).await?;
     tracing::info!("Migrations complete");
 
+    if demo {
+        demo::seed(&pool).await?;
+    }
+
+    // Retry metadata.json writes that failed after an edit
+    if !demo {
+        metadata_sync::spawn_retry_task(pool.clone());
+    }
+
+    // Pause play sessions while the system is idle (Windows only)
+    sessions::spawn_idle_monitor(pool.clone());
+
+    // Shared client for outbound requests (User-Agent and provider headers from [network])
+    let http_client = http::HttpClient::from_config(&app_config.network);
+
+    // Send the weekly/monthly playtime digest if configured
+    if !demo {
+        reports::spawn_digest_scheduler(
+            pool.clone(),
+            http_client.clone(),
+            app_config.notifications.clone(),
+        );
+    }
+
+    if !demo {
+        crash::spawn_reporter(http_client.clone(), app_config.notifications.clone());
+    }
+
+    // Without a built frontend, a status page is served (logged once here)
+    embedded::frontend_embedded();
+
+    // ETags for embedded frontend files (304s on repeat visits)
+    let etag_count = embedded::init_asset_etags();
+    tracing::debug!("Computed ETags for {} embedded assets", etag_count);
+
+    // SECURITY: CSP, framing and referrer headers (configurable under [security])
+    let security_headers = Arc::new(security::SecurityHeaders::from_config(&app_config.security));
+
+    // Image downloads share one worker pool and bandwidth limit
+    let artwork = artwork::ArtworkQueue::start(pool.clone(), app_config.cache_path());
+    let downloads = downloads::DownloadQueue::start(
+        pool.clone(),
+        http_client.clone(),
+        app_config.media.clone(),
+        artwork.clone(),
+    );
+
     // Create app state
     let state = Arc::new(AppState {
+        prefetch: match demo {
+            true => prefetch::PrefetchQueue::default(),
+            false => prefetch::PrefetchQueue::start(
+                http_client.clone(),
+                app_config.media.clone(),
+                app_config.cache_path(),
+                downloads.bandwidth(),
+            ),
+        },
+        artwork,
+        downloads,
+        search_cache: search_cache::SteamSearchCache::new(pool.clone()),
+        delete_confirmations: deletion::ConfirmationTokens::default(),
+        pairing: pairing::PairingCodes::default(),
+        session_tokens: session_tokens::SessionTokens::default(),
+        http: http_client,
+        demo,
         db: pool,
         games_path,
+        config: app_config,
     });
 
+    // Covers still missing for open wishlist entries (the budget resets on restart)
+    match db::get_wishlist(&state.db).await {
+        Ok(items) => state.prefetch.enqueue(prefetch::wishlist_app_ids(&items)),
+        Err(e) => tracing::warn!("Failed to load wishlist for cover prefetch: {}", e),
+    }
+
+    // Scan, import and image repair if configured under [startup]
+    if !demo {
+        startup::spawn(state.clone());
+    }
+
     // SECURITY: CORS configuration - restrict to localhost by default
     // Set CORS_ORIGINS env var to allow additional origins (comma-separated)
     let cors = {
@@ -145,33 +414,161 @@ async fn main() -> anyhow::Result<()> {
         CorsLayer::new()
             .allow_origin(origins)
             .allow_methods([Method::GET, Method::POST, Method::PUT, Method::OPTIONS])
-            .allow_headers([CONTENT_TYPE])
+            .allow_headers([CONTENT_TYPE, AUTHORIZATION])
     };
 
     // Build API routes (order matters - specific routes before parameterized)
-    // SECURITY: /scan and /enrich require API_KEY if configured
+    // SECURITY: each group's auth level comes from [auth] in config.toml
+    let require =
+        |group: RouteGroup| middleware::from_fn_with_state((state.clone(), group), auth_middleware);
+
+    // Endpoints that change the library (API_KEY if configured, by default)
     let protected_routes = Router::new()
         .route("/scan", post(handlers::scan_games))
+        .route("/pairing/start", post(handlers::start_pairing))
+        .route("/devices", get(handlers::list_devices))
+        .route("/devices/:id/delete", post(handlers::revoke_device))
+        .route("/profiles/switch", post(handlers::switch_profile))
+        .route("/custom-fields", post(handlers::create_custom_field))
+        .route(
+            "/custom-fields/:key/delete",
+            post(handlers::delete_custom_field),
+        )
+        .route(
+            "/games/:id/custom-fields",
+            put(handlers::update_game_custom_fields),
+        )
         .route("/enrich", post(handlers::enrich_games))
+        .route(
+            "/maintenance/rematch-pending",
+            post(handlers::rematch_pending),
+        )
+        .route(
+            "/enrichment/blocklist",
+            post(handlers::add_enrichment_blocklist_entry),
+        )
+        .route(
+            "/enrichment/blocklist/:id/delete",
+            post(handlers::delete_enrichment_blocklist_entry),
+        )
         .route("/export", post(handlers::export_all_metadata))
+        .route("/export/artwork", post(handlers::export_artwork))
         .route("/import", post(handlers::import_all_metadata))
+        .route("/import/:source", post(handlers::import_tracker))
+        .route("/mappings/import", post(handlers::import_mapping_pack))
+        .route("/mappings/delete", post(handlers::delete_title_mapping))
+        .route("/undo/:operation_id", post(handlers::undo_operation))
+        .route(
+            "/notifications/:id/read",
+            post(handlers::mark_notification_read),
+        )
+        .route(
+            "/notifications/read-all",
+            post(handlers::mark_all_notifications_read),
+        )
         .route("/games/:id", put(handlers::update_game))
+        .route("/games/:id/hide", post(handlers::hide_game))
+        .route("/games/:id/links", post(handlers::set_game_links))
+        .route("/links/detect", post(handlers::detect_links))
+        .route("/artwork/prewarm", post(handlers::prewarm_artwork))
+        .route(
+            "/links/proposals/:id/confirm",
+            post(handlers::confirm_link_proposal),
+        )
+        .route(
+            "/links/proposals/:id/reject",
+            post(handlers::reject_link_proposal),
+        )
         .route("/games/:id/match", post(handlers::rematch_game))
         .route("/games/:id/match/confirm", post(handlers::confirm_rematch))
-        .layer(middleware::from_fn(auth_middleware));
+        .route(
+            "/games/:id/backup/:filename/verify",
+            post(handlers::verify_backup),
+        )
+        .route(
+            "/games/:id/backup/:filename/encrypt",
+            post(handlers::encrypt_backup),
+        )
+        .route("/games/:id/launch", post(handlers::launch_game))
+        .route("/games/:id/sessions", post(handlers::start_play_session))
+        .route(
+            "/games/:id/sessions/stop",
+            post(handlers::stop_play_session),
+        )
+        .route(
+            "/games/:id/play-settings",
+            put(handlers::update_play_settings),
+        )
+        .route(
+            "/games/:id/workshop/sync",
+            post(handlers::sync_workshop_items),
+        )
+        .route("/steam/friends/sync", post(handlers::sync_steam_friends))
+        .route("/steam/deck/refresh", post(handlers::refresh_deck_compat))
+        .route("/steam/items/refresh", post(handlers::refresh_steam_items))
+        .route("/debug/match", get(handlers::debug_match))
+        .route(
+            "/games/:id/compat/:platform",
+            put(handlers::update_game_compat),
+        )
+        .route(
+            "/games/:id/compat/:platform/delete",
+            post(handlers::delete_game_compat),
+        )
+        .route("/games/:id/settings", put(handlers::update_game_settings))
+        .route(
+            "/games/:id/settings/delete",
+            post(handlers::delete_game_settings),
+        )
+        .route("/wishlist", post(handlers::create_wishlist_item))
+        .route("/wishlist/:id", put(handlers::update_wishlist_item))
+        .route("/wishlist/:id/delete", post(handlers::delete_wishlist_item))
+        .route(
+            "/wishlist/:id/acquire",
+            post(handlers::acquire_wishlist_item),
+        )
+        .route("/wishlist/:id/prices", post(handlers::add_wishlist_price))
+        .route("/media/scan", post(handlers::scan_media))
+        .route(
+            "/reports/playtime/digest",
+            post(handlers::send_playtime_digest),
+        )
+        .layer(require(RouteGroup::Write));
+
+    // SECURITY: Destructive endpoints require ADMIN_API_KEY
+    let admin_routes = Router::new()
+        .route("/games/:id/delete-files", post(handlers::delete_game_files))
+        .route("/archive", post(handlers::archive_games))
+        .route("/games/:id/restore", post(handlers::restore_game))
+        .route(
+            "/admin/games/:id/raw",
+            get(handlers::get_raw_game).put(handlers::update_raw_game),
+        )
+        .route("/profiles", post(handlers::create_profile))
+        .route("/profiles/:id/delete", post(handlers::delete_profile))
+        .layer(require(RouteGroup::Admin));
 
-    // Config routes (no auth required for local-only access)
+    // Config routes (no auth required for local-only access, by default)
     let config_routes = Router::new()
         .route("/config", get(handlers::get_config))
         .route("/config", put(handlers::update_config))
         .route("/config/status", get(handlers::get_config_status))
+        .route("/health/details", get(handlers::health_details))
+        .route(
+            "/games/:id/diagnostics",
+            get(handlers::get_launch_diagnostics),
+        )
         .route("/shutdown", post(handlers::shutdown_server))
-        .route("/restart", post(handlers::restart_server));
+        .route("/restart", post(handlers::restart_server))
+        .layer(require(RouteGroup::Config));
 
-    let api_routes = Router::new()
-        .route("/health", get(handlers::health))
+    // Read-only endpoints (open by default)
+    let read_routes = Router::new()
         .route("/games", get(handlers::list_games))
         .route("/games/recent", get(handlers::get_recent_games))
+        .route("/games/recently-played", get(handlers::get_recently_played))
+        .route("/games/match-review", get(handlers::get_match_review_queue))
+        .route("/games/new-releases", get(handlers::get_new_releases))
         .route("/games/search", get(handlers::search_games))
         .route("/games/:id", get(handlers::get_game))
         .route("/games/:id/cover", get(handlers::serve_game_cover))
@@ -180,17 +577,95 @@ async fn main() -> anyhow::Result<()> {
             get(handlers::serve_game_background),
         )
         .route("/games/:id/storage", get(handlers::check_folder_writable))
+        .route("/games/:id/sessions", get(handlers::list_play_sessions))
+        .route("/games/:id/workshop", get(handlers::list_workshop_items))
+        .route("/games/:id/media", get(handlers::list_game_media))
+        .route("/media/:id/file", get(handlers::serve_media_file))
+        .route("/media/:id/thumbnail", get(handlers::serve_media_thumbnail))
+        .route("/games/:id/compat", get(handlers::list_game_compat))
+        .route("/games/:id/settings", get(handlers::get_game_settings))
+        .route("/games/:id/runtimes", get(handlers::list_game_runtimes))
+        .route("/games/:id/prices", get(handlers::list_game_prices))
+        .route("/wishlist", get(handlers::list_wishlist))
+        .route("/wishlist/:id/prices", get(handlers::list_wishlist_prices))
+        .route("/wishlist/:id/cover", get(handlers::serve_wishlist_cover))
         .route("/stats", get(handlers::get_stats))
+        .route("/stats/forecast", get(handlers::get_backlog_forecast))
+        .route("/stats/storage", get(handlers::get_storage_stats))
+        .route("/metrics", get(handlers::get_metrics))
+        .route("/reports/playtime", get(handlers::get_playtime_report))
+        .route("/reports/runtimes", get(handlers::get_runtime_report))
+        .route("/export/site-data", get(handlers::export_site_data))
+        .route("/discovery", get(handlers::get_discovery_queue))
+        .route("/duplicates", get(handlers::get_duplicates))
+        .route("/links/proposals", get(handlers::list_link_proposals))
+        .route("/mappings", get(handlers::list_title_mappings))
+        .route("/mappings/export", get(handlers::export_mapping_pack))
+        .route(
+            "/enrichment/blocklist",
+            get(handlers::get_enrichment_blocklist),
+        )
+        .route("/notifications", get(handlers::list_notifications))
+        .route("/i18n", get(handlers::get_translations))
+        .route("/ws", get(handlers::library_events))
+        .route("/imports", get(handlers::list_import_reports))
+        .route("/imports/:id", get(handlers::get_import_report))
+        .route("/jobs", get(handlers::list_jobs))
+        .route("/scans", get(handlers::list_scan_reports))
+        .route("/scans/:id", get(handlers::get_scan_report))
+        .route("/undo", get(handlers::list_undo_operations))
+        .route("/profiles", get(handlers::list_profiles))
+        .route("/custom-fields", get(handlers::list_custom_fields))
+        .layer(require(RouteGroup::Read));
+
+    // Always open: health checks, companion apps claiming a pairing code and
+    // the web UI's session tokens (localhost only, checked by the handler)
+    let public_routes = Router::new()
+        .route("/health", get(handlers::health))
+        .route("/pairing/claim", post(handlers::claim_pairing))
+        .route("/session/token", post(handlers::issue_session_token))
+        .route("/session/revoke", post(handlers::revoke_session_token))
+        .merge(read_routes)
+        .merge(protected_routes);
+    let all_routes = public_routes
+        .clone()
         .merge(config_routes)
-        .merge(protected_routes)
-        .with_state(state);
+        .merge(admin_routes);
+
+    // Build a router - serve embedded static files and API
+    let app = |api_routes: Router<Arc<AppState>>| {
+        Router::new()
+            .nest("/api", api_routes.with_state(state.clone()))
+            .fallback(serve_static)
+            .layer(middleware::from_fn_with_state(
+                security_headers.clone(),
+                security::security_headers,
+            ))
+            .layer(cors.clone())
+            .layer(TraceLayer::new_for_http())
+    };
 
-    // Build main router - serve embedded static files and API
-    let app = Router::new()
-        .nest("/api", api_routes)
-        .fallback(serve_static)
-        .layer(cors)
-        .layer(TraceLayer::new_for_http());
+    // SECURITY: with [server] admin_port set, admin/config routes (shutdown
+    // included) are only served on that listener, which gets the full UI
+    let app = match state.config.server.admin_address() {
+        Some(admin_addr) => {
+            let listener = tokio::net::TcpListener::bind(&admin_addr).await?;
+            tracing::info!("Admin API listening on {}", admin_addr);
+            let admin_app = app(all_routes);
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(
+                    listener,
+                    admin_app.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .await
+                {
+                    tracing::error!("Admin listener stopped: {}", e);
+                }
+            });
+            app(public_routes)
+        }
+        None => app(all_routes),
+    };
 
     let addr = format!("{}:{}", host, port);
     let url = format!("http://localhost:{}", port);
@@ -198,8 +673,13 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Server listening on {}", addr);
     tracing::info!("Open {} in your browser", url);
 
-    // Auto-open browser if configured (and not in Docker/headless)
-    if auto_open_browser && std::env::var("DOCKER").is_err() {
+    // Auto-open browser if configured (and not in Docker/headless); a link
+    // that started GameVault always opens
+    if let Some(link) = &open_link {
+        if let Err(e) = open::that(format!("{}{}", url, link.ui_path())) {
+            tracing::warn!("Failed to open browser: {}", e);
+        }
+    } else if auto_open_browser && std::env::var("DOCKER").is_err() {
         if let Err(e) = open::that(&url) {
             tracing::warn!("Failed to open browser: {}", e);
         }
@@ -211,6 +691,7 @@ async fn main() -> anyhow::Result<()> {
     // Spawn tray command handler
     if let Some(rx) = tray_rx {
         let url_clone = url.clone();
+        let state = state.clone();
         tokio::spawn(async move {
             loop {
                 // Check for tray commands every 100ms
@@ -222,6 +703,13 @@ async fn main() -> anyhow::Result<()> {
                                 tracing::warn!("Failed to open browser: {}", e);
                             }
                         }
+                        tray::TrayCommand::PairDevice => {
+                            // Shown on the host only, never in the inbox
+                            let code =
+                                pairing::start(&state.db, &state.pairing, pairing::Scope::Control)
+                                    .await;
+                            tray::show_pairing_code(&code, pairing::CODE_TTL.as_secs() / 60);
+                        }
                         tray::TrayCommand::Quit => {
                             tracing::info!("Quit requested from tray icon");
                             std::process::exit(0);
@@ -233,7 +721,12 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    // Peer addresses let the session token exchange check for localhost
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
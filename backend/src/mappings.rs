@@ -0,0 +1,296 @@
+//! Title mappings and shareable mapping packs
+//!
+//! A title mapping pins a (normalized) game title to a Steam app id, with 0
+//! meaning "not on Steam". Confirming a rematch records the game's title as a
+//! mapping, so the correction applies to every copy and every future scan;
+//! enrichment checks these mappings before the built-in list in `steam.rs`
+//! and before searching Steam. Several titles mapping to the same app id are
+//! aliases ("gta v", "grand theft auto v").
+//!
+//! Mappings can be exported as a JSON pack and imported from packs shared by
+//! others with similar libraries (repacks tend to use the same folder
+//! names). Imports go through the import pipeline's conflict policies and
+//! dry runs, and are listed with the other import reports.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use crate::import::{self, ImportOptions, ImportReport, ImportRow, RowAction};
+use crate::models::TitleMapping;
+use crate::{db, search_cache::normalize_query, timestamps};
+
+/// Identifies a mapping pack
+pub const PACK_FORMAT: &str = "gamevault-mappings";
+/// Newest pack version this build reads
+pub const PACK_VERSION: u32 = 1;
+
+/// Most titles accepted in one pack
+pub const MAX_PACK_TITLES: usize = 10_000;
+/// Longest title accepted
+pub const MAX_TITLE_LENGTH: usize = 200;
+
+/// Recorded from a confirmed rematch
+pub const SOURCE_USER: &str = "user";
+/// Imported from a pack
+pub const SOURCE_IMPORT: &str = "import";
+
+/// Report source for pack imports
+const IMPORT_SOURCE: &str = "mapping_pack";
+
+/// One app and the titles (aliases) that map to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackEntry {
+    /// 0 marks titles that aren't on Steam
+    pub steam_app_id: i64,
+    pub titles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingPack {
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub exported_at: Option<String>,
+    pub mappings: Vec<PackEntry>,
+}
+
+/// Build a pack from stored mappings, grouping aliases by app id
+pub fn build_pack(mappings: &[TitleMapping], name: Option<String>) -> MappingPack {
+    let mut by_app_id: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    for mapping in mappings {
+        by_app_id
+            .entry(mapping.steam_app_id)
+            .or_default()
+            .push(mapping.title.clone());
+    }
+
+    MappingPack {
+        format: PACK_FORMAT.to_string(),
+        version: PACK_VERSION,
+        name,
+        exported_at: Some(timestamps::now()),
+        mappings: by_app_id
+            .into_iter()
+            .map(|(steam_app_id, mut titles)| {
+                titles.sort();
+                PackEntry {
+                    steam_app_id,
+                    titles,
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Check a pack's header before reading its entries
+pub fn validate_pack(pack: &MappingPack) -> Result<(), String> {
+    if pack.format != PACK_FORMAT {
+        return Err(format!(
+            "Not a mapping pack (format must be '{}')",
+            PACK_FORMAT
+        ));
+    }
+    if pack.version == 0 || pack.version > PACK_VERSION {
+        return Err(format!("Unsupported mapping pack version {}", pack.version));
+    }
+    let titles: usize = pack.mappings.iter().map(|e| e.titles.len()).sum();
+    if titles > MAX_PACK_TITLES {
+        return Err(format!(
+            "Mapping pack has more than {} titles",
+            MAX_PACK_TITLES
+        ));
+    }
+    Ok(())
+}
+
+/// Turn a pack into one import row per title
+///
+/// Invalid entries fail and repeated titles are skipped; the first
+/// occurrence wins.
+fn pack_rows(pack: &MappingPack) -> Vec<(String, ImportRow)> {
+    let mut seen = HashSet::new();
+    let mut rows = Vec::new();
+    for entry in &pack.mappings {
+        for title in &entry.titles {
+            let key = normalize_query(title);
+            let row = if key.is_empty() || key.len() > MAX_TITLE_LENGTH {
+                ImportRow::failed(title.clone(), None, "Title is empty or too long")
+            } else if entry.steam_app_id < 0 {
+                ImportRow::failed(key.clone(), None, "Invalid Steam app id")
+            } else if !seen.insert(key.clone()) {
+                ImportRow::skipped(key.clone(), None, "Title appears more than once")
+            } else {
+                ImportRow::new(key.clone(), None).set("steam_app_id", Some(entry.steam_app_id))
+            };
+            rows.push((key, row));
+        }
+    }
+    rows
+}
+
+/// Import a pack, applying the conflict policy to titles already mapped
+pub async fn import_pack(
+    pool: &SqlitePool,
+    pack: &MappingPack,
+    options: ImportOptions,
+) -> Result<ImportReport, sqlx::Error> {
+    let existing: HashMap<String, i64> = db::get_title_mappings(pool)
+        .await?
+        .into_iter()
+        .map(|m| (m.title, m.steam_app_id))
+        .collect();
+
+    let mut results = Vec::new();
+    for (key, row) in pack_rows(pack) {
+        let current = HashMap::from([(
+            "steam_app_id".to_string(),
+            existing
+                .get(&key)
+                .map_or(Value::Null, |id| Value::from(*id)),
+        )]);
+        results.push(import::plan_row(row, Some(&current), options.conflict));
+    }
+
+    let mut status = if options.dry_run {
+        "dry_run"
+    } else {
+        "applied"
+    };
+    let mut error = None;
+
+    if !options.dry_run {
+        let mappings: Vec<(String, i64)> = results
+            .iter()
+            .filter(|r| r.action == RowAction::Import)
+            .filter_map(|r| {
+                let app_id = r.changes.first()?.new.as_i64()?;
+                Some((r.key.clone(), app_id))
+            })
+            .collect();
+
+        if let Err(e) = db::put_title_mappings(pool, &mappings, SOURCE_IMPORT).await {
+            tracing::error!("Mapping pack import rolled back: {}", e);
+            status = "rolled_back";
+            error = Some(e.to_string());
+            for row in results.iter_mut().filter(|r| r.action == RowAction::Import) {
+                row.action = RowAction::Failed;
+                row.message = Some("Rolled back".to_string());
+            }
+        }
+    }
+
+    import::store_report(pool, IMPORT_SOURCE, options, status, error, results).await
+}
+
+/// Remember a confirmed match for the title
+pub async fn record(pool: &SqlitePool, title: &str, steam_app_id: i64) {
+    let key = normalize_query(title);
+    if key.is_empty() || key.len() > MAX_TITLE_LENGTH {
+        return;
+    }
+    if let Err(e) = db::put_title_mappings(pool, &[(key, steam_app_id)], SOURCE_USER).await {
+        tracing::warn!("Failed to record title mapping for '{}': {}", title, e);
+    }
+}
+
+/// Stored app id for a title (0 = not on Steam)
+pub async fn lookup(pool: &SqlitePool, title: &str) -> Option<i64> {
+    match db::get_title_mapping(pool, &normalize_query(title)).await {
+        Ok(app_id) => app_id,
+        Err(e) => {
+            tracing::warn!("Failed to look up title mapping for '{}': {}", title, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::ConflictPolicy;
+
+    fn pack(mappings: Vec<PackEntry>) -> MappingPack {
+        MappingPack {
+            format: PACK_FORMAT.to_string(),
+            version: PACK_VERSION,
+            name: Some("Repack fixes".to_string()),
+            exported_at: None,
+            mappings,
+        }
+    }
+
+    fn entry(steam_app_id: i64, titles: &[&str]) -> PackEntry {
+        PackEntry {
+            steam_app_id,
+            titles: titles.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_pack() {
+        assert!(validate_pack(&pack(vec![])).is_ok());
+
+        let mut wrong = pack(vec![]);
+        wrong.format = "something-else".to_string();
+        assert!(validate_pack(&wrong).is_err());
+
+        let mut newer = pack(vec![]);
+        newer.version = PACK_VERSION + 1;
+        assert!(validate_pack(&newer).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_and_export_pack() {
+        let (pool, _keepalive) = db::test_pool().await;
+
+        record(&pool, "  GTA   V ", 271590).await;
+        record(&pool, "Mystery Tool", 0).await;
+
+        let incoming = pack(vec![
+            entry(271591, &["gta v"]),
+            entry(1145360, &["Hades", "HADES ", ""]),
+        ]);
+        let options = |conflict, dry_run| ImportOptions { dry_run, conflict };
+
+        let report = import_pack(&pool, &incoming, options(ConflictPolicy::Skip, true))
+            .await
+            .unwrap();
+        assert_eq!(report.summary.status, "dry_run");
+        assert_eq!(report.summary.imported, 1);
+        assert_eq!(report.summary.skipped, 2, "conflict and repeated title");
+        assert_eq!(report.summary.failed, 1);
+        assert_eq!(lookup(&pool, "hades").await, None);
+
+        let report = import_pack(
+            &pool,
+            &incoming,
+            options(ConflictPolicy::FillMissing, false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.status, "applied");
+        assert_eq!(lookup(&pool, "Hades").await, Some(1145360));
+        assert_eq!(lookup(&pool, "gta v").await, Some(271590), "existing kept");
+
+        import_pack(&pool, &incoming, options(ConflictPolicy::Overwrite, false))
+            .await
+            .unwrap();
+        assert_eq!(lookup(&pool, "gta v").await, Some(271591));
+
+        let exported = build_pack(&db::get_title_mappings(&pool).await.unwrap(), None);
+        assert!(validate_pack(&exported).is_ok());
+        assert_eq!(
+            exported.mappings,
+            [
+                entry(0, &["mystery tool"]),
+                entry(271591, &["gta v"]),
+                entry(1145360, &["hades"]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Match review queue (GET /api/games/match-review)
+//!
+//! Games that enrichment couldn't match, or matched with less than
+//! [`matching::MATCH_REVIEW_CONFIDENCE`], need a person to pick the right
+//! Steam app. Games with a locked or hand-edited match, and games excluded
+//! from enrichment, are left out.
+//!
+//! After a scan that leaves games in the queue, a `match_needed`
+//! notification is raised and posted to the webhook with the counts and a
+//! link to the queue, so curation happens while the additions are fresh.
+//! A scan that found no new folders only raises it again once the previous
+//! one has been read.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::config::AppConfig;
+use crate::http::HttpClient;
+use crate::notifications::{self, WebhookEvent};
+use crate::{db, matching};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewReason {
+    /// No Steam app matched
+    Unmatched,
+    /// Matched below the review confidence
+    LowConfidence,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReviewEntry {
+    pub game_id: i64,
+    pub title: String,
+    pub match_status: String,
+    pub steam_app_id: Option<i64>,
+    pub match_confidence: Option<f64>,
+    pub reason: ReviewReason,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReviewQueue {
+    pub unmatched: usize,
+    pub low_confidence: usize,
+    pub games: Vec<ReviewEntry>,
+}
+
+/// Games waiting for match review that the active profile allows, by title
+pub async fn queue(pool: &SqlitePool) -> Result<ReviewQueue, sqlx::Error> {
+    let games = db::get_visible_games_for_rematch(pool, matching::MATCH_REVIEW_CONFIDENCE).await?;
+
+    let mut queue = ReviewQueue::default();
+    for game in games {
+        let reason = if game.steam_app_id.is_none() {
+            queue.unmatched += 1;
+            ReviewReason::Unmatched
+        } else {
+            queue.low_confidence += 1;
+            ReviewReason::LowConfidence
+        };
+        queue.games.push(ReviewEntry {
+            game_id: game.id,
+            title: game.title,
+            match_status: game.match_status,
+            steam_app_id: game.steam_app_id,
+            match_confidence: game.match_confidence,
+            reason,
+        });
+    }
+    Ok(queue)
+}
+
+/// Link to the queue for notifications
+pub fn review_url(config: &AppConfig) -> String {
+    let base = config.notifications.public_url.trim().trim_end_matches('/');
+    if base.is_empty() {
+        format!(
+            "http://localhost:{}/api/games/match-review",
+            config.server.port
+        )
+    } else {
+        format!("{}/api/games/match-review", base)
+    }
+}
+
+/// Webhook `data` of a `match_needed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchNeeded {
+    pub unmatched: usize,
+    pub low_confidence: usize,
+    pub total: usize,
+    pub review_url: String,
+}
+
+pub fn match_needed_event(needed: &MatchNeeded) -> WebhookEvent {
+    WebhookEvent::new(
+        notifications::KIND_MATCH_NEEDED,
+        "Games need matching",
+        match_needed_message(needed),
+    )
+    .with_data(needed)
+}
+
+fn match_needed_message(needed: &MatchNeeded) -> String {
+    format!(
+        "{} games need matching ({} unmatched, {} low confidence). Review them at {}",
+        needed.total, needed.unmatched, needed.low_confidence, needed.review_url
+    )
+}
+
+/// Raise `match_needed` after a scan if games are waiting for review
+///
+/// Failures are logged, never returned, like other notifications.
+pub async fn notify_after_scan(
+    pool: &SqlitePool,
+    client: &HttpClient,
+    config: &AppConfig,
+    new_games: usize,
+) {
+    let queue = match queue(pool).await {
+        Ok(queue) => queue,
+        Err(e) => {
+            tracing::warn!("Failed to read the match review queue: {}", e);
+            return;
+        }
+    };
+    if queue.games.is_empty() {
+        return;
+    }
+    if new_games == 0 {
+        match db::has_unread_notification(pool, notifications::KIND_MATCH_NEEDED).await {
+            Ok(false) => {}
+            Ok(true) => return,
+            Err(e) => {
+                tracing::warn!("Failed to check match needed notifications: {}", e);
+                return;
+            }
+        }
+    }
+
+    let needed = MatchNeeded {
+        unmatched: queue.unmatched,
+        low_confidence: queue.low_confidence,
+        total: queue.games.len(),
+        review_url: review_url(config),
+    };
+    notifications::notify(
+        pool,
+        notifications::KIND_MATCH_NEEDED,
+        "Games need matching",
+        &match_needed_message(&needed),
+        None,
+    )
+    .await;
+
+    let webhook_url = config.notifications.webhook_url.clone();
+    if webhook_url.is_empty() {
+        return;
+    }
+    let client = client.clone();
+    let event = match_needed_event(&needed);
+    tokio::spawn(async move {
+        if let Err(e) = notifications::send_webhook(&client, &webhook_url, &event).await {
+            tracing::warn!("Failed to send match needed webhook: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_queue_and_notification() {
+        let (pool, _keepalive) = db::test_pool().await;
+        let config = AppConfig::default();
+        let client = HttpClient::default();
+
+        // Nothing to review, nothing raised
+        notify_after_scan(&pool, &client, &config, 0).await;
+        assert_eq!(db::count_unread_notifications(&pool).await.unwrap(), 0);
+
+        for title in ["Hades", "Celeste", "Doom"] {
+            db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        sqlx::query("UPDATE games SET match_status = 'matched', steam_app_id = 1, match_confidence = 0.6 WHERE title = 'Celeste'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE games SET match_status = 'matched', steam_app_id = 2, match_confidence = 0.95 WHERE title = 'Doom'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let queue = queue(&pool).await.unwrap();
+        assert_eq!((queue.unmatched, queue.low_confidence), (1, 1));
+        let reasons: Vec<_> = queue
+            .games
+            .iter()
+            .map(|g| (g.title.as_str(), g.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                ("Celeste", ReviewReason::LowConfidence),
+                ("Hades", ReviewReason::Unmatched)
+            ]
+        );
+
+        notify_after_scan(&pool, &client, &config, 3).await;
+        let items = db::get_notifications(&pool, true, 10).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].kind, notifications::KIND_MATCH_NEEDED);
+        assert!(items[0]
+            .message
+            .ends_with("http://localhost:3000/api/games/match-review"));
+
+        // A rescan without new folders doesn't repeat an unread notification
+        notify_after_scan(&pool, &client, &config, 0).await;
+        assert_eq!(db::count_unread_notifications(&pool).await.unwrap(), 1);
+    }
+}
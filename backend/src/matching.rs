@@ -0,0 +1,309 @@
+//! Match explanations (GET /api/debug/match)
+//!
+//! Runs a title through the same steps enrichment uses: folder name cleanup,
+//! normalization, stored title mappings, the built-in mappings and the Steam
+//! search. Each step lists the candidates it looked at, with their scores and
+//! the rule that accepted or rejected them, so a bad match can be diagnosed
+//! without reading the logs. Steps after the one that produced a match are
+//! not run, as in enrichment.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::http::HttpClient;
+use crate::models::SteamSearchResult;
+use crate::search_cache::{normalize_query, SteamSearchCache};
+use crate::{mappings, scanner, steam};
+
+/// New matches below this confidence raise a "match needs review" notification
+pub const MATCH_REVIEW_CONFIDENCE: f64 = 0.8;
+
+/// Built-in mappings listed per explanation
+const MAX_KNOWN_CANDIDATES: usize = 5;
+
+pub const STAGE_STORED_MAPPING: &str = "stored_mapping";
+pub const STAGE_KNOWN_MAPPING: &str = "known_mapping";
+pub const STAGE_STEAM_SEARCH: &str = "steam_search";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchCandidate {
+    /// 0 for a mapping that marks the title as not on Steam
+    pub app_id: i64,
+    pub name: String,
+    pub similarity: Option<f64>,
+    pub year_adjustment: Option<f64>,
+    pub score: Option<f64>,
+    pub accepted: bool,
+    /// Rule that accepted or rejected the candidate
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchStep {
+    pub stage: &'static str,
+    pub outcome: String,
+    pub candidates: Vec<MatchCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchResult {
+    pub stage: &'static str,
+    /// 0 = not on Steam (enrichment skips the game)
+    pub app_id: i64,
+    pub confidence: f64,
+    /// Enrichment would raise a "match needs review" notification
+    pub needs_review: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchExplanation {
+    pub input: String,
+    /// Title after the folder name cleanup
+    pub cleaned_title: String,
+    /// Cleanup patterns that changed the input, in order
+    pub cleanup_patterns: Vec<&'static str>,
+    pub year_hint: Option<i32>,
+    /// Key for stored mappings and the search cache
+    pub normalized_title: String,
+    pub steps: Vec<MatchStep>,
+    pub result: Option<MatchResult>,
+}
+
+fn result(stage: &'static str, app_id: i64, confidence: f64) -> MatchResult {
+    MatchResult {
+        stage,
+        app_id,
+        confidence,
+        needs_review: app_id > 0 && confidence < MATCH_REVIEW_CONFIDENCE,
+    }
+}
+
+/// The built-in mapping step
+pub fn explain_known_mappings(title: &str) -> (MatchStep, Option<MatchResult>) {
+    let accepted = steam::known_mapping(title);
+    let candidates: Vec<MatchCandidate> = steam::known_mapping_candidates(title)
+        .into_iter()
+        .take(MAX_KNOWN_CANDIDATES)
+        .map(|(known_title, app_id, similarity)| {
+            let is_accepted = accepted == Some((app_id, similarity));
+            let reason = if similarity <= steam::KNOWN_MAPPING_SIMILARITY {
+                format!(
+                    "similarity not above {:.2}",
+                    steam::KNOWN_MAPPING_SIMILARITY
+                )
+            } else if app_id == 0 {
+                "marks the title as not on Steam; built-in ones are skipped".to_string()
+            } else if is_accepted {
+                "most similar mapping above the threshold".to_string()
+            } else {
+                "a more similar mapping was accepted".to_string()
+            };
+            MatchCandidate {
+                app_id,
+                name: known_title.to_string(),
+                similarity: Some(similarity),
+                year_adjustment: None,
+                score: None,
+                accepted: is_accepted,
+                reason,
+            }
+        })
+        .collect();
+
+    let outcome = match accepted {
+        Some((app_id, _)) => format!("Matched built-in mapping to app {}", app_id),
+        None => "No built-in mapping is similar enough".to_string(),
+    };
+    let step = MatchStep {
+        stage: STAGE_KNOWN_MAPPING,
+        outcome,
+        candidates,
+    };
+    let matched =
+        accepted.map(|(app_id, similarity)| result(STAGE_KNOWN_MAPPING, app_id, similarity));
+    (step, matched)
+}
+
+/// The Steam search step, given the search results
+pub fn explain_search(
+    title: &str,
+    results: &[SteamSearchResult],
+    year_hint: Option<i32>,
+) -> (Vec<MatchCandidate>, Option<MatchResult>) {
+    let scored = steam::score_search_results(title, results, year_hint);
+    let best = steam::best_scored(&scored);
+    let matched = best
+        .map(|index| &scored[index])
+        .map(|r| result(STAGE_STEAM_SEARCH, r.appid, r.similarity));
+
+    let mut candidates: Vec<MatchCandidate> = scored
+        .iter()
+        .enumerate()
+        .map(|(index, r)| {
+            let accepted = best == Some(index);
+            let reason = if accepted {
+                "highest score".to_string()
+            } else if r.similarity <= steam::SEARCH_MIN_SIMILARITY {
+                format!("similarity not above {:.2}", steam::SEARCH_MIN_SIMILARITY)
+            } else if r.score <= 0.0 {
+                "score not above 0".to_string()
+            } else {
+                "lower score than the best result".to_string()
+            };
+            MatchCandidate {
+                app_id: r.appid,
+                name: r.name.clone(),
+                similarity: Some(r.similarity),
+                year_adjustment: Some(r.year_adjustment),
+                score: Some(r.score),
+                accepted,
+                reason,
+            }
+        })
+        .collect();
+
+    candidates.extend(
+        results
+            .iter()
+            .skip(steam::SEARCH_RESULTS_CONSIDERED)
+            .map(|r| MatchCandidate {
+                app_id: r.appid,
+                name: r.name.clone(),
+                similarity: None,
+                year_adjustment: None,
+                score: None,
+                accepted: false,
+                reason: format!(
+                    "only the first {} results are considered",
+                    steam::SEARCH_RESULTS_CONSIDERED
+                ),
+            }),
+    );
+    (candidates, matched)
+}
+
+/// Explain how enrichment would match a folder name or title
+///
+/// `year` overrides the year hint read from the input.
+pub async fn explain(
+    pool: &SqlitePool,
+    client: &HttpClient,
+    cache: &SteamSearchCache,
+    input: &str,
+    year: Option<i32>,
+) -> MatchExplanation {
+    let (cleaned_title, cleanup_patterns) = scanner::clean_title_explained(input);
+    let year_hint = year.or_else(|| scanner::extract_year_hint(input));
+    let mut explanation = MatchExplanation {
+        input: input.to_string(),
+        normalized_title: normalize_query(&cleaned_title),
+        cleaned_title,
+        cleanup_patterns,
+        year_hint,
+        steps: Vec::new(),
+        result: None,
+    };
+    let title = explanation.cleaned_title.clone();
+
+    let stored = mappings::lookup(pool, &title).await;
+    explanation.steps.push(MatchStep {
+        stage: STAGE_STORED_MAPPING,
+        outcome: match stored {
+            Some(0) => "Mapped as not on Steam".to_string(),
+            Some(app_id) => format!("Mapped to app {}", app_id),
+            None => "No stored mapping".to_string(),
+        },
+        candidates: Vec::new(),
+    });
+    if let Some(app_id) = stored {
+        explanation.result = Some(result(STAGE_STORED_MAPPING, app_id, 1.0));
+        return explanation;
+    }
+
+    let (step, matched) = explain_known_mappings(&title);
+    explanation.steps.push(step);
+    if matched.is_some() {
+        explanation.result = matched;
+        return explanation;
+    }
+
+    let (outcome, candidates, matched) = match steam::cached_search(client, cache, &title).await {
+        Some((results, cached)) => {
+            let (candidates, matched) = explain_search(&title, &results, year_hint);
+            let source = if cached { "cached" } else { "fresh" };
+            let outcome = match &matched {
+                Some(m) => format!("Matched app {} ({} results)", m.app_id, source),
+                None => format!("No result accepted ({} results)", source),
+            };
+            (outcome, candidates, matched)
+        }
+        None => ("Steam search failed".to_string(), Vec::new(), None),
+    };
+    explanation.steps.push(MatchStep {
+        stage: STAGE_STEAM_SEARCH,
+        outcome,
+        candidates,
+    });
+    explanation.result = matched;
+    explanation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(names: &[(i64, &str)]) -> Vec<SteamSearchResult> {
+        names
+            .iter()
+            .map(|(appid, name)| SteamSearchResult {
+                appid: *appid,
+                name: name.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_explain_search() {
+        let found = results(&[
+            (1145350, "Hades II"),
+            (1145360, "Hades"),
+            (3, "Hades OST"),
+            (4, "Hadean Tactics"),
+            (5, "Hades Star"),
+            (6, "Hades Fan Art"),
+        ]);
+        let (candidates, matched) = explain_search("Hades", &found, None);
+        assert_eq!(candidates.len(), 6);
+        let matched = matched.unwrap();
+        assert_eq!((matched.app_id, matched.needs_review), (1145360, false));
+        let accepted: Vec<i64> = candidates
+            .iter()
+            .filter(|c| c.accepted)
+            .map(|c| c.app_id)
+            .collect();
+        assert_eq!(accepted, [1145360]);
+        assert!(candidates[5].reason.starts_with("only the first 5"));
+        assert_eq!(candidates[5].score, None);
+
+        let (candidates, matched) = explain_search("Zzyzx Quest", &found[..1], None);
+        assert!(matched.is_none());
+        assert!(candidates[0].reason.contains("similarity not above"));
+    }
+
+    #[test]
+    fn test_explain_known_mappings() {
+        let (step, matched) = explain_known_mappings("Elden Ring");
+        assert_eq!(matched.unwrap().app_id, 1245620);
+        assert!(step.candidates[0].accepted);
+        assert_eq!(step.candidates.len(), MAX_KNOWN_CANDIDATES);
+
+        // Built-in "not on Steam" entries fall through to the search
+        let (step, matched) = explain_known_mappings("Alan Wake 2");
+        assert!(matched.is_none());
+        assert_eq!(step.candidates[0].app_id, 0);
+
+        let (title, patterns) = scanner::clean_title_explained("Elden Ring [FitGirl Repack]");
+        assert_eq!(title, "Elden Ring");
+        assert_eq!(patterns, [r"\[FitGirl.*?\]", r"\s+$"]);
+    }
+}
@@ -0,0 +1,257 @@
+//! Screenshot and clip indexing
+//!
+//! Media is picked up from two places:
+//! - `.gamevault/media/` inside each game folder (always belongs to that game)
+//! - the shared `media.screenshots_dir`, where a file is assigned to a game
+//!   by the name of a parent folder (game title or Steam App ID, as written by
+//!   Steam, ShadowPlay and most capture tools) or, failing that, by a play
+//!   session that was running when the file was written.
+//!
+//! Image thumbnails are generated on demand into the cache directory.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use walkdir::WalkDir;
+
+use crate::{db, local_storage, models::Game, timestamps};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "bmp"];
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mkv", "webm", "mov", "avi"];
+
+/// Longest edge of generated thumbnails in pixels
+const THUMBNAIL_SIZE: u32 = 320;
+
+/// Source tag for files in a game's `.gamevault/media/`
+pub const SOURCE_GAME_FOLDER: &str = "game_folder";
+/// Source tag for files in the shared screenshots directory
+pub const SOURCE_SCREENSHOTS_DIR: &str = "screenshots_dir";
+
+/// Result of a media index run
+#[derive(Debug, Default, Serialize)]
+pub struct MediaIndexResult {
+    pub indexed: usize,
+    /// Files in the screenshots directory that couldn't be matched to a game
+    pub unassigned: usize,
+    /// Entries removed because the file no longer exists
+    pub removed: usize,
+}
+
+/// Classify a file as "image" or "video" by extension
+pub fn media_kind(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        Some("image")
+    } else if VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+        Some("video")
+    } else {
+        None
+    }
+}
+
+/// Lowercase alphanumerics only, for comparing folder names with titles
+fn normalize_name(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Match a capture folder name to a game by title or Steam App ID
+pub fn match_folder_to_game(folder_name: &str, games: &[Game]) -> Option<i64> {
+    if let Ok(app_id) = folder_name.parse::<i64>() {
+        return games
+            .iter()
+            .find(|g| g.steam_app_id == Some(app_id))
+            .map(|g| g.id);
+    }
+
+    let normalized = normalize_name(folder_name);
+    if normalized.is_empty() {
+        return None;
+    }
+
+    games
+        .iter()
+        .find(|g| {
+            normalize_name(&g.title) == normalized || normalize_name(&g.folder_name) == normalized
+        })
+        .map(|g| g.id)
+}
+
+/// File modification time as a database timestamp
+fn file_timestamp(modified: SystemTime) -> String {
+    timestamps::format(DateTime::<Utc>::from(modified))
+}
+
+/// Media files under a directory as (path, size, modified timestamp)
+fn collect_media_files(dir: &Path) -> Vec<(PathBuf, &'static str, Option<i64>, Option<String>)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let kind = media_kind(e.path())?;
+            let metadata = e.metadata().ok();
+            let size = metadata.as_ref().map(|m| m.len() as i64);
+            let taken_at = metadata.and_then(|m| m.modified().ok()).map(file_timestamp);
+            Some((e.into_path(), kind, size, taken_at))
+        })
+        .collect()
+}
+
+/// Index per-game media folders and the shared screenshots directory
+pub async fn index_media(
+    pool: &SqlitePool,
+    screenshots_dir: Option<&Path>,
+) -> Result<MediaIndexResult, sqlx::Error> {
+    let mut result = MediaIndexResult::default();
+    let games = db::get_all_games(pool).await?;
+
+    for game in &games {
+        let media_dir = local_storage::get_media_dir(&game.folder_path);
+        if !media_dir.is_dir() {
+            continue;
+        }
+
+        for (path, kind, size, taken_at) in collect_media_files(&media_dir) {
+            db::upsert_game_media(
+                pool,
+                game.id,
+                &path.to_string_lossy(),
+                kind,
+                SOURCE_GAME_FOLDER,
+                size,
+                taken_at.as_deref(),
+            )
+            .await?;
+            result.indexed += 1;
+        }
+    }
+
+    if let Some(root) = screenshots_dir.filter(|d| d.is_dir()) {
+        for (path, kind, size, taken_at) in collect_media_files(root) {
+            // Nearest parent folder (below the root) that names a game
+            let by_folder = path
+                .strip_prefix(root)
+                .ok()
+                .and_then(|rel| rel.parent())
+                .and_then(|rel| {
+                    rel.ancestors()
+                        .filter_map(|a| a.file_name())
+                        .find_map(|name| match_folder_to_game(&name.to_string_lossy(), &games))
+                });
+
+            let game_id = match by_folder {
+                Some(id) => Some(id),
+                None => match taken_at.as_deref() {
+                    Some(ts) => db::find_game_played_at(pool, ts).await?,
+                    None => None,
+                },
+            };
+
+            let Some(game_id) = game_id else {
+                result.unassigned += 1;
+                continue;
+            };
+
+            db::upsert_game_media(
+                pool,
+                game_id,
+                &path.to_string_lossy(),
+                kind,
+                SOURCE_SCREENSHOTS_DIR,
+                size,
+                taken_at.as_deref(),
+            )
+            .await?;
+            result.indexed += 1;
+        }
+    }
+
+    // Prune entries whose files were deleted
+    for media in db::get_all_media(pool).await? {
+        if !Path::new(&media.path).is_file() {
+            db::delete_game_media(pool, media.id).await?;
+            result.removed += 1;
+        }
+    }
+
+    tracing::info!(
+        "Media index: {} indexed, {} unassigned, {} removed",
+        result.indexed,
+        result.unassigned,
+        result.removed
+    );
+
+    Ok(result)
+}
+
+/// Where the thumbnail for a media entry is cached
+pub fn thumbnail_path(cache_dir: &Path, media_id: i64) -> PathBuf {
+    cache_dir
+        .join("thumbnails")
+        .join("media")
+        .join(format!("{}.jpg", media_id))
+}
+
+/// Whether a file generated from `source` exists and is at least as new
+pub fn is_up_to_date(source: &Path, generated: &Path) -> bool {
+    let source_modified = std::fs::metadata(source).and_then(|m| m.modified()).ok();
+    let generated_modified = std::fs::metadata(generated).and_then(|m| m.modified()).ok();
+    matches!((source_modified, generated_modified), (Some(src), Some(gen)) if gen >= src)
+}
+
+/// Create (or refresh) a JPEG thumbnail for an image
+pub fn ensure_thumbnail(source: &Path, thumbnail: &Path) -> Result<(), image::ImageError> {
+    if is_up_to_date(source, thumbnail) {
+        return Ok(());
+    }
+
+    if let Some(parent) = thumbnail.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let img = image::open(source)?;
+    img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .save_with_format(thumbnail, image::ImageFormat::Jpeg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn game(id: i64, title: &str, steam_app_id: Option<i64>) -> Game {
+        Game {
+            steam_app_id,
+            ..create_test_game(id, title)
+        }
+    }
+
+    #[test]
+    fn test_media_kind() {
+        assert_eq!(media_kind(Path::new("shot.PNG")), Some("image"));
+        assert_eq!(media_kind(Path::new("clip.mp4")), Some("video"));
+        assert_eq!(media_kind(Path::new("notes.txt")), None);
+        assert_eq!(media_kind(Path::new("README")), None);
+    }
+
+    #[test]
+    fn test_match_folder_to_game() {
+        let games = vec![
+            game(1, "Baldur's Gate 3", Some(1086940)),
+            game(2, "Hades", None),
+        ];
+
+        assert_eq!(match_folder_to_game("Baldurs Gate 3", &games), Some(1));
+        assert_eq!(match_folder_to_game("1086940", &games), Some(1));
+        assert_eq!(match_folder_to_game("HADES", &games), Some(2));
+        assert_eq!(match_folder_to_game("Desktop", &games), None);
+        assert_eq!(match_folder_to_game("730", &games), None);
+    }
+}
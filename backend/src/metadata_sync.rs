@@ -0,0 +1,145 @@
+//! Keeping metadata.json in step with the database
+//!
+//! Edits go to the database first and are then written to the game's
+//! `.gamevault/metadata.json`. When that file write fails (a share that is
+//! offline for a moment, a read-only folder, a full disk) the game is marked
+//! dirty rather than the failure only being logged, and a background task
+//! retries the write every few minutes until it goes through.
+//!
+//! Games still dirty after [`DIVERGENCE_ATTEMPTS`] failed writes are listed
+//! under `metadata_sync` in GET /api/health/details, since their folder copy
+//! would bring old values back on the next import.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::db;
+use crate::local_storage;
+use crate::models::{Game, MetadataDivergence};
+
+/// Time between retries of failed writes
+const RETRY_INTERVAL_SECS: u64 = 300;
+
+/// Failed writes after which a game counts as diverged
+pub const DIVERGENCE_ATTEMPTS: i64 = 3;
+
+/// Write a game's metadata.json after a database change
+///
+/// Returns whether the file was written; a failure marks the game dirty.
+pub async fn save(pool: &SqlitePool, game: &Game) -> bool {
+    match local_storage::save_game_metadata(game) {
+        Ok(()) => {
+            if let Err(e) = db::clear_metadata_dirty(pool, game.id).await {
+                tracing::warn!(
+                    "Failed to clear metadata sync state of game {}: {}",
+                    game.id,
+                    e
+                );
+            }
+            true
+        }
+        Err(e) => {
+            tracing::warn!("Failed to save metadata.json for game {}: {}", game.id, e);
+            if let Err(e) = db::mark_metadata_dirty(pool, game.id, &e.to_string()).await {
+                tracing::warn!(
+                    "Failed to mark game {} for a metadata retry: {}",
+                    game.id,
+                    e
+                );
+            }
+            false
+        }
+    }
+}
+
+/// Retry every failed write once, returning how many went through
+pub async fn retry_dirty(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let mut written = 0;
+    for game in db::get_metadata_dirty_games(pool).await? {
+        if save(pool, &game).await {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// Retry failed writes in the background
+pub fn spawn_retry_task(pool: SqlitePool) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(RETRY_INTERVAL_SECS)).await;
+            match retry_dirty(&pool).await {
+                Ok(0) => {}
+                Ok(written) => tracing::info!("Wrote {} pending metadata.json files", written),
+                Err(e) => tracing::warn!("Failed to retry metadata.json writes: {}", e),
+            }
+        }
+    });
+}
+
+/// Metadata sync state for the health details
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetadataSync {
+    /// Games whose metadata.json is behind the database
+    pub pending: usize,
+    /// Games whose writes failed at least [`DIVERGENCE_ATTEMPTS`] times
+    pub diverged: Vec<MetadataDivergence>,
+}
+
+pub async fn status(pool: &SqlitePool) -> Result<MetadataSync, sqlx::Error> {
+    let dirty = db::get_metadata_divergence(pool, 1).await?;
+    Ok(MetadataSync {
+        pending: dirty.len(),
+        diverged: dirty
+            .into_iter()
+            .filter(|g| g.attempts >= DIVERGENCE_ATTEMPTS)
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failed_writes_are_retried() {
+        let (pool, _keepalive) = db::test_pool().await;
+        let library = tempfile::tempdir().unwrap();
+        let folder = library.path().join("Hades");
+        let id = db::upsert_game(
+            &pool,
+            &folder.to_string_lossy(),
+            "Hades",
+            "Hades",
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let game = db::get_game_by_id(&pool, id).await.unwrap().unwrap();
+
+        // The folder is missing (share offline): the game stays dirty
+        for _ in 0..DIVERGENCE_ATTEMPTS {
+            assert!(!save(&pool, &game).await);
+        }
+        assert_eq!(retry_dirty(&pool).await.unwrap(), 0);
+        let sync = status(&pool).await.unwrap();
+        assert_eq!(sync.pending, 1);
+        assert_eq!(sync.diverged[0].game_id, id);
+        assert_eq!(sync.diverged[0].attempts, DIVERGENCE_ATTEMPTS + 1);
+        assert!(sync.diverged[0]
+            .error
+            .as_deref()
+            .is_some_and(|e| e.contains("not writable")));
+
+        // Back online: the retry writes the file and clears the state
+        std::fs::create_dir(&folder).unwrap();
+        assert_eq!(retry_dirty(&pool).await.unwrap(), 1);
+        assert!(local_storage::get_metadata_path(&game.folder_path).is_file());
+        let sync = status(&pool).await.unwrap();
+        assert_eq!(sync.pending, 0);
+        assert!(sync.diverged.is_empty());
+    }
+}
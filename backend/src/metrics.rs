@@ -0,0 +1,209 @@
+//! Library gauges in the Prometheus text format (GET /api/metrics)
+//!
+//! Homelab dashboards can scrape the state of the library (size, matching,
+//! backlog) without going through the JSON API. Values are read from the
+//! database on every scrape; the queries are aggregates, so this stays cheap
+//! even for large libraries. Restricted profiles don't apply here: the
+//! figures describe the whole library.
+
+use std::fmt::Write;
+
+use sqlx::SqlitePool;
+
+use crate::db;
+use crate::models::{BacklogProgress, LibraryTotals, Stats};
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Everything the endpoint reports
+#[derive(Debug)]
+pub struct LibraryMetrics {
+    pub stats: Stats,
+    pub by_match_status: Vec<(String, i64)>,
+    pub by_user_status: BacklogProgress,
+    pub totals: LibraryTotals,
+}
+
+pub async fn collect(pool: &SqlitePool) -> Result<LibraryMetrics, sqlx::Error> {
+    Ok(LibraryMetrics {
+        stats: db::get_stats(pool).await?,
+        by_match_status: db::count_games_by_match_status(pool).await?,
+        by_user_status: db::get_backlog_progress(pool).await?,
+        totals: db::get_library_totals(pool).await?,
+    })
+}
+
+/// Write one gauge: its help, its type and one sample per label value
+/// (a single unlabelled sample when `label` is None)
+fn gauge(out: &mut String, name: &str, help: &str, label: Option<&str>, samples: &[(&str, f64)]) {
+    let _ = writeln!(out, "# HELP gamevault_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE gamevault_{} gauge", name);
+    for (value, sample) in samples {
+        match label {
+            Some(label) => {
+                let _ = writeln!(
+                    out,
+                    "gamevault_{}{{{}=\"{}\"}} {}",
+                    name,
+                    label,
+                    escape_label(value),
+                    sample
+                );
+            }
+            None => {
+                let _ = writeln!(out, "gamevault_{} {}", name, sample);
+            }
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub fn render(metrics: &LibraryMetrics) -> String {
+    let LibraryMetrics {
+        stats,
+        by_match_status,
+        by_user_status,
+        totals,
+    } = metrics;
+    let mut out = String::new();
+
+    gauge(
+        &mut out,
+        "games",
+        "Games in the library",
+        None,
+        &[("", stats.total_games as f64)],
+    );
+    gauge(
+        &mut out,
+        "games_by_match_status",
+        "Games per Steam match status",
+        Some("status"),
+        &by_match_status
+            .iter()
+            .map(|(status, count)| (status.as_str(), *count as f64))
+            .collect::<Vec<_>>(),
+    );
+    gauge(
+        &mut out,
+        "pending_matches",
+        "Games waiting for a Steam match (blocklisted games excluded)",
+        None,
+        &[("", stats.pending_games as f64)],
+    );
+    gauge(
+        &mut out,
+        "games_by_user_status",
+        "Games per play status",
+        Some("status"),
+        &[
+            ("unplayed", by_user_status.unplayed as f64),
+            ("playing", by_user_status.playing as f64),
+            ("completed", by_user_status.completed as f64),
+            ("abandoned", by_user_status.abandoned as f64),
+        ],
+    );
+    gauge(
+        &mut out,
+        "hidden_games",
+        "Hidden games",
+        None,
+        &[("", totals.hidden_games as f64)],
+    );
+    gauge(
+        &mut out,
+        "archived_games",
+        "Games moved to cold storage",
+        None,
+        &[("", totals.archived_games as f64)],
+    );
+    gauge(
+        &mut out,
+        "backlog_games",
+        "Unplayed and in-progress games, hidden and archived ones excluded",
+        None,
+        &[("", totals.backlog_games as f64)],
+    );
+    gauge(
+        &mut out,
+        "backlog_hltb_hours",
+        "HowLongToBeat main story hours of backlog games that have a time",
+        None,
+        &[("", totals.backlog_hltb_mins as f64 / 60.0)],
+    );
+    gauge(
+        &mut out,
+        "backlog_games_without_hltb",
+        "Backlog games without a HowLongToBeat time",
+        None,
+        &[("", totals.backlog_without_hltb as f64)],
+    );
+    gauge(
+        &mut out,
+        "library_size_bytes",
+        "Size of all game folders as recorded by scans",
+        None,
+        &[("", totals.size_bytes as f64)],
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_render_library_gauges() {
+        let (pool, _keepalive) = db::test_pool().await;
+        for (title, size) in [("Doom", 40_000_000_000i64), ("Hades", 15_000_000_000)] {
+            db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                Some(size),
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            r#"
+            UPDATE games SET match_status = 'matched', hltb_main_mins = 90 WHERE title = 'Doom';
+            UPDATE games SET user_status = 'completed', hltb_main_mins = 1320 WHERE title = 'Hades';
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let text = render(&collect(&pool).await.unwrap());
+        for line in [
+            "# TYPE gamevault_games gauge",
+            "gamevault_games 2",
+            "gamevault_games_by_match_status{status=\"matched\"} 1",
+            "gamevault_games_by_match_status{status=\"pending\"} 1",
+            "gamevault_pending_matches 1",
+            "gamevault_games_by_user_status{status=\"completed\"} 1",
+            "gamevault_games_by_user_status{status=\"unplayed\"} 1",
+            "gamevault_backlog_games 1",
+            "gamevault_backlog_hltb_hours 1.5",
+            "gamevault_backlog_games_without_hltb 0",
+            "gamevault_library_size_bytes 55000000000",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::reviews::ReviewBandFilter;
+use crate::settings_profile::{ResolutionFilter, SettingsProfile, Upscaler};
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Game {
     pub id: i64,
@@ -52,6 +55,10 @@ pub struct Game {
     pub user_status: Option<String>,
     pub playtime_mins: Option<i64>,
     pub match_locked: Option<i64>,
+    /// The user's rating, 1-100
+    pub user_rating: Option<i64>,
+    /// When the user finished the game (YYYY-MM-DD)
+    pub finished_at: Option<String>,
 
     // HLTB data (HowLongToBeat)
     pub hltb_main_mins: Option<i64>,
@@ -64,11 +71,156 @@ pub struct Game {
     // Manual edit tracking
     pub manually_edited: Option<i64>,
 
+    // Launching and session tracking
+    /// Executable path relative to the game folder (None = auto-detect)
+    pub launch_exe: Option<String>,
+    /// Pause the session clock after this many idle minutes (None = disabled)
+    pub idle_timeout_mins: Option<i64>,
+
+    // Cold storage archival
+    pub archived: Option<i64>,
+    /// SECURITY: Hidden from API responses - contains local filesystem path
+    #[serde(skip_serializing)]
+    pub archive_path: Option<String>,
+    pub archived_at: Option<String>,
+    /// "archiving", "restoring" or "failed: <reason>" while/after a job runs
+    pub archive_status: Option<String>,
+
+    /// Number of Steam friends who own this game (None = never synced)
+    pub friends_owned: Option<i64>,
+
+    /// Freeform user notes (carried over from the wishlist when linked)
+    pub notes: Option<String>,
+
+    /// Steam Deck compatibility: "verified", "playable", "unsupported" or "unknown"
+    pub deck_compat: Option<String>,
+    pub deck_compat_checked_at: Option<String>,
+
+    /// Steam Trading Cards can be earned by playing (0/1)
+    pub trading_cards: Option<i64>,
+    /// Items in the Steam Points Shop for the game
+    pub points_shop_items: Option<i64>,
+    pub steam_items_checked_at: Option<String>,
+
+    /// Release year from the folder name or NFO, preferred when matching
+    pub year_hint: Option<i64>,
+
+    /// Matched by the enrichment blocklist (never matched to Steam)
+    pub excluded_from_enrichment: Option<i64>,
+
+    /// Hidden from the game list and search (e.g. a duplicate copy)
+    pub hidden: Option<i64>,
+
+    /// Base game of an edition or DLC (confirmed link)
+    pub parent_game_id: Option<i64>,
+    /// Franchise the game belongs to (confirmed link)
+    pub franchise: Option<String>,
+    /// Base game's Steam app id when Steam lists this game as DLC
+    pub steam_fullgame_app_id: Option<i64>,
+
+    /// BlurHash of the cached cover, shown while the image loads
+    pub cover_blurhash: Option<String>,
+    /// Dominant color of the cached cover ("#rrggbb")
+    pub cover_color: Option<String>,
+
+    /// `release_date` as YYYY-MM-DD, when it is a full date
+    pub release_date_iso: Option<String>,
+    pub review_band: Option<String>,
+
+    /// Genres and tags guessed by the scan from the folder name and files
+    /// (JSON string); the genre filter uses them while `genres` is empty
+    pub inferred_genres: Option<String>,
+
+    /// Minimum age from Steam (0 = not age-gated)
+    pub age_rating: Option<i64>,
+
+    /// Steam app id named by a file in the folder (see folder_appid.rs)
+    pub folder_app_id: Option<i64>,
+    /// That file, relative to the folder
+    pub folder_app_id_source: Option<String>,
+
+    /// Values of user-defined fields by key (JSON object, see
+    /// custom_fields.rs)
+    pub custom_fields: Option<String>,
+
     // Timestamps
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// A matched game with every optional field empty, for tests
+///
+/// Set other fields with struct update syntax:
+/// `Game { steam_app_id: Some(1145360), ..create_test_game(1, "Hades") }`
+#[cfg(test)]
+pub fn create_test_game(id: i64, title: &str) -> Game {
+    Game {
+        id,
+        folder_path: format!("/games/{}", title),
+        folder_name: title.to_string(),
+        title: title.to_string(),
+        igdb_id: None,
+        steam_app_id: None,
+        summary: None,
+        release_date: None,
+        cover_url: None,
+        background_url: None,
+        local_cover_path: None,
+        local_background_path: None,
+        genres: None,
+        developers: None,
+        publishers: None,
+        review_score: None,
+        review_count: None,
+        review_summary: None,
+        review_score_recent: None,
+        review_count_recent: None,
+        size_bytes: None,
+        match_confidence: None,
+        match_status: "matched".to_string(),
+        user_status: None,
+        playtime_mins: None,
+        match_locked: None,
+        user_rating: None,
+        finished_at: None,
+        hltb_main_mins: None,
+        hltb_extra_mins: None,
+        hltb_completionist_mins: None,
+        save_path_pattern: None,
+        manually_edited: None,
+        launch_exe: None,
+        idle_timeout_mins: None,
+        archived: None,
+        archive_path: None,
+        archived_at: None,
+        archive_status: None,
+        friends_owned: None,
+        notes: None,
+        deck_compat: None,
+        deck_compat_checked_at: None,
+        trading_cards: None,
+        points_shop_items: None,
+        steam_items_checked_at: None,
+        year_hint: None,
+        excluded_from_enrichment: None,
+        hidden: None,
+        parent_game_id: None,
+        franchise: None,
+        steam_fullgame_app_id: None,
+        cover_blurhash: None,
+        cover_color: None,
+        release_date_iso: None,
+        review_band: None,
+        inferred_genres: None,
+        age_rating: None,
+        folder_app_id: None,
+        folder_app_id_source: None,
+        custom_fields: None,
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+        updated_at: "2024-01-01T00:00:00Z".to_string(),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameSummary {
     pub id: i64,
@@ -81,11 +233,23 @@ pub struct GameSummary {
     pub match_status: String,
     pub user_status: Option<String>,
     pub hltb_main_mins: Option<i64>,
+    pub deck_compat: Option<String>,
+    pub cover_blurhash: Option<String>,
+    pub cover_color: Option<String>,
+    pub release_date_iso: Option<String>,
+    pub review_band: Option<String>,
+    pub inferred_genres: Option<Vec<String>>,
+    /// None until checked with Steam
+    pub trading_cards: Option<bool>,
+    pub points_shop: Option<bool>,
 }
 
 impl From<Game> for GameSummary {
     fn from(g: Game) -> Self {
         let genres = g.genres.and_then(|s| serde_json::from_str(&s).ok());
+        let inferred_genres = g
+            .inferred_genres
+            .and_then(|s| serde_json::from_str(&s).ok());
         GameSummary {
             id: g.id,
             title: g.title,
@@ -97,10 +261,416 @@ impl From<Game> for GameSummary {
             match_status: g.match_status,
             user_status: g.user_status,
             hltb_main_mins: g.hltb_main_mins,
+            deck_compat: g.deck_compat,
+            cover_blurhash: g.cover_blurhash,
+            cover_color: g.cover_color,
+            release_date_iso: g.release_date_iso,
+            review_band: g.review_band,
+            inferred_genres,
+            trading_cards: g.trading_cards.map(|c| c != 0),
+            points_shop: g.points_shop_items.map(|n| n > 0),
+        }
+    }
+}
+
+/// Where a game lives on disk (only returned to admin-authenticated requests)
+#[derive(Debug, Clone, Serialize)]
+pub struct GameLocation {
+    pub folder_path: String,
+    pub folder_name: String,
+}
+
+impl From<&Game> for GameLocation {
+    fn from(g: &Game) -> Self {
+        GameLocation {
+            folder_path: g.folder_path.clone(),
+            folder_name: g.folder_name.clone(),
+        }
+    }
+}
+
+/// A game representation, plus its location when the caller may see it
+#[derive(Debug, Clone, Serialize)]
+pub struct WithLocation<T> {
+    #[serde(flatten)]
+    pub item: T,
+    #[serde(flatten)]
+    pub location: Option<GameLocation>,
+}
+
+/// A game with what the detail view shows beyond the list fields
+#[derive(Debug, Clone, Serialize)]
+pub struct GameDetail {
+    #[serde(flatten)]
+    pub game: Game,
+    /// Folder size as recorded by scans, oldest first
+    pub size_history: Vec<SizeRecord>,
+}
+
+/// Folder size of a game at one scan, recorded when it changes
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SizeRecord {
+    pub size_bytes: i64,
+    /// Difference to the previous record (None for the first)
+    pub change_bytes: Option<i64>,
+    pub recorded_at: String,
+}
+
+/// Opt-in query flag for admin clients (`?include_paths=true`)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct IncludePathsQuery {
+    pub include_paths: bool,
+}
+
+/// A single play session for a game
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct PlaySession {
+    pub id: i64,
+    pub game_id: i64,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub idle_secs: i64,
+    pub idle_since: Option<String>,
+    pub active_mins: Option<i64>,
+    /// Machine the session ran on (None for sessions before it was recorded)
+    pub machine: Option<String>,
+    /// Restricted profile active when the session started
+    pub profile_id: Option<i64>,
+}
+
+/// A game with its latest play session
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PlayedGame {
+    #[sqlx(flatten)]
+    pub game: Game,
+    pub last_played_at: String,
+    /// A session is still open
+    pub running: bool,
+}
+
+/// A game in the recently played list
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentlyPlayed {
+    #[serde(flatten)]
+    pub game: GameSummary,
+    pub last_played_at: String,
+    pub running: bool,
+}
+
+impl From<PlayedGame> for RecentlyPlayed {
+    fn from(p: PlayedGame) -> Self {
+        RecentlyPlayed {
+            game: p.game.into(),
+            last_played_at: p.last_played_at,
+            running: p.running,
         }
     }
 }
 
+/// Playtime for one game within a report period
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GamePlaytime {
+    pub game_id: i64,
+    pub title: String,
+    pub active_mins: i64,
+    pub sessions: i64,
+    pub last_played: String,
+}
+
+/// Number of games per backlog status
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BacklogProgress {
+    pub unplayed: i64,
+    pub playing: i64,
+    pub completed: i64,
+    pub abandoned: i64,
+    pub total: i64,
+}
+
+/// Library-wide totals for the metrics endpoint (see metrics.rs)
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct LibraryTotals {
+    /// Folder sizes of all games as recorded by scans
+    pub size_bytes: i64,
+    /// Games still to be played, as in the backlog forecast
+    pub backlog_games: i64,
+    /// HLTB main story times of backlog games that have one
+    pub backlog_hltb_mins: i64,
+    pub backlog_without_hltb: i64,
+    pub hidden_games: i64,
+    pub archived_games: i64,
+}
+
+/// A subscribed Steam Workshop item for a game
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WorkshopItem {
+    pub game_id: i64,
+    pub published_file_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub preview_url: Option<String>,
+    pub file_size: Option<i64>,
+    pub time_created: Option<String>,
+    /// When the author last updated the item on Steam
+    pub time_updated: Option<String>,
+    /// When a sync first noticed the current `time_updated` (None = unchanged since subscribing)
+    pub update_seen_at: Option<String>,
+    pub synced_at: String,
+}
+
+/// Structured compatibility report for a game on one platform
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GameCompat {
+    pub game_id: i64,
+    /// e.g. "linux", "steam_deck", "windows"
+    pub platform: String,
+    /// "works", "tweaks" (needs tweaks) or "broken"
+    pub status: String,
+    /// Compatibility layer and version, e.g. "Proton-GE 9-5"
+    pub wrapper: Option<String>,
+    /// Launch options / environment, e.g. "PROTON_USE_WINED3D=1 %command%"
+    pub launch_options: Option<String>,
+    /// DXVK/VKD3D settings, e.g. "dxvk.enableAsync = True"
+    pub dxvk_flags: Option<String>,
+    pub updated_at: String,
+}
+
+/// A game's settings profile
+#[derive(Debug, Clone, Serialize)]
+pub struct GameSettings {
+    pub game_id: i64,
+    pub profile: SettingsProfile,
+    pub updated_at: String,
+}
+
+/// A game on the wishlist
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WishlistItem {
+    pub id: i64,
+    pub title: String,
+    pub steam_app_id: Option<i64>,
+    pub notes: Option<String>,
+    /// "wanted", "acquiring" or "acquired"
+    pub status: String,
+    /// Folder name reserved in the game library while acquiring
+    pub target_folder_name: Option<String>,
+    /// Library game this entry was linked to once acquired
+    pub game_id: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A recorded price for a wishlist entry
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct WishlistPrice {
+    pub id: i64,
+    pub wishlist_id: i64,
+    /// Set once the wishlist entry is linked to a library game
+    pub game_id: Option<i64>,
+    pub price_cents: i64,
+    pub currency: String,
+    pub store: Option<String>,
+    pub recorded_at: String,
+}
+
+/// An indexed screenshot or video clip
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct GameMedia {
+    pub id: i64,
+    pub game_id: i64,
+    /// SECURITY: Hidden from API responses - contains local filesystem path
+    #[serde(skip_serializing)]
+    pub path: String,
+    /// "image" or "video"
+    pub kind: String,
+    /// "game_folder" (`.gamevault/media/`) or "screenshots_dir"
+    pub source: String,
+    pub size_bytes: Option<i64>,
+    /// File modification time (UTC)
+    pub taken_at: Option<String>,
+    pub indexed_at: String,
+}
+
+/// Summary of a stored scan report (skipped folders are fetched separately)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ScanReportSummary {
+    pub id: i64,
+    pub total_found: i64,
+    pub added_or_updated: i64,
+    pub wishlist_linked: i64,
+    /// Folders left out
+    pub skipped: i64,
+    pub created_at: String,
+}
+
+/// Summary of a stored import report (rows are fetched separately)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ImportReportSummary {
+    pub id: i64,
+    /// Importer that produced the report, e.g. "metadata_json"
+    pub source: String,
+    pub dry_run: bool,
+    /// "overwrite", "fill_missing" or "skip"
+    pub conflict_policy: String,
+    /// "dry_run", "applied" or "rolled_back"
+    pub status: String,
+    pub imported: i64,
+    pub unchanged: i64,
+    pub skipped: i64,
+    pub not_found: i64,
+    pub failed: i64,
+    pub total: i64,
+    /// Why the import was rolled back
+    pub error: Option<String>,
+    pub created_at: String,
+}
+
+/// An enrichment blocklist entry: a wildcard pattern or an explicit game
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BlocklistEntry {
+    pub id: i64,
+    /// Matched against folder name and title (`*` and `?` wildcards)
+    pub pattern: Option<String>,
+    pub game_id: Option<i64>,
+    pub created_at: String,
+}
+
+/// An entry in the in-app notification inbox
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Notification {
+    pub id: i64,
+    /// "scan_complete", "match_review", "disk_low", "game_updated" or "pairing_started"
+    pub kind: String,
+    pub title: String,
+    pub message: String,
+    pub game_id: Option<i64>,
+    /// When the notification was read (None = unread)
+    pub read_at: Option<String>,
+    pub created_at: String,
+}
+
+/// A game whose metadata.json keeps failing to be written
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MetadataDivergence {
+    pub game_id: i64,
+    pub title: String,
+    /// When the first failed write left the file behind the database
+    pub dirty_since: String,
+    /// Failed writes so far
+    pub attempts: i64,
+    /// Error of the latest attempt
+    pub error: Option<String>,
+}
+
+/// A background task run (see startup.rs)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Job {
+    pub id: i64,
+    /// "scan", "import_metadata" or "repair_images"
+    pub kind: String,
+    /// What started it ("startup")
+    pub trigger: String,
+    /// "running", "completed" or "failed"
+    pub status: String,
+    /// Outcome summary or error
+    pub message: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// A restricted profile (the PIN hash is never loaded)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+    /// Highest age rating shown (games without one are hidden)
+    pub max_age: Option<i64>,
+    /// Genres never shown (JSON string)
+    pub blocked_genres: Option<String>,
+    /// Only games of these franchises are shown (JSON string)
+    pub allowed_franchises: Option<String>,
+    pub active: bool,
+    pub created_at: String,
+}
+
+/// A user-defined per-game field (see custom_fields.rs)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CustomField {
+    pub key: String,
+    pub label: String,
+    /// "text", "number", "boolean" or "date"
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub created_at: String,
+}
+
+/// A paired companion device (the token hash is never loaded)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct DeviceToken {
+    pub id: i64,
+    pub name: String,
+    /// "read" or "control"
+    pub scope: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// A title pinned to a Steam app id (see mappings.rs)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TitleMapping {
+    /// Normalized (lowercase, single spaces)
+    pub title: String,
+    /// 0 = not on Steam
+    pub steam_app_id: i64,
+    /// "user" (confirmed rematch) or "import" (mapping pack)
+    pub source: String,
+    pub updated_at: String,
+}
+
+/// A proposed edition/DLC or franchise link (see linking.rs)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct LinkProposal {
+    pub id: i64,
+    pub game_id: i64,
+    pub game_title: String,
+    /// "parent" or "franchise"
+    pub kind: String,
+    pub parent_game_id: Option<i64>,
+    pub parent_title: Option<String>,
+    pub franchise: Option<String>,
+    /// "steam" (store relationship) or "name" (title heuristics)
+    pub source: String,
+    pub reason: String,
+    /// "pending", "confirmed" or "rejected"
+    pub status: String,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// A recorded metadata write that can be reverted
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct UndoOperation {
+    pub id: i64,
+    /// "import", "edit", "raw_edit", "rematch" or "enrich"
+    pub kind: String,
+    pub description: String,
+    /// Games snapshotted before the operation
+    pub game_count: i64,
+    pub created_at: String,
+    /// When the operation was reverted (None = still applied)
+    pub undone_at: Option<String>,
+}
+
+/// An open session whose game has idle detection enabled
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IdleTrackedSession {
+    pub id: i64,
+    pub game_id: i64,
+    pub idle_since: Option<String>,
+    pub idle_timeout_mins: i64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
@@ -126,6 +696,90 @@ impl<T> ApiResponse<T> {
     }
 }
 
+/// Paging parameters accepted by list endpoints (1-based page)
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+}
+
+/// Filters for the game list (GET /games)
+#[derive(Debug, Default, Deserialize)]
+pub struct GameFilter {
+    /// Only games with a compatibility report for this platform
+    pub compat_platform: Option<String>,
+    /// Only games with a report of this status ("works", "tweaks", "broken")
+    pub compat_status: Option<String>,
+    /// Only games whose report mentions this wrapper (substring, e.g. "Proton-GE")
+    pub compat_wrapper: Option<String>,
+    /// Only games with this Steam Deck status ("verified", "playable", ...)
+    pub deck_compat: Option<String>,
+    /// Also list hidden games
+    pub include_hidden: Option<bool>,
+    /// Only games released in this year
+    pub release_year: Option<i32>,
+    /// Only games with this review band, or a group ("positive", "mixed",
+    /// "negative")
+    pub review_band: Option<ReviewBandFilter>,
+    /// Only games whose settings profile has this resolution ("2560x1440"),
+    /// or this height at any width ("1440p", "4k")
+    pub settings_resolution: Option<ResolutionFilter>,
+    /// Only games whose settings profile uses this upscaler
+    pub settings_upscaler: Option<Upscaler>,
+    /// Only games whose settings profile caps the frame rate at this FPS
+    pub settings_fps_cap: Option<u32>,
+    /// Only games with this genre (case-insensitive); inferred genres count
+    /// for games without any from a provider
+    pub genre: Option<String>,
+    /// Only games with a value for this custom field
+    pub custom_field: Option<String>,
+    /// ... equal to this one (case-insensitive for text)
+    pub custom_value: Option<String>,
+    /// Only games with (true) or without (false) Steam Trading Cards
+    pub trading_cards: Option<bool>,
+    /// Only games with (true) or without (false) Points Shop items
+    pub points_shop: Option<bool>,
+    #[serde(default)]
+    pub sort: GameSort,
+}
+
+/// Order of the game list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSort {
+    #[default]
+    Title,
+    /// Newest release first; games without a full release date last
+    ReleaseDate,
+}
+
+impl GameSort {
+    pub fn order_by(self) -> &'static str {
+        match self {
+            GameSort::Title => "title",
+            GameSort::ReleaseDate => "release_date_iso IS NULL, release_date_iso DESC, title",
+        }
+    }
+}
+
+/// One page of a list response
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub page_size: u32,
+    pub total: i64,
+}
+
+/// Game list response: a page when `page` or `page_size` is given, else the
+/// whole list as a bare array like before pagination existed
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum GameList<T> {
+    All(Vec<T>),
+    Page(Page<T>),
+}
+
 #[derive(Debug, Serialize)]
 pub struct Stats {
     pub total_games: i64,
@@ -149,7 +803,6 @@ pub struct SteamAppResult {
 
 #[derive(Debug, Deserialize)]
 pub struct SteamAppData {
-    pub steam_appid: i64,
     pub name: String,
     pub short_description: Option<String>,
     pub header_image: Option<String>,
@@ -158,17 +811,32 @@ pub struct SteamAppData {
     pub publishers: Option<Vec<String>>,
     pub genres: Option<Vec<SteamGenre>>,
     pub release_date: Option<SteamReleaseDate>,
+    /// Set on DLC: the base game
+    pub fullgame: Option<SteamFullGame>,
+    /// Sent as a number or a string
+    pub required_age: Option<serde_json::Value>,
+    pub categories: Option<Vec<SteamCategory>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SteamCategory {
+    /// A number (genres send theirs as strings)
+    pub id: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SteamFullGame {
+    /// Sent as a string by the store API
+    pub appid: serde_json::Value,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SteamGenre {
-    pub id: String,
     pub description: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SteamReleaseDate {
-    pub coming_soon: bool,
     pub date: Option<String>,
 }
 
@@ -189,7 +857,7 @@ pub struct SteamQuerySummary {
 }
 
 // Steam search
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SteamSearchResult {
     pub appid: i64,
     pub name: String,
@@ -0,0 +1,178 @@
+//! Notifications
+//!
+//! Events are posted as JSON to the configured webhook. The payload carries
+//! both `content` (Discord) and `text` (Slack) so the common chat services
+//! render it without an adapter; `event` and `data` are for automation.
+//!
+//! Library events (scan finished, match needs review, games left unmatched
+//! by a scan, disk low, game folder changed, game failed to start) are also
+//! stored in an in-app inbox with read/unread state, so the frontend can
+//! show them without any external service, and pushed to connected
+//! WebSocket clients.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::http::HttpClient;
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::{db, events};
+
+pub const KIND_SCAN_COMPLETE: &str = "scan_complete";
+pub const KIND_MATCH_REVIEW: &str = "match_review";
+pub const KIND_MATCH_NEEDED: &str = "match_needed";
+pub const KIND_DISK_LOW: &str = "disk_low";
+pub const KIND_GAME_UPDATED: &str = "game_updated";
+pub const KIND_PAIRING_STARTED: &str = "pairing_started";
+pub const KIND_LAUNCH_FAILED: &str = "launch_failed";
+
+/// Number of notifications kept in the inbox; older ones are dropped
+pub const INBOX_LIMIT: i64 = 200;
+
+/// Store a notification in the inbox
+///
+/// Failures are logged, never returned: a missing notification must not fail
+/// the operation that raised it.
+pub async fn notify(
+    pool: &SqlitePool,
+    kind: &str,
+    title: &str,
+    message: &str,
+    game_id: Option<i64>,
+) {
+    match db::insert_notification(pool, kind, title, message, game_id).await {
+        Ok(id) => events::publish(events::Event::Notification {
+            id,
+            kind: kind.to_string(),
+            title: title.to_string(),
+            message: message.to_string(),
+            game_id,
+        }),
+        Err(e) => tracing::warn!("Failed to store '{}' notification: {}", kind, e),
+    }
+}
+
+/// Raise a disk low notification if the drive holding `path` is short on space
+///
+/// Only one unread disk low notification is kept; `threshold_gb` of 0
+/// disables the check.
+pub async fn check_disk_space(pool: &SqlitePool, path: &Path, threshold_gb: u64) {
+    if threshold_gb == 0 {
+        return;
+    }
+
+    let available = match fs2::available_space(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to read free space for {}: {}", path.display(), e);
+            return;
+        }
+    };
+    if available >= threshold_gb * 1024 * 1024 * 1024 {
+        return;
+    }
+
+    match db::has_unread_notification(pool, KIND_DISK_LOW).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let message = format!(
+                "Only {:.1} GB free on the drive holding {}",
+                available as f64 / (1024.0 * 1024.0 * 1024.0),
+                path.display()
+            );
+            notify(pool, KIND_DISK_LOW, "Disk space low", &message, None).await;
+        }
+        Err(e) => tracing::warn!("Failed to check disk low notifications: {}", e),
+    }
+}
+
+/// A single webhook event
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    pub event: String,
+    pub title: String,
+    pub text: String,
+    pub content: String,
+    pub data: serde_json::Value,
+}
+
+impl WebhookEvent {
+    pub fn new(event: &str, title: impl Into<String>, text: impl Into<String>) -> Self {
+        let title = title.into();
+        let text = text.into();
+        WebhookEvent {
+            event: event.to_string(),
+            content: format!("**{}**\n{}", title, text),
+            title,
+            text,
+            data: serde_json::Value::Null,
+        }
+    }
+
+    pub fn with_data(mut self, data: impl Serialize) -> Self {
+        self.data = serde_json::to_value(data).unwrap_or(serde_json::Value::Null);
+        self
+    }
+}
+
+/// Post an event to a webhook URL
+pub async fn send_webhook(
+    client: &HttpClient,
+    url: &str,
+    event: &WebhookEvent,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let response = client
+        .post(url)
+        .json(event)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned HTTP {}", response.status()).into());
+    }
+
+    tracing::info!("Sent '{}' webhook", event.event);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_event_payload() {
+        let event = WebhookEvent::new("test", "Hello", "World").with_data(vec![1, 2]);
+        let json = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(json["event"], "test");
+        assert_eq!(json["text"], "World");
+        assert_eq!(json["content"], "**Hello**\nWorld");
+        assert_eq!(json["data"], serde_json::json!([1, 2]));
+    }
+
+    #[tokio::test]
+    async fn test_inbox_read_state() {
+        let (pool, _keepalive) = db::test_pool().await;
+
+        notify(&pool, KIND_SCAN_COMPLETE, "Scan complete", "3 games", None).await;
+        notify(&pool, KIND_DISK_LOW, "Disk space low", "1 GB free", None).await;
+        assert!(db::has_unread_notification(&pool, KIND_DISK_LOW)
+            .await
+            .unwrap());
+
+        let items = db::get_notifications(&pool, false, 10).await.unwrap();
+        assert_eq!(items[0].kind, KIND_DISK_LOW, "newest first");
+        assert!(db::mark_notification_read(&pool, items[0].id)
+            .await
+            .unwrap());
+
+        let unread = db::get_notifications(&pool, true, 10).await.unwrap();
+        assert_eq!(unread.len(), 1);
+        assert_eq!(db::count_unread_notifications(&pool).await.unwrap(), 1);
+
+        assert_eq!(db::mark_all_notifications_read(&pool).await.unwrap(), 1);
+        assert_eq!(db::count_unread_notifications(&pool).await.unwrap(), 0);
+    }
+}
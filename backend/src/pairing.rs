@@ -0,0 +1,240 @@
+//! Device pairing for companion apps
+//!
+//! Instead of copying the API key onto a phone or TV, an authenticated client
+//! (or the tray icon) starts pairing: a short-lived six-digit code is issued
+//! and returned to that client only. The code is never stored or broadcast,
+//! since the inbox and WebSocket events can be read by any client on the
+//! network; the inbox just notes that pairing started. The companion app
+//! exchanges the code for a long-lived device token, limited to a scope,
+//! that it sends like the API key (`Authorization: Bearer <token>`). Only a
+//! SHA-256 hash of each token is stored, and tokens can be revoked
+//! individually.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use crate::{db, notifications};
+
+/// How long a pairing code stays valid
+pub const CODE_TTL: Duration = Duration::from_secs(300);
+
+/// Wrong codes accepted before the pending code is discarded
+pub const MAX_CLAIM_ATTEMPTS: u32 = 5;
+
+/// Longest device name accepted
+pub const MAX_DEVICE_NAME_LENGTH: usize = 100;
+
+const CODE_DIGITS: usize = 6;
+const TOKEN_LENGTH: usize = 40;
+
+/// Prefix that makes device tokens recognizable in logs and configs
+const TOKEN_PREFIX: &str = "gvd_";
+
+/// What a device token may do
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Read-only access (browse the library)
+    #[default]
+    Read,
+    /// Also everything behind the API key (launch, scan, edit, ...)
+    Control,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Control => "control",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(Scope::Read),
+            "control" => Some(Scope::Control),
+            _ => None,
+        }
+    }
+}
+
+struct PendingCode {
+    code: String,
+    scope: Scope,
+    issued: Instant,
+    failed_attempts: u32,
+}
+
+/// The pending pairing code (only one at a time)
+#[derive(Default)]
+pub struct PairingCodes {
+    pending: Mutex<Option<PendingCode>>,
+}
+
+impl PairingCodes {
+    /// Issue a new code, replacing any earlier one
+    pub fn issue(&self, scope: Scope) -> String {
+        let mut rng = rand::thread_rng();
+        let code: String = (0..CODE_DIGITS)
+            .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+            .collect();
+
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending = Some(PendingCode {
+                code: code.clone(),
+                scope,
+                issued: Instant::now(),
+                failed_attempts: 0,
+            });
+        }
+
+        code
+    }
+
+    /// Consume a code, returning the scope it grants
+    ///
+    /// A code works once. Too many wrong guesses discard it, so a code can't
+    /// be brute-forced within its lifetime.
+    pub fn claim(&self, code: &str) -> Option<Scope> {
+        let mut pending = self.pending.lock().ok()?;
+        let current = pending.as_mut()?;
+
+        if current.issued.elapsed() >= CODE_TTL {
+            *pending = None;
+            return None;
+        }
+        if current.code != code.trim() {
+            current.failed_attempts += 1;
+            if current.failed_attempts >= MAX_CLAIM_ATTEMPTS {
+                tracing::warn!("Pairing code discarded after too many wrong attempts");
+                *pending = None;
+            }
+            return None;
+        }
+
+        pending.take().map(|p| p.scope)
+    }
+}
+
+/// A new random device token
+pub fn generate_token() -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+    format!("{}{}", TOKEN_PREFIX, random)
+}
+
+/// Hash stored for a token (hex SHA-256)
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Start pairing and return the code
+///
+/// The inbox only records that a code was issued, never the code itself.
+pub async fn start(pool: &SqlitePool, codes: &PairingCodes, scope: Scope) -> String {
+    let code = codes.issue(scope);
+    let message = format!(
+        "A pairing code for {} access was issued. It expires in {} minutes.",
+        scope.as_str(),
+        CODE_TTL.as_secs() / 60
+    );
+    notifications::notify(
+        pool,
+        notifications::KIND_PAIRING_STARTED,
+        "Pair a device",
+        &message,
+        None,
+    )
+    .await;
+    code
+}
+
+/// Scope of the device token in the Authorization header, if it is valid
+pub async fn request_scope(pool: &SqlitePool, headers: &HeaderMap) -> Option<Scope> {
+    let header = headers.get("Authorization")?.to_str().ok()?;
+    let token = header.strip_prefix("Bearer ").unwrap_or(header);
+    if !token.starts_with(TOKEN_PREFIX) {
+        return None;
+    }
+
+    match db::use_device_token(pool, &hash_token(token)).await {
+        Ok(scope) => scope.as_deref().and_then(Scope::parse),
+        Err(e) => {
+            tracing::error!("Failed to look up device token: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairing_code_single_use() {
+        let codes = PairingCodes::default();
+        let code = codes.issue(Scope::Control);
+
+        assert_eq!(code.len(), CODE_DIGITS);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(codes.claim(&format!(" {} ", code)), Some(Scope::Control));
+        assert_eq!(codes.claim(&code), None, "code can only be used once");
+    }
+
+    #[test]
+    fn test_pairing_code_discarded_after_wrong_attempts() {
+        let codes = PairingCodes::default();
+        let code = codes.issue(Scope::Read);
+
+        for _ in 0..MAX_CLAIM_ATTEMPTS {
+            assert_eq!(codes.claim("not-it"), None);
+        }
+        assert_eq!(codes.claim(&code), None);
+    }
+
+    #[tokio::test]
+    async fn test_start_keeps_code_out_of_inbox() {
+        let (pool, _keepalive) = db::test_pool().await;
+        let codes = PairingCodes::default();
+
+        let code = start(&pool, &codes, Scope::Control).await;
+
+        let inbox = db::get_notifications(&pool, false, 10).await.unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].kind, notifications::KIND_PAIRING_STARTED);
+        assert!(!inbox[0].message.contains(&code));
+        assert_eq!(codes.claim(&code), Some(Scope::Control));
+    }
+
+    #[tokio::test]
+    async fn test_request_scope() {
+        let (pool, _keepalive) = db::test_pool().await;
+
+        let token = generate_token();
+        db::insert_device_token(&pool, "Living room TV", "read", &hash_token(&token))
+            .await
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        assert_eq!(request_scope(&pool, &headers).await, Some(Scope::Read));
+
+        let device = &db::get_device_tokens(&pool).await.unwrap()[0];
+        assert!(device.last_used_at.is_some());
+
+        db::delete_device_token(&pool, device.id).await.unwrap();
+        assert_eq!(request_scope(&pool, &headers).await, None);
+    }
+}
@@ -0,0 +1,217 @@
+//! Cover prefetch for wishlist entries
+//!
+//! Wishlist games have no folder in the library to cache art into, so their
+//! Steam header images go to a central cache (`<cache>/wishlist/covers/`,
+//! one file per Steam app id) and are served from GET /api/wishlist/:id/cover.
+//!
+//! Downloads happen in a background worker with a strict budget of
+//! `[media] prefetch_per_hour` images per rolling hour, spaced out by
+//! [`PREFETCH_INTERVAL`]. Adding a few hundred entries at once fills the
+//! cache over a few hours instead of sending hundreds of requests to Steam's
+//! CDN in a burst, and the downloads share the
+//! [download limit](crate::downloads) with enrichment. Cached covers don't
+//! count against the budget. Entries are queued when they are created or get
+//! an app id, and every open entry is queued at startup, so a backlog cut
+//! short by a restart is picked up again.
+
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use crate::config::MediaConfig;
+use crate::downloads::Bandwidth;
+use crate::http::HttpClient;
+use crate::models::WishlistItem;
+use crate::{local_storage, steam, wishlist};
+
+/// Window the budget applies to
+const BUDGET_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Pause between two downloads, even with budget left
+pub const PREFETCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Where the cover for a Steam app is cached
+pub fn cover_path(cache_dir: &Path, app_id: i64) -> PathBuf {
+    cache_dir
+        .join("wishlist")
+        .join("covers")
+        .join(format!("{}.jpg", app_id))
+}
+
+/// Downloads allowed per rolling window
+#[derive(Debug)]
+pub struct Budget {
+    limit: usize,
+    window: Duration,
+    used: VecDeque<Instant>,
+}
+
+impl Budget {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Budget {
+            limit,
+            window,
+            used: VecDeque::new(),
+        }
+    }
+
+    /// Spend one download, or return how long until one is available
+    pub fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        while self
+            .used
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.window)
+        {
+            self.used.pop_front();
+        }
+        if self.used.len() < self.limit {
+            self.used.push_back(now);
+            return Ok(());
+        }
+        let oldest = self.used.front().copied().unwrap_or(now);
+        Err(self.window.saturating_sub(now.duration_since(oldest)))
+    }
+}
+
+/// App ids of open wishlist entries
+pub fn wishlist_app_ids(items: &[WishlistItem]) -> Vec<i64> {
+    items
+        .iter()
+        .filter(|item| item.status != wishlist::STATUS_ACQUIRED)
+        .filter_map(|item| item.steam_app_id)
+        .collect()
+}
+
+/// Queue of Steam app ids whose covers should be cached
+#[derive(Clone, Default)]
+pub struct PrefetchQueue {
+    /// None when prefetching is off
+    sender: Option<mpsc::UnboundedSender<i64>>,
+}
+
+impl PrefetchQueue {
+    /// Start the background worker (nothing is started with a budget of 0)
+    pub fn start(
+        http: HttpClient,
+        media: MediaConfig,
+        cache_dir: PathBuf,
+        bandwidth: Arc<Bandwidth>,
+    ) -> Self {
+        if media.prefetch_per_hour == 0 {
+            return Self::default();
+        }
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let budget = Budget::new(media.prefetch_per_hour as usize, BUDGET_WINDOW);
+        tokio::spawn(run_worker(
+            http, media, cache_dir, bandwidth, budget, receiver,
+        ));
+        Self {
+            sender: Some(sender),
+        }
+    }
+
+    /// Queue app ids (ids already queued are fetched once)
+    pub fn enqueue(&self, app_ids: impl IntoIterator<Item = i64>) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        for id in app_ids {
+            // Only fails once the worker is gone (shutdown)
+            let _ = sender.send(id);
+        }
+    }
+}
+
+async fn run_worker(
+    http: HttpClient,
+    media: MediaConfig,
+    cache_dir: PathBuf,
+    bandwidth: Arc<Bandwidth>,
+    mut budget: Budget,
+    mut receiver: mpsc::UnboundedReceiver<i64>,
+) {
+    let mut queue = VecDeque::new();
+    let mut queued = HashSet::new();
+
+    loop {
+        if queue.is_empty() {
+            let Some(id) = receiver.recv().await else {
+                return;
+            };
+            if queued.insert(id) {
+                queue.push_back(id);
+            }
+        }
+        while let Ok(id) = receiver.try_recv() {
+            if queued.insert(id) {
+                queue.push_back(id);
+            }
+        }
+
+        let Some(&app_id) = queue.front() else {
+            continue;
+        };
+        let path = cover_path(&cache_dir, app_id);
+        if app_id <= 0 || path.exists() {
+            queue.pop_front();
+            queued.remove(&app_id);
+            continue;
+        }
+
+        if let Err(wait) = budget.take(Instant::now()) {
+            tracing::info!(
+                "Cover prefetch budget used up, {} covers waiting ({}s until the next)",
+                queue.len(),
+                wait.as_secs()
+            );
+            tokio::time::sleep(wait).await;
+            continue;
+        }
+
+        queue.pop_front();
+        queued.remove(&app_id);
+        let url = steam::header_image_url(app_id);
+        if let Err(e) =
+            local_storage::download_and_save_image(&http, &media, &bandwidth, &url, &path).await
+        {
+            tracing::warn!("Failed to prefetch cover for app {}: {}", app_id, e);
+        }
+        tokio::time::sleep(PREFETCH_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget() {
+        let start = Instant::now();
+        let mut budget = Budget::new(2, Duration::from_secs(60));
+
+        assert_eq!(budget.take(start), Ok(()));
+        assert_eq!(budget.take(start + Duration::from_secs(10)), Ok(()));
+        assert_eq!(
+            budget.take(start + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+        // The first download leaves the window
+        assert_eq!(budget.take(start + Duration::from_secs(60)), Ok(()));
+        assert_eq!(
+            budget.take(start + Duration::from_secs(61)),
+            Err(Duration::from_secs(9))
+        );
+
+        let mut off = Budget::new(0, Duration::from_secs(60));
+        assert!(off.take(start).is_err());
+    }
+
+    #[test]
+    fn test_cover_path() {
+        let path = cover_path(Path::new("/cache"), 1145360);
+        assert!(path.ends_with("wishlist/covers/1145360.jpg"));
+    }
+}
@@ -0,0 +1,307 @@
+//! Restricted profiles (parental controls)
+//!
+//! A profile limits the library to what a child may see: games up to an age
+//! rating (Steam's `required_age`), without some genres, and/or only games
+//! of some franchises. While a profile is active the filter is
+//! part of every database query that returns games to a client (lists,
+//! reports, exports, notifications), and handlers taking a game id check it
+//! first, so a client can't get around it by asking differently. Background
+//! work (scans, enrichment) still sees the whole library.
+//!
+//! The active profile is server-wide and kept in the database, so it holds
+//! across restarts. Switching to a profile needs no PIN; switching away from
+//! the active one (to another profile or to no restrictions) needs its PIN.
+
+use std::time::Duration;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::db;
+use crate::models::Profile;
+
+const MIN_PIN_LEN: usize = 4;
+const MAX_PIN_LEN: usize = 32;
+const MAX_NAME_LEN: usize = 100;
+
+/// Delay before answering a wrong PIN, to slow down guessing
+const WRONG_PIN_DELAY: Duration = Duration::from_secs(1);
+
+/// A profile to create (POST /profiles)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NewProfile {
+    pub name: String,
+    pub pin: String,
+    /// Highest age rating shown
+    pub max_age: Option<i64>,
+    pub blocked_genres: Option<Vec<String>>,
+    /// Franchises (`Game::franchise`) whose games are shown, and no others
+    pub allowed_franchises: Option<Vec<String>>,
+}
+
+/// Why a profile can't be created or switched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileError {
+    Invalid(&'static str),
+    NameTaken,
+    NotFound,
+    WrongPin,
+    Database,
+}
+
+impl ProfileError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            ProfileError::Invalid(message) => message,
+            ProfileError::NameTaken => "A profile with this name exists",
+            ProfileError::NotFound => "Profile not found",
+            ProfileError::WrongPin => "Wrong PIN",
+            ProfileError::Database => "Database error",
+        }
+    }
+}
+
+impl From<sqlx::Error> for ProfileError {
+    fn from(e: sqlx::Error) -> Self {
+        tracing::error!("Profile query failed: {}", e);
+        ProfileError::Database
+    }
+}
+
+/// Trimmed, non-empty entries as a JSON list (None when there are none)
+fn json_list(values: Option<Vec<String>>) -> Option<String> {
+    let values: Vec<String> = values?
+        .into_iter()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect();
+    (!values.is_empty()).then(|| serde_json::to_string(&values).unwrap_or_default())
+}
+
+pub async fn create(pool: &SqlitePool, profile: NewProfile) -> Result<Profile, ProfileError> {
+    let name = profile.name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(ProfileError::Invalid(
+            "Profile name must be 1-100 characters",
+        ));
+    }
+    if !(MIN_PIN_LEN..=MAX_PIN_LEN).contains(&profile.pin.len()) {
+        return Err(ProfileError::Invalid("PIN must be 4-32 characters"));
+    }
+    if profile.max_age.is_some_and(|age| !(0..=99).contains(&age)) {
+        return Err(ProfileError::Invalid("max_age must be 0-99"));
+    }
+    let blocked_genres = json_list(profile.blocked_genres);
+    let allowed_franchises = json_list(profile.allowed_franchises);
+    if profile.max_age.is_none() && blocked_genres.is_none() && allowed_franchises.is_none() {
+        return Err(ProfileError::Invalid(
+            "A profile needs max_age, blocked_genres or allowed_franchises",
+        ));
+    }
+
+    let pin_hash = Argon2::default()
+        .hash_password(profile.pin.as_bytes(), &SaltString::generate(&mut OsRng))
+        .map_err(|e| {
+            tracing::error!("Failed to hash profile PIN: {}", e);
+            ProfileError::Database
+        })?
+        .to_string();
+    db::insert_profile(
+        pool,
+        name,
+        profile.max_age,
+        blocked_genres.as_deref(),
+        allowed_franchises.as_deref(),
+        &pin_hash,
+    )
+    .await?
+    .ok_or(ProfileError::NameTaken)
+}
+
+fn pin_matches(pin: &str, pin_hash: &str) -> bool {
+    PasswordHash::new(pin_hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(pin.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Switch to a profile (None = no restrictions)
+///
+/// Leaving the active profile needs its PIN.
+pub async fn switch(
+    pool: &SqlitePool,
+    profile_id: Option<i64>,
+    pin: Option<&str>,
+) -> Result<(), ProfileError> {
+    if let Some((active_id, pin_hash)) = db::get_active_profile_pin(pool).await? {
+        if profile_id == Some(active_id) {
+            return Ok(());
+        }
+        if !pin.is_some_and(|pin| pin_matches(pin, &pin_hash)) {
+            tokio::time::sleep(WRONG_PIN_DELAY).await;
+            return Err(ProfileError::WrongPin);
+        }
+    }
+    match db::set_active_profile(pool, profile_id).await? {
+        true => Ok(()),
+        false => Err(ProfileError::NotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GameFilter;
+
+    #[tokio::test]
+    async fn test_active_profile_filters_library() {
+        let (pool, _keepalive) = db::test_pool().await;
+        for title in ["Doom", "Minecraft", "Lego Star Wars", "Unrated Game"] {
+            db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            r#"
+            UPDATE games SET age_rating = 18, genres = '["Action"]' WHERE title = 'Doom';
+            UPDATE games SET age_rating = 0, genres = '["Sandbox"]' WHERE title = 'Minecraft';
+            UPDATE games SET age_rating = 0, genres = '["Action"]', franchise = 'Star Wars'
+                WHERE title = 'Lego Star Wars';
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let titles = || async {
+            db::get_games_page(&pool, &GameFilter::default(), 10, 0)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|g| g.title)
+                .collect::<Vec<_>>()
+        };
+        let doom = db::search_games(&pool, "Doom", 1).await.unwrap()[0].id;
+
+        let kids = create(
+            &pool,
+            NewProfile {
+                name: "Kids".into(),
+                pin: "1234".into(),
+                max_age: Some(12),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let no_action = create(
+            &pool,
+            NewProfile {
+                name: "No action".into(),
+                pin: "4321".into(),
+                blocked_genres: Some(vec!["action".into()]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(titles().await.len(), 4);
+
+        // Unrated games are hidden by an age limit
+        switch(&pool, Some(kids.id), None).await.unwrap();
+        assert_eq!(titles().await, ["Lego Star Wars", "Minecraft"]);
+        assert!(!db::is_game_visible(&pool, doom).await.unwrap());
+        assert!(db::search_games(&pool, "Doom", 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(db::get_recent_games(&pool, 10).await.unwrap().len(), 2);
+        assert_eq!(
+            db::count_games(&pool, &GameFilter::default())
+                .await
+                .unwrap(),
+            2
+        );
+
+        // Leaving the profile needs its PIN
+        assert_eq!(
+            switch(&pool, None, Some("0000")).await,
+            Err(ProfileError::WrongPin)
+        );
+        assert_eq!(
+            switch(&pool, Some(no_action.id), None).await,
+            Err(ProfileError::WrongPin)
+        );
+        switch(&pool, Some(no_action.id), Some("1234"))
+            .await
+            .unwrap();
+        assert_eq!(titles().await, ["Minecraft", "Unrated Game"]);
+
+        // Only one franchise
+        switch(&pool, None, Some("4321")).await.unwrap();
+        let star_wars = create(
+            &pool,
+            NewProfile {
+                name: "Star Wars".into(),
+                pin: "9999".into(),
+                allowed_franchises: Some(vec!["Star Wars".into()]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        switch(&pool, Some(star_wars.id), None).await.unwrap();
+        assert_eq!(titles().await, ["Lego Star Wars"]);
+
+        switch(&pool, None, Some("9999")).await.unwrap();
+        assert_eq!(titles().await.len(), 4);
+        assert!(db::is_game_visible(&pool, doom).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_create_validates() {
+        let (pool, _keepalive) = db::test_pool().await;
+        let profile = |name: &str, pin: &str| NewProfile {
+            name: name.into(),
+            pin: pin.into(),
+            max_age: Some(7),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            create(&pool, profile("Kids", "12")).await,
+            Err(ProfileError::Invalid(_))
+        ));
+        assert!(matches!(
+            create(
+                &pool,
+                NewProfile {
+                    blocked_genres: Some(vec![" ".into()]),
+                    max_age: None,
+                    ..profile("Kids", "1234")
+                }
+            )
+            .await,
+            Err(ProfileError::Invalid(_))
+        ));
+        create(&pool, profile("Kids", "1234")).await.unwrap();
+        assert_eq!(
+            create(&pool, profile("Kids", "1234")).await.unwrap_err(),
+            ProfileError::NameTaken
+        );
+        assert_eq!(
+            switch(&pool, Some(999), None).await,
+            Err(ProfileError::NotFound)
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! `gamevault://` links and Windows shell integration
+//!
+//! Running `GameVault.exe --register` once registers the `gamevault://`
+//! protocol for the current user, so links like `gamevault://game/123` in
+//! Discord or notes open that game in the web UI. `--register --shell` also
+//! adds "Add to GameVault" to the right-click menu of folders, which rescans
+//! the library when the folder is inside it. `--unregister` removes both.
+//! Registration only writes to `HKEY_CURRENT_USER` (via `reg.exe`), so it
+//! needs no administrator rights.
+//!
+//! Windows starts a new process for each link. When GameVault is already
+//! running, that process opens the page in the browser and exits; otherwise
+//! it starts the server as usual and opens the page instead of the home page.
+
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// URL scheme handled by GameVault
+pub const SCHEME: &str = "gamevault";
+
+const REGISTER_FLAG: &str = "--register";
+const UNREGISTER_FLAG: &str = "--unregister";
+/// With `--register`, also add the folder context menu entry
+const SHELL_FLAG: &str = "--shell";
+/// Passed by the folder context menu entry, followed by the folder
+const ADD_FLAG: &str = "--add";
+
+/// How long to wait for an already running instance to answer
+const RUNNING_CHECK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A page of the web UI that a link points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    Home,
+    Game(i64),
+    /// Rescan the library (a folder was added to it)
+    Scan,
+}
+
+impl Link {
+    /// Parse `gamevault://game/123` (or `gamevault://` for the home page)
+    pub fn parse(url: &str) -> Option<Link> {
+        let rest = strip_scheme(url)?;
+        let path = rest.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        match segments.as_slice() {
+            [] | ["library"] => Some(Link::Home),
+            ["game", id] => id.parse().ok().filter(|id| *id > 0).map(Link::Game),
+            _ => None,
+        }
+    }
+
+    /// Path and query of the web UI page
+    pub fn ui_path(&self) -> String {
+        match self {
+            Link::Home => "/".to_string(),
+            Link::Game(id) => format!("/?game={}", id),
+            Link::Scan => "/?scan=1".to_string(),
+        }
+    }
+}
+
+/// The part after `gamevault:` (the scheme is case-insensitive)
+fn strip_scheme(url: &str) -> Option<&str> {
+    let scheme = url.get(..SCHEME.len())?;
+    let rest = url.get(SCHEME.len()..)?.strip_prefix(':')?;
+    scheme.eq_ignore_ascii_case(SCHEME).then_some(rest)
+}
+
+/// What the command line asks for, besides running the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Register {
+        shell: bool,
+    },
+    Unregister,
+    Open(Link),
+    /// "Add to GameVault" on a folder
+    Add(PathBuf),
+}
+
+/// Read the command from the process arguments
+pub fn requested() -> Option<Command> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    parse_args(&args)
+}
+
+fn parse_args(args: &[String]) -> Option<Command> {
+    if args.iter().any(|a| a == REGISTER_FLAG) {
+        let shell = args.iter().any(|a| a == SHELL_FLAG);
+        return Some(Command::Register { shell });
+    }
+    if args.iter().any(|a| a == UNREGISTER_FLAG) {
+        return Some(Command::Unregister);
+    }
+    if let Some(i) = args.iter().position(|a| a == ADD_FLAG) {
+        return args
+            .get(i + 1)
+            .map(|folder| Command::Add(PathBuf::from(folder)));
+    }
+
+    let url = args.iter().find(|a| strip_scheme(a).is_some())?;
+    Some(Command::Open(Link::parse(url).unwrap_or_else(|| {
+        tracing::warn!("Unrecognized link {}, opening the home page", url);
+        Link::Home
+    })))
+}
+
+/// Page to open for "Add to GameVault": a rescan if the folder is in the library
+pub fn add_folder_link(folder: &Path, library: &Path) -> Link {
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    if canonical(folder).starts_with(canonical(library)) {
+        Link::Scan
+    } else {
+        tracing::warn!(
+            "{:?} is not inside the game library {:?}, nothing to add",
+            folder,
+            library
+        );
+        Link::Home
+    }
+}
+
+/// Open the page in an already running instance's UI
+///
+/// Returns false when nothing is listening on the port, so this process
+/// should start the server itself.
+pub fn open_in_running_instance(port: u16, link: &Link) -> bool {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    if TcpStream::connect_timeout(&addr, RUNNING_CHECK_TIMEOUT).is_err() {
+        return false;
+    }
+
+    let url = format!("http://localhost:{}{}", port, link.ui_path());
+    tracing::info!("GameVault is already running, opening {}", url);
+    if let Err(e) = open::that(&url) {
+        tracing::warn!("Failed to open browser: {}", e);
+    }
+    true
+}
+
+/// Register the protocol (and optionally the folder menu entry) for the current user
+#[cfg(windows)]
+pub fn register(shell: bool) -> anyhow::Result<()> {
+    let exe = std::env::current_exe()?;
+    let exe = exe.to_string_lossy();
+    let key = protocol_key();
+
+    reg(&["add", &key, "/ve", "/d", "URL:GameVault", "/f"])?;
+    reg(&["add", &key, "/v", "URL Protocol", "/d", "", "/f"])?;
+    reg(&[
+        "add",
+        &format!(r"{}\DefaultIcon", key),
+        "/ve",
+        "/d",
+        &format!("\"{}\",0", exe),
+        "/f",
+    ])?;
+    reg(&[
+        "add",
+        &format!(r"{}\shell\open\command", key),
+        "/ve",
+        "/d",
+        &format!("\"{}\" \"%1\"", exe),
+        "/f",
+    ])?;
+    tracing::info!("Registered {}:// links for {}", SCHEME, exe);
+
+    if shell {
+        reg(&["add", SHELL_KEY, "/ve", "/d", "Add to GameVault", "/f"])?;
+        reg(&["add", SHELL_KEY, "/v", "Icon", "/d", &exe, "/f"])?;
+        reg(&[
+            "add",
+            &format!(r"{}\command", SHELL_KEY),
+            "/ve",
+            "/d",
+            &format!("\"{}\" {} \"%1\"", exe, ADD_FLAG),
+            "/f",
+        ])?;
+        tracing::info!("Added \"Add to GameVault\" to the folder context menu");
+    }
+    Ok(())
+}
+
+/// Remove everything `register` added
+#[cfg(windows)]
+pub fn unregister() -> anyhow::Result<()> {
+    for key in [protocol_key().as_str(), SHELL_KEY] {
+        // Missing keys are fine (e.g. registered without --shell)
+        let _ = reg(&["delete", key, "/f"]);
+    }
+    tracing::info!(
+        "Removed {}:// links and the folder context menu entry",
+        SCHEME
+    );
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn register(_shell: bool) -> anyhow::Result<()> {
+    anyhow::bail!("Protocol registration is only available on Windows")
+}
+
+#[cfg(not(windows))]
+pub fn unregister() -> anyhow::Result<()> {
+    anyhow::bail!("Protocol registration is only available on Windows")
+}
+
+#[cfg(windows)]
+const SHELL_KEY: &str = r"HKCU\Software\Classes\Directory\shell\GameVault";
+
+#[cfg(windows)]
+fn protocol_key() -> String {
+    format!(r"HKCU\Software\Classes\{}", SCHEME)
+}
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> anyhow::Result<()> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let status = std::process::Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("reg {} {} failed ({})", args[0], args[1], status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_link() {
+        assert_eq!(Link::parse("gamevault://game/123"), Some(Link::Game(123)));
+        assert_eq!(Link::parse("GameVault://game/123/"), Some(Link::Game(123)));
+        assert_eq!(
+            Link::parse("gamevault://game/7?from=discord"),
+            Some(Link::Game(7))
+        );
+        assert_eq!(Link::parse("gamevault://"), Some(Link::Home));
+        assert_eq!(Link::parse("gamevault://game/abc"), None);
+        assert_eq!(Link::parse("gamevault://game/0"), None);
+        assert_eq!(Link::parse("https://game/123"), None);
+        assert_eq!(Link::Game(123).ui_path(), "/?game=123");
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&args(&[])), None);
+        assert_eq!(parse_args(&args(&["--demo"])), None);
+        assert_eq!(
+            parse_args(&args(&["--register", "--shell"])),
+            Some(Command::Register { shell: true })
+        );
+        assert_eq!(
+            parse_args(&args(&["gamevault://game/42"])),
+            Some(Command::Open(Link::Game(42)))
+        );
+        assert_eq!(
+            parse_args(&args(&["gamevault://nonsense/1/2"])),
+            Some(Command::Open(Link::Home))
+        );
+        assert_eq!(
+            parse_args(&args(&["--add", r"D:\Games\Hades"])),
+            Some(Command::Add(PathBuf::from(r"D:\Games\Hades")))
+        );
+    }
+
+    #[test]
+    fn test_add_folder_link() {
+        let library = tempfile::tempdir().unwrap();
+        let game = library.path().join("Hades");
+        std::fs::create_dir(&game).unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+
+        assert_eq!(add_folder_link(&game, library.path()), Link::Scan);
+        assert_eq!(
+            add_folder_link(elsewhere.path(), library.path()),
+            Link::Home
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Raw game rows for administrators
+//!
+//! GET/PUT /api/admin/games/:id/raw expose every column of a `games` row,
+//! including the ones the public serializer hides (folder and archive paths),
+//! so a bad value can be fixed without opening the database in another tool.
+//!
+//! Edits are checked before anything is written: the column must exist and
+//! not be managed by GameVault itself, the value must fit the column's type,
+//! and a few columns have their own rules (known statuses, JSON lists, 0/1
+//! flags, paths that must exist inside the library). The edit is recorded as
+//! an undo operation, which restores the metadata columns listed in
+//! `undo::UNDO_COLUMNS`.
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::freshness;
+use crate::reviews::ReviewBand;
+
+/// Column values by name
+pub type RawRow = Map<String, Value>;
+
+/// Storage class of a column, from its declared type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Integer,
+    Real,
+    Text,
+}
+
+impl ColumnKind {
+    /// SQLite type affinity for a declared type ("INTEGER", "TEXT", ...)
+    pub fn from_declared(declared: &str) -> Self {
+        let declared = declared.to_uppercase();
+        if declared.contains("INT") {
+            ColumnKind::Integer
+        } else if ["REAL", "FLOA", "DOUB"]
+            .iter()
+            .any(|t| declared.contains(t))
+        {
+            ColumnKind::Real
+        } else {
+            ColumnKind::Text
+        }
+    }
+}
+
+/// A column of the games table
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnKind,
+    pub not_null: bool,
+}
+
+/// Columns that are never edited directly
+const READ_ONLY: &[&str] = &["id", "created_at", "updated_at"];
+
+/// Columns limited to a set of values (when not null)
+const ENUM_COLUMNS: &[(&str, &[&str])] = &[
+    ("match_status", &["pending", "matched"]),
+    (
+        "user_status",
+        &["unplayed", "playing", "completed", "abandoned"],
+    ),
+    (
+        "deck_compat",
+        &["verified", "playable", "unsupported", "unknown"],
+    ),
+    (
+        "review_band",
+        &[
+            "overwhelmingly_positive",
+            "very_positive",
+            "positive",
+            "mostly_positive",
+            "mixed",
+            "mostly_negative",
+            "negative",
+            "very_negative",
+            "overwhelmingly_negative",
+        ],
+    ),
+];
+
+/// Columns holding a JSON array of strings
+const LIST_COLUMNS: &[&str] = &["genres", "developers", "publishers"];
+
+/// Columns holding 0 or 1
+const FLAG_COLUMNS: &[&str] = &[
+    "match_locked",
+    "manually_edited",
+    "archived",
+    "excluded_from_enrichment",
+    "hidden",
+];
+
+/// Cached images, which must be files inside the library
+const IMAGE_COLUMNS: &[&str] = &["local_cover_path", "local_background_path"];
+
+/// Check an edit against the table's columns
+///
+/// Returns the changes to write, in request order. A new `release_date`
+/// also updates `release_date_iso`, and a new `review_summary` updates
+/// `review_band`, unless the edit sets those itself.
+pub fn validate(
+    columns: &[Column],
+    edit: &RawRow,
+    games_path: &Path,
+) -> Result<Vec<(String, Value)>, String> {
+    if edit.is_empty() {
+        return Err("No columns to update".to_string());
+    }
+
+    let mut changes = Vec::new();
+    for (name, value) in edit {
+        let column = columns
+            .iter()
+            .find(|c| c.name == *name)
+            .ok_or_else(|| format!("Unknown column '{}'", name))?;
+        if READ_ONLY.contains(&name.as_str()) {
+            return Err(format!("Column '{}' is read-only", name));
+        }
+        check_value(column, value, games_path).map_err(|e| format!("{}: {}", name, e))?;
+        changes.push((name.clone(), value.clone()));
+    }
+
+    if let Some(date) = edit.get("release_date") {
+        if !edit.contains_key("release_date_iso") {
+            let iso = date.as_str().and_then(freshness::release_date_iso);
+            changes.push(("release_date_iso".to_string(), iso.into()));
+        }
+    }
+    if let Some(summary) = edit.get("review_summary") {
+        if !edit.contains_key("review_band") {
+            let band = summary
+                .as_str()
+                .and_then(ReviewBand::from_summary)
+                .map(ReviewBand::as_str);
+            changes.push(("review_band".to_string(), band.into()));
+        }
+    }
+    Ok(changes)
+}
+
+fn check_value(column: &Column, value: &Value, games_path: &Path) -> Result<(), String> {
+    let name = column.name.as_str();
+    if value.is_null() {
+        return match column.not_null {
+            true => Err("cannot be null".to_string()),
+            false => Ok(()),
+        };
+    }
+
+    match column.kind {
+        ColumnKind::Integer if value.as_i64().is_none() => {
+            return Err("expected an integer".to_string())
+        }
+        ColumnKind::Real if !value.is_number() => return Err("expected a number".to_string()),
+        ColumnKind::Text if !value.is_string() => return Err("expected a string".to_string()),
+        _ => {}
+    }
+
+    if FLAG_COLUMNS.contains(&name) && !matches!(value.as_i64(), Some(0 | 1)) {
+        return Err("expected 0 or 1".to_string());
+    }
+    if let Some((_, allowed)) = ENUM_COLUMNS.iter().find(|(c, _)| *c == name) {
+        let text = value.as_str().unwrap_or_default();
+        if !allowed.contains(&text) {
+            return Err(format!("expected one of {}", allowed.join(", ")));
+        }
+    }
+
+    let Some(text) = value.as_str() else {
+        return Ok(());
+    };
+    if text.trim().is_empty() {
+        return Err("empty string (null clears a column)".to_string());
+    }
+    if LIST_COLUMNS.contains(&name) && serde_json::from_str::<Vec<String>>(text).is_err() {
+        return Err("expected a JSON array of strings".to_string());
+    }
+    if IMAGE_COLUMNS.contains(&name) && !is_file_within(games_path, Path::new(text)) {
+        return Err("not an existing file inside the game library".to_string());
+    }
+    // An archived game's folder doesn't exist until it is restored
+    if name == "folder_path" && !is_folder_within(games_path, Path::new(text)) {
+        return Err("not a folder inside the game library".to_string());
+    }
+    if name == "archive_path" && !Path::new(text).is_file() {
+        return Err("archive file does not exist".to_string());
+    }
+    Ok(())
+}
+
+/// Whether `path` is an existing file inside `root`
+fn is_file_within(root: &Path, path: &Path) -> bool {
+    let (Ok(root), Ok(path)) = (std::fs::canonicalize(root), std::fs::canonicalize(path)) else {
+        return false;
+    };
+    path.starts_with(root) && path.is_file()
+}
+
+/// Whether `path` names a folder inside `root`: an existing one, or a
+/// missing one whose parent is inside it
+fn is_folder_within(root: &Path, path: &Path) -> bool {
+    let Ok(root) = std::fs::canonicalize(root) else {
+        return false;
+    };
+    match std::fs::canonicalize(path) {
+        Ok(path) => path != root && path.starts_with(&root) && path.is_dir(),
+        Err(_) => path
+            .parent()
+            .and_then(|parent| std::fs::canonicalize(parent).ok())
+            .is_some_and(|parent| parent.starts_with(&root)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use serde_json::json;
+
+    fn edit(value: Value) -> RawRow {
+        value.as_object().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_raw_edit() {
+        let library = tempfile::tempdir().unwrap();
+        let folder = library.path().join("Hades");
+        std::fs::create_dir(&folder).unwrap();
+        std::fs::write(folder.join("cover.jpg"), b"jpg").unwrap();
+        let cover = folder.join("cover.jpg").to_string_lossy().to_string();
+
+        let (pool, _keepalive) = db::test_pool().await;
+        let id = db::upsert_game(
+            &pool,
+            &folder.to_string_lossy(),
+            "Hades",
+            "Hades",
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let row = db::get_game_raw(&pool, id).await.unwrap().unwrap();
+        assert_eq!(row["folder_path"], json!(folder.to_string_lossy()));
+        assert_eq!(row["local_cover_path"], Value::Null);
+
+        let columns = db::get_game_columns(&pool).await.unwrap();
+        let check = |value: Value| validate(&columns, &edit(value), library.path());
+        assert!(check(json!({})).is_err());
+        assert!(check(json!({"nope": 1})).is_err());
+        assert!(check(json!({"id": 2})).is_err());
+        assert!(check(json!({"title": null})).is_err());
+        assert!(check(json!({"steam_app_id": "1145360"})).is_err());
+        assert!(check(json!({"hidden": 2})).is_err());
+        assert!(check(json!({"user_status": "finished"})).is_err());
+        assert!(check(json!({"genres": "Action"})).is_err());
+        assert!(check(json!({"local_cover_path": "/etc/passwd"})).is_err());
+        assert!(check(json!({"folder_path": library.path().to_string_lossy()})).is_err());
+        assert!(check(json!({"folder_path": "/tmp/elsewhere/Hades"})).is_err());
+        let archived = library
+            .path()
+            .join("Hades II")
+            .to_string_lossy()
+            .to_string();
+        assert!(check(json!({"folder_path": archived})).is_ok());
+
+        let changes = check(json!({
+            "local_cover_path": cover,
+            "save_path_pattern": "%APPDATA%/Hades",
+            "genres": "[\"Action\"]",
+            "release_date": "17 Sep, 2020",
+            "review_summary": "Sehr positiv",
+            "hidden": 0
+        }))
+        .unwrap();
+        assert_eq!(changes.len(), 8);
+        db::update_game_raw(&pool, id, &changes).await.unwrap();
+
+        let row = db::get_game_raw(&pool, id).await.unwrap().unwrap();
+        assert_eq!(row["local_cover_path"], json!(cover));
+        assert_eq!(row["save_path_pattern"], json!("%APPDATA%/Hades"));
+        assert_eq!(row["release_date_iso"], json!("2020-09-17"));
+        assert_eq!(row["review_band"], json!("very_positive"));
+        assert_eq!(row["hidden"], json!(0));
+        assert_eq!(db::get_undo_operations(&pool, 10).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,329 @@
+//! Redistributable runtimes bundled with games
+//!
+//! Repacks and store installs ship the runtimes a game needs (Visual C++,
+//! DirectX, .NET, ...) as installers in folders like `_CommonRedist` or
+//! `_Redist`. The scan records which ones each game bundles, and
+//! GET /api/reports/runtimes lists them library-wide, so a new machine can
+//! get every runtime installed in one pass instead of one failed launch at a
+//! time.
+//!
+//! Only files directly in a game folder and in folders whose name looks like
+//! a redist folder are looked at, so the scan stays cheap on large games.
+
+use std::path::Path;
+
+use regex::Regex;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+/// Folder names (lowercase) searched for installers
+const REDIST_DIRS: &[&str] = &[
+    "_commonredist",
+    "_redist",
+    "redist",
+    "redistributables",
+    "commonredist",
+    "prerequisites",
+    "prereqs",
+    "support",
+    "installers",
+    "directx",
+    "vcredist",
+    "dotnet",
+    "physx",
+];
+
+/// How deep to look inside a redist folder
+const MAX_REDIST_DEPTH: usize = 4;
+
+/// A runtime installer found in a game folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct Redist {
+    /// "vcredist", "directx", "dotnet", "physx", "xna" or "openal"
+    pub runtime: String,
+    /// e.g. "2015-2022" for Visual C++, "4.8" for .NET ("" = unknown)
+    pub version: String,
+    /// "x64", "x86" or "" when the installer covers both or doesn't say
+    pub arch: String,
+    /// Installer path relative to the game folder, with forward slashes
+    pub installer: String,
+}
+
+/// Runtimes bundled with a game folder, one entry per runtime, version and
+/// architecture
+pub fn detect(game_dir: &Path) -> Vec<Redist> {
+    let Ok(entries) = std::fs::read_dir(game_dir) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<Redist> = Vec::new();
+    let mut add = |path: &Path| {
+        let Ok(relative) = path.strip_prefix(game_dir) else {
+            return;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if let Some(redist) = identify(&relative) {
+            let known = found.iter().any(|r| {
+                r.runtime == redist.runtime && r.version == redist.version && r.arch == redist.arch
+            });
+            if !known {
+                found.push(redist);
+            }
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_file() {
+            add(&path);
+        } else if file_type.is_dir() && is_redist_dir(&entry.file_name().to_string_lossy()) {
+            for file in WalkDir::new(&path)
+                .max_depth(MAX_REDIST_DEPTH)
+                .into_iter()
+                .flatten()
+                .filter(|e| e.file_type().is_file())
+            {
+                add(file.path());
+            }
+        }
+    }
+
+    found.sort_by(|a, b| (&a.runtime, &a.version, &a.arch).cmp(&(&b.runtime, &b.version, &b.arch)));
+    found
+}
+
+fn is_redist_dir(name: &str) -> bool {
+    REDIST_DIRS.contains(&name.to_lowercase().as_str())
+}
+
+/// Identify the runtime an installer path belongs to
+///
+/// The file name decides; folders on the path fill in a version or
+/// architecture the name leaves out (`_CommonRedist/vcredist/2019/...`).
+pub fn identify(relative_path: &str) -> Option<Redist> {
+    let lower = relative_path.to_lowercase();
+    let (dirs, file) = match lower.rsplit_once('/') {
+        Some((dirs, file)) => (dirs, file),
+        None => ("", lower.as_str()),
+    };
+    // DirectX's .cab files next to DXSETUP.exe and the like are skipped
+    let is_installer = [".exe", ".msi"].iter().any(|ext| file.ends_with(ext));
+    if !is_installer {
+        return None;
+    }
+
+    let (runtime, version) = if file.starts_with("vc_redist") || file.starts_with("vcredist") {
+        ("vcredist", vc_version(file).or_else(|| vc_version(dirs)))
+    } else if file == "dxsetup.exe" || file == "dxwebsetup.exe" {
+        ("directx", None)
+    } else if let Some(version) = dotnet_version(file) {
+        ("dotnet", Some(version))
+    } else if file.starts_with("physx") {
+        ("physx", None)
+    } else if file.starts_with("xnafx") {
+        ("xna", xna_version(file))
+    } else if file == "oalinst.exe" {
+        ("openal", None)
+    } else if dirs.split('/').any(|d| d == "dotnet") && file.contains("dotnet") {
+        ("dotnet", None)
+    } else {
+        return None;
+    };
+
+    Some(Redist {
+        runtime: runtime.to_string(),
+        version: version.unwrap_or_default(),
+        arch: arch(file)
+            .or_else(|| arch(dirs))
+            .unwrap_or_default()
+            .to_string(),
+        installer: relative_path.to_string(),
+    })
+}
+
+/// Visual C++ runtime from a year in the text
+///
+/// 2015, 2017, 2019 and 2022 share one runtime, so they are reported as one.
+fn vc_version(text: &str) -> Option<String> {
+    let re = Regex::new(r"(?:^|[^0-9])(20(?:05|08|10|12|13|15|17|19|22))(?:[^0-9]|$)").unwrap();
+    let year = re.captures(text)?.get(1)?.as_str();
+    Some(match year {
+        "2015" | "2017" | "2019" | "2022" => "2015-2022".to_string(),
+        year => year.to_string(),
+    })
+}
+
+/// .NET version from an installer name: "NDP481-x86-x64-AllOS-ENU.exe",
+/// "dotNetFx40_Full_x86_x64.exe", "windowsdesktop-runtime-8.0.1-win-x64.exe"
+fn dotnet_version(file: &str) -> Option<String> {
+    let framework = Regex::new(r"^(?:ndp|dotnetfx)(\d)(\d)(\d)?").unwrap();
+    if let Some(c) = framework.captures(file) {
+        let mut version = format!("{}.{}", &c[1], &c[2]);
+        if let Some(patch) = c.get(3) {
+            version = format!("{}.{}", version, patch.as_str());
+        }
+        return Some(version);
+    }
+    let core = Regex::new(r"^(?:windowsdesktop-|dotnet-|aspnetcore-)runtime-(\d+\.\d+)").unwrap();
+    core.captures(file).map(|c| c[1].to_string())
+}
+
+/// XNA Framework version: "xnafx40_redist.msi" -> "4.0"
+fn xna_version(file: &str) -> Option<String> {
+    let re = Regex::new(r"^xnafx(\d)(\d)").unwrap();
+    re.captures(file).map(|c| format!("{}.{}", &c[1], &c[2]))
+}
+
+fn arch(text: &str) -> Option<&'static str> {
+    let (mut x64, mut x86) = (false, false);
+    for word in text.split(|c: char| !c.is_ascii_alphanumeric()) {
+        match word {
+            "x64" | "amd64" => x64 = true,
+            "x86" => x86 = true,
+            _ => {}
+        }
+    }
+    match (x64, x86) {
+        (true, false) => Some("x64"),
+        (false, true) => Some("x86"),
+        // "dotNetFx40_Full_x86_x64.exe" covers both
+        _ => None,
+    }
+}
+
+/// A runtime installer recorded for a library game
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct LibraryRedist {
+    pub game_id: i64,
+    pub title: String,
+    #[sqlx(flatten)]
+    pub redist: Redist,
+}
+
+/// A runtime needed somewhere in the library
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeRequirement {
+    pub runtime: String,
+    pub version: String,
+    pub arch: String,
+    /// Games bundling it, by title
+    pub games: Vec<RuntimeGame>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeGame {
+    pub game_id: i64,
+    pub title: String,
+    pub installer: String,
+}
+
+/// Group installers by runtime, version and architecture
+///
+/// Expects rows sorted by runtime, version, architecture and title.
+pub fn requirements(rows: Vec<LibraryRedist>) -> Vec<RuntimeRequirement> {
+    let mut report: Vec<RuntimeRequirement> = Vec::new();
+    for row in rows {
+        let game = RuntimeGame {
+            game_id: row.game_id,
+            title: row.title,
+            installer: row.redist.installer,
+        };
+        match report.last_mut() {
+            Some(last)
+                if last.runtime == row.redist.runtime
+                    && last.version == row.redist.version
+                    && last.arch == row.redist.arch =>
+            {
+                last.games.push(game)
+            }
+            _ => report.push(RuntimeRequirement {
+                runtime: row.redist.runtime,
+                version: row.redist.version,
+                arch: row.redist.arch,
+                games: vec![game],
+            }),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(path: &str) -> Option<(String, String, String)> {
+        identify(path).map(|r| (r.runtime, r.version, r.arch))
+    }
+
+    fn tuple(runtime: &str, version: &str, arch: &str) -> Option<(String, String, String)> {
+        Some((runtime.into(), version.into(), arch.into()))
+    }
+
+    #[test]
+    fn test_identify() {
+        assert_eq!(
+            id("_CommonRedist/vcredist/2019/VC_redist.x64.exe"),
+            tuple("vcredist", "2015-2022", "x64")
+        );
+        assert_eq!(
+            id("_Redist/vcredist_2013_x86.exe"),
+            tuple("vcredist", "2013", "x86")
+        );
+        assert_eq!(id("_Redist/vcredist_x64.exe"), tuple("vcredist", "", "x64"));
+        assert_eq!(
+            id("_CommonRedist/DirectX/Jun2010/DXSETUP.exe"),
+            tuple("directx", "", "")
+        );
+        assert_eq!(
+            id("_CommonRedist/DotNet/4.8/NDP48-x86-x64-AllOS-ENU.exe"),
+            tuple("dotnet", "4.8", "")
+        );
+        assert_eq!(
+            id("_Redist/dotNetFx40_Full_x86_x64.exe"),
+            tuple("dotnet", "4.0", "")
+        );
+        assert_eq!(
+            id("Redist/windowsdesktop-runtime-8.0.1-win-x64.exe"),
+            tuple("dotnet", "8.0", "x64")
+        );
+        assert_eq!(id("_Redist/xnafx40_redist.msi"), tuple("xna", "4.0", ""));
+        assert_eq!(id("Redist/oalinst.exe"), tuple("openal", "", ""));
+        assert_eq!(id("_Redist/DirectX/Apr2005_d3dx9_25_x64.cab"), None);
+        assert_eq!(id("Game.exe"), None);
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = tempfile::tempdir().unwrap();
+        let game = dir.path();
+        for file in [
+            "Game.exe",
+            "vcredist_x86.exe",
+            "_CommonRedist/vcredist/2019/VC_redist.x64.exe",
+            "_CommonRedist/vcredist/2022/VC_redist.x64.exe",
+            "_CommonRedist/DirectX/Jun2010/DXSETUP.exe",
+            // Not a redist folder
+            "Engine/Binaries/ThirdParty/VC_redist.x64.exe",
+        ] {
+            let path = game.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+
+        let found: Vec<_> = detect(game)
+            .into_iter()
+            .map(|r| (r.runtime, r.version, r.arch))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                tuple("directx", "", "").unwrap(),
+                tuple("vcredist", "", "x86").unwrap(),
+                tuple("vcredist", "2015-2022", "x64").unwrap(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Re-evaluating weak matches (POST /api/maintenance/rematch-pending)
+//!
+//! A game's title is cleaned from its folder name when the folder is first
+//! scanned, and enrichment only searches Steam for games that are still
+//! pending. A game scanned before a cleanup pattern was added or fixed keeps
+//! the old title, and the failed or low-confidence match that came from it.
+//! Likewise a title mapping recorded or imported later is only used for games
+//! enriched after it.
+//!
+//! This pass looks at pending and low-confidence games, re-cleans their
+//! folder names, and matches again only the ones whose title changed or
+//! whose title now has a stored mapping to another app. Games whose folder
+//! names a different app in its files (see folder_appid.rs) are matched
+//! again whatever their confidence. A new match replaces
+//! the old one only when it is better; the re-cleaned title is kept either
+//! way.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::models::Game;
+use crate::{db, mappings, matching, scanner};
+
+/// Why a game is matched again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RematchReason {
+    /// The folder name cleans to a different title now
+    TitleChanged,
+    /// A stored mapping points the title at another app
+    Mapping,
+    /// A file in the folder names another app
+    FolderAppId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RematchOutcome {
+    /// Matched to a better app and enriched
+    Improved,
+    /// The new match was no better; the old one is kept
+    NotImproved,
+    /// Nothing matched the new title
+    NotFound,
+    /// The title is mapped as not on Steam
+    NotOnSteam,
+    /// The new match's details couldn't be fetched or stored
+    Failed,
+}
+
+/// A game to match again
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub game: Game,
+    /// Title to match with (the re-cleaned one, or the current one)
+    pub title: String,
+    pub reason: RematchReason,
+    /// Stored mapping for `title`
+    pub mapped_app_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RematchEntry {
+    pub game_id: i64,
+    pub old_title: String,
+    pub new_title: String,
+    pub reason: RematchReason,
+    pub old_app_id: Option<i64>,
+    pub old_confidence: Option<f64>,
+    pub new_app_id: Option<i64>,
+    pub new_confidence: Option<f64>,
+    pub outcome: RematchOutcome,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RematchReport {
+    /// Pending and low-confidence games looked at
+    pub checked: usize,
+    /// Games matched again in this batch
+    pub reevaluated: usize,
+    pub improved: usize,
+    /// Candidates left for the next call
+    pub remaining: usize,
+    pub games: Vec<RematchEntry>,
+}
+
+/// Title the game's folder name cleans to, if it differs from the stored one
+pub fn recleaned_title(game: &Game) -> Option<String> {
+    let title = scanner::clean_title(&game.folder_name);
+    (!title.is_empty() && title != game.title).then_some(title)
+}
+
+/// Whether a new match is better than the game's current one
+pub fn is_improvement(game: &Game, confidence: f64) -> bool {
+    game.steam_app_id.is_none() || confidence > game.match_confidence.unwrap_or(0.0)
+}
+
+/// Pending and low-confidence games, and the ones among them to match again
+pub async fn candidates(pool: &SqlitePool) -> Result<(usize, Vec<Candidate>), sqlx::Error> {
+    let games = db::get_games_for_rematch(pool, matching::MATCH_REVIEW_CONFIDENCE).await?;
+    let checked = games.len();
+
+    let mut candidates = Vec::new();
+    for game in games {
+        let (title, title_changed) = match recleaned_title(&game) {
+            Some(title) => (title, true),
+            None => (game.title.clone(), false),
+        };
+        let mapped_app_id = mappings::lookup(pool, &title).await;
+
+        let reason = if title_changed {
+            RematchReason::TitleChanged
+        } else if mapped_app_id.is_some_and(|id| Some(id) != game.steam_app_id) {
+            RematchReason::Mapping
+        } else if mapped_app_id.is_none()
+            && game
+                .folder_app_id
+                .is_some_and(|id| Some(id) != game.steam_app_id)
+        {
+            RematchReason::FolderAppId
+        } else {
+            continue;
+        };
+        candidates.push(Candidate {
+            game,
+            title,
+            reason,
+            mapped_app_id,
+        });
+    }
+    Ok((checked, candidates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    #[tokio::test]
+    async fn test_candidates() {
+        let (pool, _keepalive) = db::test_pool().await;
+
+        let add = |folder: &'static str, title: &'static str| {
+            let pool = pool.clone();
+            async move {
+                db::upsert_game(
+                    &pool,
+                    &format!("/games/{}", folder),
+                    folder,
+                    title,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap()
+            }
+        };
+        // Scanned before "[FitGirl Repack]" was cleaned
+        let stale = add("Hades [FitGirl Repack]", "Hades [FitGirl Repack]").await;
+        let clean = add("Celeste", "Celeste").await;
+        let mapped = add("GTA V", "GTA V").await;
+        let confident = add("Elden Ring [DODI Repack]", "Elden Ring [DODI Repack]").await;
+        sqlx::query("UPDATE games SET match_status = 'matched', steam_app_id = 1, match_confidence = 0.95 WHERE id = ?")
+            .bind(confident)
+            .execute(&pool)
+            .await
+            .unwrap();
+        mappings::record(&pool, "GTA V", 271590).await;
+        // Confidently matched to the wrong app
+        let named = add("Witcher 3", "Witcher 3").await;
+        sqlx::query("UPDATE games SET match_status = 'matched', steam_app_id = 20920, match_confidence = 0.9, folder_app_id = 292030 WHERE id = ?")
+            .bind(named)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (checked, found) = candidates(&pool).await.unwrap();
+        assert_eq!(checked, 4);
+        let found: Vec<_> = found
+            .iter()
+            .map(|c| (c.game.id, c.title.as_str(), c.reason, c.mapped_app_id))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (mapped, "GTA V", RematchReason::Mapping, Some(271590)),
+                (stale, "Hades", RematchReason::TitleChanged, None),
+                (named, "Witcher 3", RematchReason::FolderAppId, None),
+            ]
+        );
+        assert!(!found.iter().any(|c| c.0 == clean));
+    }
+
+    #[test]
+    fn test_is_improvement() {
+        let mut game = Game {
+            match_status: "pending".to_string(),
+            ..create_test_game(1, "Hades")
+        };
+        assert!(is_improvement(&game, 0.5));
+
+        game.steam_app_id = Some(42);
+        game.match_confidence = Some(0.6);
+        assert!(is_improvement(&game, 0.9));
+        assert!(!is_improvement(&game, 0.6));
+    }
+}
@@ -0,0 +1,266 @@
+//! Playtime reports and the periodic digest
+//!
+//! Reports aggregate closed play sessions over calendar weeks (Monday start)
+//! or months, in UTC. The digest scheduler sends the previous complete period
+//! through the notification webhook once per period.
+
+use std::time::Duration;
+
+use crate::http::HttpClient;
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::{
+    config::{DigestSchedule, NotificationsConfig},
+    db,
+    models::{BacklogProgress, GamePlaytime},
+    notifications::{self, WebhookEvent},
+};
+
+/// How often the digest scheduler checks whether a digest is due
+const DIGEST_CHECK_INTERVAL_SECS: u64 = 3600;
+
+/// Number of games listed in the digest text
+const DIGEST_TOP_GAMES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportPeriod {
+    Week,
+    Month,
+}
+
+impl ReportPeriod {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "week" | "weekly" => Some(ReportPeriod::Week),
+            "month" | "monthly" => Some(ReportPeriod::Month),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportPeriod::Week => "week",
+            ReportPeriod::Month => "month",
+        }
+    }
+
+    /// Start (inclusive) and end (exclusive) of the period containing `today`,
+    /// shifted back by `offset` periods
+    pub fn bounds(&self, today: NaiveDate, offset: u32) -> (NaiveDate, NaiveDate) {
+        match self {
+            ReportPeriod::Week => {
+                let monday =
+                    today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+                let start = monday - chrono::Duration::weeks(offset as i64);
+                (start, start + chrono::Duration::weeks(1))
+            }
+            ReportPeriod::Month => {
+                let first = today.with_day(1).unwrap_or(today);
+                let start = first - Months::new(offset);
+                (start, start + Months::new(1))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaytimeReport {
+    pub period: &'static str,
+    /// First day of the period (inclusive)
+    pub start: String,
+    /// Day after the period ends (exclusive)
+    pub end: String,
+    pub total_active_mins: i64,
+    pub session_count: i64,
+    pub games: Vec<GamePlaytime>,
+    /// Games whose first-ever session falls within the period
+    pub newly_started: i64,
+    pub backlog: BacklogProgress,
+}
+
+/// Build a playtime report for a period
+pub async fn build_playtime_report(
+    pool: &SqlitePool,
+    period: ReportPeriod,
+    offset: u32,
+) -> Result<PlaytimeReport, sqlx::Error> {
+    let (start, end) = period.bounds(Utc::now().date_naive(), offset);
+    let start_ts = format!("{} 00:00:00", start);
+    let end_ts = format!("{} 00:00:00", end);
+
+    let games = db::get_playtime_by_game(pool, &start_ts, &end_ts).await?;
+    let newly_started = db::count_games_first_played_between(pool, &start_ts, &end_ts).await?;
+    let backlog = db::get_backlog_progress(pool).await?;
+
+    Ok(PlaytimeReport {
+        period: period.as_str(),
+        start: start.to_string(),
+        end: end.to_string(),
+        total_active_mins: games.iter().map(|g| g.active_mins).sum(),
+        session_count: games.iter().map(|g| g.sessions).sum(),
+        games,
+        newly_started,
+        backlog,
+    })
+}
+
+/// Format minutes as "3h 20m"
+fn format_mins(mins: i64) -> String {
+    if mins >= 60 {
+        format!("{}h {}m", mins / 60, mins % 60)
+    } else {
+        format!("{}m", mins)
+    }
+}
+
+/// Render the human-readable digest text for a report
+pub fn format_digest(report: &PlaytimeReport) -> String {
+    let mut lines = Vec::new();
+
+    if report.games.is_empty() {
+        lines.push("No play sessions recorded.".to_string());
+    } else {
+        lines.push(format!(
+            "Played {} across {} game(s) in {} session(s).",
+            format_mins(report.total_active_mins),
+            report.games.len(),
+            report.session_count
+        ));
+        for game in report.games.iter().take(DIGEST_TOP_GAMES) {
+            lines.push(format!(
+                "- {}: {}",
+                game.title,
+                format_mins(game.active_mins)
+            ));
+        }
+        if report.games.len() > DIGEST_TOP_GAMES {
+            lines.push(format!(
+                "- …and {} more",
+                report.games.len() - DIGEST_TOP_GAMES
+            ));
+        }
+    }
+
+    let b = &report.backlog;
+    lines.push(format!(
+        "Backlog: {} unplayed, {} playing, {} completed, {} abandoned ({} newly started).",
+        b.unplayed, b.playing, b.completed, b.abandoned, report.newly_started
+    ));
+
+    lines.join("\n")
+}
+
+/// Build the webhook event for a report
+pub fn digest_event(report: &PlaytimeReport) -> WebhookEvent {
+    let title = format!(
+        "GameVault {} playtime digest ({} – {})",
+        if report.period == "week" {
+            "weekly"
+        } else {
+            "monthly"
+        },
+        report.start,
+        report.end
+    );
+    WebhookEvent::new("playtime_digest", title, format_digest(report)).with_data(report)
+}
+
+/// Send the digest for the previous complete period if it hasn't been sent yet
+async fn send_due_digest(
+    pool: &SqlitePool,
+    client: &HttpClient,
+    config: &NotificationsConfig,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let period = match config.digest {
+        DigestSchedule::Off => return Ok(()),
+        DigestSchedule::Weekly => ReportPeriod::Week,
+        DigestSchedule::Monthly => ReportPeriod::Month,
+    };
+
+    let report = build_playtime_report(pool, period, 1).await?;
+    if db::digest_already_sent(pool, report.period, &report.start).await? {
+        return Ok(());
+    }
+
+    notifications::send_webhook(client, &config.webhook_url, &digest_event(&report)).await?;
+    db::record_digest_sent(pool, report.period, &report.start).await?;
+    Ok(())
+}
+
+/// Spawn the background digest scheduler (no-op when disabled or no webhook)
+pub fn spawn_digest_scheduler(pool: SqlitePool, client: HttpClient, config: NotificationsConfig) {
+    if config.digest == DigestSchedule::Off || config.webhook_url.is_empty() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = send_due_digest(&pool, &client, &config).await {
+                tracing::warn!("Failed to send playtime digest: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(DIGEST_CHECK_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_week_bounds() {
+        // 2024-05-15 is a Wednesday
+        let (start, end) = ReportPeriod::Week.bounds(date("2024-05-15"), 0);
+        assert_eq!((start, end), (date("2024-05-13"), date("2024-05-20")));
+
+        let (start, end) = ReportPeriod::Week.bounds(date("2024-05-15"), 1);
+        assert_eq!((start, end), (date("2024-05-06"), date("2024-05-13")));
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        let (start, end) = ReportPeriod::Month.bounds(date("2024-03-31"), 0);
+        assert_eq!((start, end), (date("2024-03-01"), date("2024-04-01")));
+
+        // Previous month across a year boundary
+        let (start, end) = ReportPeriod::Month.bounds(date("2024-01-10"), 1);
+        assert_eq!((start, end), (date("2023-12-01"), date("2024-01-01")));
+    }
+
+    #[test]
+    fn test_format_digest() {
+        let report = PlaytimeReport {
+            period: "week",
+            start: "2024-05-06".to_string(),
+            end: "2024-05-13".to_string(),
+            total_active_mins: 200,
+            session_count: 3,
+            games: vec![GamePlaytime {
+                game_id: 1,
+                title: "Hades".to_string(),
+                active_mins: 200,
+                sessions: 3,
+                last_played: "2024-05-10T20:00:00Z".to_string(),
+            }],
+            newly_started: 1,
+            backlog: BacklogProgress {
+                unplayed: 10,
+                playing: 2,
+                completed: 5,
+                abandoned: 1,
+                total: 18,
+            },
+        };
+
+        let text = format_digest(&report);
+        assert!(text.contains("Played 3h 20m across 1 game(s) in 3 session(s)."));
+        assert!(text.contains("- Hades: 3h 20m"));
+        assert!(text.contains("10 unplayed, 2 playing, 5 completed, 1 abandoned (1 newly started)"));
+    }
+}
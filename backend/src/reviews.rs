@@ -0,0 +1,223 @@
+//! Review score bands
+//!
+//! Steam describes a game's reviews with a summary like "Very Positive", in
+//! whatever language the store answered in ("Sehr positiv", "Très
+//! positives"). The summary is stored as returned; next to it games get a
+//! `review_band`, one of Steam's nine ratings as a stable key, so filters
+//! work whatever the language. The band comes from Steam's numeric rating
+//! when the API returns one, else from the summary text, else from the
+//! positive share and review count with Steam's own thresholds.
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+
+/// Reviews below which Steam shows no rating
+const MIN_RATED_REVIEWS: i64 = 10;
+
+/// Steam's rating of a game's user reviews, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewBand {
+    OverwhelminglyPositive,
+    VeryPositive,
+    Positive,
+    MostlyPositive,
+    Mixed,
+    MostlyNegative,
+    Negative,
+    VeryNegative,
+    OverwhelminglyNegative,
+}
+
+const ALL: [ReviewBand; 9] = [
+    ReviewBand::OverwhelminglyPositive,
+    ReviewBand::VeryPositive,
+    ReviewBand::Positive,
+    ReviewBand::MostlyPositive,
+    ReviewBand::Mixed,
+    ReviewBand::MostlyNegative,
+    ReviewBand::Negative,
+    ReviewBand::VeryNegative,
+    ReviewBand::OverwhelminglyNegative,
+];
+
+impl ReviewBand {
+    /// Column value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReviewBand::OverwhelminglyPositive => "overwhelmingly_positive",
+            ReviewBand::VeryPositive => "very_positive",
+            ReviewBand::Positive => "positive",
+            ReviewBand::MostlyPositive => "mostly_positive",
+            ReviewBand::Mixed => "mixed",
+            ReviewBand::MostlyNegative => "mostly_negative",
+            ReviewBand::Negative => "negative",
+            ReviewBand::VeryNegative => "very_negative",
+            ReviewBand::OverwhelminglyNegative => "overwhelmingly_negative",
+        }
+    }
+
+    /// English summary, the key of the `review_summary` translations
+    pub fn summary(self) -> &'static str {
+        match self {
+            ReviewBand::OverwhelminglyPositive => "Overwhelmingly Positive",
+            ReviewBand::VeryPositive => "Very Positive",
+            ReviewBand::Positive => "Positive",
+            ReviewBand::MostlyPositive => "Mostly Positive",
+            ReviewBand::Mixed => "Mixed",
+            ReviewBand::MostlyNegative => "Mostly Negative",
+            ReviewBand::Negative => "Negative",
+            ReviewBand::VeryNegative => "Very Negative",
+            ReviewBand::OverwhelminglyNegative => "Overwhelmingly Negative",
+        }
+    }
+
+    /// Steam's `review_score` (9 = Overwhelmingly Positive ... 1 =
+    /// Overwhelmingly Negative, 0 = not enough reviews)
+    pub fn from_steam_score(score: i64) -> Option<Self> {
+        match score {
+            1..=9 => Some(ALL[(9 - score) as usize]),
+            _ => None,
+        }
+    }
+
+    /// Band of a summary in any language with a translation table
+    pub fn from_summary(summary: &str) -> Option<Self> {
+        let key = i18n::find_key("review_summary", summary)?;
+        ALL.into_iter().find(|band| band.summary() == key)
+    }
+
+    /// Band Steam gives for a positive share (0-100) and review count
+    pub fn from_share(positive_percent: i64, count: i64) -> Option<Self> {
+        if count < MIN_RATED_REVIEWS {
+            return None;
+        }
+        Some(match positive_percent {
+            95.. if count >= 500 => ReviewBand::OverwhelminglyPositive,
+            80.. if count >= 50 => ReviewBand::VeryPositive,
+            80.. => ReviewBand::Positive,
+            70.. => ReviewBand::MostlyPositive,
+            40.. => ReviewBand::Mixed,
+            20.. => ReviewBand::MostlyNegative,
+            _ if count >= 500 => ReviewBand::OverwhelminglyNegative,
+            _ if count >= 50 => ReviewBand::VeryNegative,
+            _ => ReviewBand::Negative,
+        })
+    }
+
+    /// Band from whatever is known, most reliable source first
+    pub fn detect(
+        steam_score: Option<i64>,
+        summary: Option<&str>,
+        positive_percent: Option<i64>,
+        count: Option<i64>,
+    ) -> Option<Self> {
+        steam_score
+            .and_then(Self::from_steam_score)
+            .or_else(|| summary.and_then(Self::from_summary))
+            .or_else(|| Self::from_share(positive_percent?, count?))
+    }
+}
+
+/// Value of the `review_band` list filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ReviewBandFilter {
+    Group(ReviewGroup),
+    Band(ReviewBand),
+}
+
+/// Bands grouped the way the store's filters group them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewGroup {
+    /// Mostly Positive and better
+    Positive,
+    Mixed,
+    /// Mostly Negative and worse
+    Negative,
+}
+
+impl ReviewBandFilter {
+    /// Bands the filter matches
+    pub fn bands(self) -> Vec<ReviewBand> {
+        match self {
+            ReviewBandFilter::Group(ReviewGroup::Positive) => ALL[..4].to_vec(),
+            ReviewBandFilter::Group(ReviewGroup::Mixed) => vec![ReviewBand::Mixed],
+            ReviewBandFilter::Group(ReviewGroup::Negative) => ALL[5..].to_vec(),
+            ReviewBandFilter::Band(band) => vec![band],
+        }
+    }
+
+    /// JSON array of column values, for `json_each`
+    pub fn to_json(self) -> String {
+        let values: Vec<&str> = self.bands().into_iter().map(ReviewBand::as_str).collect();
+        serde_json::to_string(&values).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_band() {
+        assert_eq!(
+            ReviewBand::from_steam_score(9),
+            Some(ReviewBand::OverwhelminglyPositive)
+        );
+        assert_eq!(ReviewBand::from_steam_score(5), Some(ReviewBand::Mixed));
+        assert_eq!(ReviewBand::from_steam_score(0), None);
+
+        assert_eq!(
+            ReviewBand::from_summary("Very Positive"),
+            Some(ReviewBand::VeryPositive)
+        );
+        assert_eq!(
+            ReviewBand::from_summary("größtenteils positiv"),
+            Some(ReviewBand::MostlyPositive)
+        );
+        assert_eq!(
+            ReviewBand::from_summary("Extrêmement négatives"),
+            Some(ReviewBand::OverwhelminglyNegative)
+        );
+        assert_eq!(ReviewBand::from_summary("3 user reviews"), None);
+
+        assert_eq!(
+            ReviewBand::from_share(97, 600),
+            Some(ReviewBand::OverwhelminglyPositive)
+        );
+        assert_eq!(ReviewBand::from_share(97, 30), Some(ReviewBand::Positive));
+        assert_eq!(
+            ReviewBand::from_share(15, 60),
+            Some(ReviewBand::VeryNegative)
+        );
+        assert_eq!(ReviewBand::from_share(100, 5), None);
+
+        // Steam's rating wins over a summary in an unknown language
+        assert_eq!(
+            ReviewBand::detect(Some(8), Some("非常に好評"), Some(50), Some(100)),
+            Some(ReviewBand::VeryPositive)
+        );
+        assert_eq!(
+            ReviewBand::detect(None, Some("非常に好評"), Some(50), Some(100)),
+            Some(ReviewBand::Mixed)
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let parse = |value: &str| serde_json::from_value::<ReviewBandFilter>(value.into());
+        assert_eq!(parse("positive").unwrap().bands().len(), 4);
+        assert_eq!(
+            parse("very_positive").unwrap().to_json(),
+            r#"["very_positive"]"#
+        );
+        assert_eq!(
+            parse("negative").unwrap().bands().last(),
+            Some(&ReviewBand::OverwhelminglyNegative)
+        );
+        assert!(parse("great").is_err());
+    }
+}
@@ -1,8 +1,21 @@
 use std::path::Path;
 
+use chrono::Datelike;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
+use crate::models::ScanReportSummary;
+use crate::redist::{self, Redist};
+use crate::{blocklist, genre_inference};
+
+/// Keeps a game folder out of the library when present inside it; at the
+/// library root it lists folder names to skip (`*`/`?` wildcards, `#` comments)
+pub const GVIGNORE: &str = ".gvignore";
+
+/// Library folders that belong to GameVault or aren't games
+const RESERVED_FOLDERS: &[&str] = &["game-library-app", "GameVault", "Adult"];
+
 /// Patterns to remove from folder names to get clean game titles
 const CLEANUP_PATTERNS: &[&str] = &[
     r"\[FitGirl.*?\]",
@@ -45,16 +58,110 @@ const EXCLUSION_PATTERNS: &[&str] = &[
     r"(?i)S\d{2}E\d{2}", // TV show pattern like S01E05
 ];
 
-/// Check if a folder name matches exclusion patterns (non-game content)
-fn is_excluded(folder_name: &str) -> bool {
-    for pattern in EXCLUSION_PATTERNS {
-        if let Ok(re) = Regex::new(pattern) {
-            if re.is_match(folder_name) {
-                return true;
-            }
+/// The exclusion pattern a folder name matches (non-game content)
+fn excluded_by(folder_name: &str) -> Option<&'static str> {
+    EXCLUSION_PATTERNS.iter().copied().find(|pattern| {
+        Regex::new(pattern)
+            .map(|re| re.is_match(folder_name))
+            .unwrap_or(false)
+    })
+}
+
+/// Why a scan left a folder out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Name starts with a dot
+    Hidden,
+    /// GameVault's own or a known non-game folder
+    Reserved,
+    /// A .rar/.zip folder
+    Archive,
+    /// Name looks like a movie or TV show
+    ExclusionPattern,
+    /// Listed in the library's .gvignore or holds a .gvignore file
+    Gvignore,
+    /// Nothing left of the name after cleanup
+    NoTitle,
+    /// Reserved by a wishlist entry and still empty (download not started)
+    PendingDownload,
+    /// The folder couldn't be read or stored
+    Failed,
+}
+
+/// A folder the scan left out, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedFolder {
+    pub folder_name: String,
+    pub reason: SkipReason,
+    /// The matching pattern, or the error
+    pub detail: Option<String>,
+}
+
+impl SkippedFolder {
+    pub fn new(folder_name: impl Into<String>, reason: SkipReason, detail: Option<String>) -> Self {
+        SkippedFolder {
+            folder_name: folder_name.into(),
+            reason,
+            detail,
         }
     }
-    false
+}
+
+/// A stored scan report with the folders it skipped
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanReport {
+    #[serde(flatten)]
+    pub summary: ScanReportSummary,
+    pub skipped_folders: Vec<SkippedFolder>,
+}
+
+/// Game folders found in the library, and the folders left out
+#[derive(Default)]
+pub struct DirectoryScan {
+    pub games: Vec<ScannedGame>,
+    pub skipped: Vec<SkippedFolder>,
+}
+
+/// Patterns from the library's .gvignore (missing file = none)
+fn read_gvignore(library: &Path) -> Vec<(String, Regex)> {
+    let Ok(text) = std::fs::read_to_string(library.join(GVIGNORE)) else {
+        return Vec::new();
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| Some((line.to_string(), blocklist::compile(line)?)))
+        .collect()
+}
+
+/// Why a folder must be skipped, before looking inside it
+fn skip_reason(
+    folder_name: &str,
+    folder: &Path,
+    ignored: &[(String, Regex)],
+) -> Option<(SkipReason, Option<String>)> {
+    if folder_name.starts_with('.') {
+        return Some((SkipReason::Hidden, None));
+    }
+    if RESERVED_FOLDERS.contains(&folder_name) {
+        return Some((SkipReason::Reserved, None));
+    }
+    if folder_name.ends_with(".rar") || folder_name.ends_with(".zip") {
+        return Some((SkipReason::Archive, None));
+    }
+    if let Some((line, _)) = ignored.iter().find(|(_, re)| re.is_match(folder_name)) {
+        return Some((SkipReason::Gvignore, Some(line.clone())));
+    }
+    if folder.join(GVIGNORE).exists() {
+        return Some((
+            SkipReason::Gvignore,
+            Some(format!("{}/{}", folder_name, GVIGNORE)),
+        ));
+    }
+    // Non-game content (movies, TV shows, etc.) - check raw name before cleanup
+    excluded_by(folder_name)
+        .map(|pattern| (SkipReason::ExclusionPattern, Some(pattern.to_string())))
 }
 
 pub struct ScannedGame {
@@ -62,15 +169,35 @@ pub struct ScannedGame {
     pub folder_name: String,
     pub clean_title: String,
     pub size_bytes: Option<i64>,
+    /// Size of everything in the folder, subfolders included (for the size
+    /// history, which must see updates that land in subfolders)
+    pub install_size_bytes: Option<i64>,
+    /// Release year from the folder name or an NFO file, used to tell
+    /// remasters from originals when matching
+    pub year_hint: Option<i32>,
+    /// Runtime installers bundled in the folder
+    pub runtimes: Vec<Redist>,
+    /// Genres and tags guessed from the folder name and files
+    pub inferred_genres: Vec<String>,
 }
 
 /// Clean a folder name to extract the game title
 pub fn clean_title(folder_name: &str) -> String {
+    clean_title_explained(folder_name).0
+}
+
+/// Clean a folder name, also returning the cleanup patterns that changed it
+pub fn clean_title_explained(folder_name: &str) -> (String, Vec<&'static str>) {
     let mut title = folder_name.to_string();
+    let mut applied = Vec::new();
 
     for pattern in CLEANUP_PATTERNS {
         if let Ok(re) = Regex::new(pattern) {
-            title = re.replace_all(&title, "").to_string();
+            let cleaned = re.replace_all(&title, "").to_string();
+            if cleaned != title {
+                applied.push(*pattern);
+                title = cleaned;
+            }
         }
     }
 
@@ -81,24 +208,100 @@ pub fn clean_title(folder_name: &str) -> String {
     let re_dashes = Regex::new(r"\s*-\s*$").unwrap();
     title = re_dashes.replace_all(&title, "").to_string();
 
-    title.trim().to_string()
+    (title.trim().to_string(), applied)
+}
+
+/// NFO files larger than this are not release notes
+const MAX_NFO_BYTES: u64 = 64 * 1024;
+
+/// Keep a year only if it's plausible as a game release year
+fn plausible_year(year: i32) -> Option<i32> {
+    let max = chrono::Utc::now().year() + 1;
+    (1970..=max).contains(&year).then_some(year)
+}
+
+/// Extract a release year from a folder name
+///
+/// Only years set apart the way release names do it count: `(2015)`,
+/// `[2015]` or scene-style `.2015.`. Bare numbers are left alone so titles
+/// like "Cyberpunk 2077" or "Football Manager 2024" aren't read as years.
+pub fn extract_year_hint(folder_name: &str) -> Option<i32> {
+    let re = Regex::new(r"[(\[.]((?:19|20)\d{2})[)\].]").unwrap();
+    let year = re
+        .captures_iter(folder_name)
+        .filter_map(|c| c[1].parse().ok())
+        .find_map(plausible_year);
+    year
+}
+
+/// Extract a release year from NFO text ("Release Date: 03/2015", "Year: 2015")
+fn parse_nfo_year(text: &str) -> Option<i32> {
+    let label = Regex::new(r"(?i)\b(release|released|date|year)\b").unwrap();
+    let year = Regex::new(r"\b((?:19|20)\d{2})\b").unwrap();
+
+    text.lines()
+        .filter(|line| label.is_match(line))
+        .flat_map(|line| {
+            year.captures_iter(line)
+                .filter_map(|c| c[1].parse().ok())
+                .collect::<Vec<i32>>()
+        })
+        .find_map(plausible_year)
+}
+
+/// Read a release year from the first NFO file directly inside a game folder
+fn read_nfo_year(path: &Path) -> Option<i32> {
+    let entries = std::fs::read_dir(path).ok()?;
+
+    for entry in entries.flatten() {
+        let file_path = entry.path();
+        let is_nfo = file_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("nfo"));
+        let small = entry
+            .metadata()
+            .is_ok_and(|m| m.is_file() && m.len() <= MAX_NFO_BYTES);
+        if !is_nfo || !small {
+            continue;
+        }
+
+        // NFOs are usually CP437, so read bytes and keep what's readable
+        if let Ok(bytes) = std::fs::read(&file_path) {
+            if let Some(year) = parse_nfo_year(&String::from_utf8_lossy(&bytes)) {
+                return Some(year);
+            }
+        }
+    }
+
+    None
 }
 
 /// Scan a directory for game folders
-pub fn scan_games_directory(path: &str) -> Vec<ScannedGame> {
-    let mut games = Vec::new();
+pub fn scan_games_directory(path: &str) -> DirectoryScan {
+    let mut scan = DirectoryScan::default();
 
     let base_path = Path::new(path);
     if !base_path.exists() {
         tracing::error!("Games path does not exist: {}", path);
-        return games;
+        return scan;
     }
+    let ignored = read_gvignore(base_path);
 
     for entry in WalkDir::new(path).min_depth(1).max_depth(1) {
         let entry = match entry {
             Ok(e) => e,
             Err(e) => {
                 tracing::warn!("Error reading directory entry: {}", e);
+                let name = e
+                    .path()
+                    .and_then(Path::file_name)
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                scan.skipped.push(SkippedFolder::new(
+                    name,
+                    SkipReason::Failed,
+                    Some(e.to_string()),
+                ));
                 continue;
             }
         };
@@ -109,20 +312,10 @@ pub fn scan_games_directory(path: &str) -> Vec<ScannedGame> {
 
         let folder_name = entry.file_name().to_string_lossy().to_string();
 
-        // Skip hidden folders and known non-game folders
-        if folder_name.starts_with('.')
-            || folder_name == "game-library-app"
-            || folder_name == "GameVault"
-            || folder_name == "Adult"
-            || folder_name.ends_with(".rar")
-            || folder_name.ends_with(".zip")
-        {
-            continue;
-        }
-
-        // Skip non-game content (movies, TV shows, etc.) - check raw name before cleanup
-        if is_excluded(&folder_name) {
-            tracing::info!("Excluding non-game content: {}", folder_name);
+        if let Some((reason, detail)) = skip_reason(&folder_name, entry.path(), &ignored) {
+            tracing::debug!("Skipping folder {} ({:?})", folder_name, reason);
+            scan.skipped
+                .push(SkippedFolder::new(folder_name, reason, detail));
             continue;
         }
 
@@ -133,17 +326,30 @@ pub fn scan_games_directory(path: &str) -> Vec<ScannedGame> {
         let size_bytes = get_folder_size_estimate(entry.path());
 
         if !clean_title.is_empty() {
-            games.push(ScannedGame {
+            let year_hint = extract_year_hint(&folder_name).or_else(|| read_nfo_year(entry.path()));
+            let inferred_genres = genre_inference::infer(&folder_name, entry.path());
+            scan.games.push(ScannedGame {
                 folder_path,
                 folder_name,
                 clean_title,
                 size_bytes,
+                install_size_bytes: get_folder_size(entry.path()),
+                year_hint,
+                runtimes: redist::detect(entry.path()),
+                inferred_genres,
             });
+        } else {
+            scan.skipped
+                .push(SkippedFolder::new(folder_name, SkipReason::NoTitle, None));
         }
     }
 
-    tracing::info!("Scanned {} game folders", games.len());
-    games
+    tracing::info!(
+        "Scanned {} game folders, skipped {}",
+        scan.games.len(),
+        scan.skipped.len()
+    );
+    scan
 }
 
 /// Get an estimate of folder size (for performance, only counts top-level files)
@@ -167,6 +373,25 @@ fn get_folder_size_estimate(path: &Path) -> Option<i64> {
     }
 }
 
+/// Total size of the files in a folder and its subfolders, GameVault's own
+/// `.gamevault` folder (cached art, save backups) excluded
+fn get_folder_size(path: &Path) -> Option<i64> {
+    let total: u64 = WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|e| e.depth() != 1 || e.file_name() != ".gamevault")
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum();
+
+    if total > 0 {
+        Some(total as i64)
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,4 +420,101 @@ mod tests {
             "C&C - Remastered Collection"
         );
     }
+
+    #[test]
+    fn test_extract_year_hint() {
+        assert_eq!(
+            extract_year_hint("Tomb Raider (2013) [FitGirl Repack]"),
+            Some(2013)
+        );
+        assert_eq!(
+            extract_year_hint("Resident.Evil.4.2023.PROPER-GRP"),
+            Some(2023)
+        );
+        assert_eq!(
+            extract_year_hint("Resident.Evil.4.2005.Remastered"),
+            Some(2005)
+        );
+        assert_eq!(extract_year_hint("Doom [1993]"), Some(1993));
+        // Years that are part of the title are ignored
+        assert_eq!(extract_year_hint("Cyberpunk 2077 [FitGirl Repack]"), None);
+        assert_eq!(extract_year_hint("Football Manager 2024"), None);
+    }
+
+    #[test]
+    fn test_skipped_folders() {
+        let library = tempfile::tempdir().unwrap();
+        for folder in [
+            "Hades",
+            ".stfolder",
+            "GameVault",
+            "Old Stuff.zip",
+            "Some Movie [1080p]",
+            "Trainers",
+            "Unfinished Mod",
+            "[FitGirl Repack]",
+        ] {
+            std::fs::create_dir(library.path().join(folder)).unwrap();
+        }
+        std::fs::write(library.path().join(GVIGNORE), "# tools\ntrain*\n").unwrap();
+        std::fs::write(library.path().join("Unfinished Mod").join(GVIGNORE), "").unwrap();
+
+        let scan = scan_games_directory(&library.path().to_string_lossy());
+        let titles: Vec<_> = scan.games.iter().map(|g| g.clean_title.as_str()).collect();
+        assert_eq!(titles, ["Hades"]);
+
+        let mut skipped: Vec<_> = scan
+            .skipped
+            .iter()
+            .map(|s| (s.folder_name.as_str(), s.reason, s.detail.as_deref()))
+            .collect();
+        skipped.sort_by_key(|s| s.0);
+        assert_eq!(
+            skipped,
+            [
+                (".stfolder", SkipReason::Hidden, None),
+                ("GameVault", SkipReason::Reserved, None),
+                ("Old Stuff.zip", SkipReason::Archive, None),
+                (
+                    "Some Movie [1080p]",
+                    SkipReason::ExclusionPattern,
+                    Some(r"(?i)\[1080p\]")
+                ),
+                ("Trainers", SkipReason::Gvignore, Some("train*")),
+                (
+                    "Unfinished Mod",
+                    SkipReason::Gvignore,
+                    Some("Unfinished Mod/.gvignore")
+                ),
+                ("[FitGirl Repack]", SkipReason::NoTitle, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_install_size_counts_subfolders() {
+        let library = tempfile::tempdir().unwrap();
+        let game = library.path().join("Hades");
+        std::fs::create_dir_all(game.join("Content").join("Paks")).unwrap();
+        std::fs::create_dir_all(game.join(".gamevault")).unwrap();
+        std::fs::write(game.join("Hades.exe"), vec![0u8; 100]).unwrap();
+        std::fs::write(
+            game.join("Content").join("Paks").join("data.pak"),
+            vec![0u8; 5000],
+        )
+        .unwrap();
+        std::fs::write(game.join(".gamevault").join("cover.jpg"), vec![0u8; 70]).unwrap();
+
+        let scan = scan_games_directory(&library.path().to_string_lossy());
+        let hades = &scan.games[0];
+        assert_eq!(hades.size_bytes, Some(100));
+        assert_eq!(hades.install_size_bytes, Some(5100));
+    }
+
+    #[test]
+    fn test_parse_nfo_year() {
+        let nfo = "  Cracked by GROUP in 2099\n  Release Date : 03/2015\n  Size: 2011 MB\n";
+        assert_eq!(parse_nfo_year(nfo), Some(2015));
+        assert_eq!(parse_nfo_year("Game.....: Hades\nSize.....: 2019 MB"), None);
+    }
 }
@@ -0,0 +1,149 @@
+//! Two-level cache for Steam search responses
+//!
+//! Level 1 is an in-memory map for the lifetime of the process, level 2 is
+//! the `steam_search_cache` table so results survive restarts. Entries are
+//! keyed by normalized title. Responses that produced no usable match
+//! ("misses") expire sooner than hits, so a title that failed to match is
+//! retried eventually without hammering Steam on every enrichment run.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::SqlitePool;
+
+use crate::{db, models::SteamSearchResult, timestamps};
+
+/// How long a response with a usable match is reused
+const HIT_TTL_DAYS: i64 = 30;
+
+/// How long a response without a usable match is reused
+const MISS_TTL_DAYS: i64 = 3;
+
+#[derive(Debug, Clone)]
+struct CachedSearch {
+    results: Vec<SteamSearchResult>,
+    is_miss: bool,
+    fetched_at: NaiveDateTime,
+}
+
+pub struct SteamSearchCache {
+    pool: SqlitePool,
+    memory: Mutex<HashMap<String, CachedSearch>>,
+}
+
+/// Normalize a title into a cache key (case and whitespace insensitive)
+pub fn normalize_query(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Whether an entry fetched at `fetched_at` is still valid at `now`
+fn is_fresh(fetched_at: NaiveDateTime, is_miss: bool, now: NaiveDateTime) -> bool {
+    let ttl = if is_miss {
+        Duration::days(MISS_TTL_DAYS)
+    } else {
+        Duration::days(HIT_TTL_DAYS)
+    };
+    now - fetched_at < ttl
+}
+
+impl SteamSearchCache {
+    pub fn new(pool: SqlitePool) -> Self {
+        SteamSearchCache {
+            pool,
+            memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get fresh cached results for a title, checking memory then the database
+    pub async fn get(&self, title: &str) -> Option<Vec<SteamSearchResult>> {
+        let key = normalize_query(title);
+        let now = Utc::now().naive_utc();
+
+        if let Some(entry) = self.memory.lock().ok()?.get(&key) {
+            if is_fresh(entry.fetched_at, entry.is_miss, now) {
+                return Some(entry.results.clone());
+            }
+        }
+
+        let (results, is_miss, fetched_at) =
+            match db::get_steam_search_cache(&self.pool, &key).await {
+                Ok(row) => row?,
+                Err(e) => {
+                    tracing::warn!("Failed to read Steam search cache: {}", e);
+                    return None;
+                }
+            };
+
+        let fetched_at = timestamps::parse_naive(&fetched_at)?;
+        if !is_fresh(fetched_at, is_miss, now) {
+            return None;
+        }
+
+        let results: Vec<SteamSearchResult> = serde_json::from_str(&results).ok()?;
+
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(
+                key,
+                CachedSearch {
+                    results: results.clone(),
+                    is_miss,
+                    fetched_at,
+                },
+            );
+        }
+
+        Some(results)
+    }
+
+    /// Store a search response in both levels
+    pub async fn put(&self, title: &str, results: &[SteamSearchResult], is_miss: bool) {
+        let key = normalize_query(title);
+
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(
+                key.clone(),
+                CachedSearch {
+                    results: results.to_vec(),
+                    is_miss,
+                    fetched_at: Utc::now().naive_utc(),
+                },
+            );
+        }
+
+        let json = serde_json::to_string(results).unwrap_or_else(|_| "[]".to_string());
+        if let Err(e) = db::put_steam_search_cache(&self.pool, &key, &json, is_miss).await {
+            tracing::warn!("Failed to write Steam search cache: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(normalize_query("  The  Witcher 3 "), "the witcher 3");
+        assert_eq!(normalize_query("HADES"), normalize_query("hades"));
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let fetched = at("2024-05-01 12:00:00");
+
+        // Misses expire after a few days, hits last much longer
+        assert!(is_fresh(fetched, true, at("2024-05-03 12:00:00")));
+        assert!(!is_fresh(fetched, true, at("2024-05-05 12:00:00")));
+        assert!(is_fresh(fetched, false, at("2024-05-20 12:00:00")));
+        assert!(!is_fresh(fetched, false, at("2024-06-01 12:00:00")));
+    }
+}
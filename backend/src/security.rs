@@ -0,0 +1,93 @@
+//! Security headers for API and static responses
+//!
+//! Headers come from the `[security]` config section and are added to every
+//! response unless the handler already set them. `X-Content-Type-Options:
+//! nosniff` is always sent.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{
+            CONTENT_SECURITY_POLICY, REFERRER_POLICY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+        },
+        HeaderName, HeaderValue,
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::SecurityConfig;
+
+/// Pre-validated header values applied by [`security_headers`]
+#[derive(Debug, Default)]
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl SecurityHeaders {
+    /// Build from config, skipping empty and invalid values
+    pub fn from_config(config: &SecurityConfig) -> Self {
+        let configured = [
+            (CONTENT_SECURITY_POLICY, &config.content_security_policy),
+            (X_FRAME_OPTIONS, &config.frame_options),
+            (REFERRER_POLICY, &config.referrer_policy),
+        ];
+
+        let mut headers = vec![(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"))];
+        for (name, value) in configured {
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            if value.is_empty() {
+                continue;
+            }
+            match HeaderValue::from_str(&value) {
+                Ok(v) => headers.push((name, v)),
+                Err(_) => tracing::warn!("Ignoring invalid {} header value: {:?}", name, value),
+            }
+        }
+
+        SecurityHeaders { headers }
+    }
+}
+
+/// Middleware adding the configured security headers to each response
+pub async fn security_headers(
+    State(config): State<Arc<SecurityHeaders>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    for (name, value) in &config.headers {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config_skips_empty_and_invalid() {
+        let config = SecurityConfig {
+            content_security_policy: "default-src 'self';\n    img-src *".to_string(),
+            frame_options: String::new(),
+            referrer_policy: "bad\u{7f}value".to_string(),
+        };
+
+        let headers = SecurityHeaders::from_config(&config).headers;
+        let names: Vec<_> = headers.iter().map(|(n, _)| n.clone()).collect();
+        assert_eq!(names, vec![X_CONTENT_TYPE_OPTIONS, CONTENT_SECURITY_POLICY]);
+        assert_eq!(headers[1].1, "default-src 'self'; img-src *");
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let headers = SecurityHeaders::from_config(&SecurityConfig::default()).headers;
+        assert_eq!(headers.len(), 4);
+    }
+}
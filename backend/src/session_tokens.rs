@@ -0,0 +1,206 @@
+//! Short-lived session tokens for the embedded web UI
+//!
+//! The web UI has no login screen, and a browser page shouldn't hold the
+//! API key: anyone who opens the page on a shared machine could read it from
+//! the page and keep using it. Instead the UI asks for a session token at
+//! POST /api/session/token, which only answers requests made directly from
+//! localhost. Tokens are limited to a pairing scope (read or control, never
+//! admin), expire after `[auth] session_token_mins` and live only in memory,
+//! so a restart revokes all of them.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::http::{header, HeaderMap};
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::pairing::{hash_token, Scope};
+
+/// Prefix that tells session tokens from device tokens and the API key
+const TOKEN_PREFIX: &str = "gvs_";
+const TOKEN_LENGTH: usize = 40;
+
+/// Live tokens kept; the oldest is dropped when a new one would exceed this
+pub const MAX_SESSION_TOKENS: usize = 64;
+
+/// Headers a reverse proxy adds; their presence means the request isn't local
+const PROXY_HEADERS: &[&str] = &["forwarded", "x-forwarded-for", "x-real-ip"];
+
+struct SessionToken {
+    scope: Scope,
+    expires: Instant,
+}
+
+/// Issued session tokens by hash
+#[derive(Default)]
+pub struct SessionTokens {
+    tokens: Mutex<HashMap<String, SessionToken>>,
+}
+
+impl SessionTokens {
+    /// Issue a token valid for `ttl`
+    pub fn issue(&self, scope: Scope, ttl: Duration) -> String {
+        let random: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        let token = format!("{}{}", TOKEN_PREFIX, random);
+
+        if let Ok(mut tokens) = self.tokens.lock() {
+            let now = Instant::now();
+            tokens.retain(|_, t| t.expires > now);
+            if tokens.len() >= MAX_SESSION_TOKENS {
+                let oldest = tokens
+                    .iter()
+                    .min_by_key(|(_, t)| t.expires)
+                    .map(|(hash, _)| hash.clone());
+                if let Some(hash) = oldest {
+                    tokens.remove(&hash);
+                }
+            }
+            tokens.insert(
+                hash_token(&token),
+                SessionToken {
+                    scope,
+                    expires: now + ttl,
+                },
+            );
+        }
+        token
+    }
+
+    /// Scope of the session token in the Authorization header, if it is live
+    pub fn request_scope(&self, headers: &HeaderMap) -> Option<Scope> {
+        let token = bearer_token(headers)?;
+        if !token.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        let tokens = self.tokens.lock().ok()?;
+        tokens
+            .get(&hash_token(token))
+            .filter(|t| t.expires > Instant::now())
+            .map(|t| t.scope)
+    }
+
+    /// Revoke the session token in the Authorization header
+    pub fn revoke(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = bearer_token(headers) else {
+            return false;
+        };
+        self.tokens
+            .lock()
+            .is_ok_and(|mut tokens| tokens.remove(&hash_token(token)).is_some())
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let header = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    Some(header.strip_prefix("Bearer ").unwrap_or(header))
+}
+
+/// Whether a request comes straight from this machine
+///
+/// The peer must be a loopback address, no proxy may have forwarded it, and
+/// a browser's `Origin` (sent on cross-site requests) must be local too, so
+/// a page on another site can't obtain a token through the user's browser.
+pub fn is_local_request(peer: &SocketAddr, headers: &HeaderMap) -> bool {
+    if !peer.ip().is_loopback() || PROXY_HEADERS.iter().any(|h| headers.contains_key(*h)) {
+        return false;
+    }
+    match headers.get(header::ORIGIN) {
+        None => true,
+        Some(origin) => origin
+            .to_str()
+            .ok()
+            .and_then(origin_host)
+            .is_some_and(|host| matches!(host, "localhost" | "127.0.0.1" | "[::1]")),
+    }
+}
+
+/// Host of an origin like "http://localhost:3000"
+fn origin_host(origin: &str) -> Option<&str> {
+    let (_, host) = origin.split_once("://")?;
+    match host.rsplit_once(':') {
+        Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => Some(name),
+        _ => Some(host),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_session_tokens() {
+        let tokens = SessionTokens::default();
+        let token = tokens.issue(Scope::Control, Duration::from_secs(60));
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(tokens.request_scope(&bearer(&token)), Some(Scope::Control));
+        assert_eq!(tokens.request_scope(&bearer("gvs_unknown")), None);
+
+        let expired = tokens.issue(Scope::Read, Duration::ZERO);
+        assert_eq!(tokens.request_scope(&bearer(&expired)), None);
+
+        assert!(tokens.revoke(&bearer(&token)));
+        assert_eq!(tokens.request_scope(&bearer(&token)), None);
+
+        let first = tokens.issue(Scope::Read, Duration::from_secs(1));
+        for _ in 0..MAX_SESSION_TOKENS {
+            tokens.issue(Scope::Read, Duration::from_secs(60));
+        }
+        assert_eq!(tokens.request_scope(&bearer(&first)), None);
+    }
+
+    #[test]
+    fn test_is_local_request() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.20:50000".parse().unwrap();
+        let with = |name: header::HeaderName, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, value.parse().unwrap());
+            headers
+        };
+
+        assert!(is_local_request(&local, &HeaderMap::new()));
+        assert!(is_local_request(
+            &"[::1]:50000".parse().unwrap(),
+            &HeaderMap::new()
+        ));
+        assert!(!is_local_request(&remote, &HeaderMap::new()));
+        assert!(is_local_request(
+            &local,
+            &with(header::ORIGIN, "http://localhost:3000")
+        ));
+        assert!(is_local_request(
+            &local,
+            &with(header::ORIGIN, "http://[::1]")
+        ));
+        assert!(!is_local_request(
+            &local,
+            &with(header::ORIGIN, "https://evil.example")
+        ));
+        assert!(!is_local_request(
+            &local,
+            &with(header::ORIGIN, "http://localhost.evil.example")
+        ));
+        assert!(!is_local_request(
+            &local,
+            &with(
+                header::HeaderName::from_static("x-forwarded-for"),
+                "203.0.113.5"
+            )
+        ));
+    }
+}
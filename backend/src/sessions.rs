@@ -0,0 +1,278 @@
+//! Play session tracking with optional idle detection
+//!
+//! A session is opened when a game is launched through GameVault (or started
+//! manually from the UI) and closed when the process exits. For games with an
+//! idle timeout configured, a background monitor pauses the session clock
+//! while there has been no keyboard/mouse input across the system, so a game
+//! left paused during dinner doesn't inflate playtime.
+//!
+//! Idle detection relies on `GetLastInputInfo` and is only available on
+//! Windows; elsewhere sessions simply count wall-clock time.
+//!
+//! Each session records the machine it ran on and the restricted profile
+//! active at the time, so the recently played list (GET
+//! /games/recently-played) shows what was played here, in this profile, even
+//! when the portable build's database moves between PCs.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+
+use crate::db;
+
+/// How often the idle monitor samples system input
+const IDLE_POLL_INTERVAL_SECS: u64 = 30;
+
+/// Games in the recently played list, by default and at most
+pub const RECENTLY_PLAYED_LIMIT: usize = 10;
+pub const MAX_RECENTLY_PLAYED: usize = 50;
+
+/// Name of the machine GameVault runs on, recorded with each session
+pub fn machine_name() -> &'static str {
+    static NAME: OnceLock<String> = OnceLock::new();
+    NAME.get_or_init(|| {
+        ["COMPUTERNAME", "HOSTNAME"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok())
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    })
+}
+
+/// Seconds since the last keyboard/mouse input anywhere on the system
+#[cfg(windows)]
+pub fn system_idle_secs() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+
+    // SAFETY: `info` is a properly sized, initialized LASTINPUTINFO
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+
+    // Both values are tick counts that wrap after ~49 days
+    let now = unsafe { GetTickCount() };
+    Some(u64::from(now.wrapping_sub(info.dwTime)) / 1000)
+}
+
+/// Stub for non-Windows platforms - idle detection unavailable
+#[cfg(not(windows))]
+pub fn system_idle_secs() -> Option<u64> {
+    None
+}
+
+/// What the monitor should do with an open session after sampling input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleTransition {
+    /// No change to the session clock
+    None,
+    /// The user went idle - pause the clock at the time of the last input
+    Pause { idle_for_secs: u64 },
+    /// The user is back - resume the clock from the time of the last input
+    Resume { idle_for_secs: u64 },
+}
+
+/// Decide how an open session's clock should change
+///
+/// `paused` is whether the session is currently marked idle.
+pub fn idle_transition(idle_secs: u64, timeout_mins: i64, paused: bool) -> IdleTransition {
+    let timeout_secs = (timeout_mins.max(1) as u64) * 60;
+    let idle = idle_secs >= timeout_secs;
+
+    match (idle, paused) {
+        (true, false) => IdleTransition::Pause {
+            idle_for_secs: idle_secs,
+        },
+        (false, true) => IdleTransition::Resume {
+            idle_for_secs: idle_secs,
+        },
+        _ => IdleTransition::None,
+    }
+}
+
+/// Sample system input once and pause/resume open sessions accordingly
+pub async fn check_idle_sessions(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let Some(idle_secs) = system_idle_secs() else {
+        return Ok(());
+    };
+
+    for session in db::get_idle_tracked_sessions(pool).await? {
+        match idle_transition(
+            idle_secs,
+            session.idle_timeout_mins,
+            session.idle_since.is_some(),
+        ) {
+            IdleTransition::Pause { idle_for_secs } => {
+                tracing::info!(
+                    "Pausing session {} for game {} (idle for {}s)",
+                    session.id,
+                    session.game_id,
+                    idle_for_secs
+                );
+                db::pause_play_session(pool, session.id, idle_for_secs as i64).await?;
+            }
+            IdleTransition::Resume { idle_for_secs } => {
+                tracing::info!(
+                    "Resuming session {} for game {}",
+                    session.id,
+                    session.game_id
+                );
+                db::resume_play_session(pool, session.id, idle_for_secs as i64).await?;
+            }
+            IdleTransition::None => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawn the background idle monitor (no-op loop where idle detection is unavailable)
+pub fn spawn_idle_monitor(pool: SqlitePool) {
+    if system_idle_secs().is_none() {
+        tracing::debug!("Idle detection unavailable on this platform");
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(IDLE_POLL_INTERVAL_SECS)).await;
+            if let Err(e) = check_idle_sessions(&pool).await {
+                tracing::warn!("Idle session check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_transition() {
+        // Active user, running clock
+        assert_eq!(idle_transition(10, 5, false), IdleTransition::None);
+        // Crossed the timeout - pause from the last input
+        assert_eq!(
+            idle_transition(300, 5, false),
+            IdleTransition::Pause { idle_for_secs: 300 }
+        );
+        // Still idle while paused
+        assert_eq!(idle_transition(4000, 5, true), IdleTransition::None);
+        // Input detected while paused - resume
+        assert_eq!(
+            idle_transition(2, 5, true),
+            IdleTransition::Resume { idle_for_secs: 2 }
+        );
+        // Zero/negative timeouts are clamped to one minute
+        assert_eq!(
+            idle_transition(60, 0, false),
+            IdleTransition::Pause { idle_for_secs: 60 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_started_session_committed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (pool, mut outside) = db::testing::connect_file(dir.path()).await;
+        let game = db::upsert_game(&pool, "/games/Doom", "Doom", "Doom", None, None)
+            .await
+            .unwrap();
+
+        // Other connections must see a session as soon as it's started
+        for _ in 0..20 {
+            let id = db::start_play_session(&pool, game, "pc").await.unwrap();
+            assert!(db::testing::committed(&mut outside, "play_sessions", id).await);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recently_played() {
+        let (pool, _keepalive) = db::test_pool().await;
+        let mut ids = Vec::new();
+        for title in ["Celeste", "Hades", "Doom"] {
+            ids.push(
+                db::upsert_game(
+                    &pool,
+                    &format!("/games/{}", title),
+                    title,
+                    title,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        let (celeste, hades, doom) = (ids[0], ids[1], ids[2]);
+        for (game_id, machine, started_at) in [
+            (celeste, "desk", "2024-05-01T18:00:00Z"),
+            (hades, "desk", "2024-05-02T18:00:00Z"),
+            (celeste, "desk", "2024-05-03T18:00:00Z"),
+            (doom, "htpc", "2024-05-04T18:00:00Z"),
+        ] {
+            let id = db::start_play_session(&pool, game_id, machine)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE play_sessions SET started_at = ?, ended_at = ? WHERE id = ?")
+                .bind(started_at)
+                .bind(started_at)
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        // Still running
+        db::start_play_session(&pool, hades, "desk").await.unwrap();
+
+        let played = db::get_recently_played(&pool, Some("desk"), 10)
+            .await
+            .unwrap();
+        let games: Vec<_> = played.iter().map(|p| (p.game.id, p.running)).collect();
+        assert_eq!(games, [(hades, true), (celeste, false)]);
+        assert_eq!(played[1].last_played_at, "2024-05-03T18:00:00Z");
+
+        let everywhere = db::get_recently_played(&pool, None, 2).await.unwrap();
+        let games: Vec<_> = everywhere.iter().map(|p| p.game.id).collect();
+        assert_eq!(games, [hades, doom]);
+
+        // Sessions of another profile don't count
+        let kids = crate::profiles::create(
+            &pool,
+            crate::profiles::NewProfile {
+                name: "Kids".into(),
+                pin: "1234".into(),
+                max_age: Some(12),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        sqlx::query("UPDATE games SET age_rating = 0")
+            .execute(&pool)
+            .await
+            .unwrap();
+        crate::profiles::switch(&pool, Some(kids.id), None)
+            .await
+            .unwrap();
+        assert!(db::get_recently_played(&pool, None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        db::start_play_session(&pool, doom, "htpc").await.unwrap();
+        let games: Vec<_> = db::get_recently_played(&pool, None, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| p.game.id)
+            .collect();
+        assert_eq!(games, [doom]);
+    }
+}
@@ -0,0 +1,318 @@
+//! Per-game settings profiles
+//!
+//! The settings a game is played with (resolution, upscaler, frame rate cap,
+//! key remaps) are kept as a typed profile next to the freeform notes, so
+//! the game list can be filtered by them: which games run at 1440p/120 and
+//! which at 4K/60. Profiles are stored as JSON, one per game, and validated
+//! on the way in; unknown fields are rejected rather than silently dropped.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Longest key name in a remap
+const MAX_KEY_LENGTH: usize = 32;
+
+/// Remaps one profile may hold
+const MAX_KEY_REMAPS: usize = 64;
+
+/// Frame rate caps accepted
+const FPS_CAP_RANGE: std::ops::RangeInclusive<u32> = 1..=1000;
+
+/// Render resolution, written as "2560x1440"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TryFrom<String> for Resolution {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("Invalid resolution '{}', expected e.g. 2560x1440", value);
+        let (width, height) = value
+            .trim()
+            .to_lowercase()
+            .split_once('x')
+            .map(|(w, h)| (w.trim().parse::<u32>(), h.trim().parse::<u32>()))
+            .ok_or_else(invalid)?;
+        match (width, height) {
+            (Ok(width @ 1..=16384), Ok(height @ 1..=16384)) => Ok(Resolution { width, height }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl From<Resolution> for String {
+    fn from(resolution: Resolution) -> Self {
+        resolution.to_string()
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Upscaler or temporal reconstruction the game runs with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Upscaler {
+    /// Native resolution, no upscaling
+    None,
+    Dlss,
+    Fsr,
+    Xess,
+    /// Unreal's Temporal Super Resolution
+    Tsr,
+    /// The game's own or another upscaler
+    Other,
+}
+
+impl Upscaler {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Upscaler::None => "none",
+            Upscaler::Dlss => "dlss",
+            Upscaler::Fsr => "fsr",
+            Upscaler::Xess => "xess",
+            Upscaler::Tsr => "tsr",
+            Upscaler::Other => "other",
+        }
+    }
+}
+
+/// One remapped key or button, e.g. "Caps Lock" -> "Ctrl"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyRemap {
+    pub from: String,
+    pub to: String,
+}
+
+/// Settings a game is played with; every field is optional
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsProfile {
+    pub resolution: Option<Resolution>,
+    pub upscaler: Option<Upscaler>,
+    /// Frame rate cap in FPS (None = uncapped)
+    pub fps_cap: Option<u32>,
+    #[serde(default)]
+    pub key_remaps: Vec<KeyRemap>,
+}
+
+impl SettingsProfile {
+    /// Check the values serde can't, and trim key names
+    pub fn validate(mut self) -> Result<Self, String> {
+        if let Some(fps) = self.fps_cap {
+            if !FPS_CAP_RANGE.contains(&fps) {
+                return Err(format!(
+                    "fps_cap must be between {} and {}",
+                    FPS_CAP_RANGE.start(),
+                    FPS_CAP_RANGE.end()
+                ));
+            }
+        }
+
+        if self.key_remaps.len() > MAX_KEY_REMAPS {
+            return Err(format!("At most {} key remaps are allowed", MAX_KEY_REMAPS));
+        }
+        for remap in &mut self.key_remaps {
+            remap.from = remap.from.trim().to_string();
+            remap.to = remap.to.trim().to_string();
+            for key in [&remap.from, &remap.to] {
+                if key.is_empty() || key.chars().count() > MAX_KEY_LENGTH {
+                    return Err(format!(
+                        "Key names must be 1 to {} characters",
+                        MAX_KEY_LENGTH
+                    ));
+                }
+            }
+        }
+        for (i, remap) in self.key_remaps.iter().enumerate() {
+            if self.key_remaps[..i]
+                .iter()
+                .any(|r| r.from.eq_ignore_ascii_case(&remap.from))
+            {
+                return Err(format!("'{}' is remapped more than once", remap.from));
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Resolution filter for the game list: "2560x1440", or a height such as
+/// "1440p" or "4k" that matches any width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ResolutionFilter {
+    Exact(Resolution),
+    Height(u32),
+}
+
+impl TryFrom<String> for ResolutionFilter {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let lower = value.trim().to_lowercase();
+        let height = match lower.as_str() {
+            "4k" | "uhd" => Some(2160),
+            "2k" | "qhd" => Some(1440),
+            "fhd" => Some(1080),
+            _ => lower.strip_suffix('p').and_then(|h| h.parse().ok()),
+        };
+        match height {
+            Some(height) => Ok(ResolutionFilter::Height(height)),
+            None => Resolution::try_from(value).map(ResolutionFilter::Exact),
+        }
+    }
+}
+
+impl ResolutionFilter {
+    /// LIKE pattern for the stored "WIDTHxHEIGHT" text
+    pub fn like_pattern(self) -> String {
+        match self {
+            ResolutionFilter::Exact(resolution) => resolution.to_string(),
+            ResolutionFilter::Height(height) => format!("%x{}", height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: serde_json::Value) -> Result<SettingsProfile, String> {
+        serde_json::from_value::<SettingsProfile>(value)
+            .map_err(|e| e.to_string())?
+            .validate()
+    }
+
+    #[test]
+    fn test_settings_profile() {
+        let profile = parse(serde_json::json!({
+            "resolution": "2560X1440",
+            "upscaler": "dlss",
+            "fps_cap": 120,
+            "key_remaps": [{ "from": " Caps Lock ", "to": "Ctrl" }]
+        }))
+        .unwrap();
+        assert_eq!(
+            profile.resolution,
+            Some(Resolution {
+                width: 2560,
+                height: 1440
+            })
+        );
+        assert_eq!(profile.key_remaps[0].from, "Caps Lock");
+        assert_eq!(
+            serde_json::to_value(&profile).unwrap()["resolution"],
+            "2560x1440"
+        );
+        assert_eq!(
+            parse(serde_json::json!({})).unwrap(),
+            SettingsProfile::default()
+        );
+
+        assert!(parse(serde_json::json!({ "resolution": "1440p" })).is_err());
+        assert!(parse(serde_json::json!({ "resolution": "0x1080" })).is_err());
+        assert!(parse(serde_json::json!({ "upscaler": "magic" })).is_err());
+        assert!(parse(serde_json::json!({ "fps_cap": 0 })).is_err());
+        assert!(parse(serde_json::json!({ "fov": 90 })).is_err());
+        assert!(parse(serde_json::json!({
+            "key_remaps": [{ "from": "Q", "to": "A" }, { "from": "q", "to": "B" }]
+        }))
+        .is_err());
+        assert!(parse(serde_json::json!({ "key_remaps": [{ "from": "", "to": "A" }] })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_settings_filter() {
+        use crate::{db, models::GameFilter};
+
+        let (pool, _keepalive) = db::test_pool().await;
+        let mut ids = Vec::new();
+        for (title, profile) in [
+            ("Doom", r#"{"resolution": "2560x1440", "fps_cap": 120}"#),
+            (
+                "Hades",
+                r#"{"resolution": "3840x2160", "upscaler": "dlss", "fps_cap": 60}"#,
+            ),
+            ("Celeste", "{}"),
+        ] {
+            let id = db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            let profile: SettingsProfile = serde_json::from_str(profile).unwrap();
+            db::upsert_game_settings(&pool, id, &profile).await.unwrap();
+            ids.push(id);
+        }
+        assert_eq!(
+            db::get_game_settings(&pool, ids[1])
+                .await
+                .unwrap()
+                .unwrap()
+                .profile
+                .upscaler,
+            Some(Upscaler::Dlss)
+        );
+
+        let titles = |filter: GameFilter| {
+            let pool = pool.clone();
+            async move {
+                db::get_games_page(&pool, &filter, 10, 0)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|g| g.title)
+                    .collect::<Vec<_>>()
+            }
+        };
+        let resolution = |s: &str| Some(ResolutionFilter::try_from(s.to_string()).unwrap());
+        assert_eq!(
+            titles(GameFilter {
+                settings_resolution: resolution("1440p"),
+                settings_fps_cap: Some(120),
+                ..Default::default()
+            })
+            .await,
+            ["Doom"]
+        );
+        assert_eq!(
+            titles(GameFilter {
+                settings_resolution: resolution("3840x2160"),
+                ..Default::default()
+            })
+            .await,
+            ["Hades"]
+        );
+        assert!(titles(GameFilter {
+            settings_resolution: resolution("4k"),
+            settings_fps_cap: Some(120),
+            ..Default::default()
+        })
+        .await
+        .is_empty());
+        assert_eq!(titles(GameFilter::default()).await.len(), 3);
+    }
+
+    #[test]
+    fn test_resolution_filter() {
+        let filter = |s: &str| ResolutionFilter::try_from(s.to_string()).map(|f| f.like_pattern());
+        assert_eq!(filter("1440p").unwrap(), "%x1440");
+        assert_eq!(filter("4K").unwrap(), "%x2160");
+        assert_eq!(filter("3840x2160").unwrap(), "3840x2160");
+        assert!(filter("big").is_err());
+    }
+}
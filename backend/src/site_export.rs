@@ -0,0 +1,211 @@
+//! Catalog data for static site generators (GET /api/export/site-data)
+//!
+//! One JSON document with everything a public catalog page needs, so a
+//! static site (GitHub Pages and the like) can be built from a private
+//! instance by a script that fetches this and the art it points to.
+//!
+//! The data is sanitized for publishing: no folder paths, notes, play
+//! status, playtime or anything else about the owner's machine or habits,
+//! and hidden games are left out. Art is referenced by paths relative to
+//! `/api/` (`games/42/cover`), so the build script can download each image
+//! from the instance and store it under the same path in the site.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::models::Game;
+
+/// Format version of the document, raised on incompatible changes
+pub const SITE_DATA_VERSION: u32 = 1;
+
+/// A game as published
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteGame {
+    pub id: i64,
+    pub title: String,
+    pub steam_app_id: Option<i64>,
+    pub summary: Option<String>,
+    pub release_date: Option<String>,
+    /// `release_date` as YYYY-MM-DD, when it is a full date
+    pub release_date_iso: Option<String>,
+    pub genres: Vec<String>,
+    pub developers: Vec<String>,
+    pub publishers: Vec<String>,
+    pub review_score: Option<i64>,
+    pub review_summary: Option<String>,
+    pub deck_compat: Option<String>,
+    /// Base game of an edition or DLC
+    pub parent_game_id: Option<i64>,
+    pub franchise: Option<String>,
+    /// Cover image path relative to `/api/` (None = not cached)
+    pub cover: Option<String>,
+    /// Cover thumbnail (368px) path relative to `/api/`
+    pub cover_thumbnail: Option<String>,
+    pub cover_blurhash: Option<String>,
+    pub cover_color: Option<String>,
+    /// Background image path relative to `/api/` (None = not cached)
+    pub background: Option<String>,
+}
+
+impl From<Game> for SiteGame {
+    fn from(g: Game) -> Self {
+        let list = |json: Option<String>| {
+            json.and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok())
+                .unwrap_or_default()
+        };
+        let has_cover = g.local_cover_path.is_some();
+        SiteGame {
+            id: g.id,
+            title: g.title,
+            steam_app_id: g.steam_app_id,
+            summary: g.summary,
+            release_date: g.release_date,
+            release_date_iso: g.release_date_iso,
+            genres: list(g.genres),
+            developers: list(g.developers),
+            publishers: list(g.publishers),
+            review_score: g.review_score,
+            review_summary: g.review_summary,
+            deck_compat: g.deck_compat,
+            parent_game_id: g.parent_game_id,
+            franchise: g.franchise,
+            cover: has_cover.then(|| format!("games/{}/cover", g.id)),
+            cover_thumbnail: has_cover.then(|| format!("games/{}/cover?size=medium", g.id)),
+            cover_blurhash: g.cover_blurhash,
+            cover_color: g.cover_color,
+            background: g
+                .local_background_path
+                .is_some()
+                .then(|| format!("games/{}/background", g.id)),
+        }
+    }
+}
+
+/// Games of one franchise, by title
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteCollection {
+    pub name: String,
+    pub game_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SiteStats {
+    pub total_games: usize,
+    /// Games with a Steam match
+    pub matched_games: usize,
+    /// Games per genre
+    pub genres: BTreeMap<String, usize>,
+    /// Games per Steam Deck status
+    pub deck_compat: BTreeMap<String, usize>,
+}
+
+/// The whole catalog
+#[derive(Debug, Clone, Serialize)]
+pub struct SiteData {
+    pub version: u32,
+    pub generated_at: String,
+    pub games: Vec<SiteGame>,
+    pub collections: Vec<SiteCollection>,
+    pub stats: SiteStats,
+}
+
+/// Build the catalog from the library (expects games sorted by title)
+pub fn build(games: Vec<Game>, generated_at: String) -> SiteData {
+    let games: Vec<SiteGame> = games
+        .into_iter()
+        .filter(|g| g.hidden.unwrap_or(0) == 0)
+        .map(SiteGame::from)
+        .collect();
+
+    let mut franchises: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    let mut stats = SiteStats {
+        total_games: games.len(),
+        ..Default::default()
+    };
+    for game in &games {
+        if let Some(franchise) = &game.franchise {
+            franchises
+                .entry(franchise.clone())
+                .or_default()
+                .push(game.id);
+        }
+        if game.steam_app_id.is_some() {
+            stats.matched_games += 1;
+        }
+        for genre in &game.genres {
+            *stats.genres.entry(genre.clone()).or_default() += 1;
+        }
+        if let Some(deck) = &game.deck_compat {
+            *stats.deck_compat.entry(deck.clone()).or_default() += 1;
+        }
+    }
+
+    SiteData {
+        version: SITE_DATA_VERSION,
+        generated_at,
+        games,
+        collections: franchises
+            .into_iter()
+            .map(|(name, game_ids)| SiteCollection { name, game_ids })
+            .collect(),
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db, timestamps};
+
+    #[tokio::test]
+    async fn test_build_site_data() {
+        let (pool, _keepalive) = db::test_pool().await;
+        for title in ["Dark Souls", "Dark Souls III", "Hades", "Hades (copy)"] {
+            db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            r#"
+            UPDATE games SET franchise = 'Dark Souls', genres = '["Action","RPG"]'
+                WHERE title LIKE 'Dark Souls%';
+            UPDATE games SET steam_app_id = 1145360, genres = '["Action"]', deck_compat = 'verified',
+                local_cover_path = '/games/Hades/.gamevault/cover.jpg', notes = 'private'
+                WHERE title = 'Hades';
+            UPDATE games SET hidden = 1 WHERE title = 'Hades (copy)';
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let games = db::get_all_games(&pool).await.unwrap();
+        let site = build(games, timestamps::now());
+        let titles: Vec<_> = site.games.iter().map(|g| g.title.as_str()).collect();
+        assert_eq!(titles, ["Dark Souls", "Dark Souls III", "Hades"]);
+
+        assert_eq!(site.collections.len(), 1);
+        assert_eq!(site.collections[0].name, "Dark Souls");
+        assert_eq!(site.collections[0].game_ids.len(), 2);
+
+        assert_eq!(site.stats.total_games, 3);
+        assert_eq!(site.stats.matched_games, 1);
+        assert_eq!(site.stats.genres["Action"], 3);
+        assert_eq!(site.stats.deck_compat["verified"], 1);
+
+        let hades = &site.games[2];
+        assert_eq!(hades.cover, Some(format!("games/{}/cover", hades.id)));
+        assert_eq!(hades.background, None);
+        let json = serde_json::to_string(&site).unwrap();
+        assert!(!json.contains("/games/Hades"));
+        assert!(!json.contains("private"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Tasks run when the server starts (`[startup]` in config.toml)
+//!
+//! The portable build is often started only to browse the drive, so work
+//! that would otherwise wait for a click can run on boot instead: a scan
+//! for new and changed folders (no online enrichment), an import of
+//! `.gamevault/metadata.json` files, and an image repair that re-downloads
+//! covers and backgrounds whose cached files are gone.
+//!
+//! The tasks run one after another in the background once the server is
+//! up, so the UI is usable right away. Each run is recorded as a job
+//! (GET /api/jobs) with its outcome.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use sqlx::SqlitePool;
+
+use crate::{db, downloads, events, handlers, import, AppState};
+
+/// Job trigger for tasks run on startup
+pub const TRIGGER_STARTUP: &str = "startup";
+
+pub const JOB_IMPORT_METADATA: &str = "import_metadata";
+pub const JOB_REPAIR_IMAGES: &str = "repair_images";
+
+/// Run the configured startup tasks in the background
+pub fn spawn(state: Arc<AppState>) {
+    let tasks = &state.config.startup;
+    if !(tasks.scan || tasks.import_metadata || tasks.repair_images) {
+        return;
+    }
+    tokio::spawn(async move {
+        let tasks = state.config.startup.clone();
+        // Scan first so new folders are imported and repaired too
+        if tasks.scan {
+            record(&state.db, events::JOB_SCAN, async {
+                let s = handlers::run_scan(&state).await.summary;
+                Ok(format!(
+                    "Scan {}: {} games found, {} added or updated, {} folders skipped",
+                    s.id, s.total_found, s.added_or_updated, s.skipped
+                ))
+            })
+            .await;
+        }
+        if tasks.import_metadata {
+            record(&state.db, JOB_IMPORT_METADATA, async {
+                let report =
+                    handlers::run_metadata_import(&state, import::ImportOptions::default())
+                        .await
+                        .map_err(|e| e.to_string())?;
+                let s = &report.summary;
+                match &s.error {
+                    Some(error) => Err(format!("Import {} rolled back: {}", s.id, error)),
+                    None => Ok(format!(
+                        "Import {}: {} imported, {} unchanged, {} skipped, {} failed",
+                        s.id, s.imported, s.unchanged, s.skipped, s.failed
+                    )),
+                }
+            })
+            .await;
+        }
+        if tasks.repair_images {
+            record(&state.db, JOB_REPAIR_IMAGES, repair_images(&state)).await;
+        }
+    });
+}
+
+/// Queue downloads of missing images and thumbnails of cached covers
+async fn repair_images(state: &AppState) -> Result<String, String> {
+    let games = db::get_all_games(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let jobs = downloads::missing_images(&games);
+    let downloads = jobs.len();
+    for job in jobs {
+        state.downloads.enqueue(job);
+    }
+
+    let artwork = db::get_games_missing_artwork(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let thumbnails = artwork.len();
+    state.artwork.enqueue(artwork);
+
+    Ok(format!(
+        "Queued images of {} games and thumbnails of {} covers",
+        downloads, thumbnails
+    ))
+}
+
+/// Run a task as a job, recording when it ran and how it ended
+///
+/// A job that can't be recorded still runs.
+async fn record(pool: &SqlitePool, kind: &str, task: impl Future<Output = Result<String, String>>) {
+    tracing::info!("Running startup task: {}", kind);
+    let id = db::start_job(pool, kind, TRIGGER_STARTUP)
+        .await
+        .map_err(|e| tracing::warn!("Failed to record {} job: {}", kind, e))
+        .ok();
+
+    let (status, message) = match task.await {
+        Ok(message) => ("completed", message),
+        Err(error) => {
+            tracing::warn!("Startup task {} failed: {}", kind, error);
+            ("failed", error)
+        }
+    };
+    if let Some(id) = id {
+        if let Err(e) = db::finish_job(pool, id, status, &message).await {
+            tracing::warn!("Failed to record the end of job {}: {}", id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_jobs() {
+        let (pool, _keepalive) = db::test_pool().await;
+
+        record(&pool, events::JOB_SCAN, async {
+            Ok("3 games found".into())
+        })
+        .await;
+        record(&pool, JOB_REPAIR_IMAGES, async { Err("disk full".into()) }).await;
+
+        let jobs = db::get_jobs(&pool, 10).await.unwrap();
+        let outcomes: Vec<_> = jobs
+            .iter()
+            .map(|j| (j.kind.as_str(), j.status.as_str(), j.message.as_deref()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                (JOB_REPAIR_IMAGES, "failed", Some("disk full")),
+                (events::JOB_SCAN, "completed", Some("3 games found")),
+            ]
+        );
+        assert!(jobs
+            .iter()
+            .all(|j| j.trigger == TRIGGER_STARTUP && j.finished_at.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_started_job_committed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (pool, mut outside) = db::testing::connect_file(dir.path()).await;
+
+        for _ in 0..20 {
+            let id = db::start_job(&pool, events::JOB_SCAN, TRIGGER_STARTUP)
+                .await
+                .unwrap();
+            assert!(db::testing::committed(&mut outside, "jobs", id).await);
+        }
+    }
+}
@@ -1,13 +1,37 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use reqwest::Client;
+use crate::http::HttpClient;
+use serde::Serialize;
 use strsim::jaro_winkler;
 
-use crate::models::{SteamAppDetailsResponse, SteamReviewsResponse};
+use crate::models::{
+    SteamAppData, SteamAppDetailsResponse, SteamReviewsResponse, SteamSearchResult,
+};
+use crate::reviews::ReviewBand;
+use crate::search_cache::SteamSearchCache;
+use crate::timestamps;
 
 const STEAM_STORE_API: &str = "https://store.steampowered.com/api";
 const STEAM_SEARCH_URL: &str = "https://steamcommunity.com/actions/SearchApps";
+const STEAM_WEB_API: &str = "https://api.steampowered.com";
+const STEAM_DECK_COMPAT_URL: &str = "https://store.steampowered.com/saleaction";
+const STEAM_CDN_URL: &str = "https://cdn.akamai.steamstatic.com/steam/apps";
+
+/// Store category of apps with Steam Trading Cards
+const TRADING_CARDS_CATEGORY: u64 = 29;
+
+/// Workshop items requested per page (API maximum is 100)
+const WORKSHOP_PAGE_SIZE: usize = 100;
+/// Safety cap on pages fetched for a single game
+const WORKSHOP_MAX_PAGES: usize = 20;
+
+/// Known mappings are used above this title similarity
+pub const KNOWN_MAPPING_SIMILARITY: f64 = 0.85;
+/// Search results are accepted above this title similarity
+pub const SEARCH_MIN_SIMILARITY: f64 = 0.6;
+/// Search results considered, in Steam's order
+pub const SEARCH_RESULTS_CONSIDERED: usize = 5;
 
 /// Known game title to Steam App ID mappings
 fn get_known_mappings() -> HashMap<&'static str, i64> {
@@ -276,25 +300,93 @@ fn get_known_mappings() -> HashMap<&'static str, i64> {
 }
 
 /// Search for a Steam App ID using the search API
-pub async fn search_steam_app(client: &Client, title: &str) -> Option<(i64, f64)> {
+///
+/// Search responses are cached (see `search_cache`) so repeated enrichment
+/// runs don't re-query titles that are already known hits or misses.
+pub async fn search_steam_app(
+    client: &HttpClient,
+    cache: &SteamSearchCache,
+    title: &str,
+    year_hint: Option<i32>,
+) -> Option<(i64, f64)> {
     // First check known mappings
-    let lower_title = title.to_lowercase();
-    let mappings = get_known_mappings();
+    if let Some((app_id, similarity)) = known_mapping(title) {
+        tracing::info!(
+            "Found known mapping for '{}': {} (similarity: {:.2})",
+            title,
+            app_id,
+            similarity
+        );
+        return Some((app_id, similarity));
+    }
+
+    let (results, _) = cached_search(client, cache, title).await?;
 
-    for (known_title, app_id) in &mappings {
-        let similarity = jaro_winkler(&lower_title, known_title);
-        if similarity > 0.85 && *app_id > 0 {
+    match best_search_match(title, &results, year_hint) {
+        Some((appid, similarity)) => {
             tracing::info!(
-                "Found known mapping for '{}': {} (similarity: {:.2})",
+                "Found Steam match for '{}': {} (similarity: {:.2})",
                 title,
-                app_id,
+                appid,
                 similarity
             );
-            return Some((*app_id, similarity));
+            Some((appid, similarity))
+        }
+        None => {
+            tracing::info!("No Steam match found for '{}'", title);
+            None
         }
     }
+}
 
-    // Search Steam
+/// Built-in mappings by similarity to a title, most similar first
+///
+/// Returns (known title, app id, similarity).
+pub fn known_mapping_candidates(title: &str) -> Vec<(&'static str, i64, f64)> {
+    let lower_title = title.to_lowercase();
+    let mut candidates: Vec<(&'static str, i64, f64)> = get_known_mappings()
+        .into_iter()
+        .map(|(known_title, app_id)| (known_title, app_id, jaro_winkler(&lower_title, known_title)))
+        .collect();
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.0.cmp(b.0)));
+    candidates
+}
+
+/// The closest built-in mapping to a Steam app, if it is similar enough
+///
+/// Mappings to 0 ("not on Steam") are skipped so those titles still get
+/// searched.
+pub fn known_mapping(title: &str) -> Option<(i64, f64)> {
+    known_mapping_candidates(title)
+        .into_iter()
+        .find(|(_, app_id, similarity)| *similarity > KNOWN_MAPPING_SIMILARITY && *app_id > 0)
+        .map(|(_, app_id, similarity)| (app_id, similarity))
+}
+
+/// Search results for a title, from the cache or Steam
+///
+/// Returns the results and whether they came from the cache; None if the
+/// request failed. Request failures are not cached - only real responses.
+pub async fn cached_search(
+    client: &HttpClient,
+    cache: &SteamSearchCache,
+    title: &str,
+) -> Option<(Vec<SteamSearchResult>, bool)> {
+    if let Some(results) = cache.get(title).await {
+        tracing::debug!("Using cached Steam search results for '{}'", title);
+        return Some((results, true));
+    }
+
+    let results = fetch_steam_search(client, title).await?;
+    let is_miss = best_search_match(title, &results, None).is_none();
+    cache.put(title, &results, is_miss).await;
+    Some((results, false))
+}
+
+/// Query the Steam community search for a title
+///
+/// Returns None if the request or response parsing fails.
+async fn fetch_steam_search(client: &HttpClient, title: &str) -> Option<Vec<SteamSearchResult>> {
     let url = format!("{}/{}", STEAM_SEARCH_URL, urlencoding::encode(title));
 
     let response = match client
@@ -322,43 +414,145 @@ pub async fn search_steam_app(client: &Client, title: &str) -> Option<(i64, f64)
         }
     };
 
-    // Find best match using Jaro-Winkler similarity
-    let mut best_match: Option<(i64, f64)> = None;
-
-    for result in results.iter().take(5) {
-        if let (Some(appid), Some(name)) = (
-            result
-                .get("appid")
-                .and_then(|v| v.as_str())
-                .and_then(|s| s.parse::<i64>().ok()),
-            result.get("name").and_then(|v| v.as_str()),
-        ) {
-            let similarity = jaro_winkler(&lower_title, &name.to_lowercase());
-
-            if similarity > best_match.map(|(_, s)| s).unwrap_or(0.0) {
-                best_match = Some((appid, similarity));
-            }
-        }
+    Some(
+        results
+            .iter()
+            .filter_map(|result| {
+                Some(SteamSearchResult {
+                    appid: result
+                        .get("appid")
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| s.parse::<i64>().ok())?,
+                    name: result.get("name").and_then(|v| v.as_str())?.to_string(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Approximate year each appid range was first handed out
+///
+/// Appids are assigned in increasing order when a store page is created,
+/// so an appid tells roughly when a game was registered on Steam (the same
+/// idea as SteamDB's appid history). Registration happens at most a couple of
+/// years before release but can be much later for re-releases.
+const APPID_YEARS: &[(i64, i32)] = &[
+    (0, 2003),
+    (7_000, 2007),
+    (10_000, 2008),
+    (40_000, 2009),
+    (60_000, 2010),
+    (100_000, 2011),
+    (200_000, 2012),
+    (240_000, 2013),
+    (280_000, 2014),
+    (330_000, 2015),
+    (420_000, 2016),
+    (580_000, 2017),
+    (800_000, 2018),
+    (1_000_000, 2019),
+    (1_200_000, 2020),
+    (1_500_000, 2021),
+    (1_800_000, 2022),
+    (2_200_000, 2023),
+    (2_600_000, 2024),
+    (3_300_000, 2025),
+];
+
+/// Approximate year an appid was registered on Steam
+pub fn estimate_appid_year(app_id: i64) -> i32 {
+    APPID_YEARS
+        .iter()
+        .take_while(|(first, _)| app_id >= *first)
+        .last()
+        .map(|(_, year)| *year)
+        .unwrap_or(APPID_YEARS[0].1)
+}
+
+/// Score adjustment for how well an appid fits the release year hint
+///
+/// An app registered well before the hinted year can't be that release
+/// (it's the original when the hint points at a remaster). Among the rest,
+/// apps registered close to the hinted year are preferred over later
+/// re-releases. The adjustment is small so it only breaks near ties.
+fn year_score(app_id: i64, year_hint: i32) -> f64 {
+    let gap = estimate_appid_year(app_id) - year_hint;
+    match gap {
+        g if g < -2 => -0.1,
+        g if g <= 2 => 0.05,
+        g if g <= 6 => 0.02,
+        _ => 0.0,
     }
+}
 
-    if let Some((appid, similarity)) = best_match {
-        if similarity > 0.6 {
-            tracing::info!(
-                "Found Steam match for '{}': {} (similarity: {:.2})",
-                title,
-                appid,
-                similarity
-            );
-            return Some((appid, similarity));
+/// A search result scored against the title being matched
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredResult {
+    pub appid: i64,
+    pub name: String,
+    /// Jaro-Winkler similarity of the names
+    pub similarity: f64,
+    /// Bonus or penalty from the release year hint
+    pub year_adjustment: f64,
+    pub score: f64,
+}
+
+/// Score the search results considered for a match, in Steam's order
+pub fn score_search_results(
+    title: &str,
+    results: &[SteamSearchResult],
+    year_hint: Option<i32>,
+) -> Vec<ScoredResult> {
+    let lower_title = title.to_lowercase();
+    results
+        .iter()
+        .take(SEARCH_RESULTS_CONSIDERED)
+        .map(|result| {
+            let similarity = jaro_winkler(&lower_title, &result.name.to_lowercase());
+            let year_adjustment = year_hint.map_or(0.0, |y| year_score(result.appid, y));
+            ScoredResult {
+                appid: result.appid,
+                name: result.name.clone(),
+                similarity,
+                year_adjustment,
+                score: similarity + year_adjustment,
+            }
+        })
+        .collect()
+}
+
+/// Index of the highest scoring result (the first one on ties)
+///
+/// Only results whose similarity is above `SEARCH_MIN_SIMILARITY` compete,
+/// so the year adjustment can choose among good matches but never push a
+/// passing result aside for one that fails the threshold.
+pub fn best_scored(scored: &[ScoredResult]) -> Option<usize> {
+    let mut best: Option<usize> = None;
+    for (index, result) in scored.iter().enumerate() {
+        if result.similarity > SEARCH_MIN_SIMILARITY
+            && result.score > best.map_or(0.0, |b| scored[b].score)
+        {
+            best = Some(index);
         }
     }
+    best
+}
 
-    tracing::info!("No Steam match found for '{}'", title);
-    None
+/// Find the best match among search results using Jaro-Winkler similarity
+///
+/// Returns the appid and its name similarity. The year hint only affects
+/// which result is picked, not the reported similarity.
+fn best_search_match(
+    title: &str,
+    results: &[SteamSearchResult],
+    year_hint: Option<i32>,
+) -> Option<(i64, f64)> {
+    let scored = score_search_results(title, results, year_hint);
+    best_scored(&scored).map(|index| (scored[index].appid, scored[index].similarity))
 }
 
 /// Fetch game details from Steam
-pub async fn fetch_steam_details(client: &Client, app_id: i64) -> Option<SteamAppDetails> {
+pub async fn fetch_steam_details(client: &HttpClient, app_id: i64) -> Option<SteamAppDetails> {
     let url = format!("{}/appdetails?appids={}", STEAM_STORE_API, app_id);
 
     let response = match client
@@ -391,7 +585,6 @@ pub async fn fetch_steam_details(client: &Client, app_id: i64) -> Option<SteamAp
     let app_data = app_result.data.as_ref()?;
 
     Some(SteamAppDetails {
-        app_id,
         name: app_data.name.clone(),
         description: app_data.short_description.clone(),
         header_image: app_data.header_image.clone(),
@@ -403,11 +596,33 @@ pub async fn fetch_steam_details(client: &Client, app_id: i64) -> Option<SteamAp
             .as_ref()
             .map(|g| g.iter().map(|genre| genre.description.clone()).collect()),
         release_date: app_data.release_date.as_ref().and_then(|r| r.date.clone()),
+        fullgame_app_id: app_data.fullgame.as_ref().and_then(|f| match &f.appid {
+            serde_json::Value::String(s) => s.parse().ok(),
+            value => value.as_i64(),
+        }),
+        age_rating: app_data.required_age.as_ref().and_then(|age| match age {
+            serde_json::Value::String(s) => s.trim().parse().ok(),
+            value => value.as_i64(),
+        }),
+        trading_cards: has_trading_cards(app_data),
     })
 }
 
+fn has_trading_cards(app_data: &SteamAppData) -> bool {
+    app_data
+        .categories
+        .iter()
+        .flatten()
+        .any(|c| json_u64(&c.id) == Some(TRADING_CARDS_CATEGORY))
+}
+
+/// Store header image of an app, without asking the store API for it
+pub fn header_image_url(app_id: i64) -> String {
+    format!("{}/{}/header.jpg", STEAM_CDN_URL, app_id)
+}
+
 /// Fetch reviews from Steam
-pub async fn fetch_steam_reviews(client: &Client, app_id: i64) -> Option<SteamReviews> {
+pub async fn fetch_steam_reviews(client: &HttpClient, app_id: i64) -> Option<SteamReviews> {
     let url = format!(
         "{}/appreviews/{}?json=1&language=all&purchase_type=all&num_per_page=0",
         STEAM_STORE_API, app_id
@@ -447,16 +662,351 @@ pub async fn fetch_steam_reviews(client: &Client, app_id: i64) -> Option<SteamRe
         0
     };
 
+    let band = ReviewBand::detect(
+        summary.review_score,
+        summary.review_score_desc.as_deref(),
+        Some(score),
+        summary.total_reviews,
+    );
+
     Some(SteamReviews {
         score,
         count: summary.total_reviews.unwrap_or(0),
         summary: summary.review_score_desc.unwrap_or_default(),
+        band,
+    })
+}
+
+/// Fetch the Workshop items a user is subscribed to for one app
+///
+/// Returns None if the request fails (bad key, private profile, network).
+pub async fn fetch_subscribed_workshop_items(
+    client: &HttpClient,
+    api_key: &str,
+    steam_id: &str,
+    app_id: i64,
+) -> Option<Vec<SteamWorkshopItem>> {
+    let mut items = Vec::new();
+
+    for page in 1..=WORKSHOP_MAX_PAGES {
+        let url = format!(
+            "{}/IPublishedFileService/GetUserFiles/v1/?key={}&steamid={}&appid={}&type=mysubscriptions&page={}&numperpage={}&return_short_description=true",
+            STEAM_WEB_API,
+            urlencoding::encode(api_key),
+            urlencoding::encode(steam_id),
+            app_id,
+            page,
+            WORKSHOP_PAGE_SIZE
+        );
+
+        let response = match client
+            .get(&url)
+            .timeout(Duration::from_secs(15))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::warn!("Failed to fetch Workshop items for {}: {}", app_id, e);
+                return None;
+            }
+        };
+
+        if !response.status().is_success() {
+            tracing::warn!(
+                "Steam Web API returned {} for Workshop items of {}",
+                response.status(),
+                app_id
+            );
+            return None;
+        }
+
+        let data: serde_json::Value = match response.json().await {
+            Ok(d) => d,
+            Err(e) => {
+                tracing::warn!("Failed to parse Workshop items for {}: {}", app_id, e);
+                return None;
+            }
+        };
+
+        let body = data.get("response")?;
+        let total = body.get("total").and_then(json_u64).unwrap_or(0) as usize;
+        let page_items: Vec<SteamWorkshopItem> = body
+            .get("publishedfiledetails")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(parse_workshop_item).collect())
+            .unwrap_or_default();
+
+        let page_len = page_items.len();
+        items.extend(page_items);
+
+        if page_len == 0 || items.len() >= total {
+            break;
+        }
+    }
+
+    Some(items)
+}
+
+/// Steam Deck compatibility category name for Steam's numeric category
+pub fn deck_category_name(category: u64) -> &'static str {
+    match category {
+        3 => "verified",
+        2 => "playable",
+        1 => "unsupported",
+        _ => "unknown",
+    }
+}
+
+/// Fetch the Steam Deck compatibility category of an app
+///
+/// Returns "verified", "playable", "unsupported" or "unknown" (not yet
+/// tested by Valve), or None if the request fails.
+pub async fn fetch_deck_compatibility(client: &HttpClient, app_id: i64) -> Option<&'static str> {
+    let url = format!(
+        "{}/ajaxgetdeckappcompatibilityreport?nAppID={}&l=english",
+        STEAM_DECK_COMPAT_URL, app_id
+    );
+
+    let response = match client
+        .get(&url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!("Failed to fetch Steam Deck status for {}: {}", app_id, e);
+            return None;
+        }
+    };
+
+    let data: serde_json::Value = match response.json().await {
+        Ok(d) => d,
+        Err(e) => {
+            tracing::warn!("Failed to parse Steam Deck status for {}: {}", app_id, e);
+            return None;
+        }
+    };
+
+    parse_deck_compatibility(&data)
+}
+
+/// Parse a Deck compatibility report response
+fn parse_deck_compatibility(data: &serde_json::Value) -> Option<&'static str> {
+    if data.get("success").and_then(|s| s.as_u64()) != Some(1) {
+        return None;
+    }
+
+    let category = data
+        .get("results")
+        .and_then(|r| r.get("resolved_category"))
+        .and_then(json_u64)
+        .unwrap_or(0);
+    Some(deck_category_name(category))
+}
+
+/// Fetch how many Points Shop items (profile backgrounds, stickers, ...)
+/// an app has
+///
+/// Returns None if the request fails.
+pub async fn fetch_points_shop_items(client: &HttpClient, app_id: i64) -> Option<i64> {
+    let url = format!(
+        "{}/ILoyaltyRewardsService/QueryRewardItems/v1/?appids%5B0%5D={}&count=1",
+        STEAM_WEB_API, app_id
+    );
+    let data = fetch_web_api_json(client, &url, "Points Shop items").await?;
+    parse_points_shop_items(&data)
+}
+
+/// Item count of a QueryRewardItems response (the count of the page when
+/// the total is left out)
+fn parse_points_shop_items(data: &serde_json::Value) -> Option<i64> {
+    let response = data.get("response")?;
+    let count = response
+        .get("total_count")
+        .and_then(json_u64)
+        .or_else(|| {
+            response
+                .get("definitions")
+                .and_then(|d| d.as_array())
+                .map(|d| d.len() as u64)
+        })
+        .unwrap_or(0);
+    Some(count as i64)
+}
+
+/// GET a Steam Web API URL and parse the JSON body
+async fn fetch_web_api_json(
+    client: &HttpClient,
+    url: &str,
+    what: &str,
+) -> Option<serde_json::Value> {
+    let response = match client
+        .get(url)
+        .timeout(Duration::from_secs(15))
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!("Failed to fetch {}: {}", what, e);
+            return None;
+        }
+    };
+
+    if !response.status().is_success() {
+        tracing::warn!("Steam Web API returned {} for {}", response.status(), what);
+        return None;
+    }
+
+    match response.json().await {
+        Ok(d) => Some(d),
+        Err(e) => {
+            tracing::warn!("Failed to parse {}: {}", what, e);
+            None
+        }
+    }
+}
+
+/// Fetch the SteamID64s on a user's friend list
+///
+/// Returns None if the request fails or the friend list is private.
+pub async fn fetch_friend_ids(
+    client: &HttpClient,
+    api_key: &str,
+    steam_id: &str,
+) -> Option<Vec<String>> {
+    let url = format!(
+        "{}/ISteamUser/GetFriendList/v1/?key={}&steamid={}&relationship=friend",
+        STEAM_WEB_API,
+        urlencoding::encode(api_key),
+        urlencoding::encode(steam_id)
+    );
+
+    let data = fetch_web_api_json(client, &url, "Steam friend list").await?;
+    parse_friend_ids(&data)
+}
+
+/// A user's library as returned by `GetOwnedGames`
+#[derive(Debug, PartialEq)]
+pub enum OwnedGames {
+    /// (app ID, playtime in minutes) of each game
+    Public(Vec<(i64, i64)>),
+    /// The profile's game details are private
+    Private,
+}
+
+/// Fetch the games a user owns
+///
+/// Returns None if the request fails (network error, rate limit, server
+/// error or an unexpected response), which says nothing about the library.
+pub async fn fetch_owned_games(
+    client: &HttpClient,
+    api_key: &str,
+    steam_id: &str,
+) -> Option<OwnedGames> {
+    let url = format!(
+        "{}/IPlayerService/GetOwnedGames/v1/?key={}&steamid={}&include_played_free_games=1",
+        STEAM_WEB_API,
+        urlencoding::encode(api_key),
+        urlencoding::encode(steam_id)
+    );
+
+    let data = fetch_web_api_json(client, &url, "owned games").await?;
+    parse_owned_games(&data)
+}
+
+/// Parse a `GetFriendList` response
+fn parse_friend_ids(data: &serde_json::Value) -> Option<Vec<String>> {
+    let friends = data.get("friendslist")?.get("friends")?.as_array()?;
+    Some(
+        friends
+            .iter()
+            .filter_map(|f| f.get("steamid").and_then(json_u64))
+            .map(|id| id.to_string())
+            .collect(),
+    )
+}
+
+/// Parse a `GetOwnedGames` response
+///
+/// Private profiles return an empty `response`; public ones always have a
+/// `game_count`, and a `games` list unless the library is empty.
+fn parse_owned_games(data: &serde_json::Value) -> Option<OwnedGames> {
+    let response = data.get("response")?.as_object()?;
+    if response.is_empty() {
+        return Some(OwnedGames::Private);
+    }
+    response.get("game_count")?;
+    let games = match response.get("games") {
+        Some(games) => games.as_array()?,
+        None => return Some(OwnedGames::Public(Vec::new())),
+    };
+    Some(OwnedGames::Public(
+        games
+            .iter()
+            .filter_map(|g| {
+                let app_id = g.get("appid").and_then(json_u64)? as i64;
+                let playtime = g.get("playtime_forever").and_then(json_u64).unwrap_or(0) as i64;
+                Some((app_id, playtime))
+            })
+            .collect(),
+    ))
+}
+
+/// Read a number the Web API may encode as either a JSON number or a string (64-bit IDs)
+fn json_u64(v: &serde_json::Value) -> Option<u64> {
+    v.as_u64()
+        .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+}
+
+/// Convert a Unix timestamp to a database timestamp
+fn unix_to_datetime(ts: u64) -> Option<String> {
+    if ts == 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp(ts as i64, 0).map(timestamps::format)
+}
+
+/// Parse one entry of `publishedfiledetails`
+fn parse_workshop_item(v: &serde_json::Value) -> Option<SteamWorkshopItem> {
+    let published_file_id = json_u64(v.get("publishedfileid")?)?.to_string();
+    let title = v
+        .get("title")
+        .and_then(|t| t.as_str())
+        .filter(|t| !t.is_empty())
+        .unwrap_or("Untitled item")
+        .to_string();
+
+    Some(SteamWorkshopItem {
+        published_file_id,
+        title,
+        description: v
+            .get("short_description")
+            .or_else(|| v.get("file_description"))
+            .and_then(|d| d.as_str())
+            .filter(|d| !d.is_empty())
+            .map(String::from),
+        preview_url: v
+            .get("preview_url")
+            .and_then(|u| u.as_str())
+            .filter(|u| !u.is_empty())
+            .map(String::from),
+        file_size: v.get("file_size").and_then(json_u64).map(|s| s as i64),
+        time_created: v
+            .get("time_created")
+            .and_then(json_u64)
+            .and_then(unix_to_datetime),
+        time_updated: v
+            .get("time_updated")
+            .and_then(json_u64)
+            .and_then(unix_to_datetime),
     })
 }
 
 #[derive(Debug, Clone)]
 pub struct SteamAppDetails {
-    pub app_id: i64,
     pub name: String,
     pub description: Option<String>,
     pub header_image: Option<String>,
@@ -465,6 +1015,12 @@ pub struct SteamAppDetails {
     pub publishers: Option<Vec<String>>,
     pub genres: Option<Vec<String>>,
     pub release_date: Option<String>,
+    /// Base game's app id when this app is DLC
+    pub fullgame_app_id: Option<i64>,
+    /// Minimum age the store lists (0 = not age-gated)
+    pub age_rating: Option<i64>,
+    /// Listed with Steam Trading Cards
+    pub trading_cards: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -472,6 +1028,209 @@ pub struct SteamReviews {
     pub score: i64,
     pub count: i64,
     pub summary: String,
+    pub band: Option<ReviewBand>,
+}
+
+/// A Workshop item as returned by the Steam Web API
+#[derive(Debug, Clone)]
+pub struct SteamWorkshopItem {
+    pub published_file_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub preview_url: Option<String>,
+    pub file_size: Option<i64>,
+    pub time_created: Option<String>,
+    pub time_updated: Option<String>,
 }
 
 // urlencoding is imported from the crate
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_workshop_item() {
+        // 64-bit fields arrive as strings, timestamps as numbers
+        let value = serde_json::json!({
+            "publishedfileid": "2862421375",
+            "title": "Better Minimap",
+            "short_description": "",
+            "file_size": "1048576",
+            "preview_url": "https://steamuserimages-a.akamaihd.net/preview.jpg",
+            "time_created": 1665000000,
+            "time_updated": 1700000000
+        });
+
+        let item = parse_workshop_item(&value).unwrap();
+        assert_eq!(item.published_file_id, "2862421375");
+        assert_eq!(item.title, "Better Minimap");
+        assert_eq!(item.description, None);
+        assert_eq!(item.file_size, Some(1_048_576));
+        assert_eq!(item.time_updated.as_deref(), Some("2023-11-14T22:13:20Z"));
+    }
+
+    #[test]
+    fn test_parse_friends_and_owned_games() {
+        let friends = serde_json::json!({
+            "friendslist": { "friends": [
+                { "steamid": "76561197960287930", "relationship": "friend", "friend_since": 0 },
+                { "steamid": "76561197960435530", "relationship": "friend", "friend_since": 0 }
+            ]}
+        });
+        assert_eq!(
+            parse_friend_ids(&friends).unwrap(),
+            vec!["76561197960287930", "76561197960435530"]
+        );
+
+        let owned = serde_json::json!({
+            "response": { "game_count": 2, "games": [
+                { "appid": 620, "playtime_forever": 754 },
+                { "appid": 1145360 }
+            ]}
+        });
+        assert_eq!(
+            parse_owned_games(&owned),
+            Some(OwnedGames::Public(vec![(620, 754), (1_145_360, 0)]))
+        );
+        let empty = serde_json::json!({ "response": { "game_count": 0 } });
+        assert_eq!(
+            parse_owned_games(&empty),
+            Some(OwnedGames::Public(Vec::new()))
+        );
+
+        // Private profiles return an empty response object
+        let private = serde_json::json!({ "response": {} });
+        assert_eq!(parse_owned_games(&private), Some(OwnedGames::Private));
+
+        // Anything else is an error, not a private library
+        assert_eq!(parse_owned_games(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn test_parse_deck_compatibility() {
+        let verified = serde_json::json!({
+            "success": 1,
+            "results": { "appid": 620, "resolved_category": 3, "resolved_items": [] }
+        });
+        assert_eq!(parse_deck_compatibility(&verified), Some("verified"));
+
+        // Apps Valve hasn't reviewed have no results
+        let untested = serde_json::json!({ "success": 1, "results": [] });
+        assert_eq!(parse_deck_compatibility(&untested), Some("unknown"));
+
+        assert_eq!(
+            parse_deck_compatibility(&serde_json::json!({ "success": 2 })),
+            None
+        );
+    }
+
+    #[test]
+    fn test_has_trading_cards() {
+        let app = |categories: serde_json::Value| -> SteamAppData {
+            serde_json::from_value(serde_json::json!({
+                "steam_appid": 620,
+                "name": "Portal 2",
+                "categories": categories
+            }))
+            .unwrap()
+        };
+        assert!(has_trading_cards(&app(serde_json::json!([
+            { "id": 2, "description": "Single-player" },
+            { "id": 29, "description": "Steam Trading Cards" }
+        ]))));
+        assert!(!has_trading_cards(&app(serde_json::json!([
+            { "id": 2, "description": "Single-player" }
+        ]))));
+        assert!(!has_trading_cards(&app(serde_json::Value::Null)));
+    }
+
+    #[test]
+    fn test_parse_points_shop_items() {
+        let items = serde_json::json!({
+            "response": { "definitions": [{ "appid": 620 }], "total_count": 37 }
+        });
+        assert_eq!(parse_points_shop_items(&items), Some(37));
+
+        // Apps without items get an empty response
+        let none = serde_json::json!({ "response": {} });
+        assert_eq!(parse_points_shop_items(&none), Some(0));
+
+        assert_eq!(parse_points_shop_items(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn test_best_search_match() {
+        let results = vec![
+            SteamSearchResult {
+                appid: 1,
+                name: "Hades II".to_string(),
+            },
+            SteamSearchResult {
+                appid: 2,
+                name: "Hades".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            best_search_match("Hades", &results, None).map(|m| m.0),
+            Some(2)
+        );
+        assert_eq!(best_search_match("Zzyzx Quest", &results, None), None);
+        assert_eq!(best_search_match("Hades", &[], None), None);
+    }
+
+    #[test]
+    fn test_best_search_match_year_hint() {
+        // Original (registered 2013) vs remake (registered 2022) with equal names
+        let results = vec![
+            SteamSearchResult {
+                appid: 254700,
+                name: "Resident Evil 4".to_string(),
+            },
+            SteamSearchResult {
+                appid: 2050650,
+                name: "Resident Evil 4".to_string(),
+            },
+        ];
+
+        let pick = |year| best_search_match("Resident Evil 4", &results, year).map(|m| m.0);
+        assert_eq!(pick(None), Some(254700));
+        assert_eq!(pick(Some(2023)), Some(2050650));
+        assert_eq!(pick(Some(2005)), Some(254700));
+
+        assert_eq!(estimate_appid_year(504230), 2016);
+        assert_eq!(estimate_appid_year(10), 2003);
+    }
+
+    #[test]
+    fn test_best_search_match_year_hint_respects_threshold() {
+        // The first result passes the threshold but was registered long
+        // before the hinted year; the second fails it but gets the year bonus
+        // and so has the higher score. The passing one must still be picked.
+        let results = vec![
+            SteamSearchResult {
+                appid: 570,
+                name: "Abandon Ship".to_string(),
+            },
+            SteamSearchResult {
+                appid: 2_300_000,
+                name: "Harbor".to_string(),
+            },
+        ];
+        let scored = score_search_results("Hades", &results, Some(2023));
+        assert!(scored[0].similarity > SEARCH_MIN_SIMILARITY);
+        assert!(scored[1].similarity <= SEARCH_MIN_SIMILARITY);
+        assert!(scored[1].score > scored[0].score);
+
+        assert_eq!(
+            best_search_match("Hades", &results, Some(2023)).map(|m| m.0),
+            Some(570)
+        );
+    }
+
+    #[test]
+    fn test_parse_workshop_item_requires_id() {
+        assert!(parse_workshop_item(&serde_json::json!({ "title": "No ID" })).is_none());
+    }
+}
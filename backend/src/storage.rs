@@ -0,0 +1,329 @@
+//! Library statistics by storage tier
+//!
+//! Library locations are tagged with a tier under `[storage]`: the library
+//! drive, folders inside it that are mounts of other drives, and the archive
+//! target. Per tier the report counts games, their size and recent play, and
+//! it lists candidates to move: games played often that sit on slow storage,
+//! and games on an SSD that haven't been played in a long time.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::config::{resolve_path, StorageConfig, StorageTier};
+use crate::db;
+use crate::models::{Game, GamePlaytime};
+use crate::timestamps;
+
+/// Default window for "played recently", in days
+pub const DEFAULT_WINDOW_DAYS: i64 = 30;
+/// Default time without play before a game on an SSD is a candidate to move
+pub const DEFAULT_IDLE_DAYS: i64 = 180;
+/// Longest window accepted for either
+pub const MAX_DAYS: i64 = 3650;
+
+/// Sessions within the window that make a game "played often"
+pub const FREQUENT_SESSIONS: i64 = 3;
+
+/// Most candidates listed in each direction
+const MAX_CANDIDATES: usize = 50;
+
+impl StorageTier {
+    /// Worth moving frequently played games off
+    pub fn is_slow(self) -> bool {
+        matches!(
+            self,
+            StorageTier::Hdd | StorageTier::Nas | StorageTier::Archive
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TierStats {
+    pub tier: StorageTier,
+    pub games: usize,
+    pub size_bytes: i64,
+    /// Games with a session in the window
+    pub played_recently: usize,
+    /// Minutes played in the window
+    pub recent_mins: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveCandidate {
+    pub game_id: i64,
+    pub title: String,
+    pub tier: StorageTier,
+    pub size_bytes: Option<i64>,
+    pub recent_sessions: i64,
+    pub recent_mins: i64,
+    pub last_played: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageReport {
+    pub window_days: i64,
+    pub idle_days: i64,
+    /// Tiers that hold games, fastest first
+    pub tiers: Vec<TierStats>,
+    /// Played often but on slow storage, most played first
+    pub to_faster: Vec<MoveCandidate>,
+    /// On an SSD but not played for `idle_days`, largest first
+    pub to_slower: Vec<MoveCandidate>,
+}
+
+/// Path comparison key: forward slashes, no trailing slash, lowercase
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+/// Resolves the tier of a game from its folder
+pub struct TierMap {
+    /// Configured locations, longest path first so the closest one wins
+    locations: Vec<(String, StorageTier)>,
+    library: StorageTier,
+    archive: StorageTier,
+}
+
+impl TierMap {
+    pub fn new(config: &StorageConfig) -> Self {
+        let mut locations: Vec<(String, StorageTier)> = config
+            .tiers
+            .iter()
+            .map(|(path, tier)| (normalize(&resolve_path(path).to_string_lossy()), *tier))
+            .collect();
+        locations.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+
+        TierMap {
+            locations,
+            library: config.library_tier,
+            archive: config.archive_tier,
+        }
+    }
+
+    pub fn tier_for(&self, game: &Game) -> StorageTier {
+        if game.archived.unwrap_or(0) != 0 {
+            return self.archive;
+        }
+        let folder = normalize(&game.folder_path);
+        self.locations
+            .iter()
+            .find(|(path, _)| folder == *path || folder.starts_with(&format!("{}/", path)))
+            .map_or(self.library, |(_, tier)| *tier)
+    }
+}
+
+/// Build the report from the library and its play sessions
+///
+/// `recent` is playtime within the window, `last_played` the latest session
+/// start per game.
+pub fn build_report(
+    games: &[Game],
+    recent: &[GamePlaytime],
+    last_played: &HashMap<i64, String>,
+    tiers: &TierMap,
+    now: NaiveDateTime,
+    window_days: i64,
+    idle_days: i64,
+) -> StorageReport {
+    let recent: HashMap<i64, &GamePlaytime> = recent.iter().map(|p| (p.game_id, p)).collect();
+    let idle_since = timestamps::format_naive(now - Duration::days(idle_days));
+
+    let mut stats: BTreeMap<StorageTier, TierStats> = BTreeMap::new();
+    let mut to_faster = Vec::new();
+    let mut to_slower = Vec::new();
+
+    for game in games {
+        let tier = tiers.tier_for(game);
+        let played = recent.get(&game.id);
+        let (recent_sessions, recent_mins) = played.map_or((0, 0), |p| (p.sessions, p.active_mins));
+
+        let entry = stats.entry(tier).or_insert_with(|| TierStats {
+            tier,
+            games: 0,
+            size_bytes: 0,
+            played_recently: 0,
+            recent_mins: 0,
+        });
+        entry.games += 1;
+        entry.size_bytes += game.size_bytes.unwrap_or(0);
+        entry.played_recently += usize::from(played.is_some());
+        entry.recent_mins += recent_mins;
+
+        let last = last_played.get(&game.id);
+        let candidate = || MoveCandidate {
+            game_id: game.id,
+            title: game.title.clone(),
+            tier,
+            size_bytes: game.size_bytes,
+            recent_sessions,
+            recent_mins,
+            last_played: last.cloned(),
+        };
+
+        if tier.is_slow() && recent_sessions >= FREQUENT_SESSIONS {
+            to_faster.push(candidate());
+        } else if tier == StorageTier::Ssd {
+            // Never played: idle since it was added
+            let idle_from = last.unwrap_or(&game.created_at);
+            if idle_from.as_str() < idle_since.as_str() {
+                to_slower.push(candidate());
+            }
+        }
+    }
+
+    to_faster.sort_by_key(|c| std::cmp::Reverse(c.recent_mins));
+    to_slower.sort_by_key(|c| std::cmp::Reverse(c.size_bytes));
+    to_faster.truncate(MAX_CANDIDATES);
+    to_slower.truncate(MAX_CANDIDATES);
+
+    StorageReport {
+        window_days,
+        idle_days,
+        tiers: stats.into_values().collect(),
+        to_faster,
+        to_slower,
+    }
+}
+
+/// Storage report for the current library
+pub async fn report(
+    pool: &SqlitePool,
+    config: &StorageConfig,
+    window_days: i64,
+    idle_days: i64,
+) -> Result<StorageReport, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let format = timestamps::format_naive;
+
+    let games = db::get_visible_games(pool).await?;
+    let recent = db::get_playtime_by_game(
+        pool,
+        &format(now - Duration::days(window_days)),
+        &format(now),
+    )
+    .await?;
+    let last_played = db::get_playtime_by_game(pool, "1970-01-01T00:00:00Z", &format(now))
+        .await?
+        .into_iter()
+        .map(|p| (p.game_id, p.last_played))
+        .collect();
+
+    Ok(build_report(
+        &games,
+        &recent,
+        &last_played,
+        &TierMap::new(config),
+        now,
+        window_days,
+        idle_days,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::create_test_game;
+
+    fn game(id: i64, folder: &str, size_gb: i64, archived: bool) -> Game {
+        Game {
+            folder_path: folder.to_string(),
+            folder_name: id.to_string(),
+            size_bytes: Some(size_gb << 30),
+            archived: Some(i64::from(archived)),
+            created_at: "2023-01-01T00:00:00Z".to_string(),
+            updated_at: "2023-01-01T00:00:00Z".to_string(),
+            ..create_test_game(id, &format!("Game {}", id))
+        }
+    }
+
+    fn playtime(game_id: i64, sessions: i64, active_mins: i64) -> GamePlaytime {
+        GamePlaytime {
+            game_id,
+            title: format!("Game {}", game_id),
+            active_mins,
+            sessions,
+            last_played: "2024-05-09T20:00:00Z".to_string(),
+        }
+    }
+
+    fn tier_map() -> TierMap {
+        TierMap::new(&StorageConfig {
+            library_tier: StorageTier::Ssd,
+            tiers: HashMap::from([
+                ("/games/slow".to_string(), StorageTier::Hdd),
+                ("/games/slow/nas".to_string(), StorageTier::Nas),
+            ]),
+            archive_tier: StorageTier::Archive,
+        })
+    }
+
+    #[test]
+    fn test_tier_for() {
+        let tiers = tier_map();
+        assert_eq!(
+            tiers.tier_for(&game(1, "/games/Hades", 1, false)),
+            StorageTier::Ssd
+        );
+        assert_eq!(
+            tiers.tier_for(&game(2, "/games/Slow/Hades", 1, false)),
+            StorageTier::Hdd
+        );
+        assert_eq!(
+            tiers.tier_for(&game(3, "/games/slow/NAS/Hades", 1, false)),
+            StorageTier::Nas
+        );
+        assert_eq!(
+            tiers.tier_for(&game(4, "/games/slower/Hades", 1, false)),
+            StorageTier::Ssd
+        );
+        assert_eq!(
+            tiers.tier_for(&game(5, "/games/Hades", 1, true)),
+            StorageTier::Archive
+        );
+    }
+
+    #[test]
+    fn test_build_report() {
+        let now = timestamps::parse_naive("2024-05-10T12:00:00Z").unwrap();
+        let games = [
+            // SSD, played this week
+            game(1, "/games/Elden Ring", 50, false),
+            // SSD, last played a year ago
+            game(2, "/games/Witcher 3", 40, false),
+            // SSD, never played (added in 2023)
+            game(3, "/games/Hades", 10, false),
+            // HDD, played often
+            game(4, "/games/slow/Celeste", 1, false),
+            // NAS, played once
+            game(5, "/games/slow/nas/Doom", 20, false),
+        ];
+        let recent = [playtime(1, 2, 300), playtime(4, 5, 600), playtime(5, 1, 30)];
+        let last_played = HashMap::from([
+            (1, "2024-05-09T20:00:00Z".to_string()),
+            (2, "2023-04-01T18:00:00Z".to_string()),
+            (4, "2024-05-09T20:00:00Z".to_string()),
+            (5, "2024-05-01T20:00:00Z".to_string()),
+        ]);
+
+        let report = build_report(&games, &recent, &last_played, &tier_map(), now, 30, 180);
+        let ids = |list: &[MoveCandidate]| list.iter().map(|c| c.game_id).collect::<Vec<_>>();
+        assert_eq!(ids(&report.to_faster), [4]);
+        assert_eq!(ids(&report.to_slower), [2, 3]);
+
+        let ssd = &report.tiers[0];
+        assert_eq!(ssd.tier, StorageTier::Ssd);
+        assert_eq!(
+            (ssd.games, ssd.played_recently, ssd.recent_mins),
+            (3, 1, 300)
+        );
+        assert_eq!(ssd.size_bytes, 100 << 30);
+        let tiers: Vec<StorageTier> = report.tiers.iter().map(|t| t.tier).collect();
+        assert_eq!(
+            tiers,
+            [StorageTier::Ssd, StorageTier::Hdd, StorageTier::Nas]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Timestamp format
+//!
+//! Every timestamp the database stores and the API returns is UTC in RFC
+//! 3339 with second precision: `2024-05-10T18:02:11Z`. The format is fixed
+//! width, so timestamps still compare correctly as text, and it carries its
+//! time zone, so clients and metadata.json files written on machines in
+//! other zones compare against it correctly.
+//!
+//! SQL writes use `strftime('%Y-%m-%dT%H:%M:%SZ', 'now')` (with modifiers
+//! after `'now'` where needed) instead of SQLite's naive `datetime('now')`.
+//! Databases from before the format was fixed are rewritten at startup by
+//! `db::normalize_timestamps`; [`parse`] still accepts both forms.
+
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+
+/// Format of naive timestamps written before the format was fixed
+const LEGACY_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Current time
+pub fn now() -> String {
+    format(Utc::now())
+}
+
+pub fn format(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Format a naive UTC time
+pub fn format_naive(time: NaiveDateTime) -> String {
+    format(time.and_utc())
+}
+
+/// Parse an RFC 3339 timestamp in any offset, or a legacy naive one (UTC)
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, LEGACY_FORMAT)
+                .ok()
+                .map(|t| t.and_utc())
+        })
+}
+
+/// Parse to a naive UTC time, for date arithmetic
+pub fn parse_naive(value: &str) -> Option<NaiveDateTime> {
+    parse(value).map(|t| t.naive_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_upgrade_legacy_database() {
+        let (pool, _keepalive) = crate::demo::connect().await.unwrap();
+        // A table as older versions created it, with a row they wrote
+        sqlx::query(
+            r#"
+            CREATE TABLE notifications (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                title TEXT NOT NULL,
+                message TEXT NOT NULL,
+                game_id INTEGER,
+                read_at TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            INSERT INTO notifications (kind, title, message, created_at)
+            VALUES ('scan_complete', 'Old', 'm', '2024-05-10 18:02:11');
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        crate::db::run_migrations(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO notifications (kind, title, message) VALUES ('scan_complete', 'New', 'm')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT title, created_at FROM notifications ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            rows[0],
+            ("Old".to_string(), "2024-05-10T18:02:11Z".to_string())
+        );
+        assert_eq!(rows[1].0, "New");
+        assert!(parse(&rows[1].1).is_some_and(|t| format(t) == rows[1].1));
+
+        let indexes: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = 'idx_notifications_unread'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(indexes.0, 1);
+    }
+
+    #[test]
+    fn test_format_and_parse() {
+        let time = parse("2024-05-10T18:02:11Z").unwrap();
+        assert_eq!(format(time), "2024-05-10T18:02:11Z");
+        assert_eq!(parse("2024-05-10 18:02:11"), Some(time));
+        assert_eq!(parse("2024-05-10T20:02:11+02:00"), Some(time));
+        assert_eq!(
+            parse("2024-05-10T18:02:11.250Z").map(format),
+            Some(format(time))
+        );
+        assert_eq!(parse("yesterday"), None);
+        assert_eq!(now().len(), "2024-05-10T18:02:11Z".len());
+    }
+}
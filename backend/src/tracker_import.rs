@@ -0,0 +1,477 @@
+//! Backloggd and Grouvee imports
+//!
+//! Both trackers export a CSV of the user's games. Each row's title is
+//! matched against the library (exactly after normalizing, else by
+//! similarity), and its status, rating and finish date go through the
+//! [import pipeline](crate::import) into `user_status`, `user_rating` and
+//! `finished_at`.
+//!
+//! Titles that fit no game well enough, or fit several equally, end up in
+//! the report as `not_found` rows listing the closest games. Running the
+//! import again with a `resolve` entry for such a title (a game id, or null
+//! to leave the row out) settles it, so the usual flow is a dry run, a look
+//! at the unresolved rows, and a second run with the answers.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+use strsim::jaro_winkler;
+
+use crate::duplicates::normalize_title;
+use crate::import::{ImportRow, MatchCandidate};
+use crate::models::Game;
+
+/// Similarity a title needs to be matched without asking
+const MATCH_THRESHOLD: f64 = 0.93;
+
+/// How far ahead of the runner-up the best match must be
+const MATCH_MARGIN: f64 = 0.03;
+
+/// Closest games listed for an unresolved row
+const MAX_CANDIDATES: usize = 3;
+
+/// Similarity below which a game isn't worth listing
+const CANDIDATE_THRESHOLD: f64 = 0.6;
+
+/// Tracker an export comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackerSource {
+    Backloggd,
+    Grouvee,
+}
+
+impl TrackerSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrackerSource::Backloggd => "backloggd",
+            TrackerSource::Grouvee => "grouvee",
+        }
+    }
+}
+
+/// One game read from an export
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackerEntry {
+    pub title: String,
+    /// "unplayed", "playing", "completed" or "abandoned"
+    pub user_status: Option<&'static str>,
+    /// 1-100
+    pub user_rating: Option<i64>,
+    /// YYYY-MM-DD
+    pub finished_at: Option<String>,
+}
+
+impl TrackerEntry {
+    fn is_empty(&self) -> bool {
+        self.user_status.is_none() && self.user_rating.is_none() && self.finished_at.is_none()
+    }
+}
+
+/// Columns of an export, looked up by any of their known names
+struct Columns {
+    headers: Vec<String>,
+}
+
+impl Columns {
+    fn find(&self, names: &[&str]) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)))
+    }
+}
+
+/// Trimmed, non-empty value of a column
+fn field(record: &csv::StringRecord, column: Option<usize>) -> Option<&str> {
+    column
+        .and_then(|c| record.get(c))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Read an export
+pub fn parse(source: TrackerSource, csv_text: &str) -> Result<Vec<TrackerEntry>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv_text.trim_start_matches('\u{feff}').as_bytes());
+    let columns = Columns {
+        headers: reader
+            .headers()
+            .map_err(|e| format!("Invalid CSV: {}", e))?
+            .iter()
+            .map(|h| h.trim().to_string())
+            .collect(),
+    };
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid CSV: {}", e))?;
+
+    let title = columns
+        .find(&["name", "game name", "game", "title"])
+        .ok_or("The export has no game name column")?;
+    let rating = columns.find(&["rating", "stars"]);
+
+    // Backloggd rates out of 5 in half stars, or out of 10 in some exports
+    let rating_scale = match source {
+        TrackerSource::Grouvee => 5.0,
+        TrackerSource::Backloggd => {
+            let highest = records
+                .iter()
+                .filter_map(|r| field(r, rating)?.parse::<f64>().ok())
+                .fold(0.0, f64::max);
+            if highest > 5.0 {
+                10.0
+            } else {
+                5.0
+            }
+        }
+    };
+
+    let status = columns.find(&["status"]);
+    let flags = ["playing", "played", "backlog"].map(|flag| columns.find(&[flag]));
+    let finished = columns.find(&[
+        "finish date",
+        "finished",
+        "finished on",
+        "date finished",
+        "completion date",
+    ]);
+    let shelves = columns.find(&["shelves"]);
+    let dates = columns.find(&["dates"]);
+
+    let mut entries = Vec::new();
+    for record in &records {
+        let Some(title) = field(record, Some(title)) else {
+            continue;
+        };
+        let mut entry = TrackerEntry {
+            title: title.to_string(),
+            user_rating: field(record, rating)
+                .and_then(|r| r.parse::<f64>().ok())
+                .and_then(|r| scale_rating(r, rating_scale)),
+            ..Default::default()
+        };
+        match source {
+            TrackerSource::Backloggd => {
+                entry.user_status =
+                    field(record, status)
+                        .and_then(backloggd_status)
+                        .or_else(|| {
+                            // Older exports mark each list with a true/false column
+                            let set = |i: usize| {
+                                field(record, flags[i])
+                                    .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+                            };
+                            [(0, "playing"), (1, "completed"), (2, "unplayed")]
+                                .into_iter()
+                                .find(|(i, _)| set(*i))
+                                .map(|(_, status)| status)
+                        });
+                entry.finished_at = field(record, finished).and_then(parse_date);
+            }
+            TrackerSource::Grouvee => {
+                entry.user_status = field(record, shelves).and_then(grouvee_status);
+                entry.finished_at = field(record, dates).and_then(grouvee_finished);
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Rating out of `scale` as 1-100 (0 = unrated)
+fn scale_rating(rating: f64, scale: f64) -> Option<i64> {
+    (rating > 0.0).then(|| ((rating / scale * 100.0).round() as i64).clamp(1, 100))
+}
+
+fn backloggd_status(status: &str) -> Option<&'static str> {
+    match status.to_lowercase().as_str() {
+        "completed" | "played" | "beaten" | "mastered" => Some("completed"),
+        "playing" => Some("playing"),
+        "abandoned" | "retired" | "shelved" => Some("abandoned"),
+        "backlog" => Some("unplayed"),
+        // Wishlist entries aren't owned yet
+        _ => None,
+    }
+}
+
+/// Status from Grouvee's shelves: `{"Played": {...}, "Backlog": {...}}`
+fn grouvee_status(shelves: &str) -> Option<&'static str> {
+    let shelves: HashMap<String, serde_json::Value> = serde_json::from_str(shelves).ok()?;
+    let on = |name: &str| shelves.keys().any(|k| k.eq_ignore_ascii_case(name));
+    if on("Playing") {
+        Some("playing")
+    } else if on("Played") {
+        Some("completed")
+    } else if on("Backlog") {
+        Some("unplayed")
+    } else {
+        None
+    }
+}
+
+/// Latest finish date from Grouvee's play-through list:
+/// `[{"date_started": "2020-01-02", "date_finished": "2020-02-03", ...}]`
+fn grouvee_finished(dates: &str) -> Option<String> {
+    let runs: Vec<serde_json::Value> = serde_json::from_str(dates).ok()?;
+    runs.iter()
+        .filter_map(|run| run.get("date_finished")?.as_str().and_then(parse_date))
+        .max()
+}
+
+/// Date as YYYY-MM-DD, from "2023-04-05", a timestamp, or "Apr 05, 2023"
+fn parse_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    value
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%b %d, %Y").ok())
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+/// Outcome of matching an export title against the library
+#[derive(Debug, Clone, PartialEq)]
+pub enum TitleMatch {
+    Found(i64),
+    /// No confident match; the closest games, best first
+    Unresolved(Vec<MatchCandidate>),
+}
+
+/// Library titles prepared for matching
+pub struct TitleIndex {
+    games: Vec<(i64, String, String)>,
+}
+
+impl TitleIndex {
+    /// Index visible games (hidden copies are left out)
+    pub fn new(games: &[Game]) -> Self {
+        TitleIndex {
+            games: games
+                .iter()
+                .filter(|g| g.hidden.unwrap_or(0) == 0)
+                .map(|g| (g.id, g.title.clone(), normalize_title(&g.title)))
+                .collect(),
+        }
+    }
+
+    pub fn match_title(&self, title: &str) -> TitleMatch {
+        let wanted = normalize_title(title);
+        let candidate = |(id, title, _): &(i64, String, String), score: f64| MatchCandidate {
+            game_id: *id,
+            title: title.clone(),
+            score,
+        };
+
+        let exact: Vec<_> = self.games.iter().filter(|g| g.2 == wanted).collect();
+        match exact.as_slice() {
+            [game] => return TitleMatch::Found(game.0),
+            [] => {}
+            several => {
+                return TitleMatch::Unresolved(several.iter().map(|g| candidate(g, 1.0)).collect())
+            }
+        }
+
+        let mut scored: Vec<_> = self
+            .games
+            .iter()
+            .map(|g| (g, jaro_winkler(&wanted, &g.2)))
+            .filter(|(_, score)| *score >= CANDIDATE_THRESHOLD)
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        match scored.as_slice() {
+            // Sequels differ by little more than a number, so a number
+            // mismatch ("Hades 2" for "Hades") always needs a person
+            [(game, best), rest @ ..]
+                if *best >= MATCH_THRESHOLD
+                    && numbers(title) == numbers(&game.1)
+                    && rest
+                        .first()
+                        .is_none_or(|(_, next)| best - next >= MATCH_MARGIN) =>
+            {
+                TitleMatch::Found(game.0)
+            }
+            _ => TitleMatch::Unresolved(
+                scored
+                    .into_iter()
+                    .take(MAX_CANDIDATES)
+                    .map(|(g, score)| candidate(g, (score * 100.0).round() / 100.0))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Digit runs in a title ("Witcher 3" -> ["3"])
+fn numbers(title: &str) -> Vec<&str> {
+    title
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+/// Turn export entries into import rows
+///
+/// `resolve` settles titles by hand: a game id, or None to leave the row out.
+pub fn rows(
+    entries: Vec<TrackerEntry>,
+    index: &TitleIndex,
+    resolve: &HashMap<String, Option<i64>>,
+) -> Vec<ImportRow> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let key = entry.title.clone();
+            if entry.is_empty() {
+                return ImportRow::skipped(key, None, "No status, rating or finish date");
+            }
+            let game_id = match resolve.get(&entry.title) {
+                Some(None) => return ImportRow::skipped(key, None, "Left out by request"),
+                Some(Some(id)) => *id,
+                None => match index.match_title(&entry.title) {
+                    TitleMatch::Found(id) => id,
+                    TitleMatch::Unresolved(candidates) => {
+                        let reason = match candidates.is_empty() {
+                            true => "No game with a similar title",
+                            false => "No confident title match; resolve it by hand",
+                        };
+                        return ImportRow::unresolved(key, reason, candidates);
+                    }
+                },
+            };
+            ImportRow::new(key, Some(game_id))
+                .set("user_status", entry.user_status)
+                .set("user_rating", entry.user_rating)
+                .set("finished_at", entry.finished_at)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backloggd() {
+        let csv = "\u{feff}Game Name,Platform,Status,Rating,Review,Finish Date\n\
+                   Hades,PC,Completed,4.5,\"Great, really\",2021-03-04\n\
+                   Celeste,PC,Playing,,,\n\
+                   Elden Ring,PC,Wishlist,,,\n";
+        let entries = parse(TrackerSource::Backloggd, csv).unwrap();
+        assert_eq!(
+            entries[0],
+            TrackerEntry {
+                title: "Hades".into(),
+                user_status: Some("completed"),
+                user_rating: Some(90),
+                finished_at: Some("2021-03-04".into()),
+            }
+        );
+        assert_eq!(entries[1].user_status, Some("playing"));
+        assert!(entries[2].is_empty());
+
+        // Out of 10
+        let csv = "name,status,rating\nHades,Played,7\nCeleste,Played,10\n";
+        let entries = parse(TrackerSource::Backloggd, csv).unwrap();
+        assert_eq!(entries[0].user_rating, Some(70));
+
+        assert!(parse(TrackerSource::Backloggd, "platform\nPC\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_grouvee() {
+        let csv = r#"id,name,shelves,platforms,rating,review,dates
+1,Hades,"{""Played"": {""date_added"": ""2021-01-01T10:00:00Z""}}",,5,,"[{""date_started"": ""2021-01-02"", ""date_finished"": ""2021-02-03""}, {""date_started"": null, ""date_finished"": ""2022-05-06""}]"
+2,Celeste,"{""Backlog"": {}}",,,,[]
+"#;
+        let entries = parse(TrackerSource::Grouvee, csv).unwrap();
+        assert_eq!(entries[0].user_status, Some("completed"));
+        assert_eq!(entries[0].user_rating, Some(100));
+        assert_eq!(entries[0].finished_at.as_deref(), Some("2022-05-06"));
+        assert_eq!(entries[1].user_status, Some("unplayed"));
+        assert_eq!(entries[1].finished_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_match_and_import() {
+        use crate::{db, import};
+
+        let (pool, _keepalive) = db::test_pool().await;
+        for title in ["Hades", "Hades II", "The Witcher 3: Wild Hunt", "Celeste"] {
+            db::upsert_game(
+                &pool,
+                &format!("/games/{}", title),
+                title,
+                title,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let games = db::get_all_games(&pool).await.unwrap();
+        let id = |title: &str| games.iter().find(|g| g.title == title).unwrap().id;
+        let index = TitleIndex::new(&games);
+
+        assert_eq!(index.match_title("hades"), TitleMatch::Found(id("Hades")));
+        assert_eq!(
+            index.match_title("The Witcher 3 - Wild Hunt"),
+            TitleMatch::Found(id("The Witcher 3: Wild Hunt"))
+        );
+        let TitleMatch::Unresolved(candidates) = index.match_title("Hades 2") else {
+            panic!("expected an unresolved match");
+        };
+        assert!(candidates.iter().any(|c| c.title == "Hades II"));
+
+        let entries = vec![
+            TrackerEntry {
+                title: "Hades".into(),
+                user_status: Some("completed"),
+                user_rating: Some(90),
+                finished_at: Some("2021-03-04".into()),
+            },
+            TrackerEntry {
+                title: "Hades 2".into(),
+                user_status: Some("playing"),
+                ..Default::default()
+            },
+        ];
+        let report = import::run(
+            &pool,
+            "backloggd",
+            rows(entries.clone(), &index, &HashMap::new()),
+            import::ImportOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!((report.summary.imported, report.summary.not_found), (1, 1));
+        assert!(!report.rows[1].candidates.is_empty());
+
+        let hades = db::get_game_by_id(&pool, id("Hades"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hades.user_status.as_deref(), Some("completed"));
+        assert_eq!(hades.user_rating, Some(90));
+        assert_eq!(hades.finished_at.as_deref(), Some("2021-03-04"));
+
+        // Settle the unresolved row by hand
+        let resolve = HashMap::from([("Hades 2".to_string(), Some(id("Hades II")))]);
+        let report = import::run(
+            &pool,
+            "backloggd",
+            rows(entries, &index, &resolve),
+            import::ImportOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!((report.summary.imported, report.summary.unchanged), (1, 1));
+        let hades2 = db::get_game_by_id(&pool, id("Hades II"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hades2.user_status.as_deref(), Some("playing"));
+    }
+}
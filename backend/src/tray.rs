@@ -2,6 +2,7 @@
 //!
 //! Creates a system tray icon with a context menu for:
 //! - Opening the web UI
+//! - Pairing a companion device
 //! - Quitting the application
 
 #[cfg(windows)]
@@ -14,9 +15,11 @@ use tray_icon::{
 use std::sync::mpsc;
 
 /// Tray icon command sent from the tray thread
+#[cfg_attr(not(windows), allow(dead_code))]
 #[derive(Debug, Clone)]
 pub enum TrayCommand {
     OpenBrowser,
+    PairDevice,
     Quit,
 }
 
@@ -33,12 +36,15 @@ pub fn init_tray(port: u16) -> Option<mpsc::Receiver<TrayCommand>> {
         let menu = Menu::new();
 
         let open_item = MenuItem::new("Open GameVault", true, None);
+        let pair_item = MenuItem::new("Pair a Device", true, None);
         let quit_item = MenuItem::new("Quit", true, None);
 
         let open_id = open_item.id().clone();
+        let pair_id = pair_item.id().clone();
         let quit_id = quit_item.id().clone();
 
         menu.append(&open_item).ok();
+        menu.append(&pair_item).ok();
         menu.append(&quit_item).ok();
 
         // Create tray icon (using embedded icon or default)
@@ -65,6 +71,8 @@ pub fn init_tray(port: u16) -> Option<mpsc::Receiver<TrayCommand>> {
             if let Ok(event) = menu_receiver.recv() {
                 if event.id == open_id {
                     let _ = tx.send(TrayCommand::OpenBrowser);
+                } else if event.id == pair_id {
+                    let _ = tx.send(TrayCommand::PairDevice);
                 } else if event.id == quit_id {
                     let _ = tx.send(TrayCommand::Quit);
                     break;
@@ -108,6 +116,38 @@ fn load_icon() -> tray_icon::Icon {
     tray_icon::Icon::from_rgba(rgba, size, size).expect("Failed to create icon")
 }
 
+/// Show a pairing code in a message box
+///
+/// The box is shown from its own thread so the caller isn't blocked until
+/// it is dismissed.
+#[cfg(windows)]
+pub fn show_pairing_code(code: &str, expires_in_mins: u64) {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONINFORMATION, MB_OK};
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let text = wide(&format!(
+        "Enter {} in the companion app within {} minutes.",
+        code, expires_in_mins
+    ));
+    let caption = wide("GameVault - Pair a Device");
+
+    std::thread::spawn(move || {
+        // SAFETY: both strings are NUL-terminated UTF-16 that outlive the call
+        unsafe {
+            MessageBoxW(
+                0,
+                text.as_ptr(),
+                caption.as_ptr(),
+                MB_OK | MB_ICONINFORMATION,
+            );
+        }
+    });
+}
+
+/// Stub for non-Windows platforms
+#[cfg(not(windows))]
+pub fn show_pairing_code(_code: &str, _expires_in_mins: u64) {}
+
 /// Stub for non-Windows platforms
 #[cfg(not(windows))]
 pub fn init_tray(_port: u16) -> Option<mpsc::Receiver<TrayCommand>> {
@@ -0,0 +1,147 @@
+//! Undo for destructive metadata operations
+//!
+//! Imports, manual edits, rematches and enrichment runs overwrite game
+//! metadata. Before they do, the affected rows are snapshotted into an undo
+//! operation (`undo_operations` / `undo_snapshots`). Reverting an operation
+//! also reverts every operation recorded after it, newest first, so the
+//! library goes back to how it was right before that operation.
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::{db, metadata_sync, models::UndoOperation};
+
+pub const KIND_IMPORT: &str = "import";
+pub const KIND_EDIT: &str = "edit";
+pub const KIND_REMATCH: &str = "rematch";
+pub const KIND_ENRICH: &str = "enrich";
+pub const KIND_RAW_EDIT: &str = "raw_edit";
+pub const KIND_REMATCH_PENDING: &str = "rematch_pending";
+
+/// Number of operations kept; older snapshots are dropped
+pub const HISTORY_LIMIT: i64 = 50;
+
+/// Game columns captured in a snapshot and restored on undo
+pub const UNDO_COLUMNS: &[&str] = &[
+    "title",
+    "steam_app_id",
+    "summary",
+    "release_date",
+    "release_date_iso",
+    "cover_url",
+    "background_url",
+    "local_cover_path",
+    "local_background_path",
+    "genres",
+    "developers",
+    "publishers",
+    "review_score",
+    "review_count",
+    "review_summary",
+    "review_band",
+    "age_rating",
+    "match_confidence",
+    "match_status",
+    "manually_edited",
+    "user_status",
+    "playtime_mins",
+    "user_rating",
+    "finished_at",
+    "custom_fields",
+    "hltb_main_mins",
+    "hltb_extra_mins",
+    "hltb_completionist_mins",
+];
+
+/// Outcome of an undo request
+#[derive(Debug, Serialize)]
+pub struct UndoResult {
+    /// Operations reverted, newest first
+    pub operations: Vec<UndoOperation>,
+    /// Games whose metadata was restored
+    pub games_restored: usize,
+}
+
+/// Revert an operation and everything after it
+///
+/// metadata.json is rewritten for each restored game so the folder copy
+/// doesn't bring the undone values back on the next import.
+pub async fn revert(pool: &SqlitePool, operation_id: i64) -> Result<UndoResult, sqlx::Error> {
+    let (operations, game_ids) = db::revert_undo_operations(pool, operation_id).await?;
+
+    for id in &game_ids {
+        match db::get_game_by_id(pool, *id).await {
+            Ok(Some(game)) => {
+                metadata_sync::save(pool, &game).await;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to reload game {} after undo: {}", id, e),
+        }
+    }
+
+    tracing::info!(
+        "Undid {} operation(s), restored {} game(s)",
+        operations.len(),
+        game_ids.len()
+    );
+
+    Ok(UndoResult {
+        operations,
+        games_restored: game_ids.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{demo, import};
+
+    #[test]
+    fn test_importable_fields_are_snapshotted() {
+        for (field, _) in import::IMPORTABLE_FIELDS {
+            assert!(UNDO_COLUMNS.contains(field), "{} is not snapshotted", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_revert_restores_in_order() {
+        let (pool, _keepalive) = db::test_pool().await;
+        demo::seed(&pool).await.unwrap();
+
+        let game = &db::get_all_games(&pool).await.unwrap()[0];
+        let summary = game.summary.clone();
+
+        db::update_game_metadata(
+            &pool,
+            game.id,
+            Some("Renamed"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let first = db::get_undo_operations(&pool, 1).await.unwrap()[0].id;
+
+        let changes = [("summary", &serde_json::json!("Imported summary"))];
+        db::apply_import(&pool, "test", &[(game.id, changes.to_vec())])
+            .await
+            .unwrap();
+
+        let result = revert(&pool, first).await.unwrap();
+        assert_eq!(result.operations.len(), 2);
+        assert_eq!(result.operations[0].kind, KIND_IMPORT);
+        assert_eq!(result.games_restored, 1);
+
+        let restored = db::get_game_by_id(&pool, game.id).await.unwrap().unwrap();
+        assert_eq!(restored.title, game.title);
+        assert_eq!(restored.summary, summary);
+        assert_eq!(restored.manually_edited, game.manually_edited);
+
+        // Already undone operations are not reverted twice
+        assert!(revert(&pool, first).await.unwrap().operations.is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! Wishlist acquisition and folder reservation
+//!
+//! A wishlist entry marked "acquiring" reserves a folder name in the game
+//! library. When a scan finds that folder (or a folder whose cleaned title
+//! matches the entry) with something in it, the new game is linked to the
+//! entry instead of standing alone: the entry becomes "acquired" and its notes
+//! and price history carry over to the game.
+
+use std::path::Path;
+
+use crate::models::WishlistItem;
+use crate::scanner;
+
+pub const STATUS_WANTED: &str = "wanted";
+pub const STATUS_ACQUIRING: &str = "acquiring";
+pub const STATUS_ACQUIRED: &str = "acquired";
+
+/// Characters that aren't allowed in folder names on Windows
+const INVALID_FOLDER_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Whether a status can be set directly by the user
+pub fn is_valid_status(status: &str) -> bool {
+    matches!(status, STATUS_WANTED | STATUS_ACQUIRING | STATUS_ACQUIRED)
+}
+
+/// Folder name to reserve for a title, safe on every platform
+pub fn reserved_folder_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| {
+            if INVALID_FOLDER_CHARS.contains(&c) {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches('.')
+        .trim()
+        .to_string()
+}
+
+/// Validate a user-supplied folder name (a single path component)
+pub fn is_valid_folder_name(name: &str) -> bool {
+    !name.trim().is_empty()
+        && name == name.trim()
+        && !name.starts_with('.')
+        && !name.ends_with('.')
+        && !name
+            .chars()
+            .any(|c| c.is_control() || INVALID_FOLDER_CHARS.contains(&c))
+}
+
+/// Lowercase alphanumerics only, for comparing titles
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Find the acquiring wishlist entry a scanned folder fulfils
+///
+/// The reserved folder name wins; otherwise the folder's cleaned title must
+/// equal the entry title.
+pub fn find_reservation<'a>(
+    items: &'a [WishlistItem],
+    folder_name: &str,
+) -> Option<&'a WishlistItem> {
+    let acquiring = || items.iter().filter(|i| i.status == STATUS_ACQUIRING);
+
+    if let Some(item) = acquiring().find(|i| {
+        i.target_folder_name
+            .as_deref()
+            .is_some_and(|t| t.eq_ignore_ascii_case(folder_name))
+    }) {
+        return Some(item);
+    }
+
+    let title = normalize(&scanner::clean_title(folder_name));
+    if title.is_empty() {
+        return None;
+    }
+    acquiring().find(|i| normalize(&i.title) == title)
+}
+
+/// Whether a folder has nothing in it yet (download not started)
+pub fn is_empty_folder(path: &Path) -> bool {
+    std::fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: i64, title: &str, status: &str, folder: Option<&str>) -> WishlistItem {
+        WishlistItem {
+            id,
+            title: title.to_string(),
+            steam_app_id: None,
+            notes: None,
+            status: status.to_string(),
+            target_folder_name: folder.map(String::from),
+            game_id: None,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_reserved_folder_name() {
+        assert_eq!(
+            reserved_folder_name("The Witcher 3: Wild Hunt"),
+            "The Witcher 3 Wild Hunt"
+        );
+        assert_eq!(reserved_folder_name("..Half-Life?.."), "Half-Life");
+        assert!(is_valid_folder_name(&reserved_folder_name("A/B\\C*")));
+        assert!(!is_valid_folder_name("../escape"));
+        assert!(!is_valid_folder_name(" padded"));
+    }
+
+    #[test]
+    fn test_find_reservation() {
+        let items = vec![
+            item(1, "Hades", STATUS_WANTED, None),
+            item(2, "Celeste", STATUS_ACQUIRING, Some("Celeste Deluxe")),
+            item(3, "Hollow Knight", STATUS_ACQUIRING, Some("HK")),
+        ];
+
+        assert_eq!(
+            find_reservation(&items, "celeste deluxe").map(|i| i.id),
+            Some(2)
+        );
+        assert_eq!(
+            find_reservation(&items, "Hollow Knight [FitGirl Repack]").map(|i| i.id),
+            Some(3)
+        );
+        // Only entries being acquired are linked
+        assert!(find_reservation(&items, "Hades").is_none());
+        assert!(find_reservation(&items, "Unrelated Game").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_changes_committed_before_returning() {
+        use crate::db::{self, testing};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (pool, mut outside) = testing::connect_file(dir.path()).await;
+        let status = |id: i64| {
+            let sql = "SELECT status FROM wishlist_items WHERE id = ?";
+            sqlx::query_as::<_, (String,)>(sql).bind(id)
+        };
+
+        // A POST /wishlist followed by GET /wishlist must see the entry
+        for _ in 0..10 {
+            let item = db::create_wishlist_item(&pool, "Hades", None, None)
+                .await
+                .unwrap();
+            assert!(testing::committed(&mut outside, "wishlist_items", item.id).await);
+
+            let price = db::add_wishlist_price(&pool, item.id, 1999, "EUR", None)
+                .await
+                .unwrap();
+            assert!(testing::committed(&mut outside, "wishlist_prices", price.id).await);
+
+            db::update_wishlist_item(&pool, item.id, None, None, None, Some("dropped"))
+                .await
+                .unwrap();
+            let (after_update,) = status(item.id).fetch_one(&mut outside).await.unwrap();
+            assert_eq!(after_update, "dropped");
+
+            db::reserve_wishlist_folder(&pool, item.id, "Hades")
+                .await
+                .unwrap();
+            let (after_reserve,) = status(item.id).fetch_one(&mut outside).await.unwrap();
+            assert_eq!(after_reserve, STATUS_ACQUIRING);
+        }
+    }
+}
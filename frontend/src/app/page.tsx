@@ -8,7 +8,17 @@ import { EnrichModal } from '@/components/EnrichModal';
 import { EditModal } from '@/components/EditModal';
 import { AdjustMatchModal } from '@/components/AdjustMatchModal';
 import { SettingsModal } from '@/components/SettingsModal';
-import { Game, GameDetail, Stats, getGames, getGame, searchGames, scanGames, getStats } from '@/lib/api';
+import {
+  Game,
+  GameDetail,
+  Stats,
+  getGames,
+  getGame,
+  searchGames,
+  scanGames,
+  getStats,
+  subscribeLibraryEvents,
+} from '@/lib/api';
 
 export default function Home() {
   const [games, setGames] = useState<Game[]>([]);
@@ -51,6 +61,41 @@ export default function Home() {
     loadStats();
   }, [loadGames, loadStats]);
 
+  // Live-update the grid while scans and enrichment run
+  useEffect(() => {
+    return subscribeLibraryEvents((event) => {
+      switch (event.type) {
+        case 'game_added':
+          // Search results are refreshed by the next search instead
+          if (!searchQuery.trim()) {
+            setGames(prev =>
+              prev.some(g => g.id === event.game.id)
+                ? prev
+                : [...prev, event.game].sort((a, b) => a.title.localeCompare(b.title))
+            );
+          }
+          break;
+        case 'game_updated':
+          setGames(prev => prev.map(g => (g.id === event.game.id ? event.game : g)));
+          break;
+        case 'game_deleted':
+          setGames(prev => prev.filter(g => g.id !== event.id));
+          break;
+        case 'job_progress':
+          if (event.finished) {
+            loadStats();
+          }
+          break;
+        case 'resync':
+          if (!searchQuery.trim()) {
+            loadGames();
+          }
+          loadStats();
+          break;
+      }
+    });
+  }, [searchQuery, loadGames, loadStats]);
+
   const handleSearch = useCallback(async (query: string) => {
     setSearchQuery(query);
     if (!query.trim()) {
@@ -131,6 +176,22 @@ export default function Home() {
     }
   };
 
+  // Pages opened from gamevault:// links (see backend/src/protocol.rs)
+  useEffect(() => {
+    const params = new URLSearchParams(window.location.search);
+    const gameId = Number(params.get('game'));
+    if (!params.has('game') && !params.has('scan')) {
+      return;
+    }
+    window.history.replaceState(null, '', window.location.pathname);
+    if (params.has('scan')) {
+      handleScan();
+    } else if (gameId > 0) {
+      handleEditGame(gameId);
+    }
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
   const handleAdjustMatchSave = (updatedGame: GameDetail) => {
     // Refresh the game list to show new cover and data
     loadGames();
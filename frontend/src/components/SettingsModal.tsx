@@ -59,6 +59,7 @@ export function SettingsModal({ isOpen, onClose }: SettingsModalProps) {
         cache: cachePath,
         port,
         auto_open_browser: autoOpenBrowser,
+        revision: config?.revision,
       });
 
       if (result.restart_required) {
@@ -68,7 +69,11 @@ export function SettingsModal({ isOpen, onClose }: SettingsModalProps) {
       onClose();
     } catch (err) {
       console.error('Failed to save config:', err);
-      setError(err instanceof Error ? err.message : 'Failed to save configuration');
+      if (err instanceof Error && err.message.includes('409')) {
+        setError('Settings were changed elsewhere. Reopen Settings to see the current values.');
+      } else {
+        setError(err instanceof Error ? err.message : 'Failed to save configuration');
+      }
     } finally {
       setSaving(false);
     }
@@ -150,6 +155,14 @@ export function SettingsModal({ isOpen, onClose }: SettingsModalProps) {
                 </div>
               )}
 
+              {/* Settings set by environment variables win over saved ones */}
+              {config && config.overrides.length > 0 && (
+                <div className="p-3 rounded-lg bg-yellow-900/40 text-yellow-200 text-sm">
+                  Set by environment variables:{' '}
+                  {config.overrides.map((o) => `${o.key} (${o.variable})`).join(', ')}
+                </div>
+              )}
+
               {/* Paths Section */}
               <div className="space-y-4">
                 <h3 className="text-sm font-semibold text-gray-400 uppercase tracking-wide">Paths</h3>
@@ -11,6 +11,10 @@ export interface Game {
   match_status: string;
   user_status: string | null;
   hltb_main_mins: number | null;
+  deck_compat: string | null;
+  // null until checked with Steam
+  trading_cards?: boolean | null;
+  points_shop?: boolean | null;
 }
 
 export interface GameDetail {
@@ -22,6 +26,7 @@ export interface GameDetail {
   steam_app_id: number | null;
   summary: string | null;
   release_date: string | null;
+  release_date_iso: string | null;
   cover_url: string | null;
   background_url: string | null;
   local_cover_path: string | null;
@@ -40,6 +45,20 @@ export interface GameDetail {
   hltb_main_mins: number | null;
   hltb_extra_mins: number | null;
   hltb_completionist_mins: number | null;
+  /** Only returned by getGame */
+  size_history?: SizeRecord[];
+}
+
+export interface RecentlyPlayedGame extends Game {
+  last_played_at: string;
+  /** A play session is still open */
+  running: boolean;
+}
+
+export interface SizeRecord {
+  size_bytes: number;
+  change_bytes: number | null;
+  recorded_at: string;
 }
 
 export interface ApiResponse<T> {
@@ -48,6 +67,13 @@ export interface ApiResponse<T> {
   error: string | null;
 }
 
+export interface Page<T> {
+  items: T[];
+  page: number;
+  page_size: number;
+  total: number;
+}
+
 export interface Stats {
   total_games: number;
   matched_games: number;
@@ -55,9 +81,28 @@ export interface Stats {
   enriched_games: number;
 }
 
+export interface SkippedFolder {
+  folder_name: string;
+  reason:
+    | 'hidden'
+    | 'reserved'
+    | 'archive'
+    | 'exclusion_pattern'
+    | 'gvignore'
+    | 'no_title'
+    | 'pending_download'
+    | 'failed';
+  detail: string | null;
+}
+
 export interface ScanResult {
+  id: number;
   total_found: number;
   added_or_updated: number;
+  wishlist_linked: number;
+  skipped: number;
+  skipped_folders: SkippedFolder[];
+  created_at: string;
 }
 
 export interface EnrichResult {
@@ -82,14 +127,73 @@ export interface ImportResult {
   total: number;
 }
 
+// Short-lived session token from POST /api/session/token, so the UI never
+// needs the API key. Only issued when the UI is opened on the host itself;
+// elsewhere requests go out without one.
+interface SessionToken {
+  token: string;
+  scope: string;
+  expires_in_secs: number;
+}
+
+// Renew this long before the token expires
+const SESSION_RENEW_MARGIN_MS = 60_000;
+
+let session: { token: string; expiresAt: number } | null = null;
+let sessionRequest: Promise<string | null> | null = null;
+
+async function requestSessionToken(): Promise<string | null> {
+  try {
+    const res = await fetch(`${API_URL}/api/session/token`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ scope: 'control' }),
+    });
+    const json: ApiResponse<SessionToken> = await res.json();
+    if (!json.success || !json.data) {
+      return null;
+    }
+    session = {
+      token: json.data.token,
+      expiresAt: Date.now() + json.data.expires_in_secs * 1000,
+    };
+    return session.token;
+  } catch {
+    return null;
+  }
+}
+
+async function getSessionToken(): Promise<string | null> {
+  if (session && Date.now() < session.expiresAt - SESSION_RENEW_MARGIN_MS) {
+    return session.token;
+  }
+  if (!sessionRequest) {
+    sessionRequest = requestSessionToken().finally(() => {
+      sessionRequest = null;
+    });
+  }
+  return sessionRequest;
+}
+
 async function fetchApi<T>(endpoint: string, options?: RequestInit): Promise<T> {
-  const res = await fetch(`${API_URL}/api${endpoint}`, {
-    ...options,
-    headers: {
-      'Content-Type': 'application/json',
-      ...options?.headers,
-    },
-  });
+  const send = async () => {
+    const token = await getSessionToken();
+    return fetch(`${API_URL}/api${endpoint}`, {
+      ...options,
+      headers: {
+        'Content-Type': 'application/json',
+        ...(token ? { Authorization: `Bearer ${token}` } : {}),
+        ...options?.headers,
+      },
+    });
+  };
+
+  let res = await send();
+  // The server restarted and forgot the token: get a new one and retry once
+  if (res.status === 401 && session) {
+    session = null;
+    res = await send();
+  }
 
   if (!res.ok) {
     throw new Error(`API error: ${res.status}`);
@@ -104,8 +208,22 @@ async function fetchApi<T>(endpoint: string, options?: RequestInit): Promise<T>
   return json.data as T;
 }
 
+export async function getGamesPage(page = 1, pageSize?: number): Promise<Page<Game>> {
+  const params = new URLSearchParams({ page: String(page) });
+  if (pageSize) params.set('page_size', String(pageSize));
+  return fetchApi<Page<Game>>(`/games?${params}`);
+}
+
+// Fetch the whole library page by page (page size is capped by the server)
 export async function getGames(): Promise<Game[]> {
-  return fetchApi<Game[]>('/games');
+  const games: Game[] = [];
+  for (let page = 1; ; page++) {
+    const result = await getGamesPage(page, 500);
+    games.push(...result.items);
+    if (result.items.length === 0 || games.length >= result.total) {
+      return games;
+    }
+  }
 }
 
 export async function getGame(id: number): Promise<GameDetail> {
@@ -120,6 +238,10 @@ export async function getRecentGames(): Promise<Game[]> {
   return fetchApi<Game[]>('/games/recent');
 }
 
+export async function getRecentlyPlayed(limit = 10): Promise<RecentlyPlayedGame[]> {
+  return fetchApi<RecentlyPlayedGame[]>(`/games/recently-played?limit=${limit}`);
+}
+
 export async function scanGames(): Promise<ScanResult> {
   return fetchApi<ScanResult>('/scan', { method: 'POST' });
 }
@@ -231,9 +353,17 @@ export interface ConfigServer {
   bind_address: string;
 }
 
+// A setting taken from an environment variable rather than config.toml
+export interface ConfigOverride {
+  key: string;
+  variable: string;
+}
+
 export interface Config {
   paths: ConfigPaths;
   server: ConfigServer;
+  revision: string;
+  overrides: ConfigOverride[];
 }
 
 export interface ConfigUpdateRequest {
@@ -241,12 +371,15 @@ export interface ConfigUpdateRequest {
   cache: string;
   port: number;
   auto_open_browser: boolean;
+  // Revision read with the config; a stale one is refused with 409
+  revision?: string;
 }
 
 export interface ConfigUpdateResponse {
   success: boolean;
   restart_required: boolean;
   message: string;
+  revision: string;
 }
 
 export async function getConfig(): Promise<Config> {
@@ -277,3 +410,51 @@ export interface ConfigStatusResponse {
 export async function getConfigStatus(): Promise<ConfigStatusResponse> {
   return fetchApi<ConfigStatusResponse>('/config/status');
 }
+
+// Live library events (see backend/src/events.rs)
+export type LibraryEvent =
+  | { type: 'hello'; version: number; heartbeat_secs: number }
+  | { type: 'heartbeat' }
+  | { type: 'pong' }
+  | { type: 'resync' }
+  | { type: 'game_added'; game: Game }
+  | { type: 'game_updated'; game: Game }
+  | { type: 'game_deleted'; id: number }
+  | { type: 'job_progress'; job: 'scan' | 'enrich'; done: number; total: number; finished: boolean }
+  | { type: 'notification'; id: number; kind: string; title: string; message: string; game_id: number | null };
+
+const EVENTS_RECONNECT_MS = 5000;
+
+// Open the event socket, reconnecting when it drops. Returns a function that closes it.
+export function subscribeLibraryEvents(onEvent: (event: LibraryEvent) => void): () => void {
+  let socket: WebSocket | null = null;
+  let retry: ReturnType<typeof setTimeout> | null = null;
+  let closed = false;
+
+  const connect = () => {
+    const base = new URL(API_URL || window.location.origin, window.location.href);
+    base.protocol = base.protocol === 'https:' ? 'wss:' : 'ws:';
+    socket = new WebSocket(`${base.origin}/api/ws`);
+    socket.onmessage = (message) => {
+      try {
+        onEvent(JSON.parse(message.data) as LibraryEvent);
+      } catch (err) {
+        console.error('Invalid library event:', err);
+      }
+    };
+    socket.onclose = () => {
+      if (!closed) {
+        retry = setTimeout(connect, EVENTS_RECONNECT_MS);
+      }
+    };
+  };
+
+  connect();
+  return () => {
+    closed = true;
+    if (retry) {
+      clearTimeout(retry);
+    }
+    socket?.close();
+  };
+}